@@ -1,4 +1,4 @@
- #!/bin/bash
+#!/bin/bash
 set -e
 
 echo "Starting database migration setup..."
@@ -9,22 +9,12 @@ if [ -z "$DATABASE_URL" ]; then
     exit 1
 fi
 
-echo "Creating a temporary psql script to run migrations..."
-cat > migration_runner.sql << EOF
-\i migrations/01_migration_script.sql
-EOF
-
-echo "Running migration script directly with psql..."
-psql "$DATABASE_URL" -f migration_runner.sql
-
-# Check if migration succeeded
-if [ $? -eq 0 ]; then
+echo "Applying pending migrations..."
+if cargo run --release -- migrate; then
     echo ":white_check_mark: Migration completed successfully"
-    # Clean up
-    rm migration_runner.sql
 else
     echo ":x: Migration failed"
     exit 1
 fi
 
-echo "Database setup complete!"
\ No newline at end of file
+echo "Database setup complete!"
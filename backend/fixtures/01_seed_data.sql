@@ -0,0 +1,108 @@
+-- Development and test data: users, projects and documents used by the integration tests
+-- Loaded with `cargo run -- seed` or by the test database reset, never applied to production automatically
+
+-- Insert default users, user 1 is an admin
+INSERT INTO users(name,email,password, ai_credits, is_admin)
+VALUES('Christian','CFdefence@gmail.com','$argon2id$v=19$m=19456,t=2,p=1$kNRxgrDUnkl79WdlNuLXOw$v+gZeEyNvLQNvw2Q3l6T7HQOerrVSbRfOnp/Cx1xadk', 999, TRUE) --MyPassword--
+ON CONFLICT (email) DO NOTHING;
+
+INSERT INTO users(name,email,password, ai_credits)
+VALUES('Marko','MarkoP@gmail.com','$argon2id$v=19$m=19456,t=2,p=1$6hDoev817tzWWUfs6z/6LA$n1mPBXmxmIw915nVg9nHc3YXba0OjgRw7Yx6q85UCTE', 999) --MarkosPassword--
+ON CONFLICT (email) DO NOTHING;
+
+-- Create a default project for user 1
+INSERT INTO projects (id, name, user_id) 
+VALUES (1, 'Default Project', 1)
+ON CONFLICT (id) DO NOTHING;
+
+-- Create a second project for testing
+INSERT INTO projects (id, name, user_id) 
+VALUES (2, 'Test Project', 1)
+ON CONFLICT (id) DO NOTHING;
+
+-- Ensure user 1 has owner permission for the default project
+INSERT INTO project_permissions(project_id, user_id, role)
+VALUES(1, 1, 'owner')
+ON CONFLICT (project_id, user_id) DO UPDATE SET role = 'owner';
+
+-- Ensure user 1 has owner permission for the test project
+INSERT INTO project_permissions(project_id, user_id, role)
+VALUES(2, 1, 'owner')
+ON CONFLICT (project_id, user_id) DO UPDATE SET role = 'owner';
+
+-- Create test documents owned by user 1
+INSERT INTO documents(id, name, content, user_id)
+VALUES(1, 'Test Document 1', 'Test content for document 1', 1)
+ON CONFLICT (id) DO NOTHING;
+
+INSERT INTO documents(id, name, content, user_id)
+VALUES(2, 'Test Document 2', 'Test content for document 2', 1)
+ON CONFLICT (id) DO NOTHING;
+
+-- Ensure user 1 has owner permission for document 1
+INSERT INTO document_permissions(document_id, user_id, role)
+VALUES(1, 1, 'owner')
+ON CONFLICT (document_id, user_id) DO UPDATE SET role = 'owner';
+
+-- Ensure user 1 has owner permission for document 2
+INSERT INTO document_permissions(document_id, user_id, role)
+VALUES(2, 1, 'owner')
+ON CONFLICT (document_id, user_id) DO UPDATE SET role = 'owner';
+
+-- Add user 2 as editor for document 1
+INSERT INTO document_permissions(document_id, user_id, role)
+VALUES(1, 2, 'editor')
+ON CONFLICT (document_id, user_id) DO UPDATE SET role = 'editor';
+
+-- Add document 1 to project 1
+INSERT INTO document_projects(document_id, project_id)
+VALUES(1, 1)
+ON CONFLICT DO NOTHING;
+
+-- Create additional documents for the project
+INSERT INTO documents(id, name, content, user_id)
+VALUES
+(3, 'Project Overview', '# Project Overview\n\nThis document provides a high-level overview of our project goals and timeline.', 1),
+(4, 'Technical Specifications', '# Technical Specifications\n\nDetailed technical requirements and implementation details.', 1),
+(5, 'Meeting Notes', '# Meeting Notes\n\nNotes from our project planning meetings and discussions.', 1),
+(6, 'Research Findings', '# Research Findings\n\nSummary of research conducted for this project.', 1)
+ON CONFLICT (id) DO NOTHING;
+
+-- Add permissions for user 1 on these documents
+INSERT INTO document_permissions(document_id, user_id, role)
+VALUES
+(3, 1, 'owner'),
+(4, 1, 'owner'),
+(5, 1, 'owner'),
+(6, 1, 'owner')
+ON CONFLICT (document_id, user_id) DO UPDATE SET role = 'owner';
+
+-- Add these documents to the same project as document 1 (project 1)
+INSERT INTO document_projects(document_id, project_id)
+VALUES
+(3, 1),
+(4, 1),
+(5, 1),
+(6, 1)
+ON CONFLICT DO NOTHING;
+
+-- Give User 1 Some Custom Keybindings
+INSERT INTO user_keybindings(user_id, command_id, keybinding)
+VALUES
+(1, 1, 'Ctrl H'), -- Bind 'Bold Selected' to Ctrl H
+(1, 2, 'Ctrl E') -- Bind 'Italic Selected' to Ctrl E
+ON CONFLICT (user_id, command_id) DO NOTHING;
+
+-- Set sequence values to match the highest IDs
+SELECT setval('users_id_seq', (SELECT MAX(id) FROM users));
+SELECT setval('projects_id_seq', (SELECT MAX(id) FROM projects));
+SELECT setval('documents_id_seq', (SELECT MAX(id) FROM documents));
+
+-- Update the storage_bytes column for existing users based on their document content
+UPDATE users
+SET storage_bytes = (
+    SELECT COALESCE(SUM(LENGTH(COALESCE(d.content, ''))), 0)
+    FROM documents d
+    JOIN document_permissions dp ON d.id = dp.document_id
+    WHERE dp.user_id = users.id AND dp.role = 'owner'
+);
@@ -0,0 +1,154 @@
+-- Baseline schema
+-- Migrations are applied in order by the migration runner (src/db/migrate.rs) and recorded in _migrations.
+-- Never edit a migration that has been applied somewhere, add a new numbered file instead.
+
+CREATE EXTENSION IF NOT EXISTS vector; -- Use PGVECTOR
+
+-- Create users table
+CREATE TABLE IF NOT EXISTS users (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(255) NOT NULL,
+    email VARCHAR(255) NOT NULL UNIQUE,
+    password VARCHAR(255) NOT NULL,
+    ai_credits INT NOT NULL DEFAULT 10,
+    storage_bytes BIGINT NOT NULL DEFAULT 0,
+    max_projects INT NOT NULL DEFAULT 3,
+    max_documents INT NOT NULL DEFAULT 10
+);
+
+-- Create projects table
+CREATE TABLE IF NOT EXISTS projects (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(255) DEFAULT 'Untitled Project' NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    is_starred BOOLEAN DEFAULT FALSE,
+    is_trashed BOOLEAN DEFAULT FALSE,
+    user_id INT REFERENCES users(id) ON DELETE CASCADE
+);
+
+-- Create documents table
+CREATE TABLE IF NOT EXISTS documents (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(255) DEFAULT 'Untitled Document' NOT NULL,
+    content TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    is_starred BOOLEAN DEFAULT FALSE,
+    is_trashed BOOLEAN DEFAULT FALSE,
+    user_id INT REFERENCES users(id) ON DELETE CASCADE,
+    embedding vector(1536),
+    embedding_updated_at TIMESTAMP WITH TIME ZONE
+);
+
+-- Create vector index for similarity search
+CREATE INDEX IF NOT EXISTS document_embedding_idx ON documents USING ivfflat (embedding vector_cosine_ops) WITH (lists = 100);
+
+-- Create junction table for many-to-many relationship
+CREATE TABLE IF NOT EXISTS document_projects (
+    document_id INT REFERENCES documents(id) ON DELETE CASCADE,
+    project_id INT REFERENCES projects(id) ON DELETE CASCADE,
+    PRIMARY KEY (document_id, project_id)
+);
+
+-- Create document_permissions table for role-based access
+CREATE TABLE IF NOT EXISTS document_permissions (
+    document_id INT REFERENCES documents(id) ON DELETE CASCADE,
+    user_id INT REFERENCES users(id) ON DELETE CASCADE,
+    role VARCHAR(20) NOT NULL CHECK (role IN ('viewer', 'editor', 'owner')),
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (document_id, user_id)
+);
+
+-- Create an index for faster lookups
+CREATE INDEX IF NOT EXISTS idx_document_permissions_user_id ON document_permissions(user_id);
+
+-- Create project_permissions table for role-based access
+CREATE TABLE IF NOT EXISTS project_permissions (
+    project_id INT REFERENCES projects(id) ON DELETE CASCADE,
+    user_id INT REFERENCES users(id) ON DELETE CASCADE,
+    role VARCHAR(20) NOT NULL CHECK (role IN ('viewer', 'editor', 'owner')),
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (project_id, user_id)
+);
+
+-- Create an index for faster lookups
+CREATE INDEX IF NOT EXISTS idx_project_permissions_user_id ON project_permissions(user_id);
+
+-- Create commands table for holding all valid commands
+CREATE TABLE IF NOT EXISTS commands (
+    command_id SERIAL PRIMARY KEY,
+    command_name VARCHAR(100) NOT NULL,
+    command_description VARCHAR(150) NOT NULL,
+    default_keybinding VARCHAR(50) NOT NULL
+);
+
+-- Create user_keybindings table for holding custom keybindings users set
+CREATE TABLE IF NOT EXISTS user_keybindings (
+    user_id INT NOT NULL,
+    command_id INT NOT NULL,
+    keybinding VARCHAR(50) NOT NULL,
+    PRIMARY KEY (user_id, command_id),
+    FOREIGN KEY (command_id) REFERENCES commands(command_id)
+);
+
+-- Create table for user profile images
+CREATE TABLE IF NOT EXISTS user_profile_images (
+    user_id INTEGER PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    image_data BYTEA NOT NULL,
+    content_type VARCHAR(255) NOT NULL DEFAULT 'image/jpeg'
+);
+
+-- Create tables for user preferences
+CREATE TABLE IF NOT EXISTS default_preferences (
+    preference_id SERIAL PRIMARY KEY,
+    preference_name VARCHAR(100) NOT NULL UNIQUE,
+    preference_value VARCHAR(100) NOT NULL,
+    preference_description VARCHAR(255) NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS user_preferences (
+    user_id INT REFERENCES users(id) ON DELETE CASCADE,
+    preference_id INT REFERENCES default_preferences(preference_id) ON DELETE CASCADE,
+    preference_value VARCHAR(100) NOT NULL,
+    PRIMARY KEY (user_id, preference_id)
+);
+
+CREATE TABLE IF NOT EXISTS user_backgrounds (
+    user_id INTEGER PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    image_data BYTEA NOT NULL,
+    content_type VARCHAR(255) NOT NULL DEFAULT 'image/jpeg'
+);
+
+-- Create enum type for message roles
+DO $$ BEGIN
+    CREATE TYPE message_role_enum AS ENUM ('user', 'assistant');
+EXCEPTION WHEN duplicate_object THEN NULL;
+END $$;
+
+-- Create tables for AI writing assistant functionality
+
+-- Writing assistant sessions table
+CREATE TABLE IF NOT EXISTS writing_assistant_sessions (
+    id SERIAL PRIMARY KEY,
+    user_id INT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    document_id INT REFERENCES documents(id) ON DELETE SET NULL,
+    title VARCHAR(255) NOT NULL DEFAULT 'New Writing Session',
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Writing assistant messages table
+CREATE TABLE IF NOT EXISTS writing_assistant_messages (
+    id SERIAL PRIMARY KEY,
+    session_id INT NOT NULL REFERENCES writing_assistant_sessions(id) ON DELETE CASCADE,
+    role message_role_enum NOT NULL,
+    content TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    embedding vector(1536)
+);
+
+-- Indexes for faster queries
+CREATE INDEX IF NOT EXISTS idx_writing_messages_session_id ON writing_assistant_messages(session_id);
+CREATE INDEX IF NOT EXISTS idx_writing_sessions_user_id ON writing_assistant_sessions(user_id);
+CREATE INDEX IF NOT EXISTS idx_writing_sessions_document_id ON writing_assistant_sessions(document_id);
@@ -0,0 +1,58 @@
+-- Reference data the application needs in every environment
+
+-- Insert Default Commands
+INSERT INTO commands(command_id, command_name, command_description, default_keybinding)
+VALUES 
+(1, 'bold', 'Action: bold', 'Ctrl+B'),
+(2, 'italic', 'Action: italic', 'Ctrl+I'),
+(3, 'underline', 'Action: underline', 'Ctrl+U'),
+(4, 'openColorPicker', 'Action: open Color Picker', 'Ctrl+F'),
+(5, 'moveLeft', 'Action: move Left', 'H'),
+(6, 'moveRight', 'Action: move Right', 'L'),
+(7, 'moveUp', 'Action: move Up', 'K'),
+(8, 'moveDown', 'Action: move Down', 'J'),
+(9, 'switchToDocument1', 'Action: switch To Document1', 'Ctrl+1'),
+(10, 'switchToDocument2', 'Action: switch To Document2', 'Ctrl+2'),
+(11, 'switchToDocument3', 'Action: switch To Document3', 'Ctrl+3'),
+(12, 'switchToDocument4', 'Action: switch To Document4', 'Ctrl+4'),
+(13, 'switchToDocument5', 'Action: switch To Document5', 'Ctrl+5'),
+(14, 'switchToDocument6', 'Action: switch To Document6', 'Ctrl+6'),
+(15, 'switchToDocument7', 'Action: switch To Document7', 'Ctrl+7'),
+(16, 'switchToDocument8', 'Action: switch To Document8', 'Ctrl+8'),
+(17, 'switchToDocument9', 'Action: switch To Document9', 'Ctrl+9'),
+(18, 'enterInsertMode', 'Action: enter Insert Mode', 'I'),
+(19, 'moveToStartOfLine', 'Action: move To Start Of Line', '0'),
+(20, 'moveToEndOfLine', 'Action: move To End Of Line', 'Shift+$'),
+(21, 'moveToEndOfDocument', 'Action: move To End Of Document', 'Shift+G'),
+(22, 'moveToStartOfDocument', 'Action: move To Start Of Document', 'g'),
+(23, 'toggleCommandSheet', 'Action: toggle Command Sheet', 'Ctrl+/'),
+(24, 'findNextMatch', 'Action: find Next Match', 'N'),
+(25, 'findPreviousMatch', 'Action: find Previous Match', 'M'),
+(26, 'deleteSelectedText', 'Action: delete Selected Text', 'X'),
+(27, 'yankText', 'Action: yank Text', 'Y'),
+(28, 'deleteLine', 'Action: delete Line', 'D'),
+(29, 'pasteText', 'Action: paste Text', 'P'),
+(30, 'toggleChatAssistant', 'Action: toggle AI Chat Window', 'Alt+C')
+ON CONFLICT (command_id) DO UPDATE SET
+    command_name = EXCLUDED.command_name,
+    command_description = EXCLUDED.command_description,
+    default_keybinding = EXCLUDED.default_keybinding;
+
+-- Insert default color preferences
+INSERT INTO default_preferences(preference_id, preference_name, preference_value, preference_description)
+VALUES 
+(1, 'primary_color', '#0A1721', 'Default primary color for text and UI elements'),
+(2, 'secondary_color', '#10b981', 'Default secondary color for UI elements'),
+(3, 'primary_accent_color', '#10b981', 'Default primary accent color for UI elements'),
+(4, 'secondary_accent_color', '#808080', 'Default secondary accent color for UI elements'),
+(5, 'primary_text_color', '#10b981', 'Default primary text color for UI elements'),
+(6, 'secondary_text_color', '#FFFFFF', 'Default secondary text color for UI elements'),
+(7, 'editor_background_opacity', '0.2', 'Default opacity for the editor background')
+ON CONFLICT (preference_id) DO UPDATE SET
+    preference_name = EXCLUDED.preference_name,
+    preference_value = EXCLUDED.preference_value,
+    preference_description = EXCLUDED.preference_description;
+
+-- Keep the sequences ahead of the explicit ids above
+SELECT setval('commands_command_id_seq', (SELECT MAX(command_id) FROM commands));
+SELECT setval('default_preferences_preference_id_seq', (SELECT MAX(preference_id) FROM default_preferences));
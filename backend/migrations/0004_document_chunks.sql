@@ -0,0 +1,17 @@
+-- Document chunks
+-- One embedding per paragraph-sized piece of a document, so search can point at the matching passage.
+
+CREATE TABLE IF NOT EXISTS document_chunks (
+    id SERIAL PRIMARY KEY,
+    document_id INT NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    chunk_index INT NOT NULL,
+    heading TEXT,
+    content TEXT NOT NULL,
+    start_offset INT NOT NULL,
+    end_offset INT NOT NULL,
+    embedding vector(1536) NOT NULL,
+    UNIQUE (document_id, chunk_index)
+);
+
+-- Create vector index for similarity search
+CREATE INDEX IF NOT EXISTS document_chunks_embedding_idx ON document_chunks USING ivfflat (embedding vector_cosine_ops) WITH (lists = 100);
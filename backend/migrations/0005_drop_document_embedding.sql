@@ -0,0 +1,5 @@
+-- Drop the per-document embedding
+-- Search uses document_chunks now, so the single vector per document and its index are no longer read.
+
+DROP INDEX IF EXISTS document_embedding_idx;
+ALTER TABLE documents DROP COLUMN IF EXISTS embedding;
@@ -0,0 +1,22 @@
+-- Document revisions
+-- A snapshot of the content after each save, so earlier versions can be listed, compared and restored.
+
+-- Create enum type for what produced a document revision
+DO $$ BEGIN
+    CREATE TYPE revision_origin_enum AS ENUM ('manual', 'ai_apply', 'restore');
+EXCEPTION WHEN duplicate_object THEN NULL;
+END $$;
+
+-- Create document_revisions table holding a snapshot of the content after each save
+CREATE TABLE IF NOT EXISTS document_revisions (
+    id SERIAL PRIMARY KEY,
+    document_id INT NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    revision_number INT NOT NULL,
+    author_id INT REFERENCES users(id) ON DELETE SET NULL,
+    content TEXT NOT NULL,
+    origin revision_origin_enum NOT NULL DEFAULT 'manual',
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (document_id, revision_number)
+);
+
+CREATE INDEX IF NOT EXISTS idx_document_revisions_document_id ON document_revisions(document_id);
@@ -0,0 +1,4 @@
+-- Document versions
+-- Incremented on every save, so a save made against an older version can be rejected as a conflict.
+
+ALTER TABLE documents ADD COLUMN IF NOT EXISTS version INT NOT NULL DEFAULT 1;
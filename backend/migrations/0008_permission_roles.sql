@@ -0,0 +1,16 @@
+-- Typed permission roles
+-- Replaces the free-form role strings on document and project permissions with an enum.
+-- Values are declared from least to most capable, so roles can be compared with < and >.
+
+DO $$ BEGIN
+    CREATE TYPE permission_role_enum AS ENUM ('viewer', 'commenter', 'editor', 'owner');
+EXCEPTION WHEN duplicate_object THEN NULL;
+END $$;
+
+ALTER TABLE document_permissions DROP CONSTRAINT IF EXISTS document_permissions_role_check;
+ALTER TABLE document_permissions
+    ALTER COLUMN role TYPE permission_role_enum USING role::permission_role_enum;
+
+ALTER TABLE project_permissions DROP CONSTRAINT IF EXISTS project_permissions_role_check;
+ALTER TABLE project_permissions
+    ALTER COLUMN role TYPE permission_role_enum USING role::permission_role_enum;
@@ -0,0 +1,27 @@
+-- Inherited document access
+-- Collaborators on a project can reach every document in it without a document permission of their own.
+-- A document permission is an explicit override and wins over anything inherited from a project.
+-- Inherited access stops at editor, only a document's own owner can delete, share or transfer it.
+
+CREATE OR REPLACE VIEW document_access AS
+    SELECT document_id, user_id, role, false AS inherited
+    FROM document_permissions
+    UNION ALL
+    SELECT dp.document_id, pp.user_id, LEAST(MAX(pp.role), 'editor'::permission_role_enum), true
+    FROM document_projects dp
+    JOIN project_permissions pp ON pp.project_id = dp.project_id
+    WHERE NOT EXISTS (
+        SELECT 1 FROM document_permissions perm
+        WHERE perm.document_id = dp.document_id AND perm.user_id = pp.user_id
+    )
+    GROUP BY dp.document_id, pp.user_id;
+
+-- Sharing a project used to copy its role onto every document in it.
+-- Drop the copies that still match the project role so later project role changes reach those documents.
+DELETE FROM document_permissions perm
+USING document_projects dp, project_permissions pp
+WHERE dp.document_id = perm.document_id
+  AND pp.project_id = dp.project_id
+  AND pp.user_id = perm.user_id
+  AND perm.role = LEAST(pp.role, 'editor'::permission_role_enum)
+  AND perm.role <> 'owner';
@@ -0,0 +1,22 @@
+-- Share links
+-- A link grants a role on a document or a project to whoever opens it while logged in.
+-- Links can expire, need a password and be limited to a number of uses. They never grant ownership.
+
+CREATE TABLE IF NOT EXISTS share_links (
+    id SERIAL PRIMARY KEY,
+    token VARCHAR(64) NOT NULL UNIQUE,
+    document_id INT REFERENCES documents(id) ON DELETE CASCADE,
+    project_id INT REFERENCES projects(id) ON DELETE CASCADE,
+    role permission_role_enum NOT NULL CHECK (role <> 'owner'),
+    created_by INT REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    expires_at TIMESTAMP,
+    password_hash VARCHAR(255),
+    max_uses INT CHECK (max_uses > 0),
+    use_count INT NOT NULL DEFAULT 0,
+    revoked_at TIMESTAMP,
+    CHECK ((document_id IS NULL) <> (project_id IS NULL)) -- Exactly one target
+);
+
+CREATE INDEX IF NOT EXISTS idx_share_links_document_id ON share_links(document_id);
+CREATE INDEX IF NOT EXISTS idx_share_links_project_id ON share_links(project_id);
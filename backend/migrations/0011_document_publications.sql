@@ -0,0 +1,15 @@
+-- Published documents
+-- Publishing snapshots a document's content so anyone can read it at /p/:slug without an account.
+-- The slug is kept when a document is unpublished, so publishing it again brings back the same URL.
+
+CREATE TABLE IF NOT EXISTS document_publications (
+    document_id INT PRIMARY KEY REFERENCES documents(id) ON DELETE CASCADE,
+    slug VARCHAR(120) NOT NULL UNIQUE,
+    title VARCHAR(255) NOT NULL,
+    content TEXT NOT NULL, -- Snapshot served to readers, later edits are not visible until published again
+    revision_number INT, -- Revision the snapshot was taken from, if the document had one
+    is_published BOOLEAN NOT NULL DEFAULT TRUE,
+    published_by INT REFERENCES users(id) ON DELETE SET NULL,
+    published_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    view_count BIGINT NOT NULL DEFAULT 0
+);
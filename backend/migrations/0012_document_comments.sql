@@ -0,0 +1,34 @@
+-- Document comments
+-- A thread starts with a comment anchored to a range of the document and collects replies until it is resolved.
+-- Anchors are character offsets into the document's text together with the quoted text, so the editor can
+-- find the passage again after edits have moved it. Replies have no anchor of their own.
+
+CREATE TABLE IF NOT EXISTS document_comments (
+    id SERIAL PRIMARY KEY,
+    document_id INT NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    thread_id INT REFERENCES document_comments(id) ON DELETE CASCADE, -- NULL for the comment that starts a thread
+    author_id INT REFERENCES users(id) ON DELETE SET NULL,
+    content TEXT NOT NULL,
+    anchor_start INT,
+    anchor_end INT,
+    quoted_text TEXT,
+    resolved_at TIMESTAMP,
+    resolved_by INT REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CHECK ((thread_id IS NULL) = (anchor_start IS NOT NULL AND anchor_end IS NOT NULL)), -- Only threads are anchored
+    CHECK (anchor_start >= 0 AND anchor_end >= anchor_start),
+    CHECK (thread_id IS NULL OR resolved_at IS NULL) -- Threads are resolved as a whole
+);
+
+CREATE INDEX IF NOT EXISTS idx_document_comments_document_id ON document_comments(document_id);
+CREATE INDEX IF NOT EXISTS idx_document_comments_thread_id ON document_comments(thread_id);
+
+-- Users mentioned in a comment, only users with access to the document can be mentioned
+CREATE TABLE IF NOT EXISTS comment_mentions (
+    comment_id INT NOT NULL REFERENCES document_comments(id) ON DELETE CASCADE,
+    user_id INT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    PRIMARY KEY (comment_id, user_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_comment_mentions_user_id ON comment_mentions(user_id);
@@ -0,0 +1,35 @@
+-- Suggestions (track changes)
+-- A suggestion is a pending edit to a document from a collaborator or the writing assistant.
+-- It keeps the content it was made against and the changed ranges, so it can still be applied after
+-- unrelated edits. Accepting it saves the new content as a revision, rejecting it only closes it.
+
+DO $$ BEGIN
+    CREATE TYPE suggestion_source_enum AS ENUM ('user', 'ai');
+EXCEPTION WHEN duplicate_object THEN NULL;
+END $$;
+
+DO $$ BEGIN
+    CREATE TYPE suggestion_status_enum AS ENUM ('pending', 'accepted', 'rejected');
+EXCEPTION WHEN duplicate_object THEN NULL;
+END $$;
+
+-- Revisions created by accepting a collaborator's suggestion
+ALTER TYPE revision_origin_enum ADD VALUE IF NOT EXISTS 'suggestion';
+
+CREATE TABLE IF NOT EXISTS document_suggestions (
+    id SERIAL PRIMARY KEY,
+    document_id INT NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    author_id INT REFERENCES users(id) ON DELETE SET NULL, -- For AI suggestions, the user who asked for them
+    source suggestion_source_enum NOT NULL DEFAULT 'user',
+    base_version INT NOT NULL, -- Document version the suggestion was made against
+    old_content TEXT NOT NULL,
+    new_content TEXT NOT NULL,
+    hunks JSONB NOT NULL DEFAULT '[]', -- Changed ranges of old_content, see models/suggestion.rs
+    status suggestion_status_enum NOT NULL DEFAULT 'pending',
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    resolved_at TIMESTAMP,
+    resolved_by INT REFERENCES users(id) ON DELETE SET NULL,
+    revision_number INT -- Revision the accepted suggestion was saved as
+);
+
+CREATE INDEX IF NOT EXISTS idx_document_suggestions_document_id ON document_suggestions(document_id, status);
@@ -0,0 +1,31 @@
+-- AI credit ledger
+-- Every AI operation reserves its credits before calling the model. The reservation is committed when
+-- the operation succeeds and released, giving the credits back, when it fails. Each row records one
+-- charge with what it was for and how it ended.
+
+DO $$ BEGIN
+    CREATE TYPE ai_operation_enum AS ENUM (
+        'chat', 'grammar', 'summarize', 'rephrase', 'expand', 'shrink',
+        'rewrite', 'fact_check', 'spell_check', 'apply_suggestion'
+    );
+EXCEPTION WHEN duplicate_object THEN NULL;
+END $$;
+
+DO $$ BEGIN
+    CREATE TYPE ai_credit_status_enum AS ENUM ('reserved', 'committed', 'released');
+EXCEPTION WHEN duplicate_object THEN NULL;
+END $$;
+
+CREATE TABLE IF NOT EXISTS ai_credit_ledger (
+    id SERIAL PRIMARY KEY,
+    user_id INT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    operation ai_operation_enum NOT NULL,
+    amount INT NOT NULL CHECK (amount > 0),
+    status ai_credit_status_enum NOT NULL DEFAULT 'reserved',
+    failure VARCHAR(100), -- Error that released the reservation
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    settled_at TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_ai_credit_ledger_user_id ON ai_credit_ledger(user_id, created_at);
+CREATE INDEX IF NOT EXISTS idx_ai_credit_ledger_reserved ON ai_credit_ledger(created_at) WHERE status = 'reserved';
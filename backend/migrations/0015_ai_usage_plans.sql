@@ -0,0 +1,28 @@
+-- Token metered AI usage and plans
+-- Each ledger entry records the tokens its operation used, and credits are charged by tokens.
+-- A plan sets how many tokens a user may use per calendar month and how many credits their
+-- balance is topped up to, and how often. Existing and new users start on the free plan.
+
+ALTER TABLE ai_credit_ledger ADD COLUMN IF NOT EXISTS prompt_tokens INT NOT NULL DEFAULT 0;
+ALTER TABLE ai_credit_ledger ADD COLUMN IF NOT EXISTS completion_tokens INT NOT NULL DEFAULT 0;
+ALTER TABLE ai_credit_ledger ADD COLUMN IF NOT EXISTS tokens_estimated BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE TABLE IF NOT EXISTS plans (
+    name VARCHAR(50) PRIMARY KEY,
+    description TEXT NOT NULL DEFAULT '',
+    price_cents INT NOT NULL DEFAULT 0, -- Per month
+    monthly_token_budget BIGINT, -- NULL means unlimited
+    refill_credits INT NOT NULL, -- Balance is topped up to this many credits
+    refill_interval_days INT NOT NULL DEFAULT 30 CHECK (refill_interval_days > 0)
+);
+
+INSERT INTO plans (name, description, price_cents, monthly_token_budget, refill_credits, refill_interval_days)
+VALUES
+    ('free', 'Try the writing assistant', 0, 200000, 10, 30),
+    ('starter', 'Perfect for individual writers', 900, 1000000, 200, 30),
+    ('pro', 'For professional writers', 1900, 5000000, 1000, 30),
+    ('team', 'For writing teams', 4900, 20000000, 5000, 30)
+ON CONFLICT (name) DO NOTHING;
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS plan VARCHAR(50) NOT NULL DEFAULT 'free' REFERENCES plans(name) ON UPDATE CASCADE;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS credits_refilled_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP;
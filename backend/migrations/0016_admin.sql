@@ -0,0 +1,25 @@
+-- Administration
+-- Admins manage other users through /api/admin: their credits, plan, limits and whether they can sign in.
+-- A user's storage quota is USER_STORAGE_QUOTA_MB unless storage_quota_bytes is set for them.
+-- Every change an admin makes is recorded in admin_audit_log with what it was before and after.
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS is_admin BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS disabled_at TIMESTAMP; -- Set while the account is disabled
+ALTER TABLE users ADD COLUMN IF NOT EXISTS storage_quota_bytes BIGINT CHECK (storage_quota_bytes >= 0); -- NULL is the default quota
+
+DO $$ BEGIN
+    CREATE TYPE admin_action_enum AS ENUM ('adjust_credits', 'update_limits', 'disable_user', 'enable_user');
+EXCEPTION WHEN duplicate_object THEN NULL;
+END $$;
+
+CREATE TABLE IF NOT EXISTS admin_audit_log (
+    id SERIAL PRIMARY KEY,
+    admin_id INT REFERENCES users(id) ON DELETE SET NULL,
+    action admin_action_enum NOT NULL,
+    target_user_id INT REFERENCES users(id) ON DELETE SET NULL,
+    details JSONB NOT NULL DEFAULT '{}',
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_admin_audit_log_created_at ON admin_audit_log(created_at);
+CREATE INDEX IF NOT EXISTS idx_admin_audit_log_target_user_id ON admin_audit_log(target_user_id);
@@ -0,0 +1,5 @@
+-- Share link password attempts
+-- Wrong passwords are counted per link, every few in a row lock the link for a while so it cannot be guessed.
+
+ALTER TABLE share_links ADD COLUMN IF NOT EXISTS failed_attempts INT NOT NULL DEFAULT 0;
+ALTER TABLE share_links ADD COLUMN IF NOT EXISTS locked_until TIMESTAMP;
@@ -0,0 +1,21 @@
+-- Suggestion hunks as diff ranges
+-- Suggestions used to store a single {start, end, old_text, new_text} range. They now store the ranges of
+-- src/diff.rs, which also carry their kind and position in the new content. Old ranges are rewritten in
+-- place: a single range starts at the same character in both texts, so new_start is its start.
+
+UPDATE document_suggestions s
+SET hunks = (
+    SELECT COALESCE(jsonb_agg(jsonb_build_object(
+        'kind', CASE WHEN h->>'old_text' = '' THEN 'insert'
+                     WHEN h->>'new_text' = '' THEN 'delete'
+                     ELSE 'replace' END,
+        'old_start', (h->>'start')::INT,
+        'old_end', (h->>'end')::INT,
+        'new_start', (h->>'start')::INT,
+        'new_end', (h->>'start')::INT + char_length(h->>'new_text'),
+        'old_text', h->>'old_text',
+        'new_text', h->>'new_text'
+    ) ORDER BY position), '[]'::jsonb)
+    FROM jsonb_array_elements(s.hunks) WITH ORDINALITY AS old_hunks(h, position)
+)
+WHERE EXISTS (SELECT 1 FROM jsonb_array_elements(s.hunks) AS old_hunks(h) WHERE h ? 'start');
@@ -0,0 +1,5 @@
+-- Metered proactive diff decisions and text sanitizing
+-- Both call the model, so they are charged and counted towards the token budget like every other AI operation.
+
+ALTER TYPE ai_operation_enum ADD VALUE IF NOT EXISTS 'proactive_diff';
+ALTER TYPE ai_operation_enum ADD VALUE IF NOT EXISTS 'sanitize_text';
@@ -0,0 +1,118 @@
+// src/auth/authorization.rs
+//
+// Authorization for documents, projects and administration.
+// Every permission check goes through here. A user's role on a document is their own document
+// permission if they have one, otherwise what they inherit from the projects the document belongs to.
+// The document_access view (migrations/0009_document_access.sql) resolves this in one place.
+// Admin routes (/api/admin) additionally require the user to be an admin, see require_admin.
+
+use sqlx::PgPool;
+
+use crate::models::role::Role;
+use crate::{Error, Result};
+
+/// The user's effective role on a document, None if they have no access at all.
+pub async fn document_role(pool: &PgPool, user_id: i32, document_id: i32) -> Result<Option<Role>> {
+    let result = sqlx::query!(
+        r#"SELECT role as "role!: Role" FROM document_access
+           WHERE document_id = $1 AND user_id = $2"#,
+        document_id,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await;
+
+    match result {
+        Ok(record) => Ok(record.map(|r| r.role)),
+        Err(e) => {
+            println!("Error checking permission: {:?}", e);
+            Err(Error::PermissionError)
+        }
+    }
+}
+
+/// The user's effective role on a document while their account is enabled, None otherwise.
+/// For connections that stay open after their first check and must notice when access is gone.
+pub async fn active_document_role(pool: &PgPool, user_id: i32, document_id: i32) -> Result<Option<Role>> {
+    let result = sqlx::query!(
+        r#"SELECT da.role as "role!: Role" FROM document_access da
+           JOIN users u ON u.id = da.user_id
+           WHERE da.document_id = $1 AND da.user_id = $2 AND u.disabled_at IS NULL"#,
+        document_id,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await;
+
+    match result {
+        Ok(record) => Ok(record.map(|r| r.role)),
+        Err(e) => {
+            println!("Error checking permission: {:?}", e);
+            Err(Error::PermissionError)
+        }
+    }
+}
+
+/// The user's role on a project, None if they have no access at all.
+pub async fn project_role(pool: &PgPool, user_id: i32, project_id: i32) -> Result<Option<Role>> {
+    let result = sqlx::query!(
+        r#"SELECT role as "role: Role" FROM project_permissions
+           WHERE project_id = $1 AND user_id = $2"#,
+        project_id,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await;
+
+    match result {
+        Ok(record) => Ok(record.map(|r| r.role)),
+        Err(e) => {
+            println!("Error checking permission: {:?}", e);
+            Err(Error::PermissionError)
+        }
+    }
+}
+
+/// Check if a user has at least `required` on a document.
+pub async fn check_document_permission(
+    pool: &PgPool,
+    user_id: i32,
+    document_id: i32,
+    required: Role,
+) -> Result<bool> {
+    let role = document_role(pool, user_id, document_id).await?;
+    Ok(role.is_some_and(|role| role.allows(required)))
+}
+
+/// Check if a user has at least `required` on a project.
+pub async fn check_project_permission(
+    pool: &PgPool,
+    user_id: i32,
+    project_id: i32,
+    required: Role,
+) -> Result<bool> {
+    let role = project_role(pool, user_id, project_id).await?;
+    Ok(role.is_some_and(|role| role.allows(required)))
+}
+
+/// Fail with PermissionError unless the user is an admin.
+pub async fn require_admin(pool: &PgPool, user_id: i32) -> Result<()> {
+    let result = sqlx::query!(
+        "SELECT is_admin FROM users WHERE id = $1 AND disabled_at IS NULL",
+        user_id
+    )
+    .fetch_optional(pool)
+    .await;
+
+    match result {
+        Ok(Some(record)) if record.is_admin => Ok(()),
+        Ok(_) => {
+            println!("->> {:<12} - user {} is not an admin", "AUTH", user_id);
+            Err(Error::PermissionError)
+        }
+        Err(e) => {
+            println!("Error checking admin: {:?}", e);
+            Err(Error::PermissionError)
+        }
+    }
+}
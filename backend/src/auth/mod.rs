@@ -0,0 +1,3 @@
+pub mod authorization;
+pub mod token;
+pub mod session;
\ No newline at end of file
@@ -0,0 +1,117 @@
+// src/auth/session.rs
+//
+// Server-side store for login sessions.
+// Every issued token has a row in user_sessions, deleting the row revokes the token.
+
+use chrono::{DateTime, NaiveDateTime};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::auth::token::AuthToken;
+use crate::models::session::UserSession;
+use crate::{Error, Result};
+
+fn to_naive(timestamp: i64) -> NaiveDateTime {
+    DateTime::from_timestamp(timestamp, 0)
+        .unwrap_or_default()
+        .naive_utc()
+}
+
+/// Record a newly issued token in the session store.
+pub async fn create_session(
+    pool: &PgPool,
+    token: &AuthToken,
+    user_agent: Option<String>,
+    ip_address: Option<String>,
+) -> Result<()> {
+    // Clear out any of the user's sessions that have already expired
+    sqlx::query!(
+        "DELETE FROM user_sessions WHERE user_id = $1 AND expires_at <= NOW() AT TIME ZONE 'UTC'",
+        token.user_id
+    )
+    .execute(pool)
+    .await
+    .map_err(|_| Error::DatabaseError)?;
+
+    sqlx::query!(
+        "INSERT INTO user_sessions (id, user_id, created_at, last_seen, expires_at, user_agent, ip_address)
+         VALUES ($1, $2, $3, $3, $4, $5, $6)",
+        token.session_id,
+        token.user_id,
+        to_naive(token.issued_at),
+        to_naive(token.expires_at),
+        user_agent,
+        ip_address
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        println!("Error creating session: {:?}", e);
+        Error::SessionCreationError
+    })?;
+
+    Ok(())
+}
+
+/// Check that the token's session still exists and has not expired, and mark it as seen.
+/// Returns false if the session was revoked or is unknown, or the account has been disabled.
+pub async fn touch_session(pool: &PgPool, token: &AuthToken) -> Result<bool> {
+    let result = sqlx::query!(
+        "UPDATE user_sessions s
+         SET last_seen = NOW() AT TIME ZONE 'UTC'
+         FROM users u
+         WHERE s.id = $1 AND s.user_id = $2 AND s.expires_at > NOW() AT TIME ZONE 'UTC'
+           AND u.id = s.user_id AND u.disabled_at IS NULL",
+        token.session_id,
+        token.user_id
+    )
+    .execute(pool)
+    .await
+    .map_err(|_| Error::DatabaseError)?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Get all active sessions for a user, most recently used first.
+pub async fn list_sessions(pool: &PgPool, user_id: i32) -> Result<Vec<UserSession>> {
+    sqlx::query_as!(
+        UserSession,
+        r#"SELECT id, created_at, last_seen, expires_at, user_agent, ip_address
+           FROM user_sessions
+           WHERE user_id = $1 AND expires_at > NOW() AT TIME ZONE 'UTC'
+           ORDER BY last_seen DESC"#,
+        user_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|_| Error::DatabaseError)
+}
+
+/// Revoke a single session belonging to the user.
+pub async fn revoke_session(pool: &PgPool, user_id: i32, session_id: Uuid) -> Result<()> {
+    let result = sqlx::query!(
+        "DELETE FROM user_sessions WHERE id = $1 AND user_id = $2",
+        session_id,
+        user_id
+    )
+    .execute(pool)
+    .await
+    .map_err(|_| Error::DatabaseError)?;
+
+    if result.rows_affected() == 0 {
+        return Err(Error::SessionNotFoundError);
+    }
+
+    Ok(())
+}
+
+/// Revoke every session for the user ("sign out everywhere").
+/// Returns the number of sessions removed.
+pub async fn revoke_all_sessions(pool: &PgPool, user_id: i32) -> Result<u64> {
+    let result = sqlx::query!("DELETE FROM user_sessions WHERE user_id = $1", user_id)
+        .execute(pool)
+        .await
+        .map_err(|_| Error::DatabaseError)?;
+
+    Ok(result.rows_affected())
+}
@@ -0,0 +1,134 @@
+// src/auth/token.rs
+//
+// Signed session tokens stored in the `auth-token` cookie.
+// Token format: "{user_id}.{issued_at}.{expires_at}.{session_id}.{signature}"
+// where the signature is a base64url HMAC-SHA256 over everything before it.
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::env;
+use std::sync::OnceLock;
+use tower_cookies::Cookies;
+use uuid::Uuid;
+
+use crate::{Error, Result};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Name of the cookie holding the signed token
+pub const AUTH_COOKIE_NAME: &str = "auth-token";
+
+/// How long a freshly issued token stays valid (matches the cookie max age)
+pub const TOKEN_TTL_DAYS: i64 = 3;
+
+// Secret used to sign tokens, read once from the environment
+static TOKEN_SECRET: OnceLock<Vec<u8>> = OnceLock::new();
+
+/// Get the token signing secret from AUTH_TOKEN_SECRET.
+/// Called once in main so a missing secret fails at startup instead of on the first request.
+pub fn token_secret() -> &'static [u8] {
+    TOKEN_SECRET.get_or_init(|| {
+        env::var("AUTH_TOKEN_SECRET")
+            .expect("AUTH_TOKEN_SECRET must be set")
+            .into_bytes()
+    })
+}
+
+#[derive(Debug, Clone)]
+pub struct AuthToken {
+    pub user_id: i32,
+    pub issued_at: i64,  // unix seconds
+    pub expires_at: i64, // unix seconds
+    pub session_id: Uuid,
+}
+
+impl AuthToken {
+    /// Create a new token for a user with a fresh session id
+    pub fn new(user_id: i32) -> Self {
+        let now = Utc::now().timestamp();
+
+        Self {
+            user_id,
+            issued_at: now,
+            expires_at: now + TOKEN_TTL_DAYS * 24 * 60 * 60,
+            session_id: Uuid::new_v4(),
+        }
+    }
+
+    fn payload(&self) -> String {
+        format!(
+            "{}.{}.{}.{}",
+            self.user_id, self.issued_at, self.expires_at, self.session_id
+        )
+    }
+
+    /// Serialize and sign the token for storing in the cookie
+    pub fn encode(&self) -> String {
+        let payload = self.payload();
+        let signature = URL_SAFE_NO_PAD.encode(sign(payload.as_bytes()));
+
+        format!("{}.{}", payload, signature)
+    }
+
+    /// Parse a token, verify its signature and check it has not expired
+    pub fn decode(value: &str) -> Result<Self> {
+        // Signature is always the last segment
+        let (payload, signature) = value
+            .rsplit_once('.')
+            .ok_or(Error::InvalidAuthTokenError)?;
+
+        let signature = URL_SAFE_NO_PAD
+            .decode(signature)
+            .map_err(|_| Error::InvalidAuthTokenError)?;
+
+        // verify_slice compares in constant time
+        let mut mac = HmacSha256::new_from_slice(token_secret())
+            .map_err(|_| Error::InvalidAuthTokenError)?;
+        mac.update(payload.as_bytes());
+        mac.verify_slice(&signature).map_err(|_| {
+            println!("->> {:<12} - auth token signature mismatch", "AUTH");
+            Error::InvalidAuthTokenError
+        })?;
+
+        let parts: Vec<&str> = payload.split('.').collect();
+        if parts.len() != 4 {
+            return Err(Error::InvalidAuthTokenError);
+        }
+
+        let token = Self {
+            user_id: parts[0].parse().map_err(|_| Error::InvalidAuthTokenError)?,
+            issued_at: parts[1].parse().map_err(|_| Error::InvalidAuthTokenError)?,
+            expires_at: parts[2].parse().map_err(|_| Error::InvalidAuthTokenError)?,
+            session_id: Uuid::parse_str(parts[3]).map_err(|_| Error::InvalidAuthTokenError)?,
+        };
+
+        if token.expires_at <= Utc::now().timestamp() {
+            println!("->> {:<12} - auth token for user {} expired", "AUTH", token.user_id);
+            return Err(Error::InvalidAuthTokenError);
+        }
+
+        Ok(token)
+    }
+}
+
+fn sign(payload: &[u8]) -> Vec<u8> {
+    // HMAC accepts keys of any length so this cannot fail
+    let mut mac = HmacSha256::new_from_slice(token_secret()).expect("HMAC accepts any key length");
+    mac.update(payload);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Read and validate the auth token from the request cookies.
+pub fn get_token_from_cookie(cookies: &Cookies) -> Result<AuthToken> {
+    let cookie = cookies.get(AUTH_COOKIE_NAME).ok_or(Error::NoAuthTokenError)?;
+
+    AuthToken::decode(cookie.value())
+}
+
+/// Helper function to extract a validated user ID from the auth cookie
+pub fn get_user_id_from_cookie(cookies: &Cookies) -> Result<i32> {
+    get_token_from_cookie(cookies).map(|token| token.user_id)
+}
@@ -0,0 +1,6 @@
+// Real-time collaborative editing.
+// Editors of a document connect to /api/document/:id/ws and exchange text operations through a room,
+// see room.rs for the protocol and ot.rs for how concurrent edits are merged.
+
+pub mod ot;
+pub mod room;
\ No newline at end of file
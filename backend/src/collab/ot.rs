@@ -0,0 +1,127 @@
+// src/collab/ot.rs
+//
+// Operational transformation for plain text edits.
+// Positions and lengths count Unicode characters, not bytes.
+// When two edits were made against the same version, transform rewrites one so it can be
+// applied after the other and every editor ends up with the same text.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, Result};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum TextOp {
+    Insert { pos: usize, text: String },
+    Delete { pos: usize, len: usize },
+}
+
+impl TextOp {
+    /// Apply the operation to the text, failing if it points outside of it
+    pub fn apply(&self, text: &mut String) -> Result<()> {
+        let char_count = text.chars().count();
+
+        match self {
+            TextOp::Insert { pos, text: inserted } => {
+                if *pos > char_count {
+                    return Err(Error::InvalidRequestFormatError);
+                }
+                text.insert_str(byte_index(text, *pos), inserted);
+            }
+            TextOp::Delete { pos, len } => {
+                if pos + len > char_count {
+                    return Err(Error::InvalidRequestFormatError);
+                }
+                let start = byte_index(text, *pos);
+                let end = byte_index(text, pos + len);
+                text.replace_range(start..end, "");
+            }
+        }
+
+        Ok(())
+    }
+
+    fn is_noop(&self) -> bool {
+        match self {
+            TextOp::Insert { text, .. } => text.is_empty(),
+            TextOp::Delete { len, .. } => *len == 0,
+        }
+    }
+}
+
+fn byte_index(text: &str, char_pos: usize) -> usize {
+    text.char_indices().nth(char_pos).map_or(text.len(), |(i, _)| i)
+}
+
+/// Rewrite `op` so it applies after `against`, both having been made on the same text.
+/// `wins_ties` decides which of two inserts at the same position goes first.
+fn transform_op(op: &TextOp, against: &TextOp, wins_ties: bool) -> Vec<TextOp> {
+    let transformed = match (op, against) {
+        (TextOp::Insert { pos, text }, TextOp::Insert { pos: other_pos, text: other_text }) => {
+            let pos = if *pos < *other_pos || (*pos == *other_pos && wins_ties) {
+                *pos
+            } else {
+                pos + other_text.chars().count()
+            };
+            vec![TextOp::Insert { pos, text: text.clone() }]
+        }
+        (TextOp::Insert { pos, text }, TextOp::Delete { pos: del_pos, len: del_len }) => {
+            // Text inserted inside a deleted range lands where the range was
+            let pos = if *pos <= *del_pos {
+                *pos
+            } else if *pos >= del_pos + del_len {
+                pos - del_len
+            } else {
+                *del_pos
+            };
+            vec![TextOp::Insert { pos, text: text.clone() }]
+        }
+        (TextOp::Delete { pos, len }, TextOp::Insert { pos: ins_pos, text }) => {
+            let ins_len = text.chars().count();
+            if *ins_pos <= *pos {
+                vec![TextOp::Delete { pos: pos + ins_len, len: *len }]
+            } else if *ins_pos >= pos + len {
+                vec![TextOp::Delete { pos: *pos, len: *len }]
+            } else {
+                // Delete around the inserted text, not through it
+                let before = ins_pos - pos;
+                vec![
+                    TextOp::Delete { pos: *pos, len: before },
+                    TextOp::Delete { pos: pos + ins_len, len: len - before },
+                ]
+            }
+        }
+        (TextOp::Delete { pos, len }, TextOp::Delete { pos: other_pos, len: other_len }) => {
+            let (start, end) = (*pos, pos + len);
+            let (other_start, other_end) = (*other_pos, other_pos + other_len);
+
+            // Characters the other delete already removed before and inside this range
+            let removed_before = start.min(other_end).saturating_sub(other_start);
+            let overlap = end.min(other_end).saturating_sub(start.max(other_start));
+
+            vec![TextOp::Delete { pos: start - removed_before, len: len - overlap }]
+        }
+    };
+
+    transformed.into_iter().filter(|op| !op.is_noop()).collect()
+}
+
+/// Transform two sequences of operations made on the same text against each other.
+/// Returns `(ops', applied')` where `ops'` applies after `applied` and `applied'` applies after `ops`.
+/// `applied` wins ties since the server accepted it first.
+pub fn transform(ops: &[TextOp], applied: &[TextOp]) -> (Vec<TextOp>, Vec<TextOp>) {
+    match (ops, applied) {
+        ([], _) | (_, []) => (ops.to_vec(), applied.to_vec()),
+        ([op], [other]) => (transform_op(op, other, false), transform_op(other, op, true)),
+        ([first, rest @ ..], _) if !rest.is_empty() => {
+            let (first, applied) = transform(std::slice::from_ref(first), applied);
+            let (rest, applied) = transform(rest, &applied);
+            ([first, rest].concat(), applied)
+        }
+        (_, [first, rest @ ..]) => {
+            let (ops, first) = transform(ops, std::slice::from_ref(first));
+            let (ops, rest) = transform(&ops, rest);
+            (ops, [first, rest].concat())
+        }
+    }
+}
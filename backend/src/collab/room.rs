@@ -0,0 +1,330 @@
+// src/collab/room.rs
+//
+// One room per document being edited live.
+// The room holds the authoritative text, orders incoming edits, transforms them against edits the
+// sender had not seen yet and broadcasts the result to every connected client.
+// A background task writes the merged text back to the database while the room has unsaved edits.
+// If the document was saved outside the room in the meantime, the room reloads it and clients start over.
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::collab::ot::{transform, TextOp};
+use crate::db::revision::{enforce_storage_quota, get_document_owner, record_revision};
+use crate::models::revision::RevisionOrigin;
+use crate::rag::embed::reindex_document;
+use crate::{Error, Result};
+
+/// How often a room with unsaved edits is written back to the database
+const PERSIST_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Edits kept for transforming late operations, clients further behind must resync
+const HISTORY_LIMIT: usize = 500;
+
+/// Messages buffered per client before a slow client starts missing updates and must resync
+const BROADCAST_CAPACITY: usize = 256;
+
+/// Messages sent by a client
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    /// Edits made on top of `base_version`
+    Op { base_version: u64, ops: Vec<TextOp> },
+    /// Ask for the full text again, e.g. after falling too far behind
+    Resync,
+}
+
+/// Messages sent to clients
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    /// Full text, sent when joining and on resync
+    Init { client_id: Uuid, version: u64, content: String, read_only: bool },
+    /// Edits accepted by the server, already transformed to apply on top of `version - 1`
+    Op { client_id: Uuid, user_id: i32, version: u64, ops: Vec<TextOp> },
+    Error { message: String },
+    /// The room reloaded the document from the database, each client is sent a fresh init instead
+    #[serde(skip_serializing)]
+    Reloaded,
+}
+
+struct RoomState {
+    content: String,
+    version: u64,
+    history: VecDeque<Vec<TextOp>>, // Most recent edits, the last one produced `version`
+    dirty: bool,
+    last_editor: Option<i32>,
+    connections: usize,
+    saved_version: i32, // documents.version the room last loaded or saved
+    unindexed: bool,    // Saved since the document was last indexed for search
+}
+
+pub struct Room {
+    document_id: i32,
+    state: Mutex<RoomState>,
+    sender: broadcast::Sender<ServerMessage>,
+}
+
+impl Room {
+    /// Receive every edit accepted from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<ServerMessage> {
+        self.sender.subscribe()
+    }
+
+    /// Snapshot of the current text for a client
+    pub fn init_message(&self, client_id: Uuid, read_only: bool) -> ServerMessage {
+        let state = self.state.lock().unwrap();
+        ServerMessage::Init {
+            client_id,
+            version: state.version,
+            content: state.content.clone(),
+            read_only,
+        }
+    }
+
+    /// Transform, apply and broadcast edits a client made on top of `base_version`
+    pub fn apply(&self, client_id: Uuid, user_id: i32, base_version: u64, ops: Vec<TextOp>) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+
+        if base_version > state.version {
+            return Err(Error::InvalidRequestFormatError);
+        }
+
+        // Bring the edits up to date with everything accepted since the client's version
+        let mut ops = ops;
+        let missed = (state.version - base_version) as usize;
+        if missed > state.history.len() {
+            return Err(Error::InvalidRequestFormatError);
+        }
+        for applied in state.history.iter().skip(state.history.len() - missed) {
+            ops = transform(&ops, applied).0;
+        }
+
+        // Apply to a copy so a bad operation leaves the text untouched
+        let mut content = state.content.clone();
+        for op in &ops {
+            op.apply(&mut content)?;
+        }
+
+        state.content = content;
+        state.version += 1;
+        state.dirty = true;
+        state.last_editor = Some(user_id);
+
+        let version = state.version;
+        state.history.push_back(ops.clone());
+        if state.history.len() > HISTORY_LIMIT {
+            state.history.pop_front();
+        }
+
+        // Nobody listening is fine, the edit is still saved
+        let _ = self.sender.send(ServerMessage::Op { client_id, user_id, version, ops });
+
+        Ok(())
+    }
+
+    /// Write the merged text back to the document if it has unsaved edits
+    async fn persist(&self, pool: &PgPool) -> Result<()> {
+        let (content, author_id, saved_version) = {
+            let mut state = self.state.lock().unwrap();
+            match (state.dirty, state.last_editor) {
+                (true, Some(author_id)) => {
+                    state.dirty = false;
+                    (state.content.clone(), author_id, state.saved_version)
+                }
+                _ => return Ok(()),
+            }
+        };
+
+        match self.save(pool, &content, author_id, saved_version).await {
+            Ok(version) => {
+                let mut state = self.state.lock().unwrap();
+                state.saved_version = version;
+                state.unindexed = true;
+                Ok(())
+            }
+            // Saved outside the room since it was loaded, theirs wins over the room's unsaved edits
+            Err(Error::DocumentConflictError { .. }) => self.reload(pool).await,
+            Err(e) => {
+                // Try again on the next tick
+                self.state.lock().unwrap().dirty = true;
+                Err(e)
+            }
+        }
+    }
+
+    /// Save the text if the document is still at `saved_version`, returns the version it is at now
+    async fn save(&self, pool: &PgPool, content: &str, author_id: i32, saved_version: i32) -> Result<i32> {
+        let document_id = self.document_id;
+
+        let owner_id = get_document_owner(pool, document_id).await?;
+        let mut tx = pool.begin().await.map_err(|_| Error::DatabaseError)?;
+
+        // The stale embedding is replaced when the room closes
+        let updated = sqlx::query!(
+            "UPDATE documents
+             SET content = $1, updated_at = $2, embedding_updated_at = NULL, version = version + 1
+             WHERE id = $3 AND version = $4
+             RETURNING version",
+            content,
+            Utc::now().naive_utc(),
+            document_id,
+            saved_version
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|_| Error::DocumentUpdateError { document_id })?;
+
+        let Some(updated) = updated else {
+            let current = sqlx::query!("SELECT version FROM documents WHERE id = $1", document_id)
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(|_| Error::DatabaseError)?
+                .ok_or(Error::DocumentNotFoundError { document_id })?;
+            return Err(Error::DocumentConflictError { document_id, current_version: current.version });
+        };
+
+        record_revision(&mut tx, document_id, author_id, content, RevisionOrigin::Manual).await?;
+
+        enforce_storage_quota(&mut tx, owner_id, document_id).await?;
+        tx.commit().await.map_err(|_| Error::DatabaseError)?;
+
+        Ok(updated.version)
+    }
+
+    /// Replace the text with what is in the database and make every client start over from it
+    async fn reload(&self, pool: &PgPool) -> Result<()> {
+        let document_id = self.document_id;
+
+        let document = sqlx::query!("SELECT content, version FROM documents WHERE id = $1", document_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|_| Error::DatabaseError)?
+            .ok_or(Error::DocumentNotFoundError { document_id })?;
+
+        {
+            let mut state = self.state.lock().unwrap();
+            state.content = document.content.unwrap_or_default();
+            state.saved_version = document.version;
+            state.dirty = false;
+
+            // Edits based on the old text can no longer be transformed, their senders get an error and resync
+            state.version += 1;
+            state.history.clear();
+        }
+
+        println!("->> {:<12} - document {} was saved elsewhere, reloaded its room", "COLLAB", document_id);
+        let _ = self.sender.send(ServerMessage::Reloaded);
+
+        Ok(())
+    }
+}
+
+/// All open rooms, shared by the WebSocket handlers
+#[derive(Clone, Default)]
+pub struct CollabHub {
+    rooms: Arc<Mutex<HashMap<i32, Arc<Room>>>>,
+}
+
+impl CollabHub {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Join the room of a document, opening it from the database if nobody is editing it yet.
+    /// Every join must be matched by a call to leave.
+    pub async fn join(&self, pool: &PgPool, document_id: i32) -> Result<Arc<Room>> {
+        if let Some(room) = self.join_open_room(document_id) {
+            return Ok(room);
+        }
+
+        let document = sqlx::query!("SELECT content, version FROM documents WHERE id = $1", document_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|_| Error::DatabaseError)?
+            .ok_or(Error::DocumentNotFoundError { document_id })?;
+
+        let mut rooms = self.rooms.lock().unwrap();
+
+        // Someone else may have opened the room while the document was loading
+        if let Some(room) = rooms.get(&document_id) {
+            room.state.lock().unwrap().connections += 1;
+            return Ok(room.clone());
+        }
+
+        let (sender, _) = broadcast::channel(BROADCAST_CAPACITY);
+        let room = Arc::new(Room {
+            document_id,
+            state: Mutex::new(RoomState {
+                content: document.content.unwrap_or_default(),
+                version: 0,
+                history: VecDeque::new(),
+                dirty: false,
+                last_editor: None,
+                connections: 1,
+                saved_version: document.version,
+                unindexed: false,
+            }),
+            sender,
+        });
+        rooms.insert(document_id, room.clone());
+
+        tokio::spawn(self.clone().run_persistence(pool.clone(), room.clone()));
+
+        Ok(room)
+    }
+
+    fn join_open_room(&self, document_id: i32) -> Option<Arc<Room>> {
+        let rooms = self.rooms.lock().unwrap();
+        let room = rooms.get(&document_id)?;
+        room.state.lock().unwrap().connections += 1;
+        Some(room.clone())
+    }
+
+    /// Leave a room, it is closed by its persistence task once the last edits are saved
+    pub fn leave(&self, room: &Room) {
+        let mut state = room.state.lock().unwrap();
+        state.connections = state.connections.saturating_sub(1);
+    }
+
+    // Periodically save the room, and close it once everyone has left and everything is saved.
+    // Failed saves are retried on every tick, a room is only given up on once its document is gone.
+    async fn run_persistence(self, pool: PgPool, room: Arc<Room>) {
+        let mut interval = tokio::time::interval(PERSIST_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            let persisted = room.persist(&pool).await;
+            if let Err(e) = &persisted {
+                println!("->> {:<12} - failed to save document {}: {:?}", "COLLAB", room.document_id, e);
+            }
+            let deleted = matches!(persisted, Err(Error::DocumentNotFoundError { .. }));
+
+            // Check under the hub lock so nobody can join a room that is being closed
+            let reindex = {
+                let mut rooms = self.rooms.lock().unwrap();
+                let state = room.state.lock().unwrap();
+                if state.connections > 0 || (state.dirty && !deleted) {
+                    continue;
+                }
+
+                rooms.remove(&room.document_id);
+                println!("->> {:<12} - closed room for document {}", "COLLAB", room.document_id);
+                (state.unindexed && !deleted).then(|| state.content.clone())
+            };
+
+            // Search the text the room saved, once rather than on every save
+            if let Some(content) = reindex {
+                reindex_document(&pool, room.document_id, &content).await;
+            }
+            break;
+        }
+    }
+}
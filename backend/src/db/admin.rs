@@ -0,0 +1,332 @@
+// src/db/admin.rs
+//
+// User administration.
+// Admins look users up with their limits and usage, change their credits, plan and limits, and disable
+// or re-enable their accounts. Each change is written to admin_audit_log in the same transaction as the
+// change itself, with the values before and after, so the log never misses or invents a change.
+// Disabling an account revokes all of its sessions, and touch_session and login refuse it until it is
+// enabled again.
+
+use chrono::Utc;
+use serde_json::{json, Value};
+use sqlx::types::Json;
+use sqlx::{PgConnection, PgPool};
+
+use crate::models::admin::{
+    AdminAction, AdminAuditEntry, AdminUserInfo, UpdateUserLimitsPayload, UserCounts, UserStorageUsage,
+};
+use crate::{Error, Result};
+
+/// Users with their limits and usage, by id.
+/// `search` matches name or email, `disabled` only returns disabled (true) or active (false) accounts.
+pub async fn list_users(
+    pool: &PgPool,
+    search: Option<&str>,
+    disabled: Option<bool>,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<AdminUserInfo>> {
+    query_users(pool, None, search, disabled, limit, offset).await
+}
+
+/// A single user with their limits and usage.
+pub async fn get_user(pool: &PgPool, user_id: i32) -> Result<AdminUserInfo> {
+    query_users(pool, Some(user_id), None, None, 1, 0)
+        .await?
+        .pop()
+        .ok_or(Error::UserNotFoundError { user_id })
+}
+
+async fn query_users(
+    pool: &PgPool,
+    user_id: Option<i32>,
+    search: Option<&str>,
+    disabled: Option<bool>,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<AdminUserInfo>> {
+    let pattern = search.map(|search| format!("%{}%", search));
+
+    sqlx::query_as!(
+        AdminUserInfo,
+        r#"SELECT u.id, u.name, u.email, u.is_admin, u.disabled_at, u.plan, u.ai_credits,
+                  u.max_projects, u.max_documents, u.storage_quota_bytes,
+                  (SELECT COUNT(*) FROM project_permissions pp
+                   WHERE pp.user_id = u.id AND pp.role = 'owner') as "project_count!",
+                  (SELECT COUNT(*) FROM document_permissions dp
+                   WHERE dp.user_id = u.id AND dp.role = 'owner') as "document_count!",
+                  COALESCE((SELECT SUM(LENGTH(COALESCE(d.content, '')))
+                            FROM documents d
+                            JOIN document_permissions dp ON d.id = dp.document_id
+                            WHERE dp.user_id = u.id AND dp.role = 'owner'), 0)
+                + COALESCE((SELECT SUM(LENGTH(r.content))
+                            FROM document_revisions r
+                            JOIN document_permissions dp ON r.document_id = dp.document_id
+                            WHERE dp.user_id = u.id AND dp.role = 'owner'), 0) as "storage_bytes!"
+           FROM users u
+           WHERE ($1::INT IS NULL OR u.id = $1)
+             AND ($2::TEXT IS NULL OR u.name ILIKE $2 OR u.email ILIKE $2)
+             AND ($3::BOOLEAN IS NULL OR (u.disabled_at IS NOT NULL) = $3)
+           ORDER BY u.id
+           LIMIT $4 OFFSET $5"#,
+        user_id,
+        pattern,
+        disabled,
+        limit,
+        offset
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|_| Error::DatabaseError)
+}
+
+/// Add `delta` credits to the user's balance, which cannot go below zero.
+/// Returns the new balance.
+pub async fn adjust_credits(
+    pool: &PgPool,
+    admin_id: i32,
+    user_id: i32,
+    delta: i32,
+    reason: Option<&str>,
+) -> Result<i32> {
+    let mut tx = pool.begin().await.map_err(|_| Error::DatabaseError)?;
+
+    let user = sqlx::query!("SELECT ai_credits FROM users WHERE id = $1 FOR UPDATE", user_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|_| Error::DatabaseError)?
+        .ok_or(Error::UserNotFoundError { user_id })?;
+
+    let balance = match user.ai_credits.checked_add(delta) {
+        Some(balance) if balance >= 0 => balance,
+        _ => {
+            println!("->> {:<12} - cannot change {} credits by {}", "ADMIN", user.ai_credits, delta);
+            return Err(Error::InvalidRequestFormatError);
+        }
+    };
+
+    sqlx::query!("UPDATE users SET ai_credits = $1 WHERE id = $2", balance, user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|_| Error::UserUpdateError { user_id })?;
+
+    let details = json!({ "delta": delta, "before": user.ai_credits, "after": balance, "reason": reason });
+    record_action(&mut tx, admin_id, AdminAction::AdjustCredits, user_id, details).await?;
+
+    tx.commit().await.map_err(|_| Error::DatabaseError)?;
+
+    Ok(balance)
+}
+
+/// Change the user's plan, project and document limits and storage quota, keeping what the payload leaves out.
+pub async fn update_limits(
+    pool: &PgPool,
+    admin_id: i32,
+    user_id: i32,
+    payload: &UpdateUserLimitsPayload,
+) -> Result<()> {
+    let negative = payload.max_projects.is_some_and(|max| max < 0)
+        || payload.max_documents.is_some_and(|max| max < 0)
+        || payload.storage_quota_bytes.flatten().is_some_and(|quota| quota < 0);
+    if negative {
+        return Err(Error::InvalidRequestFormatError);
+    }
+
+    let mut tx = pool.begin().await.map_err(|_| Error::DatabaseError)?;
+
+    let before = sqlx::query!(
+        "SELECT plan, max_projects, max_documents, storage_quota_bytes FROM users WHERE id = $1 FOR UPDATE",
+        user_id
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|_| Error::DatabaseError)?
+    .ok_or(Error::UserNotFoundError { user_id })?;
+
+    if let Some(plan) = &payload.plan {
+        let exists = sqlx::query!("SELECT name FROM plans WHERE name = $1", plan)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|_| Error::DatabaseError)?;
+        if exists.is_none() {
+            println!("->> {:<12} - unknown plan {}", "ADMIN", plan);
+            return Err(Error::InvalidRequestFormatError);
+        }
+    }
+
+    let plan = payload.plan.clone().unwrap_or_else(|| before.plan.clone());
+    let max_projects = payload.max_projects.unwrap_or(before.max_projects);
+    let max_documents = payload.max_documents.unwrap_or(before.max_documents);
+    let storage_quota_bytes = payload.storage_quota_bytes.unwrap_or(before.storage_quota_bytes);
+
+    sqlx::query!(
+        "UPDATE users SET plan = $1, max_projects = $2, max_documents = $3, storage_quota_bytes = $4 WHERE id = $5",
+        plan,
+        max_projects,
+        max_documents,
+        storage_quota_bytes,
+        user_id
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|_| Error::UserUpdateError { user_id })?;
+
+    let details = json!({
+        "before": {
+            "plan": before.plan,
+            "max_projects": before.max_projects,
+            "max_documents": before.max_documents,
+            "storage_quota_bytes": before.storage_quota_bytes,
+        },
+        "after": {
+            "plan": plan,
+            "max_projects": max_projects,
+            "max_documents": max_documents,
+            "storage_quota_bytes": storage_quota_bytes,
+        },
+        "reason": payload.reason,
+    });
+    record_action(&mut tx, admin_id, AdminAction::UpdateLimits, user_id, details).await?;
+
+    tx.commit().await.map_err(|_| Error::DatabaseError)?;
+
+    Ok(())
+}
+
+/// Disable the account and revoke all its sessions, or enable it again.
+/// Admins cannot disable themselves. Returns the number of sessions revoked.
+pub async fn set_account_disabled(
+    pool: &PgPool,
+    admin_id: i32,
+    user_id: i32,
+    disabled: bool,
+    reason: Option<&str>,
+) -> Result<u64> {
+    if disabled && admin_id == user_id {
+        println!("->> {:<12} - admin {} tried to disable their own account", "ADMIN", admin_id);
+        return Err(Error::InvalidRequestFormatError);
+    }
+
+    let mut tx = pool.begin().await.map_err(|_| Error::DatabaseError)?;
+
+    let before = sqlx::query!("SELECT disabled_at FROM users WHERE id = $1 FOR UPDATE", user_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|_| Error::DatabaseError)?
+        .ok_or(Error::UserNotFoundError { user_id })?;
+
+    // Disabling again keeps the original time
+    let disabled_at = if disabled { Some(before.disabled_at.unwrap_or_else(|| Utc::now().naive_utc())) } else { None };
+
+    sqlx::query!("UPDATE users SET disabled_at = $1 WHERE id = $2", disabled_at, user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|_| Error::UserUpdateError { user_id })?;
+
+    let revoked = if disabled {
+        sqlx::query!("DELETE FROM user_sessions WHERE user_id = $1", user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|_| Error::DatabaseError)?
+            .rows_affected()
+    } else {
+        0
+    };
+
+    let (action, details) = if disabled {
+        (AdminAction::DisableUser, json!({ "revoked_sessions": revoked, "reason": reason }))
+    } else {
+        (AdminAction::EnableUser, json!({ "disabled_at": before.disabled_at, "reason": reason }))
+    };
+    record_action(&mut tx, admin_id, action, user_id, details).await?;
+
+    tx.commit().await.map_err(|_| Error::DatabaseError)?;
+
+    Ok(revoked)
+}
+
+/// Write an admin's change to the audit log, inside the transaction making the change.
+async fn record_action(
+    conn: &mut PgConnection,
+    admin_id: i32,
+    action: AdminAction,
+    target_user_id: i32,
+    details: Value,
+) -> Result<()> {
+    sqlx::query!(
+        "INSERT INTO admin_audit_log (admin_id, action, target_user_id, details) VALUES ($1, $2, $3, $4)",
+        admin_id,
+        action as AdminAction,
+        target_user_id,
+        Json(details) as _
+    )
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| {
+        println!("->> {:<12} - failed to record {:?} by admin {}: {:?}", "ERROR", action, admin_id, e);
+        Error::DatabaseError
+    })?;
+
+    println!("->> {:<12} - admin {} {:?} user {}", "ADMIN", admin_id, action, target_user_id);
+    Ok(())
+}
+
+/// The audit log, newest first, optionally for one user and only entries older than the id `before`
+pub async fn list_audit_log(
+    pool: &PgPool,
+    target_user_id: Option<i32>,
+    before: Option<i32>,
+    limit: i64,
+) -> Result<Vec<AdminAuditEntry>> {
+    sqlx::query_as!(
+        AdminAuditEntry,
+        r#"SELECT a.id, a.admin_id, u.name as "admin_name?", a.action as "action: AdminAction",
+                  a.target_user_id, a.details as "details: Json<Value>", a.created_at
+           FROM admin_audit_log a
+           LEFT JOIN users u ON a.admin_id = u.id
+           WHERE ($1::INT IS NULL OR a.target_user_id = $1) AND ($2::INT IS NULL OR a.id < $2)
+           ORDER BY a.id DESC
+           LIMIT $3"#,
+        target_user_id,
+        before,
+        limit
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|_| Error::DatabaseError)
+}
+
+/// Number of accounts, admins and disabled accounts
+pub async fn count_users(pool: &PgPool) -> Result<UserCounts> {
+    sqlx::query_as!(
+        UserCounts,
+        r#"SELECT COUNT(*) as "total!",
+                  COUNT(*) FILTER (WHERE is_admin) as "admins!",
+                  COUNT(*) FILTER (WHERE disabled_at IS NOT NULL) as "disabled!"
+           FROM users"#
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|_| Error::DatabaseError)
+}
+
+/// The users using the most storage, largest first
+pub async fn largest_storage_users(pool: &PgPool, limit: i64) -> Result<Vec<UserStorageUsage>> {
+    sqlx::query_as!(
+        UserStorageUsage,
+        r#"SELECT u.id as user_id, u.name, u.email, u.storage_quota_bytes,
+                  COALESCE(SUM(LENGTH(COALESCE(d.content, ''))
+                      + COALESCE((SELECT SUM(LENGTH(r.content)) FROM document_revisions r WHERE r.document_id = d.id), 0)), 0)::BIGINT
+                      as "storage_bytes!"
+           FROM users u
+           JOIN document_permissions dp ON dp.user_id = u.id AND dp.role = 'owner'
+           JOIN documents d ON d.id = dp.document_id
+           GROUP BY u.id
+           ORDER BY "storage_bytes!" DESC, u.id
+           LIMIT $1"#,
+        limit
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|_| Error::DatabaseError)
+}
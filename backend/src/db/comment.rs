@@ -0,0 +1,252 @@
+// src/db/comment.rs
+//
+// Comments and threads on documents.
+// A thread is its first comment, anchored to a range of the document, and every reply points back at it.
+// Mentions are stored per comment and limited to users who can open the document, so mentioning someone
+// never reveals the discussion to a user without access.
+
+use sqlx::{PgPool, Postgres, Transaction};
+
+use crate::models::comment::{CommentThread, DocumentComment};
+use crate::{Error, Result};
+
+/// Longest comment accepted, in characters
+const MAX_COMMENT_CHARS: usize = 10_000;
+
+/// Trim a comment and make sure it is neither empty nor too long
+pub fn validate_content(content: &str) -> Result<String> {
+    let content = content.trim();
+    if content.is_empty() || content.chars().count() > MAX_COMMENT_CHARS {
+        return Err(Error::InvalidRequestFormatError);
+    }
+    Ok(content.to_string())
+}
+
+/// Every comment on a document, oldest first.
+pub async fn list_comments(pool: &PgPool, document_id: i32) -> Result<Vec<DocumentComment>> {
+    sqlx::query_as!(
+        DocumentComment,
+        r#"SELECT c.id, c.document_id, c.thread_id, c.author_id, u.name as "author_name?", c.content,
+                  c.anchor_start, c.anchor_end, c.quoted_text, c.resolved_at, c.resolved_by,
+                  c.created_at, c.updated_at,
+                  ARRAY(SELECT m.user_id FROM comment_mentions m WHERE m.comment_id = c.id ORDER BY m.user_id) as "mentions!"
+           FROM document_comments c
+           LEFT JOIN users u ON c.author_id = u.id
+           WHERE c.document_id = $1
+           ORDER BY c.created_at ASC, c.id ASC"#,
+        document_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|_| Error::DatabaseError)
+}
+
+/// A single comment. It must belong to the document, so access to one document never exposes another's comments.
+pub async fn get_comment(pool: &PgPool, document_id: i32, comment_id: i32) -> Result<DocumentComment> {
+    sqlx::query_as!(
+        DocumentComment,
+        r#"SELECT c.id, c.document_id, c.thread_id, c.author_id, u.name as "author_name?", c.content,
+                  c.anchor_start, c.anchor_end, c.quoted_text, c.resolved_at, c.resolved_by,
+                  c.created_at, c.updated_at,
+                  ARRAY(SELECT m.user_id FROM comment_mentions m WHERE m.comment_id = c.id ORDER BY m.user_id) as "mentions!"
+           FROM document_comments c
+           LEFT JOIN users u ON c.author_id = u.id
+           WHERE c.id = $1 AND c.document_id = $2"#,
+        comment_id,
+        document_id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|_| Error::DatabaseError)?
+    .ok_or(Error::CommentNotFoundError { comment_id })
+}
+
+/// Group a document's comments into threads, in the order the threads were started.
+pub fn group_threads(comments: Vec<DocumentComment>) -> Vec<CommentThread> {
+    let mut threads: Vec<CommentThread> = Vec::new();
+    let mut replies: Vec<DocumentComment> = Vec::new();
+
+    for comment in comments {
+        if comment.thread_id.is_some() {
+            replies.push(comment);
+        } else {
+            threads.push(CommentThread { comment, replies: Vec::new() });
+        }
+    }
+
+    // Replies are already oldest first, so pushing keeps each thread in order
+    for reply in replies {
+        if let Some(thread) = threads.iter_mut().find(|t| Some(t.comment.id) == reply.thread_id) {
+            thread.replies.push(reply);
+        }
+    }
+
+    threads
+}
+
+/// Replace the mentions of a comment, dropping users without access to the document
+async fn save_mentions(
+    tx: &mut Transaction<'_, Postgres>,
+    document_id: i32,
+    comment_id: i32,
+    mentions: &[i32],
+) -> Result<()> {
+    sqlx::query!("DELETE FROM comment_mentions WHERE comment_id = $1", comment_id)
+        .execute(&mut **tx)
+        .await
+        .map_err(|_| Error::DatabaseError)?;
+
+    if mentions.is_empty() {
+        return Ok(());
+    }
+
+    sqlx::query!(
+        "INSERT INTO comment_mentions (comment_id, user_id)
+         SELECT $1, a.user_id FROM document_access a
+         WHERE a.document_id = $2 AND a.user_id = ANY($3)
+         ON CONFLICT DO NOTHING",
+        comment_id,
+        document_id,
+        mentions
+    )
+    .execute(&mut **tx)
+    .await
+    .map_err(|_| Error::DatabaseError)?;
+
+    Ok(())
+}
+
+/// Start a thread anchored to a range of the document. Returns the new comment's id.
+pub async fn create_thread(
+    pool: &PgPool,
+    document_id: i32,
+    author_id: i32,
+    content: &str,
+    anchor: (i32, i32),
+    quoted_text: &str,
+    mentions: &[i32],
+) -> Result<i32> {
+    let (anchor_start, anchor_end) = anchor;
+    if anchor_start < 0 || anchor_end < anchor_start {
+        return Err(Error::InvalidRequestFormatError);
+    }
+
+    let mut tx = pool.begin().await.map_err(|_| Error::DatabaseError)?;
+
+    let comment = sqlx::query!(
+        "INSERT INTO document_comments (document_id, author_id, content, anchor_start, anchor_end, quoted_text)
+         VALUES ($1, $2, $3, $4, $5, $6)
+         RETURNING id",
+        document_id,
+        author_id,
+        content,
+        anchor_start,
+        anchor_end,
+        quoted_text
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        println!("->> {:<12} - failed to create comment on document {}: {:?}", "ERROR", document_id, e);
+        Error::DatabaseError
+    })?;
+
+    save_mentions(&mut tx, document_id, comment.id, mentions).await?;
+    tx.commit().await.map_err(|_| Error::DatabaseError)?;
+
+    Ok(comment.id)
+}
+
+/// Reply to a thread. Replying to a reply adds to the same thread. Returns the new comment's id.
+pub async fn create_reply(
+    pool: &PgPool,
+    document_id: i32,
+    thread: &DocumentComment,
+    author_id: i32,
+    content: &str,
+    mentions: &[i32],
+) -> Result<i32> {
+    let thread_id = thread.thread_id.unwrap_or(thread.id);
+
+    let mut tx = pool.begin().await.map_err(|_| Error::DatabaseError)?;
+
+    let comment = sqlx::query!(
+        "INSERT INTO document_comments (document_id, thread_id, author_id, content)
+         VALUES ($1, $2, $3, $4)
+         RETURNING id",
+        document_id,
+        thread_id,
+        author_id,
+        content
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        println!("->> {:<12} - failed to reply to comment {}: {:?}", "ERROR", thread_id, e);
+        Error::DatabaseError
+    })?;
+
+    save_mentions(&mut tx, document_id, comment.id, mentions).await?;
+    tx.commit().await.map_err(|_| Error::DatabaseError)?;
+
+    Ok(comment.id)
+}
+
+/// Change the text and mentions of a comment.
+pub async fn update_comment(
+    pool: &PgPool,
+    document_id: i32,
+    comment_id: i32,
+    content: &str,
+    mentions: &[i32],
+) -> Result<()> {
+    let mut tx = pool.begin().await.map_err(|_| Error::DatabaseError)?;
+
+    sqlx::query!(
+        "UPDATE document_comments SET content = $1, updated_at = CURRENT_TIMESTAMP
+         WHERE id = $2 AND document_id = $3",
+        content,
+        comment_id,
+        document_id
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|_| Error::DatabaseError)?;
+
+    save_mentions(&mut tx, document_id, comment_id, mentions).await?;
+    tx.commit().await.map_err(|_| Error::DatabaseError)?;
+
+    Ok(())
+}
+
+/// Resolve a thread as `resolved_by`, or reopen it when None.
+pub async fn set_resolved(pool: &PgPool, document_id: i32, thread_id: i32, resolved_by: Option<i32>) -> Result<()> {
+    sqlx::query!(
+        "UPDATE document_comments
+         SET resolved_at = CASE WHEN $1::INT IS NULL THEN NULL ELSE CURRENT_TIMESTAMP END,
+             resolved_by = $1
+         WHERE id = $2 AND document_id = $3 AND thread_id IS NULL",
+        resolved_by,
+        thread_id,
+        document_id
+    )
+    .execute(pool)
+    .await
+    .map_err(|_| Error::DatabaseError)?;
+
+    Ok(())
+}
+
+/// Delete a comment. Deleting the first comment of a thread deletes its replies too.
+pub async fn delete_comment(pool: &PgPool, document_id: i32, comment_id: i32) -> Result<()> {
+    sqlx::query!(
+        "DELETE FROM document_comments WHERE id = $1 AND document_id = $2",
+        comment_id,
+        document_id
+    )
+    .execute(pool)
+    .await
+    .map_err(|_| Error::DatabaseError)?;
+
+    Ok(())
+}
@@ -0,0 +1,384 @@
+// src/db/credit.rs
+//
+// AI credits, token usage and plans.
+// An operation reserves credits before calling the model, which takes them from the balance right away
+// so concurrent requests cannot spend the same credit twice. When the operation is done the reservation is
+// committed if it succeeded, charging one credit per TOKENS_PER_CREDIT tokens it used, and released,
+// returning the credits, if it failed. The tokens are recorded either way and count towards the monthly
+// token budget of the user's plan. The plan also tops the balance up on a schedule, which happens the next
+// time the user spends credits after a refill is due.
+// Reservations that were never settled, because the server stopped mid-request, are released after
+// RESERVATION_TIMEOUT_MINUTES the same way.
+
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, Utc};
+use sqlx::PgPool;
+
+use crate::models::credit::{AiCreditLedgerEntry, AiCreditStatus, AiOperation, AiUsageSummary, Plan};
+use crate::rag::provider::TokenUsage;
+use crate::{Error, Result};
+
+/// No AI operation runs this long, an older reservation belongs to a request that was lost
+const RESERVATION_TIMEOUT_MINUTES: i64 = 15;
+
+/// Tokens covered by one credit, every operation costs at least one
+const TOKENS_PER_CREDIT: i32 = 1000;
+
+/// Credits held for an operation in progress, settle with commit_credits or release_credits
+#[derive(Debug)]
+pub struct CreditReservation {
+    pub id: i32,
+    pub user_id: i32,
+    pub amount: i32,
+}
+
+/// Credits charged for an operation that used `usage`
+pub fn credits_for(usage: TokenUsage) -> i32 {
+    ((usage.total() + TOKENS_PER_CREDIT - 1) / TOKENS_PER_CREDIT).max(1)
+}
+
+/// Start of the calendar month token budgets are counted in
+pub fn period_start(now: NaiveDateTime) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(now.year(), now.month(), 1)
+        .and_then(|day| day.and_hms_opt(0, 0, 0))
+        .unwrap_or(now)
+}
+
+/// Take `amount` credits from the user for `operation`.
+/// Fails if the balance is too low or the plan's token budget for this month is used up.
+pub async fn reserve_credits(
+    pool: &PgPool,
+    user_id: i32,
+    operation: AiOperation,
+    amount: i32,
+) -> Result<CreditReservation> {
+    release_expired_reservations(pool, user_id).await?;
+    refill_credits_if_due(pool, user_id).await?;
+    ensure_token_budget(pool, user_id).await?;
+
+    let mut tx = pool.begin().await.map_err(|_| Error::DatabaseError)?;
+
+    let balance = sqlx::query!(
+        "UPDATE users SET ai_credits = ai_credits - $1 WHERE id = $2 AND ai_credits >= $1 RETURNING ai_credits",
+        amount,
+        user_id
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|_| Error::DatabaseError)?;
+
+    let Some(balance) = balance else {
+        println!("->> {:<12} - User {} has insufficient AI credits for {:?}", "CREDIT_CHECK", user_id, operation);
+        return Err(Error::InsufficientAiCredits);
+    };
+
+    let entry = sqlx::query!(
+        "INSERT INTO ai_credit_ledger (user_id, operation, amount) VALUES ($1, $2, $3) RETURNING id",
+        user_id,
+        operation as AiOperation,
+        amount
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|_| Error::DatabaseError)?;
+
+    tx.commit().await.map_err(|_| Error::DatabaseError)?;
+
+    println!("->> {:<12} - Reserved {} AI credits for user {}. Remaining: {}", "CREDIT_CHECK", amount, user_id, balance.ai_credits);
+    Ok(CreditReservation { id: entry.id, user_id, amount })
+}
+
+/// Charge the operation for the tokens it used.
+/// Credits beyond the reservation are taken from the balance as far as it goes, it never drops below zero,
+/// and unused reserved credits are given back. The ledger records what was actually deducted.
+pub async fn commit_credits(pool: &PgPool, reservation: &CreditReservation, usage: TokenUsage) -> Result<()> {
+    let charge = credits_for(usage);
+    let mut tx = pool.begin().await.map_err(|_| Error::DatabaseError)?;
+
+    // Only once, a reservation that was already settled keeps its outcome
+    let reserved = sqlx::query!(
+        "SELECT id FROM ai_credit_ledger WHERE id = $1 AND status = 'reserved' FOR UPDATE",
+        reservation.id
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|_| Error::DatabaseError)?;
+    if reserved.is_none() {
+        return Ok(());
+    }
+
+    let balance = sqlx::query!("SELECT ai_credits FROM users WHERE id = $1 FOR UPDATE", reservation.user_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|_| Error::DatabaseError)?
+        .ai_credits;
+
+    // Positive takes more credits, negative gives part of the reservation back
+    let adjustment = (charge - reservation.amount).min(balance.max(0));
+    if adjustment != 0 {
+        sqlx::query!(
+            "UPDATE users SET ai_credits = ai_credits - $1 WHERE id = $2",
+            adjustment,
+            reservation.user_id
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|_| Error::DatabaseError)?;
+    }
+    let deducted = reservation.amount + adjustment;
+
+    sqlx::query!(
+        "UPDATE ai_credit_ledger
+         SET status = $1, amount = $2, prompt_tokens = $3, completion_tokens = $4, tokens_estimated = $5, settled_at = $6
+         WHERE id = $7",
+        AiCreditStatus::Committed as AiCreditStatus,
+        deducted,
+        usage.prompt_tokens,
+        usage.completion_tokens,
+        usage.estimated,
+        Utc::now().naive_utc(),
+        reservation.id
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|_| Error::DatabaseError)?;
+
+    tx.commit().await.map_err(|_| Error::DatabaseError)?;
+
+    if deducted < charge {
+        println!("->> {:<12} - User {} could only pay {} of {} AI credits", "CREDIT_CHECK", reservation.user_id, deducted, charge);
+    }
+    println!("->> {:<12} - Charged {} AI credits for {} tokens to user {}", "CREDIT_CHECK", deducted, usage.total(), reservation.user_id);
+    Ok(())
+}
+
+/// Give the reserved credits back, recording the tokens used and why the operation failed (the error variant name).
+pub async fn release_credits(
+    pool: &PgPool,
+    reservation: &CreditReservation,
+    usage: TokenUsage,
+    failure: &str,
+) -> Result<()> {
+    let mut tx = pool.begin().await.map_err(|_| Error::DatabaseError)?;
+
+    // Only once, a reservation that was already settled keeps its outcome
+    let released = sqlx::query!(
+        "UPDATE ai_credit_ledger
+         SET status = $1, failure = $2, prompt_tokens = $3, completion_tokens = $4, tokens_estimated = $5, settled_at = $6
+         WHERE id = $7 AND status = 'reserved'",
+        AiCreditStatus::Released as AiCreditStatus,
+        failure,
+        usage.prompt_tokens,
+        usage.completion_tokens,
+        usage.estimated,
+        Utc::now().naive_utc(),
+        reservation.id
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|_| Error::DatabaseError)?;
+
+    if released.rows_affected() > 0 {
+        sqlx::query!(
+            "UPDATE users SET ai_credits = ai_credits + $1 WHERE id = $2",
+            reservation.amount,
+            reservation.user_id
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|_| Error::DatabaseError)?;
+    }
+
+    tx.commit().await.map_err(|_| Error::DatabaseError)?;
+
+    println!("->> {:<12} - Released {} AI credits for user {} after {}", "CREDIT_CHECK", reservation.amount, reservation.user_id, failure);
+    Ok(())
+}
+
+/// Commit the reservation if the operation succeeded and release it if it failed, then pass the result on.
+pub async fn settle_credits<T>(
+    pool: &PgPool,
+    reservation: CreditReservation,
+    usage: TokenUsage,
+    result: Result<T>,
+) -> Result<T> {
+    match &result {
+        Ok(_) => commit_credits(pool, &reservation, usage).await?,
+        Err(e) => release_credits(pool, &reservation, usage, e.as_ref()).await?,
+    }
+    result
+}
+
+/// Release the user's reservations that are older than RESERVATION_TIMEOUT_MINUTES.
+async fn release_expired_reservations(pool: &PgPool, user_id: i32) -> Result<()> {
+    let cutoff = Utc::now().naive_utc() - Duration::minutes(RESERVATION_TIMEOUT_MINUTES);
+
+    let expired = sqlx::query!(
+        "SELECT id, amount FROM ai_credit_ledger WHERE user_id = $1 AND status = 'reserved' AND created_at < $2",
+        user_id,
+        cutoff
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|_| Error::DatabaseError)?;
+
+    for entry in expired {
+        let reservation = CreditReservation { id: entry.id, user_id, amount: entry.amount };
+        release_credits(pool, &reservation, TokenUsage::default(), "ReservationExpired").await?;
+    }
+
+    Ok(())
+}
+
+/// Top the balance up to the plan's refill_credits if the refill interval has passed.
+async fn refill_credits_if_due(pool: &PgPool, user_id: i32) -> Result<()> {
+    let now = Utc::now().naive_utc();
+
+    let refilled = sqlx::query!(
+        "UPDATE users u
+         SET ai_credits = GREATEST(u.ai_credits, p.refill_credits), credits_refilled_at = $1
+         FROM plans p
+         WHERE u.plan = p.name AND u.id = $2 AND u.credits_refilled_at <= $1::TIMESTAMP - make_interval(days => p.refill_interval_days)
+         RETURNING u.ai_credits",
+        now,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|_| Error::DatabaseError)?;
+
+    if let Some(refilled) = refilled {
+        println!("->> {:<12} - Refilled AI credits for user {} to {}", "CREDIT_CHECK", user_id, refilled.ai_credits);
+    }
+
+    Ok(())
+}
+
+/// Fail if the user has used the monthly token budget of their plan.
+async fn ensure_token_budget(pool: &PgPool, user_id: i32) -> Result<()> {
+    let plan = get_user_plan(pool, user_id).await?;
+    let Some(budget) = plan.monthly_token_budget else {
+        return Ok(());
+    };
+
+    let used = tokens_used_since(pool, user_id, period_start(Utc::now().naive_utc())).await?;
+    if used >= budget {
+        println!("->> {:<12} - User {} used {} of {} tokens this month", "CREDIT_CHECK", user_id, used, budget);
+        return Err(Error::TokenBudgetExceededError { budget });
+    }
+
+    Ok(())
+}
+
+/// The plan the user is on
+pub async fn get_user_plan(pool: &PgPool, user_id: i32) -> Result<Plan> {
+    sqlx::query_as!(
+        Plan,
+        "SELECT p.name, p.description, p.price_cents, p.monthly_token_budget, p.refill_credits, p.refill_interval_days
+         FROM plans p
+         JOIN users u ON u.plan = p.name
+         WHERE u.id = $1",
+        user_id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|_| Error::DatabaseError)?
+    .ok_or(Error::UserNotFoundError { user_id })
+}
+
+/// Move the user to another free plan, its token budget and refills apply from now on.
+/// There is no payment here, so only admins can put a user on a paid plan (see db/admin.rs update_limits).
+pub async fn set_user_plan(pool: &PgPool, user_id: i32, plan: &str) -> Result<Plan> {
+    let price = sqlx::query!("SELECT price_cents FROM plans WHERE name = $1", plan)
+        .fetch_optional(pool)
+        .await
+        .map_err(|_| Error::DatabaseError)?;
+    let Some(price) = price else {
+        println!("->> {:<12} - unknown plan {}", "CREDIT_CHECK", plan);
+        return Err(Error::InvalidRequestFormatError);
+    };
+    if price.price_cents > 0 {
+        println!("->> {:<12} - User {} cannot select paid plan {}", "CREDIT_CHECK", user_id, plan);
+        return Err(Error::PermissionError);
+    }
+
+    sqlx::query!("UPDATE users SET plan = $1 WHERE id = $2", plan, user_id)
+        .execute(pool)
+        .await
+        .map_err(|_| Error::DatabaseError)?;
+
+    get_user_plan(pool, user_id).await
+}
+
+/// Every plan, cheapest first
+pub async fn list_plans(pool: &PgPool) -> Result<Vec<Plan>> {
+    sqlx::query_as!(
+        Plan,
+        "SELECT name, description, price_cents, monthly_token_budget, refill_credits, refill_interval_days
+         FROM plans
+         ORDER BY price_cents, name"
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|_| Error::DatabaseError)
+}
+
+/// Tokens the user's operations used since `since`, failed ones included
+pub async fn tokens_used_since(pool: &PgPool, user_id: i32, since: NaiveDateTime) -> Result<i64> {
+    let used = sqlx::query!(
+        r#"SELECT COALESCE(SUM(prompt_tokens + completion_tokens), 0)::BIGINT as "used!"
+           FROM ai_credit_ledger
+           WHERE user_id = $1 AND created_at >= $2"#,
+        user_id,
+        since
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|_| Error::DatabaseError)?;
+
+    Ok(used.used)
+}
+
+/// The user's ledger, newest first, optionally only entries older than the ledger id `before`
+pub async fn list_ledger(
+    pool: &PgPool,
+    user_id: i32,
+    before: Option<i32>,
+    limit: i64,
+) -> Result<Vec<AiCreditLedgerEntry>> {
+    sqlx::query_as!(
+        AiCreditLedgerEntry,
+        r#"SELECT id, operation as "operation: AiOperation", amount, status as "status: AiCreditStatus", failure,
+                  prompt_tokens, completion_tokens, tokens_estimated, created_at, settled_at
+           FROM ai_credit_ledger
+           WHERE user_id = $1 AND ($2::INT IS NULL OR id < $2)
+           ORDER BY id DESC
+           LIMIT $3"#,
+        user_id,
+        before,
+        limit
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|_| Error::DatabaseError)
+}
+
+/// The user's plan, balance and usage this month, with a page of their ledger
+pub async fn usage_summary(pool: &PgPool, user_id: i32, before: Option<i32>, limit: i64) -> Result<AiUsageSummary> {
+    let user = sqlx::query!("SELECT ai_credits, credits_refilled_at FROM users WHERE id = $1", user_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|_| Error::DatabaseError)?
+        .ok_or(Error::UserNotFoundError { user_id })?;
+
+    let plan = get_user_plan(pool, user_id).await?;
+    let period_start = period_start(Utc::now().naive_utc());
+
+    Ok(AiUsageSummary {
+        ai_credits: user.ai_credits,
+        period_start,
+        tokens_used: tokens_used_since(pool, user_id, period_start).await?,
+        next_refill_at: user.credits_refilled_at + Duration::days(plan.refill_interval_days as i64),
+        history: list_ledger(pool, user_id, before, limit).await?,
+        plan,
+    })
+}
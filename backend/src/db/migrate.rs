@@ -0,0 +1,229 @@
+// src/db/migrate.rs
+//
+// Forward-only schema migrations.
+// Migrations are embedded into the binary, applied in order and recorded in the _migrations table,
+// so running them again only applies the ones a database is missing. Nothing here ever drops data.
+// Seed data for development and tests lives separately in fixtures/ and is only loaded on request.
+
+use sha2::{Digest, Sha256};
+use sqlx::{Connection, Executor, PgPool};
+
+use crate::{Error, Result};
+
+struct Migration {
+    version: i32,
+    name: &'static str,
+    sql: &'static str,
+}
+
+/// Every migration in order. Add new files at the end, never edit or reorder applied ones.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "initial_schema",
+        sql: include_str!("../../migrations/0001_initial_schema.sql"),
+    },
+    Migration {
+        version: 2,
+        name: "reference_data",
+        sql: include_str!("../../migrations/0002_reference_data.sql"),
+    },
+    Migration {
+        version: 3,
+        name: "user_sessions",
+        sql: include_str!("../../migrations/0003_user_sessions.sql"),
+    },
+    Migration {
+        version: 4,
+        name: "document_chunks",
+        sql: include_str!("../../migrations/0004_document_chunks.sql"),
+    },
+    Migration {
+        version: 5,
+        name: "drop_document_embedding",
+        sql: include_str!("../../migrations/0005_drop_document_embedding.sql"),
+    },
+    Migration {
+        version: 6,
+        name: "document_revisions",
+        sql: include_str!("../../migrations/0006_document_revisions.sql"),
+    },
+    Migration {
+        version: 7,
+        name: "document_version",
+        sql: include_str!("../../migrations/0007_document_version.sql"),
+    },
+    Migration {
+        version: 8,
+        name: "permission_roles",
+        sql: include_str!("../../migrations/0008_permission_roles.sql"),
+    },
+    Migration {
+        version: 9,
+        name: "document_access",
+        sql: include_str!("../../migrations/0009_document_access.sql"),
+    },
+    Migration {
+        version: 10,
+        name: "share_links",
+        sql: include_str!("../../migrations/0010_share_links.sql"),
+    },
+    Migration {
+        version: 11,
+        name: "document_publications",
+        sql: include_str!("../../migrations/0011_document_publications.sql"),
+    },
+    Migration {
+        version: 12,
+        name: "document_comments",
+        sql: include_str!("../../migrations/0012_document_comments.sql"),
+    },
+    Migration {
+        version: 13,
+        name: "document_suggestions",
+        sql: include_str!("../../migrations/0013_document_suggestions.sql"),
+    },
+    Migration {
+        version: 14,
+        name: "ai_credit_ledger",
+        sql: include_str!("../../migrations/0014_ai_credit_ledger.sql"),
+    },
+    Migration {
+        version: 15,
+        name: "ai_usage_plans",
+        sql: include_str!("../../migrations/0015_ai_usage_plans.sql"),
+    },
+    Migration {
+        version: 16,
+        name: "admin",
+        sql: include_str!("../../migrations/0016_admin.sql"),
+    },
+    Migration {
+        version: 17,
+        name: "share_link_attempts",
+        sql: include_str!("../../migrations/0017_share_link_attempts.sql"),
+    },
+    Migration {
+        version: 18,
+        name: "suggestion_hunk_ranges",
+        sql: include_str!("../../migrations/0018_suggestion_hunk_ranges.sql"),
+    },
+    Migration {
+        version: 19,
+        name: "ai_meta_operations",
+        sql: include_str!("../../migrations/0019_ai_meta_operations.sql"),
+    },
+];
+
+/// Development and test data, loaded by `backend seed` and the test database reset
+const FIXTURES: &[(&str, &str)] = &[("01_seed_data", include_str!("../../fixtures/01_seed_data.sql"))];
+
+/// Arbitrary key for the advisory lock that keeps two instances from migrating at the same time
+const MIGRATION_LOCK_KEY: i64 = 0x56_79_6e_6e; // "Vynn"
+
+fn checksum(sql: &str) -> String {
+    format!("{:x}", Sha256::digest(sql.as_bytes()))
+}
+
+/// Apply every migration the database does not have yet.
+/// Returns the number of migrations applied.
+pub async fn run_migrations(pool: &PgPool) -> Result<usize> {
+    let mut conn = pool.acquire().await.map_err(|_| Error::DatabaseConnectionError)?;
+
+    let migration_error = |e: sqlx::Error| {
+        println!("->> {:<12} - {:?}", "MIGRATION", e);
+        Error::MigrationExecutionError
+    };
+
+    sqlx::query("SELECT pg_advisory_lock($1)")
+        .bind(MIGRATION_LOCK_KEY)
+        .execute(&mut *conn)
+        .await
+        .map_err(migration_error)?;
+
+    let result = apply_pending(&mut conn).await;
+
+    sqlx::query("SELECT pg_advisory_unlock($1)")
+        .bind(MIGRATION_LOCK_KEY)
+        .execute(&mut *conn)
+        .await
+        .map_err(migration_error)?;
+
+    result
+}
+
+async fn apply_pending(conn: &mut sqlx::PgConnection) -> Result<usize> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS _migrations (
+            version INT PRIMARY KEY,
+            name VARCHAR(255) NOT NULL,
+            checksum VARCHAR(64) NOT NULL,
+            applied_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )",
+    )
+    .await
+    .map_err(|_| Error::MigrationExecutionError)?;
+
+    let applied: Vec<(i32, String)> = sqlx::query_as("SELECT version, checksum FROM _migrations")
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(|_| Error::MigrationExecutionError)?;
+
+    let mut count = 0;
+    for migration in MIGRATIONS {
+        let checksum = checksum(migration.sql);
+
+        if let Some((_, applied_checksum)) = applied.iter().find(|(version, _)| *version == migration.version) {
+            // An applied migration that changed afterwards would leave databases out of step
+            if *applied_checksum != checksum {
+                println!(
+                    "->> {:<12} - migration {} ({}) was changed after it was applied",
+                    "MIGRATION", migration.version, migration.name
+                );
+                return Err(Error::MigrationExecutionError);
+            }
+            continue;
+        }
+
+        println!("->> {:<12} - applying {:04}_{}", "MIGRATION", migration.version, migration.name);
+
+        // Each migration and its record are committed together
+        let mut tx = conn.begin().await.map_err(|_| Error::MigrationExecutionError)?;
+
+        (&mut *tx).execute(migration.sql).await.map_err(|e| {
+            println!("->> {:<12} - migration {} failed: {:?}", "MIGRATION", migration.version, e);
+            Error::MigrationExecutionError
+        })?;
+
+        sqlx::query("INSERT INTO _migrations (version, name, checksum) VALUES ($1, $2, $3)")
+            .bind(migration.version)
+            .bind(migration.name)
+            .bind(&checksum)
+            .execute(&mut *tx)
+            .await
+            .map_err(|_| Error::MigrationExecutionError)?;
+
+        tx.commit().await.map_err(|_| Error::MigrationExecutionError)?;
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+/// Load the development and test fixtures. Safe to run more than once.
+pub async fn load_fixtures(pool: &PgPool) -> Result<()> {
+    for (name, sql) in FIXTURES {
+        println!("->> {:<12} - loading fixture {}", "MIGRATION", name);
+
+        let mut tx = pool.begin().await.map_err(|_| Error::DatabaseConnectionError)?;
+
+        (&mut *tx).execute(*sql).await.map_err(|e| {
+            println!("->> {:<12} - fixture {} failed: {:?}", "MIGRATION", name, e);
+            Error::MigrationExecutionError
+        })?;
+
+        tx.commit().await.map_err(|_| Error::MigrationExecutionError)?;
+    }
+
+    Ok(())
+}
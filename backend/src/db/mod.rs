@@ -1 +1,8 @@
-pub mod pool;
\ No newline at end of file
+pub mod admin;
+pub mod comment;
+pub mod credit;
+pub mod migrate;
+pub mod pool;
+pub mod revision;
+pub mod share_link;
+pub mod suggestion;
\ No newline at end of file
@@ -0,0 +1,174 @@
+// src/db/revision.rs
+//
+// Revision history for documents.
+// Every save stores a snapshot of the content in document_revisions. Snapshots count against the
+// document owner's storage quota, so the oldest ones are dropped first when space runs out.
+// A save writes the document, records its revision and enforces the quota in one transaction.
+
+use chrono::{Duration, Utc};
+use sqlx::{PgConnection, PgPool};
+
+use crate::models::revision::RevisionOrigin;
+use crate::models::storage::StorageManager;
+use crate::{Error, Result};
+
+/// Manual saves by the same author within this window update the latest revision
+/// instead of adding a new one, so autosave does not flood the history.
+const REVISION_COALESCE_MINUTES: i64 = 5;
+
+/// Get the user who owns a document, their quota pays for its content and history
+pub async fn get_document_owner(pool: &PgPool, document_id: i32) -> Result<i32> {
+    let owner = sqlx::query!(
+        "SELECT user_id FROM document_permissions
+         WHERE document_id = $1 AND role = 'owner'",
+        document_id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|_| Error::DatabaseError)?
+    .ok_or(Error::DocumentNotFoundError { document_id })?;
+
+    Ok(owner.user_id)
+}
+
+/// Keep the owner within their storage quota once a save has been written, inside the save's transaction.
+/// Old revisions of the saved document are pruned to make room, never the one just recorded. If that is
+/// not enough the save is refused, and rolling back the transaction brings the pruned revisions back.
+pub async fn enforce_storage_quota(conn: &mut PgConnection, owner_id: i32, document_id: i32) -> Result<()> {
+    let max_storage_bytes = StorageManager::get_user_limits(&mut *conn, owner_id)
+        .await
+        .map_err(|_| Error::DatabaseError)?
+        .max_storage_bytes;
+    let current_storage = StorageManager::get_user_storage_used(&mut *conn, owner_id)
+        .await
+        .map_err(|_| Error::DatabaseError)?;
+
+    let excess = current_storage - max_storage_bytes;
+    if excess <= 0 {
+        return Ok(());
+    }
+
+    let freed = prune_oldest_revisions(conn, document_id, excess).await?;
+    if freed >= excess {
+        return Ok(());
+    }
+
+    Err(Error::LimitExceededError {
+        message: format!(
+            "Storage limit of {}MB exceeded. Usage with this save: {}MB",
+            max_storage_bytes / 1024 / 1024,
+            (current_storage - freed) / 1024 / 1024
+        ),
+    })
+}
+
+/// Delete the document's oldest revisions, except its latest, until at least `bytes_needed` are freed.
+/// Returns how many bytes were actually freed.
+async fn prune_oldest_revisions(conn: &mut PgConnection, document_id: i32, bytes_needed: i64) -> Result<i64> {
+    let revisions = sqlx::query!(
+        r#"SELECT id, LENGTH(content) as "size_bytes!"
+           FROM document_revisions
+           WHERE document_id = $1
+             AND revision_number < (SELECT MAX(revision_number) FROM document_revisions WHERE document_id = $1)
+           ORDER BY revision_number ASC"#,
+        document_id
+    )
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|_| Error::DatabaseError)?;
+
+    let mut freed: i64 = 0;
+    let mut to_delete = Vec::new();
+    for revision in revisions {
+        if freed >= bytes_needed {
+            break;
+        }
+        freed += revision.size_bytes as i64;
+        to_delete.push(revision.id);
+    }
+
+    if !to_delete.is_empty() {
+        sqlx::query!("DELETE FROM document_revisions WHERE id = ANY($1)", &to_delete)
+            .execute(&mut *conn)
+            .await
+            .map_err(|_| Error::DatabaseError)?;
+    }
+
+    Ok(freed)
+}
+
+/// Record the content a document was just saved with, in the same transaction as the save.
+/// Returns the revision number the content was stored under.
+pub async fn record_revision(
+    conn: &mut PgConnection,
+    document_id: i32,
+    author_id: i32,
+    content: &str,
+    origin: RevisionOrigin,
+) -> Result<i32> {
+    let latest = sqlx::query!(
+        r#"SELECT id, revision_number, author_id, origin as "origin: RevisionOrigin", created_at
+           FROM document_revisions
+           WHERE document_id = $1
+           ORDER BY revision_number DESC
+           LIMIT 1"#,
+        document_id
+    )
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|_| Error::DatabaseError)?;
+
+    let now = Utc::now().naive_utc();
+
+    // Fold consecutive manual saves from the same author into one revision
+    if let Some(latest) = &latest {
+        let is_recent = now.signed_duration_since(latest.created_at) < Duration::minutes(REVISION_COALESCE_MINUTES);
+
+        if origin == RevisionOrigin::Manual
+            && latest.origin == RevisionOrigin::Manual
+            && latest.author_id == Some(author_id)
+            && is_recent
+        {
+            sqlx::query!(
+                "UPDATE document_revisions SET content = $1, created_at = $2 WHERE id = $3",
+                content,
+                now,
+                latest.id
+            )
+            .execute(&mut *conn)
+            .await
+            .map_err(|_| Error::DatabaseError)?;
+
+            return Ok(latest.revision_number);
+        }
+    }
+
+    let revision_number = latest.map_or(1, |latest| latest.revision_number + 1);
+
+    sqlx::query!(
+        "INSERT INTO document_revisions (document_id, revision_number, author_id, content, origin, created_at)
+         VALUES ($1, $2, $3, $4, $5, $6)",
+        document_id,
+        revision_number,
+        author_id,
+        content,
+        origin as RevisionOrigin,
+        now
+    )
+    .execute(&mut *conn)
+    .await
+    .map_err(|_| Error::DatabaseError)?;
+
+    // Enforce the retention limit, newest revisions are kept
+    sqlx::query!(
+        "DELETE FROM document_revisions
+         WHERE document_id = $1 AND revision_number <= $2",
+        document_id,
+        revision_number - StorageManager::get_revision_limit() as i32
+    )
+    .execute(&mut *conn)
+    .await
+    .map_err(|_| Error::DatabaseError)?;
+
+    Ok(revision_number)
+}
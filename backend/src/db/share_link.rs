@@ -0,0 +1,331 @@
+// src/db/share_link.rs
+//
+// Share links for documents and projects.
+// A link carries a random token and a role. Accepting it while logged in grants that role to the user,
+// unless they already have at least as much access. Expired, used up and revoked links grant nothing.
+// Wrong passwords are counted per link, and every MAX_PASSWORD_ATTEMPTS in a row lock it for a while.
+
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::Utc;
+use sqlx::{PgConnection, PgPool};
+
+use crate::auth::authorization::{document_role, project_role};
+use crate::models::role::Role;
+use crate::models::share_link::{CreateShareLinkPayload, ShareLink};
+use crate::{Error, Result};
+
+/// Wrong passwords in a row before a link is locked
+const MAX_PASSWORD_ATTEMPTS: i32 = 5;
+
+/// How long a link stays locked after too many wrong passwords
+const PASSWORD_LOCKOUT_MINUTES: i32 = 15;
+
+/// What a share link gives access to
+#[derive(Debug, Clone, Copy)]
+pub enum ShareTarget {
+    Document(i32),
+    Project(i32),
+}
+
+impl ShareTarget {
+    /// The (document_id, project_id) columns of a link to this target
+    pub fn ids(self) -> (Option<i32>, Option<i32>) {
+        match self {
+            Self::Document(id) => (Some(id), None),
+            Self::Project(id) => (None, Some(id)),
+        }
+    }
+
+    fn of(link: &ShareLink) -> Result<Self> {
+        match (link.document_id, link.project_id) {
+            (Some(id), None) => Ok(Self::Document(id)),
+            (None, Some(id)) => Ok(Self::Project(id)),
+            _ => Err(Error::ShareLinkNotFoundError),
+        }
+    }
+}
+
+/// 32 random bytes, base64url encoded
+fn generate_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Create a link to a document or project.
+pub async fn create_link(
+    pool: &PgPool,
+    target: ShareTarget,
+    created_by: i32,
+    payload: &CreateShareLinkPayload,
+) -> Result<ShareLink> {
+    // Ownership can only be transferred explicitly
+    if payload.role == Role::Owner {
+        return Err(Error::InvalidRequestFormatError);
+    }
+    if payload.max_uses.is_some_and(|max| max < 1) {
+        return Err(Error::InvalidRequestFormatError);
+    }
+    if payload.expires_at.is_some_and(|at| at <= Utc::now().naive_utc()) {
+        return Err(Error::InvalidRequestFormatError);
+    }
+
+    let password_hash = match payload.password.as_deref().filter(|p| !p.is_empty()) {
+        Some(password) => {
+            let salt = SaltString::generate(&mut OsRng);
+            let hash = Argon2::default()
+                .hash_password(password.as_bytes(), &salt)
+                .map_err(|e| {
+                    println!("->> {:<12} - password hashing error: {:?}", "ERROR", e);
+                    Error::PermissionCreationError
+                })?;
+            Some(hash.to_string())
+        }
+        None => None,
+    };
+
+    let (document_id, project_id) = target.ids();
+
+    sqlx::query_as!(
+        ShareLink,
+        r#"INSERT INTO share_links (token, document_id, project_id, role, created_by, expires_at, password_hash, max_uses)
+           VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+           RETURNING id, token, document_id, project_id, role as "role: Role", created_by, created_at,
+                     expires_at, password_hash, max_uses, use_count, revoked_at"#,
+        generate_token(),
+        document_id,
+        project_id,
+        payload.role as Role,
+        created_by,
+        payload.expires_at,
+        password_hash,
+        payload.max_uses
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        println!("->> {:<12} - failed to create share link: {:?}", "ERROR", e);
+        Error::PermissionCreationError
+    })
+}
+
+/// Links to a document or project that have not been revoked, newest first.
+pub async fn list_links(pool: &PgPool, target: ShareTarget) -> Result<Vec<ShareLink>> {
+    let (document_id, project_id) = target.ids();
+
+    sqlx::query_as!(
+        ShareLink,
+        r#"SELECT id, token, document_id, project_id, role as "role: Role", created_by, created_at,
+                  expires_at, password_hash, max_uses, use_count, revoked_at
+           FROM share_links
+           WHERE document_id IS NOT DISTINCT FROM $1 AND project_id IS NOT DISTINCT FROM $2
+             AND revoked_at IS NULL
+           ORDER BY created_at DESC"#,
+        document_id,
+        project_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|_| Error::DatabaseError)
+}
+
+/// Revoke a link. The link must belong to the target so owners can only revoke their own links.
+pub async fn revoke_link(pool: &PgPool, target: ShareTarget, link_id: i32) -> Result<()> {
+    let (document_id, project_id) = target.ids();
+
+    let result = sqlx::query!(
+        "UPDATE share_links SET revoked_at = NOW() AT TIME ZONE 'UTC'
+         WHERE id = $1 AND document_id IS NOT DISTINCT FROM $2 AND project_id IS NOT DISTINCT FROM $3
+           AND revoked_at IS NULL",
+        link_id,
+        document_id,
+        project_id
+    )
+    .execute(pool)
+    .await
+    .map_err(|_| Error::DatabaseError)?;
+
+    if result.rows_affected() == 0 {
+        return Err(Error::ShareLinkNotFoundError);
+    }
+
+    Ok(())
+}
+
+/// Look up a link that can still be used.
+pub async fn find_active_link(pool: &PgPool, token: &str) -> Result<ShareLink> {
+    let link = sqlx::query_as!(
+        ShareLink,
+        r#"SELECT id, token, document_id, project_id, role as "role: Role", created_by, created_at,
+                  expires_at, password_hash, max_uses, use_count, revoked_at
+           FROM share_links WHERE token = $1"#,
+        token
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|_| Error::DatabaseError)?
+    .ok_or(Error::ShareLinkNotFoundError)?;
+
+    check_usable(&link)?;
+    Ok(link)
+}
+
+fn check_usable(link: &ShareLink) -> Result<()> {
+    if link.revoked_at.is_some() {
+        return Err(Error::ShareLinkNotFoundError);
+    }
+    if link.expires_at.is_some_and(|at| at <= Utc::now().naive_utc()) {
+        return Err(Error::ShareLinkExpiredError);
+    }
+    if link.max_uses.is_some_and(|max| link.use_count >= max) {
+        return Err(Error::ShareLinkExpiredError);
+    }
+    Ok(())
+}
+
+fn check_password(hash: Option<&str>, password: Option<&str>) -> Result<()> {
+    let Some(hash) = hash else {
+        return Ok(());
+    };
+
+    let parsed_hash = PasswordHash::new(hash).map_err(|_| Error::ShareLinkPasswordError)?;
+    let password = password.ok_or(Error::ShareLinkPasswordError)?;
+
+    Argon2::default()
+        .verify_password(password.as_bytes(), &parsed_hash)
+        .map_err(|_| Error::ShareLinkPasswordError)
+}
+
+/// Count a wrong password, locking the link once too many were tried in a row.
+async fn record_failed_attempt(conn: &mut PgConnection, link_id: i32) -> Result<()> {
+    sqlx::query!(
+        "UPDATE share_links
+         SET failed_attempts = failed_attempts + 1,
+             locked_until = CASE WHEN (failed_attempts + 1) % $2 = 0
+                                 THEN NOW() AT TIME ZONE 'UTC' + make_interval(mins => $3)
+                                 ELSE locked_until END
+         WHERE id = $1",
+        link_id,
+        MAX_PASSWORD_ATTEMPTS,
+        PASSWORD_LOCKOUT_MINUTES
+    )
+    .execute(conn)
+    .await
+    .map_err(|_| Error::DatabaseError)?;
+
+    Ok(())
+}
+
+/// Check the link's password with its row locked.
+/// Concurrent guesses on the same link wait for each other, so each sees the count and lock the previous one left.
+async fn check_password_attempt(pool: &PgPool, link_id: i32, password: Option<&str>) -> Result<()> {
+    let mut tx = pool.begin().await.map_err(|_| Error::DatabaseError)?;
+
+    let attempt = sqlx::query!(
+        "SELECT password_hash, failed_attempts, locked_until FROM share_links WHERE id = $1 FOR UPDATE",
+        link_id
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|_| Error::DatabaseError)?;
+
+    if let Some(locked_until) = attempt.locked_until {
+        let retry_after_seconds = (locked_until - Utc::now().naive_utc()).num_seconds();
+        if retry_after_seconds > 0 {
+            return Err(Error::ShareLinkLockedError { retry_after_seconds });
+        }
+    }
+
+    if let Err(e) = check_password(attempt.password_hash.as_deref(), password) {
+        record_failed_attempt(&mut tx, link_id).await?;
+        tx.commit().await.map_err(|_| Error::DatabaseError)?;
+        return Err(e);
+    }
+    if attempt.failed_attempts > 0 {
+        sqlx::query!("UPDATE share_links SET failed_attempts = 0 WHERE id = $1", link_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|_| Error::DatabaseError)?;
+    }
+
+    tx.commit().await.map_err(|_| Error::DatabaseError)?;
+    Ok(())
+}
+
+/// Accept a link for a user.
+/// Returns the target and the user's role on it afterwards. A use is only counted when access was granted.
+pub async fn accept_link(
+    pool: &PgPool,
+    token: &str,
+    user_id: i32,
+    password: Option<&str>,
+) -> Result<(ShareTarget, Role)> {
+    let link = find_active_link(pool, token).await?;
+    check_password_attempt(pool, link.id, password).await?;
+
+    let target = ShareTarget::of(&link)?;
+    let current = match target {
+        ShareTarget::Document(id) => document_role(pool, user_id, id).await?,
+        ShareTarget::Project(id) => project_role(pool, user_id, id).await?,
+    };
+
+    // Never take away access the user already has
+    if let Some(role) = current.filter(|role| role.allows(link.role)) {
+        return Ok((target, role));
+    }
+
+    let mut tx = pool.begin().await.map_err(|_| Error::DatabaseError)?;
+
+    // Count the use first, this fails if a concurrent accept took the last one
+    let counted = sqlx::query!(
+        "UPDATE share_links SET use_count = use_count + 1
+         WHERE id = $1 AND revoked_at IS NULL
+           AND (max_uses IS NULL OR use_count < max_uses)
+           AND (expires_at IS NULL OR expires_at > NOW() AT TIME ZONE 'UTC')",
+        link.id
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|_| Error::DatabaseError)?;
+
+    if counted.rows_affected() == 0 {
+        return Err(Error::ShareLinkExpiredError);
+    }
+
+    let granted = match target {
+        ShareTarget::Document(document_id) => {
+            sqlx::query!(
+                "INSERT INTO document_permissions (document_id, user_id, role)
+                 VALUES ($1, $2, $3)
+                 ON CONFLICT (document_id, user_id) DO UPDATE SET role = $3",
+                document_id,
+                user_id,
+                link.role as Role
+            )
+            .execute(&mut *tx)
+            .await
+        }
+        ShareTarget::Project(project_id) => {
+            sqlx::query!(
+                "INSERT INTO project_permissions (project_id, user_id, role)
+                 VALUES ($1, $2, $3)
+                 ON CONFLICT (project_id, user_id) DO UPDATE SET role = $3",
+                project_id,
+                user_id,
+                link.role as Role
+            )
+            .execute(&mut *tx)
+            .await
+        }
+    };
+    granted.map_err(|_| Error::PermissionCreationError)?;
+
+    tx.commit().await.map_err(|_| Error::DatabaseError)?;
+
+    println!("->> {:<12} - user {} accepted share link {} for {:?}", "SHARE", user_id, link.id, target);
+    Ok((target, link.role))
+}
@@ -0,0 +1,202 @@
+// src/db/suggestion.rs
+//
+// Suggestions (track changes) on documents.
+// A suggestion stores the content it was made against, the proposed content and the ranges that differ
+// as computed by src/diff.rs.
+// Accepting it applies those ranges to the document as it is now, so edits made elsewhere in the meantime
+// are kept. If a changed range cannot be found any more the suggestion conflicts and has to be redone.
+
+use chrono::Utc;
+use sqlx::types::Json;
+use sqlx::PgPool;
+
+use crate::db::revision::{enforce_storage_quota, get_document_owner, record_revision};
+use crate::diff::{apply_hunks, diff_hunks, DiffHunk};
+use crate::models::revision::RevisionOrigin;
+use crate::models::suggestion::{DocumentSuggestion, SuggestionSource, SuggestionStatus};
+use crate::rag::embed::reindex_document;
+use crate::{Error, Result};
+
+/// Store a pending suggestion for a document. Returns None when it would not change anything.
+pub async fn create_suggestion(
+    pool: &PgPool,
+    document_id: i32,
+    author_id: i32,
+    source: SuggestionSource,
+    base_version: i32,
+    old_content: &str,
+    new_content: &str,
+) -> Result<Option<i32>> {
+    let hunks = diff_hunks(old_content, new_content);
+    if hunks.is_empty() {
+        return Ok(None);
+    }
+
+    let suggestion = sqlx::query!(
+        "INSERT INTO document_suggestions (document_id, author_id, source, base_version, old_content, new_content, hunks)
+         VALUES ($1, $2, $3, $4, $5, $6, $7)
+         RETURNING id",
+        document_id,
+        author_id,
+        source as SuggestionSource,
+        base_version,
+        old_content,
+        new_content,
+        Json(hunks) as _
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        println!("->> {:<12} - failed to create suggestion for document {}: {:?}", "ERROR", document_id, e);
+        Error::DocumentUpdateError { document_id }
+    })?;
+
+    Ok(Some(suggestion.id))
+}
+
+/// Suggestions on a document, newest first, optionally only those with `status`.
+pub async fn list_suggestions(
+    pool: &PgPool,
+    document_id: i32,
+    status: Option<SuggestionStatus>,
+) -> Result<Vec<DocumentSuggestion>> {
+    sqlx::query_as!(
+        DocumentSuggestion,
+        r#"SELECT s.id, s.document_id, s.author_id, u.name as "author_name?",
+                  s.source as "source: SuggestionSource", s.base_version, s.old_content, s.new_content,
+                  s.hunks as "hunks: Json<Vec<DiffHunk>>", s.status as "status: SuggestionStatus",
+                  s.created_at, s.resolved_at, s.resolved_by, s.revision_number
+           FROM document_suggestions s
+           LEFT JOIN users u ON s.author_id = u.id
+           WHERE s.document_id = $1 AND ($2::suggestion_status_enum IS NULL OR s.status = $2)
+           ORDER BY s.created_at DESC, s.id DESC"#,
+        document_id,
+        status as Option<SuggestionStatus>
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|_| Error::DatabaseError)
+}
+
+/// A single suggestion, which must belong to the document.
+pub async fn get_suggestion(pool: &PgPool, document_id: i32, suggestion_id: i32) -> Result<DocumentSuggestion> {
+    sqlx::query_as!(
+        DocumentSuggestion,
+        r#"SELECT s.id, s.document_id, s.author_id, u.name as "author_name?",
+                  s.source as "source: SuggestionSource", s.base_version, s.old_content, s.new_content,
+                  s.hunks as "hunks: Json<Vec<DiffHunk>>", s.status as "status: SuggestionStatus",
+                  s.created_at, s.resolved_at, s.resolved_by, s.revision_number
+           FROM document_suggestions s
+           LEFT JOIN users u ON s.author_id = u.id
+           WHERE s.id = $1 AND s.document_id = $2"#,
+        suggestion_id,
+        document_id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|_| Error::DatabaseError)?
+    .ok_or(Error::SuggestionNotFoundError { suggestion_id })
+}
+
+/// Close a pending suggestion without applying it.
+pub async fn reject_suggestion(pool: &PgPool, suggestion: &DocumentSuggestion, rejected_by: i32) -> Result<()> {
+    let result = sqlx::query!(
+        "UPDATE document_suggestions
+         SET status = 'rejected', resolved_at = $1, resolved_by = $2
+         WHERE id = $3 AND status = 'pending'",
+        Utc::now().naive_utc(),
+        rejected_by,
+        suggestion.id
+    )
+    .execute(pool)
+    .await
+    .map_err(|_| Error::DatabaseError)?;
+
+    if result.rows_affected() == 0 {
+        return Err(Error::SuggestionNotPendingError { suggestion_id: suggestion.id });
+    }
+
+    Ok(())
+}
+
+/// Apply a pending suggestion to the document and record the result as a new revision.
+/// Returns the revision number the document was saved as.
+pub async fn accept_suggestion(pool: &PgPool, suggestion: &DocumentSuggestion, accepted_by: i32) -> Result<i32> {
+    let document_id = suggestion.document_id;
+
+    if suggestion.status != SuggestionStatus::Pending {
+        return Err(Error::SuggestionNotPendingError { suggestion_id: suggestion.id });
+    }
+
+    let current = sqlx::query!("SELECT content, version FROM documents WHERE id = $1", document_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|_| Error::DatabaseError)?
+        .ok_or(Error::DocumentNotFoundError { document_id })?;
+
+    let current_content = current.content.unwrap_or_default();
+    let new_content = apply_hunks(&suggestion.old_content, &current_content, &suggestion.hunks)
+        .ok_or(Error::DocumentConflictError { document_id, current_version: current.version })?;
+
+    let owner_id = get_document_owner(pool, document_id).await?;
+    let mut tx = pool.begin().await.map_err(|_| Error::DatabaseError)?;
+
+    // Only if nobody saved since the content was read, and only once per suggestion
+    let updated = sqlx::query!(
+        "UPDATE documents
+         SET content = $1, updated_at = $2, version = version + 1
+         WHERE id = $3 AND version = $4",
+        new_content,
+        Utc::now().naive_utc(),
+        document_id,
+        current.version
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|_| Error::DocumentUpdateError { document_id })?;
+
+    if updated.rows_affected() == 0 {
+        return Err(Error::DocumentConflictError { document_id, current_version: current.version + 1 });
+    }
+
+    let closed = sqlx::query!(
+        "UPDATE document_suggestions
+         SET status = 'accepted', resolved_at = $1, resolved_by = $2
+         WHERE id = $3 AND status = 'pending'",
+        Utc::now().naive_utc(),
+        accepted_by,
+        suggestion.id
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|_| Error::DatabaseError)?;
+
+    if closed.rows_affected() == 0 {
+        return Err(Error::SuggestionNotPendingError { suggestion_id: suggestion.id });
+    }
+
+    // The revision is credited to whoever made the suggestion
+    let origin = match suggestion.source {
+        SuggestionSource::Ai => RevisionOrigin::AiApply,
+        SuggestionSource::User => RevisionOrigin::Suggestion,
+    };
+    let author_id = suggestion.author_id.unwrap_or(accepted_by);
+    let revision_number = record_revision(&mut tx, document_id, author_id, &new_content, origin).await?;
+
+    sqlx::query!(
+        "UPDATE document_suggestions SET revision_number = $1 WHERE id = $2",
+        revision_number,
+        suggestion.id
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|_| Error::DatabaseError)?;
+
+    enforce_storage_quota(&mut tx, owner_id, document_id).await?;
+    tx.commit().await.map_err(|_| Error::DatabaseError)?;
+
+    // Search the content with the suggestion applied
+    reindex_document(pool, document_id, &new_content).await;
+
+    Ok(revision_number)
+}
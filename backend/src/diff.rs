@@ -0,0 +1,303 @@
+// src/diff.rs
+//
+// Word and HTML aware diffs of document content.
+// Content is split into tokens first: whole tags, words, runs of whitespace and single punctuation marks.
+// The diff is computed over tokens, so a change never starts in the middle of a word or a tag.
+// Offsets in hunks count Unicode characters, like the text operations in collab/ot.rs.
+// Hunks can be applied again to content edited since they were computed, see apply_hunks.
+
+use serde::{Deserialize, Serialize};
+
+/// Above this many token comparisons the changed middle is reported as one replacement.
+/// Each comparison is a cell of the LCS table, so this keeps a table to about 1MB.
+const MAX_DIFF_CELLS: usize = 250_000;
+
+/// Characters of unchanged text around a range used to find it again in edited content
+const HUNK_CONTEXT_CHARS: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HunkKind {
+    Insert,
+    Delete,
+    Replace,
+}
+
+/// A changed range: old_text at old_start..old_end was replaced by new_text at new_start..new_end
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiffHunk {
+    pub kind: HunkKind,
+    pub old_start: usize,
+    pub old_end: usize,
+    pub new_start: usize,
+    pub new_end: usize,
+    pub old_text: String,
+    pub new_text: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RunKind {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// Consecutive tokens with the same fate, for showing a whole text with its changes marked
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiffRun {
+    #[serde(rename = "type")]
+    pub kind: RunKind,
+    pub text: String,
+}
+
+struct Token<'a> {
+    text: &'a str,
+    start: usize, // Character offset
+    len: usize,   // In characters
+}
+
+/// Split content into tags, words, whitespace runs and single other characters
+fn tokenize(content: &str) -> Vec<Token<'_>> {
+    let chars: Vec<(usize, char)> = content.char_indices().collect();
+    let byte_at = |i: usize| chars.get(i).map_or(content.len(), |(b, _)| *b);
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i].1;
+        let next = chars.get(i + 1).map(|(_, c)| *c);
+
+        let end = if c == '<' && next.is_some_and(|n| n.is_ascii_alphabetic() || n == '/' || n == '!') {
+            // A tag runs to its closing bracket, a stray '<' is just a character
+            chars[i..].iter().position(|(_, c)| *c == '>').map_or(i + 1, |p| i + p + 1)
+        } else if c == '&' && next.is_some_and(|n| n.is_ascii_alphanumeric() || n == '#') {
+            // Entities like &nbsp; are one character to the reader
+            chars[i..].iter().take(12).position(|(_, c)| *c == ';').map_or(i + 1, |p| i + p + 1)
+        } else if c.is_whitespace() {
+            i + chars[i..].iter().take_while(|(_, c)| c.is_whitespace()).count()
+        } else if c.is_alphanumeric() {
+            let mut j = i + 1;
+            while j < chars.len() {
+                let c = chars[j].1;
+                let joins_word = (c == '\'' || c == '-')
+                    && chars.get(j + 1).is_some_and(|(_, n)| n.is_alphanumeric());
+                if !c.is_alphanumeric() && !joins_word {
+                    break;
+                }
+                j += 1;
+            }
+            j
+        } else {
+            i + 1
+        };
+
+        tokens.push(Token { text: &content[byte_at(i)..byte_at(end)], start: i, len: end - i });
+        i = end;
+    }
+
+    tokens
+}
+
+/// Token level edit script as (kind, old index or new index) pairs
+fn diff_tokens(old: &[Token], new: &[Token]) -> Vec<(RunKind, usize)> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a.text == b.text).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a.text == b.text)
+        .count();
+
+    let (n, m) = (old.len() - prefix - suffix, new.len() - prefix - suffix);
+    let old_mid = &old[prefix..prefix + n];
+    let new_mid = &new[prefix..prefix + m];
+
+    let mut ops: Vec<(RunKind, usize)> = (0..prefix).map(|i| (RunKind::Equal, i)).collect();
+
+    if n * m > MAX_DIFF_CELLS {
+        ops.extend((0..n).map(|i| (RunKind::Delete, prefix + i)));
+        ops.extend((0..m).map(|j| (RunKind::Insert, prefix + j)));
+    } else {
+        // Longest common subsequence table, lcs[i][j] covers old_mid[i..] and new_mid[j..]
+        let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                lcs[i][j] = if old_mid[i].text == new_mid[j].text {
+                    lcs[i + 1][j + 1] + 1
+                } else {
+                    lcs[i + 1][j].max(lcs[i][j + 1])
+                };
+            }
+        }
+
+        let (mut i, mut j) = (0, 0);
+        while i < n || j < m {
+            if i < n && j < m && old_mid[i].text == new_mid[j].text {
+                ops.push((RunKind::Equal, prefix + i));
+                i += 1;
+                j += 1;
+            } else if i < n && (j == m || lcs[i + 1][j] >= lcs[i][j + 1]) {
+                ops.push((RunKind::Delete, prefix + i));
+                i += 1;
+            } else {
+                ops.push((RunKind::Insert, prefix + j));
+                j += 1;
+            }
+        }
+    }
+
+    ops.extend((old.len() - suffix..old.len()).map(|i| (RunKind::Equal, i)));
+    ops
+}
+
+/// The whole text as equal, deleted and inserted runs.
+pub fn diff_runs(old: &str, new: &str) -> Vec<DiffRun> {
+    let (old_tokens, new_tokens) = (tokenize(old), tokenize(new));
+    let mut runs: Vec<DiffRun> = Vec::new();
+
+    for (kind, index) in diff_tokens(&old_tokens, &new_tokens) {
+        let text = match kind {
+            RunKind::Insert => new_tokens[index].text,
+            _ => old_tokens[index].text,
+        };
+        match runs.last_mut() {
+            Some(run) if run.kind == kind => run.text.push_str(text),
+            _ => runs.push(DiffRun { kind, text: text.to_string() }),
+        }
+    }
+
+    runs
+}
+
+/// Only the changed ranges, in order. Changes separated by nothing but whitespace form one hunk.
+pub fn diff_hunks(old: &str, new: &str) -> Vec<DiffHunk> {
+    let (old_tokens, new_tokens) = (tokenize(old), tokenize(new));
+    let ops = diff_tokens(&old_tokens, &new_tokens);
+
+    let old_end_pos = old_tokens.last().map_or(0, |t| t.start + t.len);
+    let new_end_pos = new_tokens.last().map_or(0, |t| t.start + t.len);
+    let old_pos = |i: usize| old_tokens.get(i).map_or(old_end_pos, |t| t.start);
+    let new_pos = |j: usize| new_tokens.get(j).map_or(new_end_pos, |t| t.start);
+
+    // Token ranges of each change as (old_from, old_to, new_from, new_to)
+    let mut ranges: Vec<(usize, usize, usize, usize)> = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    let mut current: Option<(usize, usize)> = None;
+
+    for (kind, _) in &ops {
+        match kind {
+            RunKind::Equal => {
+                if let Some((from_i, from_j)) = current.take() {
+                    ranges.push((from_i, i, from_j, j));
+                }
+                i += 1;
+                j += 1;
+            }
+            RunKind::Delete => {
+                current.get_or_insert((i, j));
+                i += 1;
+            }
+            RunKind::Insert => {
+                current.get_or_insert((i, j));
+                j += 1;
+            }
+        }
+    }
+    if let Some((from_i, from_j)) = current {
+        ranges.push((from_i, i, from_j, j));
+    }
+
+    // Join neighbouring changes when only whitespace is left between them
+    let mut merged: Vec<(usize, usize, usize, usize)> = Vec::new();
+    for range in ranges {
+        if let Some(last) = merged.last_mut() {
+            let gap_is_whitespace = old_tokens[last.1..range.0].iter().all(|t| t.text.trim().is_empty());
+            if gap_is_whitespace {
+                last.1 = range.1;
+                last.3 = range.3;
+                continue;
+            }
+        }
+        merged.push(range);
+    }
+
+    let text_of = |tokens: &[Token], from: usize, to: usize| -> String {
+        tokens[from..to].iter().map(|t| t.text).collect()
+    };
+
+    merged
+        .into_iter()
+        .map(|(old_from, old_to, new_from, new_to)| {
+            let old_text = text_of(&old_tokens, old_from, old_to);
+            let new_text = text_of(&new_tokens, new_from, new_to);
+            let kind = match (old_text.is_empty(), new_text.is_empty()) {
+                (true, _) => HunkKind::Insert,
+                (_, true) => HunkKind::Delete,
+                _ => HunkKind::Replace,
+            };
+
+            DiffHunk {
+                kind,
+                old_start: old_pos(old_from),
+                old_end: old_pos(old_to),
+                new_start: new_pos(new_from),
+                new_end: new_pos(new_to),
+                old_text,
+                new_text,
+            }
+        })
+        .collect()
+}
+
+/// Byte index of the character at `char_index`, or the end of the text
+fn byte_index(text: &str, char_index: usize) -> usize {
+    text.char_indices().nth(char_index).map_or(text.len(), |(i, _)| i)
+}
+
+/// Apply hunks computed against `base` to `current`, which may have been edited since.
+/// Each range is located by its text and surrounding context, None if one is missing or ambiguous.
+pub fn apply_hunks(base: &str, current: &str, hunks: &[DiffHunk]) -> Option<String> {
+    // Later hunks first, so earlier ranges are not moved by the replacements
+    if base == current {
+        let mut result = base.to_string();
+        for hunk in hunks.iter().rev() {
+            let (start, end) = (byte_index(&result, hunk.old_start), byte_index(&result, hunk.old_end));
+            result.replace_range(start..end, &hunk.new_text);
+        }
+        return Some(result);
+    }
+
+    let mut result = current.to_string();
+    for (i, hunk) in hunks.iter().enumerate().rev() {
+        // Context stops at the neighbouring hunks, the one after has already been replaced
+        let previous_end = if i > 0 { hunks[i - 1].old_end } else { 0 };
+        let next_start = hunks.get(i + 1).map_or(usize::MAX, |next| next.old_start);
+        let context_start = hunk.old_start.saturating_sub(HUNK_CONTEXT_CHARS).max(previous_end);
+        let context_end = (hunk.old_end + HUNK_CONTEXT_CHARS).min(next_start);
+
+        let before = &base[byte_index(base, context_start)..byte_index(base, hunk.old_start)];
+        let after = &base[byte_index(base, hunk.old_end)..byte_index(base, context_end)];
+
+        // Edits next to the range may have changed one side of its context, so either side alone
+        // is enough as long as it only matches once
+        let start = [(before, after), (before, ""), ("", after)].iter().find_map(|(before, after)| {
+            let needle = format!("{}{}{}", before, hunk.old_text, after);
+            find_unique(&result, &needle).map(|position| position + before.len())
+        })?;
+
+        result.replace_range(start..start + hunk.old_text.len(), &hunk.new_text);
+    }
+
+    Some(result)
+}
+
+/// Byte offset of the only occurrence of `needle` in `text`, counting overlapping ones, e.g. "aa" in "aaa"
+pub fn find_unique(text: &str, needle: &str) -> Option<usize> {
+    let position = text.find(needle)?;
+    let next = position + text[position..].chars().next().map_or(1, char::len_utf8);
+    match text.get(next..).and_then(|rest| rest.find(needle)) {
+        Some(_) => None,
+        None => Some(position),
+    }
+}
@@ -4,6 +4,7 @@ use axum::http::StatusCode;
 use axum::response::IntoResponse;
 use axum::response::Response;
 use serde::Serialize;
+use serde_json::{json, Value};
 
 #[derive(Debug, Clone, Serialize, strum_macros::AsRefStr)]
 #[serde(tag = "type", content = "data")]
@@ -11,6 +12,7 @@ pub enum Error {
     // Database Errors
     MigrationExecutionError,
     MigrationKeyError,
+    DbResetDisabledError,
     DatabaseConnectionError,
 
     // User Errors
@@ -22,20 +24,42 @@ pub enum Error {
     ProfilePicError,
     ProfilePicSizeError,
     PasswordValidationError,
+    AccountDisabledError, // Disabled by an admin, cannot sign in
+
+    // Auth Token Errors
+    NoAuthTokenError,
+    InvalidAuthTokenError,
+
+    // Session Errors
+    SessionCreationError,
+    SessionNotFoundError,
 
     // Document Errors
     DocumentNotFoundError { document_id: i32 },
     DocumentUpdateError { document_id: i32 },
     DocumentCreationError,
     DocumentDeletionError { document_id: i32 },
+    DocumentConflictError { document_id: i32, current_version: i32 },
+    RevisionNotFoundError { document_id: i32, revision_number: i32 },
+    PublicationNotFoundError { slug: String },
+    CommentNotFoundError { comment_id: i32 },
+    SuggestionNotFoundError { suggestion_id: i32 },
+    SuggestionNotPendingError { suggestion_id: i32 },
 
     // General Errors
     InvalidRequestFormatError,
+    RouteNotFoundError { path: String },
 
     // Document Permission Errors
     PermissionError,
     PermissionCreationError,
 
+    // Share Link Errors
+    ShareLinkNotFoundError,
+    ShareLinkExpiredError,
+    ShareLinkPasswordError,
+    ShareLinkLockedError { retry_after_seconds: i64 }, // Too many wrong passwords
+
     // Signup Errors
     EmailAlreadyExistsError,
     DatabaseError,
@@ -51,8 +75,12 @@ pub enum Error {
     EmbeddingError,
     APIKeyError,
     LlmQueryError,
+    LlmResponseFormatError, // Reply was not JSON of the expected shape, even after a retry
+    UnknownLlmProviderError { provider: String },
     InsufficientAiCredits,
+    TokenBudgetExceededError { budget: i64 }, // Monthly token budget of the user's plan is used up
     FailedApplyChanges,
+    EditAnchorMismatchError { document_id: i32 }, // Text quoted by an AI edit is not in the document (any more)
     
     // Preference Errors
     PreferenceNotFoundError { preference_id: i32 },
@@ -66,15 +94,30 @@ pub enum Error {
 #[allow(non_camel_case_types)]
 pub enum ClientError {
     LOGIN_FAIL,
+    ACCOUNT_DISABLED,
     NO_AUTH,
     EMAIL_ALREADY_EXISTS,
+    DOCUMENT_CONFLICT,
+    SHARE_LINK_EXPIRED,
+    SHARE_LINK_PASSWORD,
+    SHARE_LINK_LOCKED,
     PASSWORD_VALIDATION_ERROR,
     INVALID_PARAMS,
     RESOURCE_NOT_FOUND,
     INSUFFICIENT_AI_CREDITS,
+    TOKEN_BUDGET_EXCEEDED,
     SERVICE_ERROR,
 }
 
+// Allows errors to be returned from main, e.g. by the migrate command
+impl core::fmt::Display for Error {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::result::Result<(), core::fmt::Error> {
+        write!(fmt, "{self:?}")
+    }
+}
+
+impl std::error::Error for Error {}
+
 impl IntoResponse for Error {
     fn into_response(self) -> Response {
         println!("->> {:<12} - {self:?}", "INTO_RESPONSE");
@@ -95,9 +138,13 @@ impl Error {
         match self {
             // Auth / Login Errors
             Self::LoginFailError => (StatusCode::INTERNAL_SERVER_ERROR, ClientError::LOGIN_FAIL),
+            Self::AccountDisabledError => (StatusCode::FORBIDDEN, ClientError::ACCOUNT_DISABLED),
             Self::UserNotFoundError { .. } | Self::PermissionError => (StatusCode::FORBIDDEN, ClientError::NO_AUTH),
             Self::EmailAlreadyExistsError => (StatusCode::CONFLICT, ClientError::EMAIL_ALREADY_EXISTS),
             Self::PasswordValidationError => (StatusCode::BAD_REQUEST, ClientError::PASSWORD_VALIDATION_ERROR),
+            Self::NoAuthTokenError | Self::InvalidAuthTokenError => (StatusCode::UNAUTHORIZED, ClientError::NO_AUTH),
+            Self::SessionNotFoundError => (StatusCode::NOT_FOUND, ClientError::RESOURCE_NOT_FOUND),
+            Self::SessionCreationError => (StatusCode::INTERNAL_SERVER_ERROR, ClientError::LOGIN_FAIL),
 
             // Request / Model Errors
             Self::InvalidRequestFormatError => (StatusCode::BAD_REQUEST, ClientError::INVALID_PARAMS),
@@ -105,20 +152,35 @@ impl Error {
             
             // AI Specific Errors
             Self::InsufficientAiCredits => (StatusCode::PAYMENT_REQUIRED, ClientError::INSUFFICIENT_AI_CREDITS),
+            Self::TokenBudgetExceededError { .. } => (StatusCode::PAYMENT_REQUIRED, ClientError::TOKEN_BUDGET_EXCEEDED),
             Self::APIKeyError => (StatusCode::INTERNAL_SERVER_ERROR, ClientError::SERVICE_ERROR), // Could be config issue
             Self::EmbeddingError => (StatusCode::INTERNAL_SERVER_ERROR, ClientError::SERVICE_ERROR),
             Self::LlmQueryError => (StatusCode::INTERNAL_SERVER_ERROR, ClientError::SERVICE_ERROR),
+            Self::LlmResponseFormatError => (StatusCode::BAD_GATEWAY, ClientError::SERVICE_ERROR),
+            Self::UnknownLlmProviderError { .. } => (StatusCode::INTERNAL_SERVER_ERROR, ClientError::SERVICE_ERROR),
 
             // Apply Suggestion Errors
             Self::FailedApplyChanges { .. } => (StatusCode::INTERNAL_SERVER_ERROR, ClientError::SERVICE_ERROR),
+            Self::EditAnchorMismatchError { .. } => (StatusCode::CONFLICT, ClientError::DOCUMENT_CONFLICT),
 
             // Resource Errors (Could argue some are Forbidden/No_Auth if based on user context)
             Self::DocumentNotFoundError { .. } => (StatusCode::NOT_FOUND, ClientError::RESOURCE_NOT_FOUND),
+            Self::RevisionNotFoundError { .. } => (StatusCode::NOT_FOUND, ClientError::RESOURCE_NOT_FOUND),
+            Self::PublicationNotFoundError { .. } => (StatusCode::NOT_FOUND, ClientError::RESOURCE_NOT_FOUND),
+            Self::CommentNotFoundError { .. } => (StatusCode::NOT_FOUND, ClientError::RESOURCE_NOT_FOUND),
+            Self::SuggestionNotFoundError { .. } => (StatusCode::NOT_FOUND, ClientError::RESOURCE_NOT_FOUND),
+            Self::SuggestionNotPendingError { .. } => (StatusCode::CONFLICT, ClientError::INVALID_PARAMS),
             Self::ProjectNotFoundError { .. } => (StatusCode::NOT_FOUND, ClientError::RESOURCE_NOT_FOUND),
+            Self::RouteNotFoundError { .. } => (StatusCode::NOT_FOUND, ClientError::RESOURCE_NOT_FOUND),
             Self::DocumentCreationError => (StatusCode::INTERNAL_SERVER_ERROR, ClientError::SERVICE_ERROR),
             Self::DocumentUpdateError { .. } => (StatusCode::INTERNAL_SERVER_ERROR, ClientError::SERVICE_ERROR),
+            Self::DocumentConflictError { .. } => (StatusCode::CONFLICT, ClientError::DOCUMENT_CONFLICT),
             Self::DocumentDeletionError { .. } => (StatusCode::INTERNAL_SERVER_ERROR, ClientError::SERVICE_ERROR),
             Self::PermissionCreationError => (StatusCode::INTERNAL_SERVER_ERROR, ClientError::SERVICE_ERROR),
+            Self::ShareLinkNotFoundError => (StatusCode::NOT_FOUND, ClientError::RESOURCE_NOT_FOUND),
+            Self::ShareLinkExpiredError => (StatusCode::GONE, ClientError::SHARE_LINK_EXPIRED),
+            Self::ShareLinkPasswordError => (StatusCode::FORBIDDEN, ClientError::SHARE_LINK_PASSWORD),
+            Self::ShareLinkLockedError { .. } => (StatusCode::TOO_MANY_REQUESTS, ClientError::SHARE_LINK_LOCKED),
             Self::UserCreationError => (StatusCode::INTERNAL_SERVER_ERROR, ClientError::SERVICE_ERROR),
             Self::UserUpdateError { .. } => (StatusCode::INTERNAL_SERVER_ERROR, ClientError::SERVICE_ERROR),
             Self::UserIdUpdateError => (StatusCode::INTERNAL_SERVER_ERROR, ClientError::SERVICE_ERROR), // Internal state issue
@@ -129,8 +191,9 @@ impl Error {
             // Database / Migration Errors (Internal Server Errors)
             Self::DatabaseError | 
             Self::DatabaseConnectionError | 
-            Self::MigrationExecutionError | 
-            Self::MigrationKeyError => (StatusCode::INTERNAL_SERVER_ERROR, ClientError::SERVICE_ERROR),
+            Self::MigrationExecutionError => (StatusCode::INTERNAL_SERVER_ERROR, ClientError::SERVICE_ERROR),
+            Self::MigrationKeyError => (StatusCode::FORBIDDEN, ClientError::NO_AUTH),
+            Self::DbResetDisabledError => (StatusCode::NOT_FOUND, ClientError::RESOURCE_NOT_FOUND),
 
             // Preference Errors
             Self::PreferenceNotFoundError { .. } => (StatusCode::NOT_FOUND, ClientError::RESOURCE_NOT_FOUND),
@@ -142,4 +205,19 @@ impl Error {
             _ => (StatusCode::INTERNAL_SERVER_ERROR, ClientError::SERVICE_ERROR),
         }
     }
+    /// Details the client needs to recover from the error, sent along as error.data
+    pub fn client_data(&self) -> Option<Value> {
+        match self {
+            // Let the editor know which version it has to merge with before saving again
+            Self::DocumentConflictError { document_id, current_version } => Some(json!({
+                "document_id": document_id,
+                "current_version": current_version,
+            })),
+            // When the link can be tried again
+            Self::ShareLinkLockedError { retry_after_seconds } => Some(json!({
+                "retry_after_seconds": retry_after_seconds,
+            })),
+            _ => None,
+        }
+    }
 }
\ No newline at end of file
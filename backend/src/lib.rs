@@ -1,7 +1,18 @@
 // Global Defined Functions
-use anyhow::Result;
 use httpc_test::Client;
-use tower_cookies::Cookies;
+use serde_json::json;
+
+mod error;
+pub use self::error::{Error, Result};
+
+// Modules that need no server or database, shared with the binary so tests can call them directly
+pub mod diff;
+pub mod rag {
+    pub mod chunk;
+    pub mod llm;
+    pub mod provider;
+    pub mod structured;
+}
 
 pub fn result_to_string(result: &anyhow::Result<()>) -> &str {
     if result.is_ok() {
@@ -11,9 +22,12 @@ pub fn result_to_string(result: &anyhow::Result<()>) -> &str {
     }
 }
 
-pub async fn test_reset_db(hc: &Client) -> Result<()> {
+pub async fn test_reset_db(hc: &Client) -> anyhow::Result<()> {
     print!("TEST - Reset Database");
-    let response = hc.do_get("/api/db/reset?secret=secret_key").await?;
+    // The server under test must run with ALLOW_DB_RESET=true and the same DB_RESET_SECRET
+    dotenv::dotenv().ok();
+    let secret = std::env::var("DB_RESET_SECRET").unwrap_or_default();
+    let response = hc.do_post("/api/db/reset", json!({ "secret": secret })).await?;
     response.print().await?;
 
     if !response.status().is_success() {
@@ -25,15 +39,3 @@ pub async fn test_reset_db(hc: &Client) -> Result<()> {
 
     Ok(())
 }
-
-// Helper function to extract user ID from auth cookie
-pub fn get_user_id_from_cookie(cookies: &Cookies) -> Option<i32> {
-    cookies.get("auth-token").and_then(|cookie| {
-        let value = cookie.value();
-        // Parse user ID from cookie value (format: "user-{id}.exp.sign")
-        value
-            .strip_prefix("user-")
-            .and_then(|s| s.split('.').next())
-            .and_then(|id_str| id_str.parse::<i32>().ok())
-    })
-}
\ No newline at end of file
@@ -1,8 +1,9 @@
+use std::net::IpAddr;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use crate::{Error, Result};
 use crate::error::ClientError;
-use backend::get_user_id_from_cookie;
+use crate::auth::token::get_user_id_from_cookie;
 use http::{Method, Uri};
 use serde::Serialize;
 use serde_json::{json, Value};
@@ -34,7 +35,7 @@ pub async fn log_request(
         timestamp: timestamp.to_string(),
         req_path: uri.to_string(),
         req_method: req_method.to_string(),
-        user_id: get_user_id_from_cookie(cookies),
+        user_id: get_user_id_from_cookie(cookies).ok(),
         client_error_type: client_error.map(|ce| ce.as_ref().to_string()),
         error_type,
         error_data,
@@ -64,4 +65,37 @@ struct RequestLogLine {
     // Http request attributes
     req_path: String,
     req_method: String,
-}
\ No newline at end of file
+}
+/// Record a sensitive action, whoever triggered it and however it ended
+pub fn log_audit_event(event: &str, user_id: Option<i32>, ip: IpAddr, outcome: &str) {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis();
+
+    let log_line = AuditLogLine {
+        timestamp: timestamp.to_string(),
+        event: event.to_string(),
+        user_id,
+        ip: ip.to_string(),
+        outcome: outcome.to_string(),
+    };
+
+    println!("  ->> log_audit: \n{}", json!(log_line));
+
+    // TODO SEND THE ABOVE LOG TO A LOGGING SERVICE
+}
+
+#[skip_serializing_none]
+#[derive(Serialize)]
+struct AuditLogLine {
+    timestamp: String,
+    event: String,
+
+    // Who made the request
+    user_id: Option<i32>,
+    ip: String,
+
+    // "success" or the error that stopped it
+    outcome: String,
+}
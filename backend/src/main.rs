@@ -1,7 +1,10 @@
 // backend/src/main.rs
 pub use self::error::{Error, Result}; // export types
 
+mod auth;
+mod collab;
 mod db;
+mod diff;
 mod error;
 mod models;
 mod web;
@@ -10,7 +13,7 @@ mod log;
 
 use axum::middleware;
 use axum::response::{IntoResponse, Response};
-use axum::{routing::get_service, Extension, Json, Router};
+use axum::{Extension, Json, Router};
 use dotenv::dotenv;
 use http::header::HeaderValue;
 use http::{Method, Uri, Request};
@@ -21,22 +24,52 @@ use std::env; // Import env module
 use std::str::FromStr; // Import FromStr trait for SocketAddr parsing
 use tower_cookies::{CookieManagerLayer, Cookies};
 use tower_http::cors::CorsLayer;
-use tower_http::services::ServeDir;
 use serde_json::json; // Import the json! macro
 use axum::middleware::Next;
 use axum::body::Body;
 
+use crate::collab::room::CollabHub;
+use crate::db::migrate::{load_fixtures, run_migrations};
 use crate::db::pool::create_pool; // Import the connection pool
+use crate::web::middleware::auth::{mw_require_auth, mw_validate_session};
+use crate::web::static_files::static_fallback;
 
 #[tokio::main] // Indicates that the main function is an async function using tokiopub mod web;
 async fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
     // Load environment variables from .env file
     dotenv().ok();
 
+    /*
+    / Command line
+    / `backend migrate` applies pending migrations and exits
+    / `backend seed` applies pending migrations, loads the development fixtures and exits
+    / `backend` with no arguments starts the server
+    */
+    let command = env::args().nth(1);
+    if let Some(command) = command.as_deref() {
+        let pool = create_pool().await;
+        let applied = run_migrations(&pool).await?;
+        println!("Applied {} migration(s)", applied);
+
+        match command {
+            "migrate" => {}
+            "seed" => {
+                load_fixtures(&pool).await?;
+                println!("Loaded fixtures");
+            }
+            other => {
+                eprintln!("Unknown command '{}', expected 'migrate' or 'seed'", other);
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+
     // Read environment variables
     let api_base_url = env::var("API_BASE_URL").expect("API_BASE_URL must be set");
     let front_end_url = env::var("FRONTEND_URL").expect("FRONTEND_URL must be set");
     let bind_address = env::var("BIND_ADDRESS").expect("BIND_ADDRESS must be set");
+    auth::token::token_secret(); // Panics early if AUTH_TOKEN_SECRET is missing
 
     /*
     / Creating the Pool using SQLx
@@ -45,6 +78,12 @@ async fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
     */
     let pool = create_pool().await;
 
+    // Bring the schema up to date unless migrations are run as a separate deploy step
+    if env::var("MIGRATE_ON_STARTUP").map_or(true, |v| v != "false") {
+        let applied = run_migrations(&pool).await?;
+        println!("Applied {} migration(s)", applied);
+    }
+
     /*
     / Configure CORS
     / CORS is needed when a frontend (running on one domain or port)
@@ -70,12 +109,22 @@ async fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
     / When the route is hit, the hello function is called
     */
     let user_api_routes = web::routes::user_controller::user_routes();
-    let doc_api_routes = web::routes::doc_controller::doc_routes();
+    let doc_api_routes = web::routes::doc_controller::doc_routes()
+        .merge(web::routes::revision_controller::revision_routes())
+        .merge(web::routes::collab_controller::collab_routes())
+        .merge(web::routes::share_controller::document_link_routes())
+        .merge(web::routes::publish_controller::publish_routes())
+        .merge(web::routes::comment_controller::comment_routes())
+        .merge(web::routes::suggestion_controller::suggestion_routes());
     let db_api_routes = web::routes::db_controller::db_routes(pool.clone());
-    let project_api_routes = web::routes::proj_controller::project_routes();
+    let project_api_routes = web::routes::proj_controller::project_routes()
+        .merge(web::routes::share_controller::project_link_routes());
+    let share_api_routes = web::routes::share_controller::share_routes();
+    let public_routes = web::routes::publish_controller::public_routes();
     let key_api_routes = web::routes::key_controller::key_routes();
     let writing_assistant_routes = web::routes::ai_controller::writing_assistant_routes();
     let pref_api_routes = web::routes::pref_controller::pref_routes();
+    let admin_api_routes = web::routes::admin_controller::admin_routes();
 
     let cookie_layer = CookieManagerLayer::new();
 
@@ -84,14 +133,20 @@ async fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
         .nest("/api/users", user_api_routes) // Merge routes from user_controller
         .nest("/api/document", doc_api_routes) // Merge routes from document_controller
         .nest("/api/project", project_api_routes)
+        .nest("/api/share", share_api_routes)
         .nest("/api/command", key_api_routes)
         .nest("/api/writing-assistant", writing_assistant_routes)
         .nest("/api/preference", pref_api_routes)
+        .nest("/api/admin", admin_api_routes) // Admins only, checked by each handler
+        .nest("/p", public_routes) // Published documents, readable without an account
+        .fallback(static_fallback) // Built frontend, unknown /api paths get a JSON 404
+        .layer(middleware::from_fn(mw_require_auth)) // Reject unauthenticated API calls outside the allow-list
+        .layer(middleware::from_fn(mw_validate_session)) // Resolve the auth cookie to a session, drop it if revoked
         .layer(Extension(pool.clone())) // Make the pool available to all handlers,Attachs the PgPool as an Axum Extension
+        .layer(Extension(CollabHub::new())) // Live editing rooms shared by all document WebSockets
         .layer(middleware::from_fn(mw_log_requests))
         .layer(cookie_layer)
-        .layer(cors) // Add the CORS layer
-        .fallback_service(routes_static()); // Fallback route if route cannot be found above
+        .layer(cors); // Add the CORS layer
 
     /*
     / Bind the router to a specific port
@@ -107,19 +162,13 @@ async fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
     / We will start the server with the configured router and address
     */
     axum::Server::bind(&addr)
-        .serve(app.into_make_service())
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>()) // Connect info is used to record session IPs
         .await
         .unwrap();
 
     Ok(())
 }
 
-// Fallback Route If One Cannot Be Resolved
-fn routes_static() -> Router {
-    println!("->> {:<12} - routes_static", "FALLBACK HIT");
-    Router::new().nest_service("/", get_service(ServeDir::new("./")))
-}
-
 // Custom logging middleware
 async fn mw_log_requests(
     cookies: Cookies, // Extractor for cookies
@@ -144,13 +193,18 @@ async fn mw_log_requests(
         client_status_error
         .as_ref()
         .map(|(status_code, client_error)| {
-            let client_error_body = json!({
+            let mut client_error_body = json!({
                 "error": {
                     "type": client_error.as_ref(),
                     "req_uuid": uuid.to_string(),
                 }
             });
 
+            // Some errors carry details the client can act on
+            if let Some(data) = service_error.and_then(|e| e.client_data()) {
+                client_error_body["error"]["data"] = data;
+            }
+
             println!("  ->> client_error_body: {client_error_body}");
 
             // Build new response for client
@@ -0,0 +1,101 @@
+use serde::{Deserialize, Serialize};
+use chrono::NaiveDateTime;
+use serde_json::Value;
+use sqlx::types::Json;
+
+/// What an admin did, recorded in admin_audit_log
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, sqlx::Type, PartialEq)]
+#[sqlx(type_name = "admin_action_enum", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum AdminAction {
+    AdjustCredits,
+    UpdateLimits, // Plan, project and document limits or storage quota
+    DisableUser,
+    EnableUser,
+}
+
+/// A user as admins see them, with their limits and what they are using of them
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct AdminUserInfo {
+    pub id: i32,
+    pub name: String,
+    pub email: String,
+    pub is_admin: bool,
+    pub disabled_at: Option<NaiveDateTime>,
+    pub plan: String,
+    pub ai_credits: i32,
+    pub max_projects: i32,
+    pub max_documents: i32,
+    pub storage_quota_bytes: Option<i64>, // None is the default quota
+    pub project_count: i64,
+    pub document_count: i64,
+    pub storage_bytes: i64, // Owned documents plus their revision history
+}
+
+/// One change made by an admin, newest first
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct AdminAuditEntry {
+    pub id: i32,
+    pub admin_id: Option<i32>,
+    pub admin_name: Option<String>,
+    pub action: AdminAction,
+    pub target_user_id: Option<i32>,
+    pub details: Json<Value>, // Values before and after the change, and the reason if one was given
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AdminUserQuery {
+    pub q: Option<String>, // Matches name or email
+    pub disabled: Option<bool>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AdminAuditQuery {
+    pub target_user_id: Option<i32>,
+    pub limit: Option<i64>,
+    pub before: Option<i32>, // Only entries older than this id, for paging
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AdjustCreditsPayload {
+    pub delta: i32, // Added to the balance, negative to take credits away
+    pub reason: Option<String>,
+}
+
+/// Only the fields that are present are changed
+#[derive(Debug, Deserialize)]
+pub struct UpdateUserLimitsPayload {
+    pub plan: Option<String>,
+    pub max_projects: Option<i32>,
+    pub max_documents: Option<i32>,
+    // null goes back to the default quota, leaving it out keeps the current one
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    pub storage_quota_bytes: Option<Option<i64>>,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AccountStatusPayload {
+    pub reason: Option<String>,
+}
+
+/// Storage used by one user, for the largest users in the storage overview
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct UserStorageUsage {
+    pub user_id: i32,
+    pub name: String,
+    pub email: String,
+    pub storage_bytes: i64,
+    pub storage_quota_bytes: Option<i64>, // None is the default quota
+}
+
+/// How many accounts there are, and how many of them are admins or disabled
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct UserCounts {
+    pub total: i64,
+    pub admins: i64,
+    pub disabled: i64,
+}
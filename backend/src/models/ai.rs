@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 use chrono::NaiveDateTime;
 
+use crate::diff::DiffHunk;
+
 #[derive(Debug, Serialize, Deserialize, Clone, sqlx::Type, PartialEq)]
 #[sqlx(type_name = "message_role_enum", rename_all = "lowercase")]
 pub enum MessageRole {
@@ -132,15 +134,37 @@ pub struct ApplySuggestionPayload {
 
 #[derive(Debug, Serialize)]
 pub struct SuggestedDocumentChange {
+    pub suggestion_id: i32, // Pending suggestion the change was stored as
     pub document_id: i32,
     pub old_content: String,
     pub new_content: String,
+    pub hunks: Vec<DiffHunk>, // Changed ranges of old_content
+}
+
+/// Reply of apply-suggestion: the changes stored as suggestions and the documents whose edits were rejected
+#[derive(Debug, Serialize)]
+pub struct ApplySuggestionResponse {
+    pub changes: Vec<SuggestedDocumentChange>,
+    pub rejected: Vec<RejectedDocumentChange>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RejectedDocumentChange {
+    pub document_id: i32,
+    pub reason: String, // Which edit could not be placed and why
+}
+
+/// Replacement of one quoted piece of a document, see rag/edit.rs
+#[derive(Debug, Deserialize)]
+pub struct LlmEditOperation {
+    pub find: String, // Exact text of the document, must occur once. Empty only to fill an empty document
+    pub replace: String,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct LlmDocChange {
     pub document_id: i32,
-    pub new_content: String,
+    pub edits: Vec<LlmEditOperation>,
 }
 
 #[derive(Serialize)]
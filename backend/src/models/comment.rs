@@ -0,0 +1,50 @@
+use serde::{Deserialize, Serialize};
+use chrono::NaiveDateTime;
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct DocumentComment {
+    pub id: i32,
+    pub document_id: i32,
+    pub thread_id: Option<i32>, // None for the comment that starts a thread
+    pub author_id: Option<i32>,
+    pub author_name: Option<String>,
+    pub content: String,
+    pub anchor_start: Option<i32>,
+    pub anchor_end: Option<i32>,
+    pub quoted_text: Option<String>,
+    pub resolved_at: Option<NaiveDateTime>,
+    pub resolved_by: Option<i32>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub mentions: Vec<i32>, // Ids of the mentioned users
+}
+
+/// A thread's first comment with its replies, oldest first
+#[derive(Debug, Serialize)]
+pub struct CommentThread {
+    #[serde(flatten)]
+    pub comment: DocumentComment,
+    pub replies: Vec<DocumentComment>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateCommentPayload {
+    pub content: String,
+    pub anchor_start: i32,
+    pub anchor_end: i32,
+    pub quoted_text: String,
+    pub mentions: Option<Vec<i32>>,
+}
+
+/// Used both for replying to a thread and for editing a comment
+#[derive(Debug, Deserialize)]
+pub struct CommentContentPayload {
+    pub content: String,
+    pub mentions: Option<Vec<i32>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CommentListQuery {
+    pub resolved: Option<bool>,  // Only resolved or only open threads, both if not given
+    pub mentioned: Option<bool>, // Only threads that mention the current user
+}
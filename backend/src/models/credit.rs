@@ -0,0 +1,77 @@
+use serde::{Deserialize, Serialize};
+use chrono::NaiveDateTime;
+
+/// What the credits of a ledger entry were spent on
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, sqlx::Type, PartialEq)]
+#[sqlx(type_name = "ai_operation_enum", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum AiOperation {
+    Chat, // Writing assistant message, plain or streamed
+    Grammar,
+    Summarize,
+    Rephrase,
+    Expand,
+    Shrink,
+    Rewrite,
+    FactCheck,
+    SpellCheck,
+    ApplySuggestion,
+    ProactiveDiff, // Deciding whether to show a suggestion's diff right away
+    SanitizeText,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, sqlx::Type, PartialEq)]
+#[sqlx(type_name = "ai_credit_status_enum", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum AiCreditStatus {
+    Reserved,  // Taken from the balance while the operation runs
+    Committed, // The operation succeeded, the credits are spent
+    Released,  // The operation failed, the credits were given back
+}
+
+/// One AI operation with what it was charged, newest first in the usage history
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct AiCreditLedgerEntry {
+    pub id: i32,
+    pub operation: AiOperation,
+    pub amount: i32, // Credits, reserved while the operation runs, then what it was charged
+    pub status: AiCreditStatus,
+    pub failure: Option<String>,
+    pub prompt_tokens: i32,
+    pub completion_tokens: i32,
+    pub tokens_estimated: bool,
+    pub created_at: NaiveDateTime,
+    pub settled_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Plan {
+    pub name: String,
+    pub description: String,
+    pub price_cents: i32,
+    pub monthly_token_budget: Option<i64>, // None is unlimited
+    pub refill_credits: i32,
+    pub refill_interval_days: i32,
+}
+
+/// The user's plan, how much of it is used this month and the latest operations
+#[derive(Debug, Serialize)]
+pub struct AiUsageSummary {
+    pub plan: Plan,
+    pub ai_credits: i32,
+    pub period_start: NaiveDateTime,
+    pub tokens_used: i64,
+    pub next_refill_at: NaiveDateTime,
+    pub history: Vec<AiCreditLedgerEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AiUsageQuery {
+    pub limit: Option<i64>,
+    pub before: Option<i32>, // Only entries older than this ledger id, for paging
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SelectPlanPayload {
+    pub plan: String, // Name of the plan to move to
+}
@@ -1,5 +1,6 @@
 use serde::Deserialize;
 
+/// Body of POST /api/db/reset
 #[derive(Deserialize)]
 pub struct WipeParams {
     pub secret: Option<String>,
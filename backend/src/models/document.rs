@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 use chrono::NaiveDateTime;
 
+use crate::models::revision::RevisionOrigin;
+
 #[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
 pub struct Document {
     pub id: i32,
@@ -11,6 +13,7 @@ pub struct Document {
     pub user_id: Option<i32>,
     pub is_starred: Option<bool>,
     pub is_trashed: Option<bool>,
+    pub version: i32,
 }
 
 #[derive(Debug, Deserialize)]
@@ -25,5 +28,8 @@ pub struct CreateDocumentPayload {
 pub struct UpdateDocumentPayload {
     pub name: String,
     pub content: Option<String>,
-    pub updated_at: NaiveDateTime
+    pub updated_at: NaiveDateTime,
+    pub version: i32, // Version the edit is based on, a stale version is rejected with 409
+    #[serde(default)]
+    pub origin: Option<RevisionOrigin>, // "ai_apply" when saving an applied suggestion, manual otherwise
 }
\ No newline at end of file
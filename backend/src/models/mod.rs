@@ -1,9 +1,18 @@
+pub mod admin;
+pub mod comment;
+pub mod credit;
 pub mod db;
 pub mod document;
 pub mod permission;
 pub mod project;
 pub mod project_permission;
+pub mod publication;
+pub mod role;
 pub mod user;
 pub mod commands;
 pub mod ai;
-pub mod storage;
\ No newline at end of file
+pub mod storage;
+pub mod session;
+pub mod revision;
+pub mod share_link;
+pub mod suggestion;
\ No newline at end of file
@@ -1,11 +1,13 @@
 use serde::{Deserialize, Serialize};
 use chrono::NaiveDateTime;
 
+use crate::models::role::Role;
+
 #[derive(Debug, sqlx::FromRow, Serialize, Deserialize)]
 pub struct DocumentPermission {
     pub document_id: i32,
     pub user_id: i32,
-    pub role: String,
+    pub role: Role,
     pub created_at: NaiveDateTime,
 }
 
@@ -14,17 +16,18 @@ pub struct UserPermissions {
     pub user_id: i32,
     pub name: String,
     pub email: String,
-    pub role: String,
+    pub role: Role,
+    pub inherited: bool, // Access comes from a project, not a permission on the document
 }
 
 #[derive(Debug, Deserialize)]
 pub struct CreatePermissionPayload {
     pub user_id: i32,
-    pub role: String
+    pub role: Role
 }
 
 #[derive(Debug, Deserialize)]
 pub struct UpdatePermissionPayload {
     pub user_id: i32,
-    pub role: String
+    pub role: Role
 } 
\ No newline at end of file
@@ -1,11 +1,13 @@
 use serde::{Deserialize, Serialize};
 use chrono::NaiveDateTime;
 
+use crate::models::role::Role;
+
 #[derive(Debug, sqlx::FromRow, Serialize, Deserialize)]
 pub struct ProjectPermission {
     pub project_id: i32,
     pub user_id: i32,
-    pub role: String,
+    pub role: Role,
     pub created_at: NaiveDateTime,
 }
 
@@ -14,17 +16,17 @@ pub struct UserProjectPermissions {
     pub user_id: i32,
     pub name: String,
     pub email: String,
-    pub role: String,
+    pub role: Role,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct CreateProjectPermissionPayload {
     pub user_id: i32,
-    pub role: String
+    pub role: Role
 }
 
 #[derive(Debug, Deserialize)]
 pub struct UpdateProjectPermissionPayload {
     pub user_id: i32,
-    pub role: String
+    pub role: Role
 } 
\ No newline at end of file
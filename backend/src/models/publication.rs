@@ -0,0 +1,16 @@
+use serde::Serialize;
+use chrono::NaiveDateTime;
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct DocumentPublication {
+    pub document_id: i32,
+    pub slug: String,
+    pub title: String,
+    #[serde(skip_serializing)]
+    pub content: String,
+    pub revision_number: Option<i32>,
+    pub is_published: bool,
+    pub published_by: Option<i32>,
+    pub published_at: NaiveDateTime,
+    pub view_count: i64,
+}
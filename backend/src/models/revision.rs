@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+use chrono::NaiveDateTime;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, sqlx::Type, PartialEq)]
+#[sqlx(type_name = "revision_origin_enum", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum RevisionOrigin {
+    Manual,     // Saved from the editor
+    AiApply,    // Saved after applying a writing assistant suggestion
+    Restore,    // Created by restoring an older revision
+    Suggestion, // Saved after accepting a collaborator's suggestion
+}
+
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+pub struct DocumentRevision {
+    pub id: i32,
+    pub document_id: i32,
+    pub revision_number: i32,
+    pub author_id: Option<i32>,
+    pub author_name: Option<String>,
+    pub content: String,
+    pub origin: RevisionOrigin,
+    pub created_at: NaiveDateTime,
+}
+
+/// A revision without its content, used when listing a document's history
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+pub struct RevisionSummary {
+    pub id: i32,
+    pub revision_number: i32,
+    pub author_id: Option<i32>,
+    pub author_name: Option<String>,
+    pub origin: RevisionOrigin,
+    pub created_at: NaiveDateTime,
+    pub size_bytes: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RevisionDiffQuery {
+    pub from: i32,
+    pub to: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DocumentDiffQuery {
+    pub from: i32,
+    pub to: Option<i32>, // The current content if not given
+}
@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+
+/// A user's role on a document or project.
+/// Variants are ordered from least to most capable, a role grants everything the roles below it do.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, sqlx::Type, PartialEq, Eq, PartialOrd, Ord)]
+#[sqlx(type_name = "permission_role_enum", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Viewer,    // Can read
+    Commenter, // Can read and comment
+    Editor,    // Can change content
+    Owner,     // Can also share, delete and transfer ownership
+}
+
+impl Role {
+    /// Whether this role is enough for an action that requires `required`
+    pub fn allows(self, required: Role) -> bool {
+        self >= required
+    }
+}
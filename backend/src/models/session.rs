@@ -0,0 +1,25 @@
+use serde::Serialize;
+use chrono::NaiveDateTime;
+use uuid::Uuid;
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct UserSession {
+    pub id: Uuid,
+    pub created_at: NaiveDateTime,
+    pub last_seen: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+}
+
+/// Session as returned to the client, flagging the one making the request
+#[derive(Debug, Serialize)]
+pub struct SessionInfo {
+    pub id: Uuid,
+    pub created_at: NaiveDateTime,
+    pub last_seen: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub is_current: bool,
+}
@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+use chrono::NaiveDateTime;
+
+use crate::models::role::Role;
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct ShareLink {
+    pub id: i32,
+    pub token: String,
+    pub document_id: Option<i32>,
+    pub project_id: Option<i32>,
+    pub role: Role,
+    pub created_by: Option<i32>,
+    pub created_at: NaiveDateTime,
+    pub expires_at: Option<NaiveDateTime>,
+    pub password_hash: Option<String>,
+    pub max_uses: Option<i32>,
+    pub use_count: i32,
+    pub revoked_at: Option<NaiveDateTime>,
+}
+
+/// Share link as shown to the owner of the document or project, without the password hash
+#[derive(Debug, Serialize)]
+pub struct ShareLinkInfo {
+    pub id: i32,
+    pub token: String,
+    pub role: Role,
+    pub created_by: Option<i32>,
+    pub created_at: NaiveDateTime,
+    pub expires_at: Option<NaiveDateTime>,
+    pub has_password: bool,
+    pub max_uses: Option<i32>,
+    pub use_count: i32,
+}
+
+impl From<ShareLink> for ShareLinkInfo {
+    fn from(link: ShareLink) -> Self {
+        Self {
+            id: link.id,
+            token: link.token,
+            role: link.role,
+            created_by: link.created_by,
+            created_at: link.created_at,
+            expires_at: link.expires_at,
+            has_password: link.password_hash.is_some(),
+            max_uses: link.max_uses,
+            use_count: link.use_count,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateShareLinkPayload {
+    pub role: Role,
+    pub expires_at: Option<NaiveDateTime>, // UTC
+    pub password: Option<String>,
+    pub max_uses: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AcceptShareLinkPayload {
+    pub password: Option<String>,
+}
@@ -1,5 +1,5 @@
 use serde::{Deserialize, Serialize};
-use sqlx::PgPool;
+use sqlx::{PgExecutor, PgPool};
 use std::env;
 
 /// Storage configuration that can be adjusted based on environment
@@ -13,6 +13,9 @@ pub struct StorageConfig {
     
     /// Maximum number of users we expect to support
     pub expected_max_users: i32,
+
+    /// Maximum number of revisions kept per document (default: 50)
+    pub max_revisions_per_document: i64,
 }
 
 impl Default for StorageConfig {
@@ -31,15 +34,29 @@ impl Default for StorageConfig {
             .ok()
             .and_then(|v| v.parse::<i32>().ok())
             .unwrap_or(1000); // Default expectation: 1000 users
+
+        let max_revisions_per_document = env::var("MAX_REVISIONS_PER_DOCUMENT")
+            .ok()
+            .and_then(|v| v.parse::<i64>().ok())
+            .unwrap_or(50); // Default: keep the last 50 saves of each document
             
         StorageConfig {
             total_db_storage: total_storage_gb * 1024 * 1024 * 1024, // Convert GB to bytes
             default_user_quota: user_quota_mb * 1024 * 1024, // Convert MB to bytes
             expected_max_users,
+            max_revisions_per_document,
         }
     }
 }
 
+/// Limits that apply to one user, set per user by admins
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct UserLimits {
+    pub max_projects: i32,
+    pub max_documents: i32,
+    pub max_storage_bytes: i64,
+}
+
 pub struct StorageManager;
 
 impl StorageManager {
@@ -61,11 +78,54 @@ impl StorageManager {
         StorageConfig::default().total_db_storage
     }
     
-    /// Get user storage quota
+    /// Get the default user storage quota
     pub fn get_user_quota() -> i64 {
         StorageConfig::default().default_user_quota
     }
     
+    /// Get the limits of a user, their own storage quota if one was set, otherwise the default
+    pub async fn get_user_limits(executor: impl PgExecutor<'_>, user_id: i32) -> Result<UserLimits, sqlx::Error> {
+        let result = sqlx::query!(
+            "SELECT max_projects, max_documents, storage_quota_bytes FROM users WHERE id = $1",
+            user_id
+        )
+        .fetch_one(executor)
+        .await?;
+
+        Ok(UserLimits {
+            max_projects: result.max_projects,
+            max_documents: result.max_documents,
+            max_storage_bytes: result.storage_quota_bytes.unwrap_or_else(Self::get_user_quota),
+        })
+    }
+
+    /// Get the number of revisions kept per document
+    pub fn get_revision_limit() -> i64 {
+        StorageConfig::default().max_revisions_per_document
+    }
+
+    /// Get the bytes a user is using: the content of the documents they own plus their revision history
+    pub async fn get_user_storage_used(executor: impl PgExecutor<'_>, user_id: i32) -> Result<i64, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+            SELECT
+                COALESCE((SELECT SUM(LENGTH(COALESCE(d.content, '')))
+                          FROM documents d
+                          JOIN document_permissions dp ON d.id = dp.document_id
+                          WHERE dp.user_id = $1 AND dp.role = 'owner'), 0)
+              + COALESCE((SELECT SUM(LENGTH(r.content))
+                          FROM document_revisions r
+                          JOIN document_permissions dp ON r.document_id = dp.document_id
+                          WHERE dp.user_id = $1 AND dp.role = 'owner'), 0) as "total_bytes!"
+            "#,
+            user_id
+        )
+        .fetch_one(executor)
+        .await?;
+
+        Ok(result.total_bytes)
+    }
+    
     /// Get current database usage percentage
     pub async fn get_db_usage_percentage(pool: &PgPool) -> Result<f64, sqlx::Error> {
         let current_size = Self::get_db_size(pool).await?;
@@ -0,0 +1,52 @@
+use serde::{Deserialize, Serialize};
+use chrono::NaiveDateTime;
+use sqlx::types::Json;
+
+use crate::diff::DiffHunk;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, sqlx::Type, PartialEq)]
+#[sqlx(type_name = "suggestion_source_enum", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum SuggestionSource {
+    User, // Made by a collaborator in suggestion mode
+    Ai,   // Proposed by the writing assistant
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, sqlx::Type, PartialEq)]
+#[sqlx(type_name = "suggestion_status_enum", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum SuggestionStatus {
+    Pending,
+    Accepted,
+    Rejected,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct DocumentSuggestion {
+    pub id: i32,
+    pub document_id: i32,
+    pub author_id: Option<i32>,
+    pub author_name: Option<String>,
+    pub source: SuggestionSource,
+    pub base_version: i32,
+    #[serde(skip_serializing)]
+    pub old_content: String,
+    pub new_content: String,
+    pub hunks: Json<Vec<DiffHunk>>, // Changed ranges of old_content
+    pub status: SuggestionStatus,
+    pub created_at: NaiveDateTime,
+    pub resolved_at: Option<NaiveDateTime>,
+    pub resolved_by: Option<i32>,
+    pub revision_number: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateSuggestionPayload {
+    pub base_version: i32, // Version of the document the editor has open
+    pub new_content: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SuggestionListQuery {
+    pub status: Option<SuggestionStatus>,
+}
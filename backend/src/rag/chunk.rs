@@ -0,0 +1,180 @@
+// Splits document HTML into chunks for embedding.
+// Chunks follow paragraph and heading boundaries so a retrieved chunk is a readable passage,
+// and each chunk remembers where it came from in the original document.
+
+use regex::Regex;
+use std::sync::OnceLock;
+
+/// Target size of a chunk in characters of plain text (~300-400 tokens)
+const MAX_CHUNK_CHARS: usize = 1500;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextChunk {
+    pub chunk_index: i32,
+    pub heading: Option<String>, // Closest heading above the chunk, if any
+    pub content: String,         // Plain text, HTML stripped
+    pub start_offset: i32,       // Byte offset into documents.content where the chunk starts
+    pub end_offset: i32,         // Byte offset into documents.content where the chunk ends
+}
+
+// One paragraph, heading or list item from the document
+struct Block {
+    text: String,
+    start: usize,
+    end: usize,
+    is_heading: bool,
+}
+
+fn block_boundary_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"(?i)</(p|h[1-6]|li|blockquote|pre|div)\s*>|<br\s*/?>|\n\s*\n")
+            .expect("valid block boundary regex")
+    })
+}
+
+fn heading_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?i)<h[1-6][\s>]").expect("valid heading regex"))
+}
+
+fn tag_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new("<[^>]*>").expect("valid tag regex"))
+}
+
+/// Strip HTML tags and decode the handful of entities the editor produces.
+pub fn html_to_text(html: &str) -> String {
+    let text = tag_regex().replace_all(html, " ");
+    let text = text
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&");
+
+    // Collapse the whitespace left behind by removed tags
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn split_blocks(html: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut last = 0;
+
+    let mut push_block = |start: usize, end: usize| {
+        let raw = &html[start..end];
+        let text = html_to_text(raw);
+        if !text.is_empty() {
+            blocks.push(Block {
+                text,
+                start,
+                end,
+                is_heading: heading_regex().is_match(raw),
+            });
+        }
+    };
+
+    for boundary in block_boundary_regex().find_iter(html) {
+        push_block(last, boundary.end());
+        last = boundary.end();
+    }
+    if last < html.len() {
+        push_block(last, html.len());
+    }
+
+    blocks
+}
+
+// Split text that is too long for one chunk at sentence ends, falling back to word boundaries
+fn split_long_text(text: &str) -> Vec<String> {
+    let mut pieces = Vec::new();
+    let mut current = String::new();
+
+    for word in text.split(' ') {
+        if !current.is_empty() && current.len() + word.len() + 1 > MAX_CHUNK_CHARS {
+            pieces.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(word);
+
+        // Prefer to break right after a sentence once the piece is reasonably full
+        if current.len() > MAX_CHUNK_CHARS * 3 / 4 && word.ends_with(['.', '!', '?']) {
+            pieces.push(std::mem::take(&mut current));
+        }
+    }
+    if !current.is_empty() {
+        pieces.push(current);
+    }
+
+    pieces
+}
+
+/// Split document HTML into ordered chunks.
+/// A heading always starts a new chunk and is carried along as the heading of the chunks below it.
+/// Paragraphs are never split unless a single paragraph is longer than a whole chunk.
+pub fn chunk_document(html: &str) -> Vec<TextChunk> {
+    let mut chunks: Vec<TextChunk> = Vec::new();
+    let mut heading: Option<String> = None;
+
+    // The chunk currently being filled: (text, start, end)
+    let mut current: Option<(String, usize, usize)> = None;
+
+    fn flush(chunks: &mut Vec<TextChunk>, current: &mut Option<(String, usize, usize)>, heading: &Option<String>) {
+        if let Some((text, start, end)) = current.take() {
+            chunks.push(TextChunk {
+                chunk_index: chunks.len() as i32,
+                heading: heading.clone(),
+                content: text,
+                start_offset: start as i32,
+                end_offset: end as i32,
+            });
+        }
+    }
+
+    for block in split_blocks(html) {
+        if block.is_heading {
+            flush(&mut chunks, &mut current, &heading);
+            heading = Some(block.text.clone());
+        }
+
+        if block.text.len() > MAX_CHUNK_CHARS {
+            // Keep a heading together with the paragraph right below it instead of as a chunk of its own
+            let mut heading_prefix = None;
+            if let Some((text, start, _)) = &current {
+                if heading.as_ref() == Some(text) {
+                    heading_prefix = Some((text.clone(), *start));
+                    current = None;
+                }
+            }
+            flush(&mut chunks, &mut current, &heading);
+
+            // Oversized paragraph, each piece points at the whole paragraph
+            for piece in split_long_text(&block.text) {
+                current = match heading_prefix.take() {
+                    Some((prefix, start)) => Some((format!("{}\n{}", prefix, piece), start, block.end)),
+                    None => Some((piece, block.start, block.end)),
+                };
+                flush(&mut chunks, &mut current, &heading);
+            }
+            continue;
+        }
+
+        match current.as_mut() {
+            Some((text, _, end)) if text.len() + block.text.len() < MAX_CHUNK_CHARS => {
+                text.push('\n');
+                text.push_str(&block.text);
+                *end = block.end;
+            }
+            _ => {
+                flush(&mut chunks, &mut current, &heading);
+                current = Some((block.text, block.start, block.end));
+            }
+        }
+    }
+    flush(&mut chunks, &mut current, &heading);
+
+    chunks
+}
@@ -0,0 +1,55 @@
+// src/rag/edit.rs
+//
+// Edit operations returned by the language model.
+// Instead of rewriting whole documents the model quotes the text it wants to change and gives its
+// replacement. The quotes are looked up in the content as it is when the reply arrives, so a change
+// can only touch text the model has actually seen, and everything it does not quote is kept as is.
+// A quote that is missing or ambiguous rejects every edit of that document.
+
+use crate::diff::find_unique;
+use crate::models::ai::LlmEditOperation;
+
+/// Apply the edits of one document to `content`, the error says which edit could not be placed.
+pub fn apply_edits(content: &str, edits: &[LlmEditOperation]) -> Result<String, String> {
+    // An empty document has nothing to quote, it can only be filled as a whole
+    if let [edit] = edits {
+        if edit.find.is_empty() {
+            if !content.trim().is_empty() {
+                return Err("an empty find is only allowed for an empty document".to_string());
+            }
+            return Ok(edit.replace.clone());
+        }
+    }
+
+    // Byte range of every quote, each must be found exactly once
+    let mut ranges: Vec<(usize, usize, &str)> = Vec::with_capacity(edits.len());
+    for (i, edit) in edits.iter().enumerate() {
+        if edit.find.is_empty() {
+            return Err(format!("edit {} has an empty find", i + 1));
+        }
+
+        // Overlapping occurrences count too, "aa" is ambiguous in "aaa"
+        let start = match find_unique(content, &edit.find) {
+            Some(start) => start,
+            None if content.contains(edit.find.as_str()) => {
+                return Err(format!("edit {} quotes text that occurs more than once", i + 1));
+            }
+            None => return Err(format!("edit {} quotes text that is not in the document", i + 1)),
+        };
+
+        ranges.push((start, start + edit.find.len(), edit.replace.as_str()));
+    }
+
+    ranges.sort_by_key(|(start, _, _)| *start);
+    if ranges.windows(2).any(|pair| pair[0].1 > pair[1].0) {
+        return Err("edits overlap".to_string());
+    }
+
+    // Later ranges first, so earlier ones keep their position
+    let mut result = content.to_string();
+    for (start, end, replace) in ranges.into_iter().rev() {
+        result.replace_range(start..end, replace);
+    }
+
+    Ok(result)
+}
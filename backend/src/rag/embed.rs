@@ -1,49 +1,112 @@
-use langchain_rust::embedding::{openai::OpenAiEmbedder, Embedder};
-use langchain_rust::llm::OpenAIConfig;
-use std::env;
 use crate::Error;
 use pgvector::Vector;
 use crate::models::ai::WritingAssistantMessage;
 use sqlx::PgPool;
 use chrono::Utc;
 use crate::models::ai::MessageRole;
+use crate::rag::chunk::chunk_document;
+use crate::rag::provider::{embedding_provider_from_env, EmbeddingProvider};
 
+/// Embedding model used for messages and document chunks.
+/// The backend (OpenAI, local server or mock) is chosen by EMBEDDING_PROVIDER, see rag::provider.
 pub struct EmbeddingModel {
-    model: OpenAiEmbedder<OpenAIConfig>
+    provider: Box<dyn EmbeddingProvider>
 }
 
 impl EmbeddingModel {
     pub fn new() -> Result<Self, Error> {    
-        let embedding_model = OpenAiEmbedder::new({
-            OpenAIConfig::default().with_api_key(env::var("OPENAI_API_KEY")
-        .map_err(|_| Error::APIKeyError)?)
-        });
-          
-        Ok(Self { model: embedding_model })
+        Ok(Self { provider: embedding_provider_from_env()? })
     }
 
     pub async fn embed_message(&self, message: &WritingAssistantMessage) -> Result<Vector, Error> {
-        let embedding_vec_f64 = self.model.embed_query(&message.content).await
-            .map_err(|e| {
-                eprintln!("OpenAI embedding query failed for message: {:?}", e);
-                Error::EmbeddingError
-            })?;
-        
-        // Map to pgvector f32
-        let embedding_vec_f32: Vec<f32> = embedding_vec_f64.into_iter().map(|f| f as f32).collect();
-        Ok(Vector::from(embedding_vec_f32))
+        self.embed_document(&message.content).await
     }
 
     pub async fn embed_document(&self, content: &str) -> Result<Vector, Error> {
-        let embedding_vec_f64 = self.model.embed_query(content).await
-            .map_err(|e| {
-                eprintln!("OpenAI embedding query failed for document: {:?}", e);
-                Error::EmbeddingError
-            })?;
-        
-        // Map to pgvector f32
-        let embedding_vec_f32: Vec<f32> = embedding_vec_f64.into_iter().map(|f| f as f32).collect();
-        Ok(Vector::from(embedding_vec_f32))
+        self.provider
+            .embed(&[content.to_string()])
+            .await?
+            .pop()
+            .ok_or(Error::EmbeddingError)
+    }
+
+    /// Embed several pieces of text in one request, returned in the same order.
+    pub async fn embed_chunks(&self, texts: &[String]) -> Result<Vec<Vector>, Error> {
+        self.provider.embed(texts).await
+    }
+}
+
+// Function to split a document into chunks, embed them and replace its stored chunks
+pub async fn index_document_chunks(
+    embedding_model: &EmbeddingModel,
+    pool: &PgPool,
+    document_id: i32,
+    content: &str,
+) -> Result<usize, Error> {
+    let chunks = chunk_document(content);
+    println!("->> {:<12} - Split document {} into {} chunks", "EMBED", document_id, chunks.len());
+
+    let texts: Vec<String> = chunks.iter().map(|chunk| chunk.content.clone()).collect();
+    let embeddings = if texts.is_empty() {
+        Vec::new()
+    } else {
+        embedding_model.embed_chunks(&texts).await?
+    };
+
+    // Swap the old chunks for the new ones in one go so searches never see a half indexed document
+    let mut tx = pool.begin().await.map_err(|_| Error::DatabaseError)?;
+
+    sqlx::query!("DELETE FROM document_chunks WHERE document_id = $1", document_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|_| Error::DatabaseError)?;
+
+    for (chunk, embedding) in chunks.iter().zip(embeddings) {
+        sqlx::query!(
+            r#"
+            INSERT INTO document_chunks (document_id, chunk_index, heading, content, start_offset, end_offset, embedding)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+            document_id,
+            chunk.chunk_index,
+            chunk.heading,
+            chunk.content,
+            chunk.start_offset,
+            chunk.end_offset,
+            embedding as _
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            eprintln!("DB Error storing document chunk: {:?}", e);
+            Error::DatabaseError
+        })?;
+    }
+
+    sqlx::query!(
+        "UPDATE documents SET embedding_updated_at = $1 WHERE id = $2",
+        Utc::now(),
+        document_id
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|_| Error::DatabaseError)?;
+
+    tx.commit().await.map_err(|_| Error::DatabaseError)?;
+
+    Ok(chunks.len())
+}
+
+// Function to index a document whose content was written outside the regular save, like a new document.
+// Failures are only logged, search keeps the previous chunks until the next save re-indexes it.
+pub async fn reindex_document(pool: &PgPool, document_id: i32, content: &str) {
+    let result = match EmbeddingModel::new() {
+        Ok(embedding_model) => index_document_chunks(&embedding_model, pool, document_id, content).await,
+        Err(e) => Err(e),
+    };
+
+    if let Err(e) = result {
+        println!("->> {:<12} - Failed to index document {}: {:?}", "ERROR", document_id, e);
     }
 }
 
@@ -1,29 +1,72 @@
-use langchain_rust::language_models::llm::LLM;
-use langchain_rust::llm::openai::OpenAI;
-use langchain_rust::llm::OpenAIConfig;
-use std::env;
+use serde::de::DeserializeOwned;
+use std::sync::Mutex;
+
+use crate::rag::provider::{llm_provider_from_env, LlmProvider, TokenStream, TokenUsage};
+use crate::rag::structured::{repair_prompt, OutputSchema};
 use crate::Error;
 
+/// Text generation model used by the writing assistant.
+/// The backend (OpenAI, local server or mock) is chosen by LLM_PROVIDER, see rag::provider.
+/// Tokens used by every request made through it are added up, so an operation can be charged for them.
 pub struct QueryModel {
-    model: OpenAI<OpenAIConfig>
+    provider: Box<dyn LlmProvider>,
+    usage: Mutex<TokenUsage>,
 }
 
 impl QueryModel {
     pub fn new() -> Result<Self, Error> {
-        let open_ai = OpenAI::default().with_config(
-            OpenAIConfig::default().with_api_key(
-                env::var("OPENAI_API_KEY")
-                    .map_err(|_| Error::APIKeyError)?
-            )
-        );
-        Ok(Self { model: open_ai })
+        Ok(Self::with_provider(llm_provider_from_env()?))
+    }
+
+    /// Use a given provider instead of the one selected by the environment
+    pub fn with_provider(provider: Box<dyn LlmProvider>) -> Self {
+        Self { provider, usage: Mutex::new(TokenUsage::default()) }
+    }
+
+    /// Tokens used so far
+    pub fn usage(&self) -> TokenUsage {
+        *self.usage.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn add_usage(&self, usage: TokenUsage) {
+        *self.usage.lock().unwrap_or_else(|e| e.into_inner()) += usage;
     }
 
     pub async fn query_model(&self, prompt: &str) -> Result<String, Error> {
-        self.model.invoke(prompt).await
-            .map_err(|_err| {
-                eprintln!("LLM Query Error occurred");
-                Error::LlmQueryError
-            })
+        let completion = self.provider.complete(prompt).await?;
+        self.add_usage(completion.usage);
+        Ok(completion.text)
+    }
+
+    /// Same as query_model but yields the response in pieces as it is generated.
+    /// Call add_streamed with what was received, streams do not report their usage.
+    pub async fn stream_model(&self, prompt: &str) -> Result<TokenStream, Error> {
+        self.add_usage(TokenUsage::estimate(prompt, ""));
+        self.provider.stream(prompt).await
+    }
+
+    /// Count the text received from stream_model
+    pub fn add_streamed(&self, completion: &str) {
+        self.add_usage(TokenUsage::estimate("", completion));
+    }
+
+    /// Ask for JSON matching `schema` and deserialize it.
+    /// A reply that cannot be used is sent back once with the problem, before giving up.
+    pub async fn query_json<T: DeserializeOwned>(&self, prompt: &str, schema: &OutputSchema) -> Result<T, Error> {
+        let reply = self.provider.complete_json(prompt, schema).await?;
+        self.add_usage(reply.usage);
+        let error = match schema.parse(&reply.text) {
+            Ok(value) => return Ok(value),
+            Err(error) => error,
+        };
+
+        println!("->> {:<12} - unusable {} reply ({}), retrying", "LLM", schema.name, error);
+
+        let retry = self.provider.complete_json(&repair_prompt(prompt, &reply.text, &error, schema), schema).await?;
+        self.add_usage(retry.usage);
+        schema.parse(&retry.text).map_err(|error| {
+            eprintln!("LLM reply did not match {}: {}", schema.name, error);
+            Error::LlmResponseFormatError
+        })
     }
-}
\ No newline at end of file
+}
@@ -1,4 +1,8 @@
+pub mod chunk;
+pub mod edit;
 pub mod embed;
 pub mod prompt;
 pub mod retrieval;
-pub mod llm;
\ No newline at end of file
+pub mod llm;
+pub mod provider;
+pub mod structured;
\ No newline at end of file
@@ -1,5 +1,8 @@
 use crate::models::ai::{ChatHistory, MessageRole, ContextDocument, ProactiveDiffContextPayload};
 use crate::rag::retrieval::RetrievedChunk;
+use crate::rag::provider::{CONTEXT_DECISION_FORMAT, DOCUMENT_CHANGES_SCHEMA, PROACTIVE_DIFF_FORMAT};
+use crate::rag::structured::OutputSchema;
+use serde_json::json;
 
 const MAX_HISTORY_TOKENS: usize = 1000; // Example token limit for history
 const MAX_CONTEXT_TOKENS: usize = 1500; // Example token limit for context
@@ -25,6 +28,7 @@ pub fn construct_generic_prompt(
         and the 'Chat History' to answer the 'User Query'. \
         Synthesize information from the context and history to provide a specific and helpful response. \
         If the context contains information relevant to the query, use it directly in your answer. \
+        When you use a passage from the context, say which document (and section, if given) it came from. \
         Your response should be plain text only, without any markdown, HTML, or code formatting.\n\n"
     );
 
@@ -41,7 +45,10 @@ pub fn construct_generic_prompt(
     if !context_chunks.is_empty() {
         let mut current_context_tokens = 0;
         for chunk in context_chunks {
-            let chunk_header = format!("--- Source Document (ID: {}, Name: {}) ---\n", chunk.document_id, chunk.document_name);
+            let chunk_header = match &chunk.heading {
+                Some(heading) => format!("--- Source Document (ID: {}, Name: {}, Section: {}, Passage: {}) ---\n", chunk.document_id, chunk.document_name, heading, chunk.chunk_index + 1),
+                None => format!("--- Source Document (ID: {}, Name: {}, Passage: {}) ---\n", chunk.document_id, chunk.document_name, chunk.chunk_index + 1),
+            };
             let chunk_content = &chunk.content;
             let chunk_tokens = estimate_tokens(&chunk_header) + estimate_tokens(chunk_content);
 
@@ -54,7 +61,7 @@ pub fn construct_generic_prompt(
             prompt.push_str("\n---\n"); // Separator after each chunk
             current_context_tokens += chunk_tokens;
         }
-        prompt.push_str("\n"); // Add a final newline after context section
+        prompt.push('\n'); // Add a final newline after context section
 
     } else {
         prompt.push_str("(No relevant context found from other documents)\n\n"); // Indicate no context was found
@@ -215,7 +222,7 @@ pub fn construct_apply_suggestion_prompt(
         if let Some((_, _, active_doc_content)) = project_documents.iter().find(|(id, _, _)| *id == active_id) {
             if active_doc_content.trim().is_empty() {
                 system_instruction.push_str(
-                    "This active document is currently empty. If the 'Suggestion to Apply' is suitable as new content for an empty document (e.g., a complete story, article, or section), then the 'new_content' for this active document should be the 'Suggestion to Apply' itself. "
+                    "This active document is currently empty. If the 'Suggestion to Apply' is suitable as new content for an empty document (e.g., a complete story, article, or section), then return a single edit for this active document with an empty 'find' and the 'Suggestion to Apply' itself as 'replace'. "
                 );
             } else {
                 system_instruction.push_str(
@@ -230,7 +237,7 @@ pub fn construct_apply_suggestion_prompt(
     }
 
     system_instruction.push_str(
-        "Determine which documents need modification based on the suggestion. For ONLY the documents that need changes, generate their complete new content. Your response MUST be a JSON array containing objects, where each object represents a changed document and has the following structure: { \"document_id\": <integer>, \"new_content\": \"<full_new_document_content_as_string>\" }. Do NOT include documents that remain unchanged in the JSON array. Ensure the 'new_content' is the complete text of the document after applying the suggestion. If the suggestion cannot be applied or no documents need changes, return an empty JSON array []. Output ONLY the JSON array, with no other text before or after it. Do not return any markdown text!\n\n"
+        "Determine which documents need modification based on the suggestion. Do NOT rewrite whole documents, describe each change as an edit instead. Your response MUST be a JSON array containing objects, where each object represents a changed document and has the following structure: { \"document_id\": <integer>, \"edits\": [ { \"find\": \"<exact text from the document>\", \"replace\": \"<text to put in its place>\" } ] }. Each 'find' must be copied character for character from the document's current content, including any HTML tags, and must occur only once in it; quote a few more words if a short passage appears more than once. Keep each 'find' as short as that allows, and never let two edits of the same document overlap. To insert text, quote the text next to the insertion point and repeat it in 'replace' along with the new text. To delete text, use an empty 'replace'. Text that is not quoted stays exactly as it is. Do NOT include documents that remain unchanged in the JSON array. If the suggestion cannot be applied or no documents need changes, return an empty JSON array []. Output ONLY the JSON array, with no other text before or after it. Do not return any markdown text!\n\n"
     );
     prompt.push_str(&system_instruction);
 
@@ -260,6 +267,36 @@ pub fn construct_apply_suggestion_prompt(
     Ok(prompt)
 }
 
+/// Schema of the reply to construct_apply_suggestion_prompt
+pub fn apply_suggestion_schema() -> OutputSchema {
+    OutputSchema::new(
+        DOCUMENT_CHANGES_SCHEMA,
+        json!({
+            "type": "array",
+            "items": {
+                "type": "object",
+                "properties": {
+                    "document_id": { "type": "integer" },
+                    "edits": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "find": { "type": "string" },
+                                "replace": { "type": "string" }
+                            },
+                            "required": ["find", "replace"],
+                            "additionalProperties": false
+                        }
+                    }
+                },
+                "required": ["document_id", "edits"],
+                "additionalProperties": false
+            }
+        }),
+    )
+}
+
 pub fn construct_proactive_diff_decision_prompt(
     ai_response_content: &str,
     context: &ProactiveDiffContextPayload,
@@ -291,7 +328,7 @@ pub fn construct_proactive_diff_decision_prompt(
     // Construct the prompt with clear instructions for the LLM
     let mut prompt_segments: Vec<String> = Vec::new();
 
-    prompt_segments.push("You are an AI assistant that helps decide if a user interface should proactively show a diff view. Your sole output MUST be 'True' or 'False'.".to_string());
+    prompt_segments.push(format!("You are an AI assistant that helps decide if a user interface should proactively show a diff view. {}", PROACTIVE_DIFF_FORMAT));
     prompt_segments.push(format!("User's action: {}", user_intent_description));
     prompt_segments.push(format!("Current document state: {}", document_context_description));
     prompt_segments.push(format!("AI's response to user: \"{}\".", ai_response_content.chars().take(500).collect::<String>())); // Limit length of AI response in prompt
@@ -325,7 +362,7 @@ pub fn construct_sanitize_text_prompt(text_with_markdown_html: &str) -> String {
 
 pub fn construct_context_decision_prompt(user_prompt: &str) -> String {
     format!(
-        "You are an AI assistant. Based on the following user prompt, decide if the AI needs context from the current document, the current project, or neither. Respond with only 'document', 'project', or 'none'.\n\nUser Prompt:\n{}\n\nResponse:",
-        user_prompt
+        "You are an AI assistant. Based on the following user prompt, decide if the AI needs context from the current document, the current project, or neither. {}\n\nUser Prompt:\n{}\n\nResponse:",
+        CONTEXT_DECISION_FORMAT, user_prompt
     )
 }
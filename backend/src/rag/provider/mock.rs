@@ -0,0 +1,149 @@
+// Deterministic providers for CI and offline development.
+// The same input always gives the same output and no network access is needed.
+
+use async_trait::async_trait;
+use futures_util::stream;
+use pgvector::Vector;
+use serde_json::{json, Value};
+
+use super::{
+    fit_dimensions, Completion, EmbeddingProvider, LlmProvider, TokenStream, TokenUsage, CONTEXT_DECISION_FORMAT,
+    DOCUMENT_CHANGES_SCHEMA, EMBEDDING_DIMENSIONS, PROACTIVE_DIFF_FORMAT,
+};
+use crate::rag::structured::OutputSchema;
+use crate::Result;
+
+pub const MOCK_RESPONSE: &str = "This is a mock response from the Vynn writing assistant.";
+
+/// Answers each of the assistant's prompt shapes with something the handlers can parse.
+/// Decision prompts are recognised by their answer format, JSON replies by the schema they must match.
+pub struct MockLlmProvider;
+
+impl MockLlmProvider {
+    fn reply(prompt: &str) -> String {
+        if prompt.contains(CONTEXT_DECISION_FORMAT) {
+            return "none".to_string();
+        }
+
+        if prompt.contains(PROACTIVE_DIFF_FORMAT) {
+            return "False".to_string();
+        }
+
+        // Text command prompts wrap the input in a fenced block, hand it back unchanged
+        if let Some(text) = fenced_text(prompt) {
+            return text;
+        }
+
+        MOCK_RESPONSE.to_string()
+    }
+}
+
+#[async_trait]
+impl LlmProvider for MockLlmProvider {
+    async fn complete(&self, prompt: &str) -> Result<Completion> {
+        let text = Self::reply(prompt);
+        let usage = TokenUsage::estimate(prompt, &text);
+        Ok(Completion { text, usage })
+    }
+
+    // Stream word by word so clients exercise the same path as with a real provider
+    async fn stream(&self, prompt: &str) -> Result<TokenStream> {
+        let pieces: Vec<Result<String>> = Self::reply(prompt)
+            .split_inclusive(' ')
+            .map(|piece| Ok(piece.to_string()))
+            .collect();
+
+        Ok(Box::pin(stream::iter(pieces)))
+    }
+
+    async fn complete_json(&self, prompt: &str, schema: &OutputSchema) -> Result<Completion> {
+        // Document changes follow the suggestion, anything else gets an empty object
+        let text = match schema.name {
+            DOCUMENT_CHANGES_SCHEMA => document_changes(prompt).to_string(),
+            _ => "{}".to_string(),
+        };
+        let usage = TokenUsage::estimate(prompt, &text);
+        Ok(Completion { text, usage })
+    }
+}
+
+// Get the contents of the first ``` fenced block in the prompt
+fn fenced_text(prompt: &str) -> Option<String> {
+    let start = prompt.find("```")? + 3;
+    let rest = &prompt[start..];
+    // Skip a language tag such as ```text
+    let rest = match rest.split_once('\n') {
+        Some((tag, body)) if tag.trim().chars().all(|c| c.is_ascii_alphabetic()) => body,
+        _ => rest,
+    };
+    let end = rest.find("```")?;
+
+    Some(rest[..end].trim().to_string())
+}
+
+// Edits for an apply suggestion prompt. A suggestion saying `Replace "old" with "new"` becomes an edit of
+// every document containing "old", quoted as given even where it occurs more than once, so both accepted
+// and rejected edits can be tested. Any other suggestion changes nothing.
+fn document_changes(prompt: &str) -> Value {
+    // The documents are the fenced block, the suggestion comes after it
+    let documents = fenced_text(prompt).and_then(|docs| serde_json::from_str::<Vec<Value>>(&docs).ok());
+    let replacement = prompt.rsplit_once("```").and_then(|(_, suggestion)| replace_instruction(suggestion));
+    let (Some(documents), Some((find, replace))) = (documents, replacement) else {
+        return json!([]);
+    };
+
+    documents
+        .iter()
+        .filter(|doc| doc["content"].as_str().is_some_and(|content| content.contains(find)))
+        .map(|doc| json!({ "document_id": doc["id"], "edits": [{ "find": find, "replace": replace }] }))
+        .collect()
+}
+
+// The quoted texts of `Replace "old" with "new"`
+fn replace_instruction(text: &str) -> Option<(&str, &str)> {
+    let (_, rest) = text.split_once("Replace \"")?;
+    let (find, rest) = rest.split_once("\" with \"")?;
+    let (replace, _) = rest.split_once('"')?;
+    Some((find, replace))
+}
+
+/// Hashes words into buckets so texts sharing words end up close together,
+/// which keeps semantic search meaningful in tests.
+pub struct MockEmbeddingProvider;
+
+#[async_trait]
+impl EmbeddingProvider for MockEmbeddingProvider {
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vector>> {
+        texts.iter().map(|text| fit_dimensions(embed_words(text))).collect()
+    }
+}
+
+fn embed_words(text: &str) -> Vec<f32> {
+    let mut values = vec![0.0f32; EMBEDDING_DIMENSIONS];
+
+    for word in text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+    {
+        let bucket = (fnv1a(&word.to_lowercase()) % EMBEDDING_DIMENSIONS as u64) as usize;
+        values[bucket] += 1.0;
+    }
+
+    // Normalize so cosine distance only depends on word overlap
+    let norm = values.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        values.iter_mut().for_each(|v| *v /= norm);
+    } else {
+        // pgvector cannot compute cosine distance for an all zero vector
+        values[0] = 1.0;
+    }
+
+    values
+}
+
+// Stable across builds and platforms, unlike std's hasher
+fn fnv1a(word: &str) -> u64 {
+    word.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
@@ -0,0 +1,232 @@
+// Pluggable backends for text generation and embeddings.
+// The backend is picked from the environment so the writing assistant can run against
+// OpenAI, a local OpenAI-compatible server (Ollama, llama.cpp, vLLM) or a deterministic mock.
+//
+// LLM_PROVIDER        openai (default) | local | mock
+// LLM_BASE_URL        base URL of the local server, defaults to http://localhost:11434/v1 (Ollama)
+// LLM_MODEL           chat model name, defaults to gpt-4o-mini (openai) or llama3 (local)
+// LLM_API_KEY         API key for the local server if it needs one
+// EMBEDDING_PROVIDER  same values as LLM_PROVIDER, defaults to LLM_PROVIDER
+// EMBEDDING_BASE_URL  defaults to LLM_BASE_URL when both providers are the same kind
+// EMBEDDING_MODEL     embedding model name, defaults to text-embedding-ada-002 (openai) or nomic-embed-text (local)
+// LLM_JSON_SCHEMA     true | false, whether the chat model can be constrained to a JSON schema,
+//                     defaults to true for openai and false for local servers
+
+pub mod mock;
+pub mod openai;
+
+use async_trait::async_trait;
+use futures_util::stream::{self, Stream};
+use pgvector::Vector;
+use std::env;
+use std::pin::Pin;
+
+use crate::rag::structured::OutputSchema;
+use crate::{Error, Result};
+
+use self::mock::{MockEmbeddingProvider, MockLlmProvider};
+use self::openai::{OpenAiEmbeddingProvider, OpenAiLlmProvider};
+
+/// Answer formats of the decision prompts. Prompts include them word for word and the mock provider
+/// recognises a prompt by them, so rewording one cannot silently change what the mock answers.
+pub const CONTEXT_DECISION_FORMAT: &str = "Respond with only 'document', 'project', or 'none'.";
+pub const PROACTIVE_DIFF_FORMAT: &str = "Your sole output MUST be 'True' or 'False'.";
+
+/// Name of the schema the apply suggestion reply must match
+pub const DOCUMENT_CHANGES_SCHEMA: &str = "document_changes";
+
+/// Size of the vector columns in the database, every provider must return vectors of this size
+pub const EMBEDDING_DIMENSIONS: usize = 1536;
+
+/// Pieces of a completion in the order the provider produced them
+pub type TokenStream = Pin<Box<dyn Stream<Item = Result<String>> + Send>>;
+
+/// Roughly how many characters of English text make up one token
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Tokens used by one or more requests
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TokenUsage {
+    pub prompt_tokens: i32,
+    pub completion_tokens: i32,
+    pub estimated: bool, // At least part of the count was estimated from the text, not reported by the provider
+}
+
+impl TokenUsage {
+    /// Count for a provider that does not report usage
+    pub fn estimate(prompt: &str, completion: &str) -> Self {
+        let tokens = |text: &str| text.chars().count().div_ceil(CHARS_PER_TOKEN) as i32;
+        Self { prompt_tokens: tokens(prompt), completion_tokens: tokens(completion), estimated: true }
+    }
+
+    pub fn total(&self) -> i32 {
+        self.prompt_tokens + self.completion_tokens
+    }
+}
+
+impl std::ops::AddAssign for TokenUsage {
+    fn add_assign(&mut self, other: Self) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.estimated |= other.estimated;
+    }
+}
+
+/// A full completion and what it cost
+#[derive(Debug, Clone)]
+pub struct Completion {
+    pub text: String,
+    pub usage: TokenUsage,
+}
+
+#[async_trait]
+pub trait LlmProvider: Send + Sync {
+    /// Send a single prompt and return the full completion
+    async fn complete(&self, prompt: &str) -> Result<Completion>;
+
+    /// Send a single prompt and receive the completion as it is generated.
+    /// Providers without streaming support return the whole completion as one piece.
+    /// Streams carry no usage, callers estimate it from the text.
+    async fn stream(&self, prompt: &str) -> Result<TokenStream> {
+        let completion = self.complete(prompt).await?;
+        Ok(Box::pin(stream::once(async move { Ok(completion.text) })))
+    }
+
+    /// Send a prompt whose reply must be JSON matching `schema`.
+    /// Providers that can constrain generation to a schema do so, the others rely on the prompt alone.
+    async fn complete_json(&self, prompt: &str, _schema: &OutputSchema) -> Result<Completion> {
+        self.complete(prompt).await
+    }
+}
+
+#[async_trait]
+pub trait EmbeddingProvider: Send + Sync {
+    /// Embed each text, returned in the same order
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vector>>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProviderKind {
+    OpenAi,
+    Local,
+    Mock,
+}
+
+impl ProviderKind {
+    fn parse(value: &str) -> Result<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "" | "openai" => Ok(Self::OpenAi),
+            "local" | "ollama" => Ok(Self::Local),
+            "mock" => Ok(Self::Mock),
+            other => Err(Error::UnknownLlmProviderError { provider: other.to_string() }),
+        }
+    }
+}
+
+/// Connection settings for an OpenAI-compatible API
+#[derive(Debug, Clone)]
+pub struct ProviderConfig {
+    pub kind: ProviderKind,
+    pub base_url: Option<String>,
+    pub api_key: Option<String>,
+    pub model: Option<String>,
+    pub json_schema: bool, // Structured outputs (response_format json_schema) are supported
+}
+
+impl ProviderConfig {
+    /// Read the settings for `prefix`, looking variables up with `lookup` (std::env::var outside of tests)
+    fn from_vars(prefix: &str, fallback_prefix: Option<&str>, lookup: &dyn Fn(&str) -> Option<String>) -> Result<Self> {
+        let var = |prefix: &str, name: &str| lookup(&format!("{}_{}", prefix, name)).filter(|v| !v.is_empty());
+        let kind_of = |prefix: &str| match var(prefix, "PROVIDER") {
+            Some(value) => ProviderKind::parse(&value),
+            None => Ok(ProviderKind::OpenAi),
+        };
+
+        let fallback_kind = fallback_prefix.map(kind_of).transpose()?;
+        let kind = match var(prefix, "PROVIDER") {
+            Some(value) => ProviderKind::parse(&value)?,
+            None => fallback_kind.unwrap_or(ProviderKind::OpenAi),
+        };
+
+        // The fallback's URL points at its own kind of server, an OpenAI embedding must not go to a local LLM
+        let base_url = var(prefix, "BASE_URL").or_else(|| match fallback_prefix {
+            Some(fallback) if fallback_kind == Some(kind) => var(fallback, "BASE_URL"),
+            _ => None,
+        });
+
+        // OpenAI always uses OPENAI_API_KEY, local servers usually need no key at all
+        let api_key = match kind {
+            ProviderKind::OpenAi => Some(lookup("OPENAI_API_KEY").ok_or(Error::APIKeyError)?),
+            _ => lookup("LLM_API_KEY"),
+        };
+
+        let json_schema = match var(prefix, "JSON_SCHEMA") {
+            Some(value) => value.trim().eq_ignore_ascii_case("true"),
+            None => kind == ProviderKind::OpenAi,
+        };
+
+        Ok(Self {
+            kind,
+            base_url,
+            api_key,
+            model: var(prefix, "MODEL"),
+            json_schema,
+        })
+    }
+
+    /// Read the text generation settings (LLM_*)
+    pub fn llm_from_env() -> Result<Self> {
+        Self::llm_from_vars(&|name| env::var(name).ok())
+    }
+
+    /// Read the embedding settings (EMBEDDING_*), falling back to the LLM settings
+    pub fn embedding_from_env() -> Result<Self> {
+        Self::embedding_from_vars(&|name| env::var(name).ok())
+    }
+
+    /// Same as llm_from_env with the variables taken from `lookup`
+    pub fn llm_from_vars(lookup: &dyn Fn(&str) -> Option<String>) -> Result<Self> {
+        Self::from_vars("LLM", None, lookup)
+    }
+
+    /// Same as embedding_from_env with the variables taken from `lookup`
+    pub fn embedding_from_vars(lookup: &dyn Fn(&str) -> Option<String>) -> Result<Self> {
+        Self::from_vars("EMBEDDING", Some("LLM"), lookup)
+    }
+}
+
+/// Build the text generation provider selected by the environment
+pub fn llm_provider_from_env() -> Result<Box<dyn LlmProvider>> {
+    let config = ProviderConfig::llm_from_env()?;
+
+    Ok(match config.kind {
+        ProviderKind::Mock => Box::new(MockLlmProvider),
+        ProviderKind::OpenAi | ProviderKind::Local => Box::new(OpenAiLlmProvider::new(&config)),
+    })
+}
+
+/// Build the embedding provider selected by the environment
+pub fn embedding_provider_from_env() -> Result<Box<dyn EmbeddingProvider>> {
+    let config = ProviderConfig::embedding_from_env()?;
+
+    Ok(match config.kind {
+        ProviderKind::Mock => Box::new(MockEmbeddingProvider),
+        ProviderKind::OpenAi | ProviderKind::Local => Box::new(OpenAiEmbeddingProvider::new(&config)),
+    })
+}
+
+/// Bring a vector to EMBEDDING_DIMENSIONS.
+/// Smaller local models are zero padded, which leaves cosine distances between their vectors unchanged.
+pub fn fit_dimensions(mut values: Vec<f32>) -> Result<Vector> {
+    if values.len() > EMBEDDING_DIMENSIONS {
+        eprintln!(
+            "Embedding model returned {} dimensions, the database only stores {}",
+            values.len(),
+            EMBEDDING_DIMENSIONS
+        );
+        return Err(Error::EmbeddingError);
+    }
+
+    values.resize(EMBEDDING_DIMENSIONS, 0.0);
+    Ok(Vector::from(values))
+}
@@ -0,0 +1,222 @@
+// OpenAI and OpenAI-compatible providers.
+// Local servers such as Ollama, llama.cpp and vLLM expose the same API, so they only differ by base URL and model.
+
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use langchain_rust::embedding::{openai::OpenAiEmbedder, Embedder};
+use langchain_rust::language_models::llm::LLM;
+use langchain_rust::llm::openai::OpenAI;
+use langchain_rust::llm::OpenAIConfig;
+use langchain_rust::schemas::Message;
+use pgvector::Vector;
+use reqwest::Client;
+use serde_json::{json, Value};
+
+use super::{
+    fit_dimensions, Completion, EmbeddingProvider, LlmProvider, ProviderConfig, ProviderKind, TokenStream, TokenUsage,
+};
+use crate::rag::structured::OutputSchema;
+use crate::{Error, Result};
+
+const DEFAULT_OPENAI_BASE_URL: &str = "https://api.openai.com/v1";
+const DEFAULT_OPENAI_CHAT_MODEL: &str = "gpt-4o-mini";
+const DEFAULT_LOCAL_BASE_URL: &str = "http://localhost:11434/v1";
+const DEFAULT_LOCAL_CHAT_MODEL: &str = "llama3";
+const DEFAULT_LOCAL_EMBEDDING_MODEL: &str = "nomic-embed-text";
+
+/// Statuses servers answer with when a model or server does not support response_format json_schema
+const STRUCTURED_OUTPUT_UNSUPPORTED: [u16; 3] = [400, 404, 422];
+
+fn openai_config(config: &ProviderConfig) -> OpenAIConfig {
+    let mut openai_config = OpenAIConfig::default();
+
+    if let Some(api_key) = &config.api_key {
+        openai_config = openai_config.with_api_key(api_key);
+    }
+
+    match (&config.base_url, config.kind) {
+        (Some(base_url), _) => openai_config.with_api_base(base_url),
+        (None, ProviderKind::Local) => openai_config.with_api_base(DEFAULT_LOCAL_BASE_URL),
+        (None, _) => openai_config,
+    }
+}
+
+pub struct OpenAiLlmProvider {
+    model: OpenAI<OpenAIConfig>,
+    config: ProviderConfig,
+}
+
+impl OpenAiLlmProvider {
+    pub fn new(config: &ProviderConfig) -> Self {
+        let mut model = OpenAI::default().with_config(openai_config(config));
+
+        match (&config.model, config.kind) {
+            (Some(name), _) => model = model.with_model(name),
+            (None, ProviderKind::Local) => model = model.with_model(DEFAULT_LOCAL_CHAT_MODEL),
+            _ => {} // langchain defaults to gpt-4o-mini
+        }
+
+        Self { model, config: config.clone() }
+    }
+
+    /// Chat completion with response_format json_schema, which langchain does not expose.
+    /// None when the server or model rejects the request because it does not support structured outputs.
+    async fn complete_with_schema(&self, prompt: &str, schema: &OutputSchema) -> Result<Option<Completion>> {
+        let (default_base_url, default_model) = match self.config.kind {
+            ProviderKind::Local => (DEFAULT_LOCAL_BASE_URL, DEFAULT_LOCAL_CHAT_MODEL),
+            _ => (DEFAULT_OPENAI_BASE_URL, DEFAULT_OPENAI_CHAT_MODEL),
+        };
+        let base_url = self.config.base_url.as_deref().unwrap_or(default_base_url);
+        let model = self.config.model.as_deref().unwrap_or(default_model);
+
+        let body = json!({
+            "model": model,
+            "messages": [{ "role": "user", "content": prompt }],
+            "response_format": {
+                "type": "json_schema",
+                "json_schema": {
+                    "name": schema.name,
+                    "schema": schema.object_root(),
+                    "strict": true
+                }
+            }
+        });
+
+        let mut request = Client::new()
+            .post(format!("{}/chat/completions", base_url.trim_end_matches('/')))
+            .json(&body);
+        if let Some(api_key) = &self.config.api_key {
+            request = request.bearer_auth(api_key);
+        }
+
+        let response = request.send().await.map_err(|e| {
+            eprintln!("LLM Query Error occurred: {:?}", e);
+            Error::LlmQueryError
+        })?;
+
+        let status = response.status();
+        if STRUCTURED_OUTPUT_UNSUPPORTED.contains(&status.as_u16()) {
+            let message = response.text().await.unwrap_or_default();
+            println!(
+                "->> {:<12} - {} rejected structured output ({}: {}), using a plain completion",
+                "LLM",
+                model,
+                status,
+                message.chars().take(200).collect::<String>()
+            );
+            return Ok(None);
+        }
+
+        if !status.is_success() {
+            eprintln!("LLM Query Error occurred: structured output request returned {}", status);
+            return Err(Error::LlmQueryError);
+        }
+
+        let reply: Value = response.json().await.map_err(|e| {
+            eprintln!("LLM Query Error occurred: {:?}", e);
+            Error::LlmQueryError
+        })?;
+
+        let text = reply["choices"][0]["message"]["content"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or(Error::LlmQueryError)?;
+
+        let usage = match (reply["usage"]["prompt_tokens"].as_i64(), reply["usage"]["completion_tokens"].as_i64()) {
+            (Some(prompt_tokens), Some(completion_tokens)) => TokenUsage {
+                prompt_tokens: prompt_tokens as i32,
+                completion_tokens: completion_tokens as i32,
+                estimated: false,
+            },
+            _ => TokenUsage::estimate(prompt, &text),
+        };
+
+        Ok(Some(Completion { text, usage }))
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OpenAiLlmProvider {
+    async fn complete(&self, prompt: &str) -> Result<Completion> {
+        let result = self.model.generate(&[Message::new_human_message(prompt)]).await.map_err(|e| {
+            eprintln!("LLM Query Error occurred: {:?}", e);
+            Error::LlmQueryError
+        })?;
+
+        // Local servers do not always report usage
+        let usage = match result.tokens {
+            Some(tokens) => TokenUsage {
+                prompt_tokens: tokens.prompt_tokens as i32,
+                completion_tokens: tokens.completion_tokens as i32,
+                estimated: false,
+            },
+            None => TokenUsage::estimate(prompt, &result.generation),
+        };
+
+        Ok(Completion { text: result.generation, usage })
+    }
+
+    async fn stream(&self, prompt: &str) -> Result<TokenStream> {
+        let stream = self
+            .model
+            .stream(&[Message::new_human_message(prompt)])
+            .await
+            .map_err(|e| {
+                eprintln!("LLM Stream Error occurred: {:?}", e);
+                Error::LlmQueryError
+            })?;
+
+        Ok(Box::pin(stream.map(|data| {
+            data.map(|data| data.content).map_err(|e| {
+                eprintln!("LLM Stream Error occurred: {:?}", e);
+                Error::LlmQueryError
+            })
+        })))
+    }
+
+    async fn complete_json(&self, prompt: &str, schema: &OutputSchema) -> Result<Completion> {
+        if self.config.json_schema {
+            // Only a rejected response_format falls back, the prompt still asks for JSON.
+            // Network, auth and server errors are reported like any other failed query.
+            if let Some(reply) = self.complete_with_schema(prompt, schema).await? {
+                return Ok(reply);
+            }
+        }
+
+        self.complete(prompt).await
+    }
+}
+
+pub struct OpenAiEmbeddingProvider {
+    model: OpenAiEmbedder<OpenAIConfig>,
+}
+
+impl OpenAiEmbeddingProvider {
+    pub fn new(config: &ProviderConfig) -> Self {
+        let mut model = OpenAiEmbedder::new(openai_config(config));
+
+        match (&config.model, config.kind) {
+            (Some(name), _) => model = model.with_model(name),
+            (None, ProviderKind::Local) => model = model.with_model(DEFAULT_LOCAL_EMBEDDING_MODEL),
+            _ => {} // langchain defaults to text-embedding-ada-002
+        }
+
+        Self { model }
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for OpenAiEmbeddingProvider {
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vector>> {
+        let embeddings_f64 = self.model.embed_documents(texts).await.map_err(|e| {
+            eprintln!("OpenAI embedding query failed: {:?}", e);
+            Error::EmbeddingError
+        })?;
+
+        // Map to pgvector f32
+        embeddings_f64
+            .into_iter()
+            .map(|embedding| fit_dimensions(embedding.into_iter().map(|f| f as f32).collect()))
+            .collect()
+    }
+}
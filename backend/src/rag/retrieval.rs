@@ -3,7 +3,6 @@
 use sqlx::{PgPool, Row};
 use pgvector::Vector;
 use crate::{Error, Result};
-use regex::Regex;
 // Import necessary models
 use crate::models::ai::{WritingAssistantMessage, ChatHistory, MessageRole};
 
@@ -13,38 +12,62 @@ pub struct RetrievedChunk {
     pub document_id: i32,
     pub document_name: String, // Assuming name is always available
     pub content: String,
+    pub chunk_index: i32,
+    pub heading: Option<String>,
+    pub start_offset: i32, // Byte offsets into the document's HTML content
+    pub end_offset: i32,
+}
+
+impl RetrievedChunk {
+    /// Wrap an entire document as a single chunk (used when the whole document is added as context)
+    pub fn whole_document(document_id: i32, document_name: String, content: String) -> Self {
+        let end_offset = content.len() as i32;
+        Self {
+            document_id,
+            document_name,
+            content,
+            chunk_index: 0,
+            heading: None,
+            start_offset: 0,
+            end_offset,
+        }
+    }
 }
 
 /// Retrieves the top 'k' most relevant document chunks.
-/// Returns a vector of RetrievedChunk containing ID, name, and content.
+/// Only documents the user can access, directly or through a project, are searched, so other users' documents never reach the prompt.
+/// Returns a vector of RetrievedChunk containing the document, the passage and where it sits in the document.
 pub async fn semantic_search(
     pool: &PgPool, 
+    user_id: i32,
     project_id: Option<i32>, 
     query_embedding: &Vector, 
     k: i64
 ) -> Result<Vec<RetrievedChunk>> {
     
-    println!("->> {:<12} - Retrieving relevant chunks (k={}) for project_id: {:?}", "RETRIEVAL", k, project_id);
+    println!("->> {:<12} - Retrieving relevant chunks (k={}) for user_id: {}, project_id: {:?}", "RETRIEVAL", k, user_id, project_id);
     // Log a snippet of the query embedding
     println!("->> {:<12} - Using query embedding (first 5 dims): {:?}", "RETRIEVAL", query_embedding.as_slice().iter().take(5).collect::<Vec<_>>());
 
     // Define the base query selecting necessary fields
-    let base_select = "SELECT d.id, d.name, d.content "; // <-- Select id and name
-    let order_limit = "ORDER BY d.embedding <=> $vector::vector LIMIT $lim";
+    let base_select = "SELECT d.id, d.name, c.content, c.chunk_index, c.heading, c.start_offset, c.end_offset \
+                       FROM document_chunks c \
+                       JOIN documents d ON d.id = c.document_id";
+    let order_limit = "ORDER BY c.embedding <=> $vector::vector LIMIT $lim";
 
     let rows = if let Some(p_id) = project_id {
         let query_str = format!(
             "{} \
-             FROM documents d \
              JOIN document_projects dp ON d.id = dp.document_id \
              WHERE dp.project_id = $1 \
-               AND d.embedding IS NOT NULL \
                AND d.is_trashed = false \
+               AND EXISTS (SELECT 1 FROM document_access da WHERE da.document_id = d.id AND da.user_id = $2) \
              {}", base_select, order_limit
         );
-        // Query within a specific project
-        sqlx::query(&query_str.replace("$vector", "$2").replace("$lim", "$3")) // Replace placeholders
+        // Query within a specific project the user can access
+        sqlx::query(&query_str.replace("$vector", "$3").replace("$lim", "$4")) // Replace placeholders
             .bind(p_id)
+            .bind(user_id)
             .bind(query_embedding)
             .bind(k)
             .fetch_all(pool)
@@ -56,13 +79,13 @@ pub async fn semantic_search(
     } else {
         let query_str = format!(
             "{} \
-             FROM documents d \
-             WHERE d.embedding IS NOT NULL \
-               AND d.is_trashed = false \
+             WHERE d.is_trashed = false \
+               AND EXISTS (SELECT 1 FROM document_access da WHERE da.document_id = d.id AND da.user_id = $1) \
              {}", base_select, order_limit
         );
-        // Query across all documents
-         sqlx::query(&query_str.replace("$vector", "$1").replace("$lim", "$2")) // Replace placeholders
+        // Query across all documents the user has access to
+         sqlx::query(&query_str.replace("$vector", "$2").replace("$lim", "$3")) // Replace placeholders
+            .bind(user_id)
             .bind(query_embedding)
             .bind(k)
             .fetch_all(pool)
@@ -75,38 +98,32 @@ pub async fn semantic_search(
 
     println!("->> {:<12} - Rows fetched from DB: {}", "RETRIEVAL_DEBUG", rows.len());
 
-    let html_tag_regex = Regex::new("<[^>]*>")
-        .map_err(|e| {
-            eprintln!("Regex creation failed: {:?}", e);
-            Error::DatabaseError // Map to DatabaseError (adjust if a better variant exists)
-        })?;
-    
-
-    // Map rows to Vec<RetrievedChunk>
+    // Map rows to Vec<RetrievedChunk>, chunk content is already plain text
     let chunks: Vec<RetrievedChunk> = rows.iter()
         .filter_map(|row| { // Use filter_map to handle potential errors in getting columns
-            let id_res = row.try_get("id"); // Infer type
-            let name_res = row.try_get("name"); // Infer type
-            let content_res = row.try_get::<String, _>("content"); // Keep String type here
+            let chunk = (|| -> std::result::Result<RetrievedChunk, sqlx::Error> {
+                Ok(RetrievedChunk {
+                    document_id: row.try_get("id")?,
+                    document_name: row.try_get("name")?,
+                    content: row.try_get("content")?,
+                    chunk_index: row.try_get("chunk_index")?,
+                    heading: row.try_get("heading")?,
+                    start_offset: row.try_get("start_offset")?,
+                    end_offset: row.try_get("end_offset")?,
+                })
+            })();
 
-            match (id_res, name_res, content_res) {
-                (Ok(id), Ok(name), Ok(html_content)) => {
-                    let plain_text = html_tag_regex.replace_all(&html_content, "").to_string();
-                    Some(RetrievedChunk {
-                        document_id: id,
-                        document_name: name,
-                        content: plain_text,
-                    })
-                }
-                _ => {
-                    eprintln!("->> {:<12} - Failed to get id, name, or content from row", "RETRIEVAL_DEBUG");
+            match chunk {
+                Ok(chunk) => Some(chunk),
+                Err(e) => {
+                    eprintln!("->> {:<12} - Failed to read chunk row: {:?}", "RETRIEVAL_DEBUG", e);
                     None // Skip row if any column is missing/wrong type
                 }
             }
         })
         .collect();
 
-    println!("->> {:<12} - Retrieved {} relevant chunks (after stripping HTML)", "RETRIEVAL", chunks.len());
+    println!("->> {:<12} - Retrieved {} relevant chunks", "RETRIEVAL", chunks.len());
     Ok(chunks) // <--- Return Vec<RetrievedChunk>
 }
 
@@ -0,0 +1,203 @@
+// src/rag/structured.rs
+//
+// Structured output from the language model.
+// Endpoints that expect JSON describe it with an OutputSchema. Providers that support it constrain
+// generation to the schema, for the others the JSON is picked out of whatever the model replied:
+// fenced or not, with or without prose around it. The value is then checked against the schema
+// before it is deserialized, so a malformed reply is caught with a message the model can act on.
+
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+
+/// Start positions tried when looking for JSON in a reply, bounds the work on very long replies
+const MAX_JSON_CANDIDATES: usize = 32;
+
+/// A JSON schema (the subset checked by `validate`) with a name for providers that want one
+pub struct OutputSchema {
+    pub name: &'static str,
+    pub schema: Value,
+}
+
+impl OutputSchema {
+    pub fn new(name: &'static str, schema: Value) -> Self {
+        Self { name, schema }
+    }
+
+    fn expects_array(&self) -> bool {
+        self.schema["type"] == "array"
+    }
+
+    /// Providers only accept an object at the root, arrays are wrapped in { "items": [...] }
+    pub fn object_root(&self) -> Value {
+        if self.expects_array() {
+            json!({
+                "type": "object",
+                "properties": { "items": self.schema },
+                "required": ["items"],
+                "additionalProperties": false
+            })
+        } else {
+            self.schema.clone()
+        }
+    }
+
+    /// Parse a reply into `T`, the error says what was wrong with it
+    pub fn parse<T: DeserializeOwned>(&self, reply: &str) -> Result<T, String> {
+        let value = self.extract(reply)?;
+        serde_json::from_value(value).map_err(|e| format!("JSON does not have the expected shape: {}", e))
+    }
+
+    /// The first JSON value in the reply that matches the schema
+    fn extract(&self, reply: &str) -> Result<Value, String> {
+        let candidates = json_candidates(reply);
+        if candidates.is_empty() {
+            return Err("the reply contains no JSON".to_string());
+        }
+
+        let mut first_error = None;
+        for candidate in candidates {
+            let candidate = self.unwrap_root(candidate);
+            match validate(&candidate, &self.schema, "$") {
+                Ok(()) => return Ok(candidate),
+                Err(e) => {
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+
+        Err(first_error.unwrap_or_default())
+    }
+
+    /// Undo the wrapping of object_root, also taken when the model wraps an array on its own
+    fn unwrap_root(&self, value: Value) -> Value {
+        if !self.expects_array() {
+            return value;
+        }
+        let inner = match &value {
+            Value::Object(map) if map.len() == 1 => map.values().next().filter(|inner| inner.is_array()).cloned(),
+            _ => None,
+        };
+        inner.unwrap_or(value)
+    }
+}
+
+/// Every JSON object or array that can be parsed from the text, in the order they start.
+/// Values inside one that was already found are not reported again.
+fn json_candidates(text: &str) -> Vec<Value> {
+    let trimmed = text.trim();
+    if let Ok(value) = serde_json::from_str::<Value>(trimmed) {
+        if value.is_object() || value.is_array() {
+            return vec![value];
+        }
+    }
+
+    let mut candidates = Vec::new();
+    let mut position = 0;
+    let mut attempts = 0;
+
+    while let Some(offset) = text[position..].find(['{', '[']) {
+        if attempts == MAX_JSON_CANDIDATES {
+            break;
+        }
+        attempts += 1;
+
+        let start = position + offset;
+        let mut values = serde_json::Deserializer::from_str(&text[start..]).into_iter::<Value>();
+        match values.next() {
+            Some(Ok(value)) => {
+                candidates.push(value);
+                position = start + values.byte_offset();
+            }
+            _ => position = start + 1,
+        }
+    }
+
+    candidates
+}
+
+/// Check a value against a schema using type, properties, required, additionalProperties, items and enum.
+fn validate(value: &Value, schema: &Value, path: &str) -> Result<(), String> {
+    if let Some(expected) = schema.get("type") {
+        let types: Vec<&str> = match expected {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(ts) => ts.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !types.is_empty() && !types.iter().any(|t| has_type(value, t)) {
+            return Err(format!("{} should be {}, got {}", path, types.join(" or "), type_name(value)));
+        }
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(value) {
+            return Err(format!("{} should be one of {}", path, Value::Array(allowed.clone())));
+        }
+    }
+
+    if let Value::Object(map) = value {
+        let properties = schema.get("properties").and_then(Value::as_object);
+
+        for required in schema.get("required").and_then(Value::as_array).into_iter().flatten() {
+            if let Some(name) = required.as_str() {
+                if !map.contains_key(name) {
+                    return Err(format!("{} is missing \"{}\"", path, name));
+                }
+            }
+        }
+
+        for (name, field) in map {
+            match properties.and_then(|p| p.get(name)) {
+                Some(field_schema) => validate(field, field_schema, &format!("{}.{}", path, name))?,
+                None if schema.get("additionalProperties") == Some(&Value::Bool(false)) => {
+                    return Err(format!("{} has unexpected field \"{}\"", path, name));
+                }
+                None => {}
+            }
+        }
+    }
+
+    if let (Value::Array(items), Some(item_schema)) = (value, schema.get("items")) {
+        for (i, item) in items.iter().enumerate() {
+            validate(item, item_schema, &format!("{}[{}]", path, i))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn has_type(value: &Value, expected: &str) -> bool {
+    match expected {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Prompt asking the model to fix a reply that could not be used
+pub fn repair_prompt(original_prompt: &str, reply: &str, error: &str, schema: &OutputSchema) -> String {
+    // The original prompt carries the documents, the reply only needs to be long enough to recognise
+    let reply: String = reply.chars().take(4000).collect();
+
+    format!(
+        "{}\n\n---\n\nYour previous reply could not be used: {}.\n\nPrevious reply:\n{}\n\n\
+         Reply again with ONLY valid JSON matching this JSON schema, with no other text and no markdown:\n{}\n",
+        original_prompt, error, reply, schema.schema
+    )
+}
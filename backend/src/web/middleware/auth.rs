@@ -0,0 +1,100 @@
+// src/web/middleware/auth.rs
+//
+// Authentication for the API.
+// mw_validate_session resolves the auth cookie to an AuthUser (checked against the session store),
+// mw_require_auth rejects any /api request without one unless the route is on the public allow-list.
+// Handlers take AuthUser as an argument instead of reading cookies themselves.
+
+use axum::async_trait;
+use axum::body::Body;
+use axum::extract::{Extension, FromRequestParts};
+use axum::middleware::Next;
+use axum::response::Response;
+use http::request::Parts;
+use http::{Method, Request};
+use sqlx::PgPool;
+use tower_cookies::{Cookie, Cookies};
+use uuid::Uuid;
+
+use crate::auth::session::touch_session;
+use crate::auth::token::{get_token_from_cookie, AUTH_COOKIE_NAME};
+use crate::{Error, Result};
+
+/// Routes under /api that can be called without being logged in.
+/// Everything else requires a valid session.
+const PUBLIC_API_ROUTES: &[(Method, &str)] = &[
+    (Method::POST, "/api/users"),       // signup
+    (Method::POST, "/api/users/login"),
+    (Method::GET, "/api/users/logout"), // clearing an already dead cookie must not fail
+    (Method::GET, "/api/users/check-auth"),
+    (Method::GET, "/api/users/plans"),  // shown before signing up
+    (Method::GET, "/api/preference/default-background"),
+    (Method::GET, "/api/db/test"),  // health check
+    (Method::POST, "/api/db/reset"), // test mode only, guarded by its own secret
+];
+
+/// The authenticated user making the request.
+/// Inserted into the request extensions by mw_validate_session.
+#[derive(Debug, Clone)]
+pub struct AuthUser {
+    pub user_id: i32,
+    pub session_id: Uuid,
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for AuthUser {
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self> {
+        parts
+            .extensions
+            .get::<AuthUser>()
+            .cloned()
+            .ok_or(Error::NoAuthTokenError)
+    }
+}
+
+/// Middleware that checks the auth cookie against the session store on every request.
+/// If the session is active the AuthUser is attached to the request. If it was revoked
+/// or has expired the cookie is dropped so the browser clears its copy.
+pub async fn mw_validate_session(
+    cookies: Cookies,
+    Extension(pool): Extension<PgPool>,
+    mut request: Request<Body>,
+    next: Next<Body>,
+) -> Result<Response> {
+    println!("->> {:<12} - mw_validate_session", "MIDDLEWARE");
+
+    // Only tokens with a valid signature are worth looking up
+    if let Ok(token) = get_token_from_cookie(&cookies) {
+        if touch_session(&pool, &token).await? {
+            request.extensions_mut().insert(AuthUser {
+                user_id: token.user_id,
+                session_id: token.session_id,
+            });
+        } else {
+            println!("->> {:<12} - session {} is no longer active", "AUTH", token.session_id);
+            cookies.remove(Cookie::build(AUTH_COOKIE_NAME, "").path("/").finish());
+        }
+    }
+
+    Ok(next.run(request).await)
+}
+
+/// Middleware that stops unauthenticated requests to the API before they reach a handler.
+/// Must run inside mw_validate_session.
+pub async fn mw_require_auth(request: Request<Body>, next: Next<Body>) -> Result<Response> {
+    let path = request.uri().path();
+
+    let is_public = !path.starts_with("/api/")
+        || PUBLIC_API_ROUTES.iter().any(|(method, route)| {
+            method == request.method() && *route == path.trim_end_matches('/')
+        });
+
+    if !is_public && request.extensions().get::<AuthUser>().is_none() {
+        println!("->> {:<12} - rejected unauthenticated request to {}", "AUTH", path);
+        return Err(Error::NoAuthTokenError);
+    }
+
+    Ok(next.run(request).await)
+}
@@ -1 +1 @@
-pub mod middleware;
\ No newline at end of file
+pub mod auth;
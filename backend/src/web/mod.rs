@@ -1,2 +1,4 @@
 pub mod middleware;
-pub mod routes;
\ No newline at end of file
+pub mod routes;
+pub mod sanitize;
+pub mod static_files;
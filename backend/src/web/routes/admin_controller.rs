@@ -0,0 +1,226 @@
+/*
+/ src/web/routes/admin_controller.rs
+/ Request Handlers
+/
+/ File containing API Backend endpoints for administering users and watching storage
+/
+/ API Summary:
+/ api_list_users        GET     /users?q=&disabled=&limit=&offset=     - List or Search Users With Their Limits and Usage
+/ api_get_user          GET     /users/:id                             - Get a Single User With Their Limits and Usage
+/ api_adjust_credits    POST    /users/:id/credits                     - Add or Remove AI Credits
+/ api_update_limits     PUT     /users/:id/limits                      - Change Plan, Project/Document Limits and Storage Quota
+/ api_disable_user      POST    /users/:id/disable                     - Disable the Account and Revoke Its Sessions
+/ api_enable_user       POST    /users/:id/enable                      - Enable a Disabled Account Again
+/ api_get_storage       GET     /storage                               - Database Size, Quotas and the Largest Users
+/ api_get_audit_log     GET     /audit?target_user_id=&before=&limit=  - Changes Made by Admins, Newest First
+/
+/ Every endpoint requires the caller to be an admin. Changes are recorded in the audit log.
+/
+*/
+
+use axum::routing::{get, post, put};
+use axum::{
+    extract::{Extension, Json, Path, Query},
+    Router,
+};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+
+use crate::auth::authorization::require_admin;
+use crate::db::admin::{
+    adjust_credits, count_users, get_user, largest_storage_users, list_audit_log, list_users,
+    set_account_disabled, update_limits,
+};
+use crate::models::admin::{
+    AccountStatusPayload, AdjustCreditsPayload, AdminAuditEntry, AdminAuditQuery, AdminUserInfo,
+    AdminUserQuery, UpdateUserLimitsPayload,
+};
+use crate::models::storage::StorageManager;
+use crate::web::middleware::auth::AuthUser;
+use crate::{Error, Result};
+
+/// Users or audit entries returned when no limit is given, and the most that can be asked for
+const DEFAULT_ADMIN_PAGE: i64 = 50;
+const MAX_ADMIN_PAGE: i64 = 200;
+
+/// Users listed in the storage overview
+const LARGEST_USERS: i64 = 10;
+
+/// GET handler for listing users, optionally matching a name or email and only disabled or active accounts.
+/// Accessible via: GET /api/admin/users?q=&disabled=&limit=&offset=
+/// Test: test_admin.rs/test_list_users()
+/// Frontend: admin.ts/list_users()
+pub async fn api_list_users(
+    auth_user: AuthUser,
+    Query(params): Query<AdminUserQuery>,
+    Extension(pool): Extension<PgPool>,
+) -> Result<Json<Vec<AdminUserInfo>>> {
+    println!("->> {:<12} - list_users", "HANDLER");
+
+    require_admin(&pool, auth_user.user_id).await?;
+
+    let search = params.q.as_deref().map(str::trim).filter(|q| !q.is_empty());
+    let limit = params.limit.unwrap_or(DEFAULT_ADMIN_PAGE).clamp(1, MAX_ADMIN_PAGE);
+    let offset = params.offset.unwrap_or(0).max(0);
+
+    Ok(Json(list_users(&pool, search, params.disabled, limit, offset).await?))
+}
+
+/// GET handler for a single user with their limits and usage.
+/// Accessible via: GET /api/admin/users/:id
+/// Test: test_admin.rs/test_update_limits()
+/// Frontend: admin.ts/get_user()
+pub async fn api_get_user(
+    auth_user: AuthUser,
+    Path(user_id): Path<i32>,
+    Extension(pool): Extension<PgPool>,
+) -> Result<Json<AdminUserInfo>> {
+    println!("->> {:<12} - admin_get_user", "HANDLER");
+
+    require_admin(&pool, auth_user.user_id).await?;
+
+    Ok(Json(get_user(&pool, user_id).await?))
+}
+
+/// POST handler for adding credits to a user's balance, or taking them away with a negative delta.
+/// Accessible via: POST /api/admin/users/:id/credits
+/// Test: test_admin.rs/test_adjust_credits()
+/// Frontend: admin.ts/adjust_credits()
+pub async fn api_adjust_credits(
+    auth_user: AuthUser,
+    Path(user_id): Path<i32>,
+    Extension(pool): Extension<PgPool>,
+    Json(payload): Json<AdjustCreditsPayload>,
+) -> Result<Json<AdminUserInfo>> {
+    println!("->> {:<12} - adjust_credits", "HANDLER");
+
+    require_admin(&pool, auth_user.user_id).await?;
+
+    adjust_credits(&pool, auth_user.user_id, user_id, payload.delta, payload.reason.as_deref()).await?;
+
+    Ok(Json(get_user(&pool, user_id).await?))
+}
+
+/// PUT handler for changing a user's plan, project and document limits and storage quota.
+/// Fields left out keep their value, a null storage_quota_bytes goes back to the default quota.
+/// Accessible via: PUT /api/admin/users/:id/limits
+/// Test: test_admin.rs/test_update_limits()
+/// Frontend: admin.ts/update_limits()
+pub async fn api_update_limits(
+    auth_user: AuthUser,
+    Path(user_id): Path<i32>,
+    Extension(pool): Extension<PgPool>,
+    Json(payload): Json<UpdateUserLimitsPayload>,
+) -> Result<Json<AdminUserInfo>> {
+    println!("->> {:<12} - update_limits", "HANDLER");
+
+    require_admin(&pool, auth_user.user_id).await?;
+
+    update_limits(&pool, auth_user.user_id, user_id, &payload).await?;
+
+    Ok(Json(get_user(&pool, user_id).await?))
+}
+
+/// POST handler for disabling an account. The user is signed out everywhere and cannot sign in again
+/// until the account is enabled.
+/// Accessible via: POST /api/admin/users/:id/disable
+/// Test: test_admin.rs/test_disable_user()
+/// Frontend: admin.ts/set_account_disabled()
+pub async fn api_disable_user(
+    auth_user: AuthUser,
+    Path(user_id): Path<i32>,
+    Extension(pool): Extension<PgPool>,
+    payload: Option<Json<AccountStatusPayload>>,
+) -> Result<Json<AdminUserInfo>> {
+    println!("->> {:<12} - disable_user", "HANDLER");
+
+    require_admin(&pool, auth_user.user_id).await?;
+
+    let reason = payload.and_then(|Json(payload)| payload.reason);
+    set_account_disabled(&pool, auth_user.user_id, user_id, true, reason.as_deref()).await?;
+
+    Ok(Json(get_user(&pool, user_id).await?))
+}
+
+/// POST handler for enabling a disabled account again.
+/// Accessible via: POST /api/admin/users/:id/enable
+/// Test: test_admin.rs/test_disable_user()
+/// Frontend: admin.ts/set_account_disabled()
+pub async fn api_enable_user(
+    auth_user: AuthUser,
+    Path(user_id): Path<i32>,
+    Extension(pool): Extension<PgPool>,
+    payload: Option<Json<AccountStatusPayload>>,
+) -> Result<Json<AdminUserInfo>> {
+    println!("->> {:<12} - enable_user", "HANDLER");
+
+    require_admin(&pool, auth_user.user_id).await?;
+
+    let reason = payload.and_then(|Json(payload)| payload.reason);
+    set_account_disabled(&pool, auth_user.user_id, user_id, false, reason.as_deref()).await?;
+
+    Ok(Json(get_user(&pool, user_id).await?))
+}
+
+/// GET handler for the storage overview: database size against what is allocated, the default quotas
+/// and the users using the most storage.
+/// Accessible via: GET /api/admin/storage
+/// Test: test_admin.rs/test_get_storage()
+/// Frontend: admin.ts/get_storage()
+pub async fn api_get_storage(
+    auth_user: AuthUser,
+    Extension(pool): Extension<PgPool>,
+) -> Result<Json<Value>> {
+    println!("->> {:<12} - admin_get_storage", "HANDLER");
+
+    require_admin(&pool, auth_user.user_id).await?;
+
+    let db_size = StorageManager::get_db_size(&pool).await.map_err(|_| Error::DatabaseError)?;
+    let db_total = StorageManager::get_total_db_allocated();
+    let db_usage_percentage = StorageManager::get_db_usage_percentage(&pool)
+        .await
+        .map_err(|_| Error::DatabaseError)?;
+
+    Ok(Json(json!({
+        "database": {
+            "used_bytes": db_size,
+            "total_bytes": db_total,
+            "used_percentage": db_usage_percentage
+        },
+        "default_user_quota_bytes": StorageManager::get_user_quota(),
+        "revisions_per_document": StorageManager::get_revision_limit(),
+        "users": count_users(&pool).await?,
+        "largest_users": largest_storage_users(&pool, LARGEST_USERS).await?
+    })))
+}
+
+/// GET handler for the audit log, optionally for one user, newest first.
+/// Accessible via: GET /api/admin/audit?target_user_id=&before=&limit=
+/// Test: test_admin.rs/test_get_audit_log()
+/// Frontend: admin.ts/get_audit_log()
+pub async fn api_get_audit_log(
+    auth_user: AuthUser,
+    Query(params): Query<AdminAuditQuery>,
+    Extension(pool): Extension<PgPool>,
+) -> Result<Json<Vec<AdminAuditEntry>>> {
+    println!("->> {:<12} - get_audit_log", "HANDLER");
+
+    require_admin(&pool, auth_user.user_id).await?;
+
+    let limit = params.limit.unwrap_or(DEFAULT_ADMIN_PAGE).clamp(1, MAX_ADMIN_PAGE);
+
+    Ok(Json(list_audit_log(&pool, params.target_user_id, params.before, limit).await?))
+}
+
+// Combine admin routes into one Router instance.
+pub fn admin_routes() -> Router {
+    Router::new()
+        .route("/users", get(api_list_users))
+        .route("/users/:id", get(api_get_user))
+        .route("/users/:id/credits", post(api_adjust_credits))
+        .route("/users/:id/limits", put(api_update_limits))
+        .route("/users/:id/disable", post(api_disable_user))
+        .route("/users/:id/enable", post(api_enable_user))
+        .route("/storage", get(api_get_storage))
+        .route("/audit", get(api_get_audit_log))
+}
@@ -9,6 +9,7 @@
 / api_create_writing_session     POST    /                   - Create A New Writing Session
 / api_get_writing_session        GET     /:id                - Get Writing Session By ID With Messages
 / api_send_writing_message       POST    /:id/message        - Send Message And Get AI Response
+/ api_stream_writing_message     POST    /:id/message/stream - Send Message And Stream AI Response (SSE)
 / api_delete_writing_session     DELETE  /:id                - Delete Writing Session And All Messages
 / api_get_document_suggestions   GET     /:id/suggestions    - NOT IMPLEMENTED: Get Writing Suggestions For Document
 / api_analyze_document           POST    /analyze            - NOT IMPLEMENTED: Analyze Document For Writing Issues
@@ -18,20 +19,23 @@
 
 use axum::{
     extract::{Extension, Json, Path},
+    response::sse::{Event, KeepAlive, Sse},
     routing::{get, post, delete},
     Router,
 };
+use futures_util::stream::{self, Stream, StreamExt};
 use serde_json::{json, Value};
 use sqlx::PgPool;
-use tower_cookies::Cookies;
 use chrono::Utc;
-use std::collections::HashMap;
+use std::collections::HashSet;
+use std::convert::Infallible;
+use tokio::sync::mpsc;
 
 use crate::models::ai::{
     WritingAssistantSession, WritingAssistantMessage, SessionWithMessages, 
     CreateSessionPayload, SendMessagePayload, MessageRole, SelectedTextContext,
     RewritePayload, WritingAssistantSessionWithSnippet, SessionWithMessageContent,
-    ApplySuggestionPayload, SuggestedDocumentChange, LlmDocChange,
+    ApplySuggestionPayload, ApplySuggestionResponse, RejectedDocumentChange, SuggestedDocumentChange, LlmDocChange,
     DecisionAgentPayload, DecisionAgentResponse,
     SanitizeTextPayload, SanitizeTextResponse
 };
@@ -39,16 +43,28 @@ use crate::models::ai::{
 // use crate::cag::retrieval::semantic_search;
 use crate::{Error, Result};
 
-use backend::get_user_id_from_cookie;
+use crate::web::middleware::auth::AuthUser;
+use crate::auth::authorization::check_document_permission;
+use crate::db::credit::{release_credits, reserve_credits, settle_credits};
+use crate::db::suggestion::create_suggestion;
+use crate::diff::diff_hunks;
+use crate::models::credit::AiOperation;
+use crate::models::role::Role;
+use crate::models::suggestion::SuggestionSource;
 
 // Import RAG components
 use crate::rag::embed::{EmbeddingModel, embed_and_store_user_message, embed_and_store_assistant_message};
 use crate::rag::llm::QueryModel;
+use crate::rag::provider::TokenStream;
+use crate::rag::edit::apply_edits;
 use crate::rag::prompt;
 use crate::rag::retrieval;
 use pgvector::Vector;
 use crate::rag::prompt::construct_context_decision_prompt;
 
+/// Credits reserved while an operation runs, the final charge depends on the tokens it used
+const CREDITS_PER_OPERATION: i32 = 1;
+
 /// GET handler for retrieving all writing sessions for current user.
 /// Accessible via: GET /api/writing-assistant
 /// Test: test_ai.rs/test_get_all_writing_sessions_success()
@@ -56,13 +72,12 @@ use crate::rag::prompt::construct_context_decision_prompt;
 /// Returns a list of all writing assistant sessions belonging to the authenticated user.
 /// Sessions are ordered by last updated, with most recent first, and include a snippet of the last message.
 pub async fn api_get_all_writing_sessions(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Extension(pool): Extension<PgPool>,
 ) -> Result<Json<Vec<WritingAssistantSessionWithSnippet>>> { // Update return type
     println!("->> {:<12} - get_all_writing_sessions", "HANDLER");
 
-    // Get user_id from cookies
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
 
     // Query to get sessions and the content of the last message for each
     let sessions_raw = sqlx::query_as!(
@@ -126,19 +141,25 @@ pub async fn api_get_all_writing_sessions(
 
 /// POST handler for creating a new writing assistant session.
 /// Accessible via: POST /api/writing-assistant
-/// Test: test_ai.rs/test_create_writing_session_success()
+/// Test: test_ai.rs/test_create_writing_session_success(), test_ai.rs/test_linked_session_user_isolation()
 /// Frontend: ai.ts/create_writing_session()
 /// Creates a new writing assistant session and initializes it with a welcome message.
-/// Can optionally be linked to a document by providing a document_id in the payload.
+/// Can optionally be linked to a document by providing a document_id in the payload,
+/// which needs at least viewer access to that document.
 pub async fn api_create_writing_session(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Extension(pool): Extension<PgPool>,
     Json(payload): Json<CreateSessionPayload>,
 ) -> Result<Json<WritingAssistantSession>> {
     println!("->> {:<12} - create_writing_session", "HANDLER");
 
-    // Get user_id from cookies
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
+
+    if let Some(document_id) = payload.document_id {
+        if !check_document_permission(&pool, user_id, document_id, Role::Viewer).await? {
+            return Err(Error::PermissionError);
+        }
+    }
 
     // Create a new chat session
     let session = sqlx::query_as!(
@@ -184,14 +205,13 @@ pub async fn api_create_writing_session(
 /// Returns detailed information about a specific writing session including all messages.
 /// Only the owner of the session can access it.
 pub async fn api_get_writing_session(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Path(session_id): Path<i32>,
     Extension(pool): Extension<PgPool>,
 ) -> Result<Json<SessionWithMessages>> {
     println!("->> {:<12} - get_writing_session", "HANDLER");
 
-    // Get user_id from cookies
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
 
     // Get the chat session
     let session = sqlx::query_as!(
@@ -241,7 +261,7 @@ pub async fn api_get_writing_session(
 /// Sends a user message to the AI writing assistant and returns the AI's response.
 /// If the session is linked to a document, the document content will be used as context for the AI.
 pub async fn api_send_writing_message(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Path(session_id): Path<i32>,
     Extension(pool): Extension<PgPool>,
     Json(payload): Json<SendMessagePayload>,
@@ -249,11 +269,159 @@ pub async fn api_send_writing_message(
     println!("->> {:<12} - send_writing_message", "HANDLER");
     println!("->> {:<12} - Payload: {:?}", "HANDLER", payload);
 
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
+
+    let query_model = QueryModel::new()?;
+    let credits = reserve_credits(&pool, user_id, AiOperation::Chat, CREDITS_PER_OPERATION).await?;
+
+    let result: Result<Value> = async {
+        let prepared = prepare_writing_message(&pool, &query_model, user_id, session_id, &payload).await?;
+
+        // --- Query LLM --- 
+        println!("->> {:<12} - Querying LLM", "RAG FUNCTION");
+        let llm_response_content = query_model.query_model(&prepared.prompt).await?;
+        println!("->> {:<12} - LLM response received: \"{}...\"", "RAG FUNCTION", llm_response_content.chars().take(70).collect::<String>());
+
+        // --- Embed and Store Assistant Response --- 
+        println!("->> {:<12} - Assistant response content: \"{}\"", "RAG FUNCTION", llm_response_content);
+        embed_and_store_assistant_message(
+            &prepared.embedding_model,
+            &pool,
+            session_id,
+            &llm_response_content // Pass LLM response content
+        ).await?;
+
+        Ok(json!({ "role": "assistant", "content": llm_response_content, "sources": prepared.sources }))
+    }
+    .await;
+
+    // Credits are only spent if the response was generated and stored
+    let response_json = settle_credits(&pool, credits, query_model.usage(), result).await?;
+
+    // --- Return Response --- 
+    println!("->> {:<12} - Sending response", "RAG FUNCTION");
+    println!("->> {:<12} - Response JSON: {:?}", "RES_MAPPER", response_json);
+    Ok(Json(response_json))
+}
+
+/// POST handler for sending a message and streaming the AI response as it is generated.
+/// Accessible via: POST /api/writing-assistant/:id/message/stream
+/// Test: test_ai.rs/test_stream_writing_message_success()
+/// Frontend: // TODO: No frontend function implemented yet
+/// Responds with Server-Sent Events: a `token` event per piece of the response, then a single `done`
+/// event carrying the full message and its sources, or an `error` event if generation fails.
+/// If the client disconnects the generation is stopped and the partial response is stored,
+/// so the chat history matches what the user saw. The credit is only returned if nothing was generated
+/// or the response could not be stored.
+pub async fn api_stream_writing_message(
+    auth_user: AuthUser,
+    Path(session_id): Path<i32>,
+    Extension(pool): Extension<PgPool>,
+    Json(payload): Json<SendMessagePayload>,
+) -> Result<Sse<impl Stream<Item = std::result::Result<Event, Infallible>>>> {
+    println!("->> {:<12} - stream_writing_message", "HANDLER");
+
+    let user_id = auth_user.user_id;
+    let query_model = QueryModel::new()?;
+    let credits = reserve_credits(&pool, user_id, AiOperation::Chat, CREDITS_PER_OPERATION).await?;
+
+    let started: Result<(PreparedMessage, TokenStream)> = async {
+        let prepared = prepare_writing_message(&pool, &query_model, user_id, session_id, &payload).await?;
+        let tokens = query_model.stream_model(&prepared.prompt).await?;
+        Ok((prepared, tokens))
+    }
+    .await;
+
+    let (prepared, mut tokens) = match started {
+        Ok(started) => started,
+        Err(e) => {
+            release_credits(&pool, &credits, query_model.usage(), e.as_ref()).await?;
+            return Err(e);
+        }
+    };
+
+    // The LLM is read in its own task so the response still gets stored when the client goes away
+    let (sender, receiver) = mpsc::channel::<Event>(32);
+    tokio::spawn(async move {
+        let mut llm_response_content = String::new();
+
+        while let Some(piece) = tokens.next().await {
+            match piece {
+                Ok(piece) => {
+                    llm_response_content.push_str(&piece);
+                    let event = Event::default().event("token").json_data(json!({ "content": piece }));
+                    if let Ok(event) = event {
+                        if sender.send(event).await.is_err() {
+                            // Receiver dropped means the client disconnected, stop generating
+                            println!("->> {:<12} - Client disconnected from session {} mid-stream", "STREAM", session_id);
+                            break;
+                        }
+                    }
+                }
+                Err(e) => {
+                    eprintln!("LLM stream failed for session {}: {:?}", session_id, e);
+                    query_model.add_streamed(&llm_response_content);
+                    let _ = release_credits(&pool, &credits, query_model.usage(), e.as_ref()).await;
+                    let _ = sender.send(Event::default().event("error").data("LLM query failed")).await;
+                    return;
+                }
+            }
+        }
+
+        query_model.add_streamed(&llm_response_content);
+
+        if llm_response_content.is_empty() {
+            let _ = release_credits(&pool, &credits, query_model.usage(), Error::LlmQueryError.as_ref()).await;
+            return;
+        }
+
+        // --- Embed and Store Assistant Response --- 
+        let stored = embed_and_store_assistant_message(
+            &prepared.embedding_model,
+            &pool,
+            session_id,
+            &llm_response_content
+        ).await;
+
+        // A response cut short by the client still counts, the user saw it
+        let stored = settle_credits(&pool, credits, query_model.usage(), stored).await;
+
+        let final_event = match stored {
+            Ok(()) => Event::default()
+                .event("done")
+                .json_data(json!({ "role": "assistant", "content": llm_response_content, "sources": prepared.sources }))
+                .unwrap_or_else(|_| Event::default().event("done")),
+            Err(e) => {
+                eprintln!("Failed to store streamed message for session {}: {:?}", session_id, e);
+                Event::default().event("error").data("Failed to store response")
+            }
+        };
+        // Nobody to tell if the client already left
+        let _ = sender.send(final_event).await;
+    });
 
-    // Check and decrement credits before proceeding
-    check_and_decrement_ai_credits(&pool, user_id).await?;
+    let events = stream::unfold(receiver, |mut receiver| async move {
+        receiver.recv().await.map(|event| (Ok(event), receiver))
+    });
 
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+// Everything needed to answer a chat message, shared by the plain and streaming handlers
+struct PreparedMessage {
+    embedding_model: EmbeddingModel,
+    prompt: String,
+    sources: Vec<Value>,
+}
+
+// Stores the user's message and builds the prompt with the retrieved context
+async fn prepare_writing_message(
+    pool: &PgPool,
+    query_model: &QueryModel,
+    user_id: i32,
+    session_id: i32,
+    payload: &SendMessagePayload,
+) -> Result<PreparedMessage> {
     let session = sqlx::query_as!(
         WritingAssistantSession,
         r#"
@@ -264,7 +432,7 @@ pub async fn api_send_writing_message(
         session_id,
         user_id
     )
-    .fetch_one(&pool)
+    .fetch_one(pool)
     .await
     .map_err(|_| Error::PermissionError)?;
 
@@ -281,7 +449,7 @@ pub async fn api_send_writing_message(
     println!("->> {:<12} - User message content: \"{}\"", "RAG FUNCTION", payload.content);
     let user_embedding: Vector = embed_and_store_user_message(
         &embedding_model,
-        &pool,
+        pool,
         session_id,
         &user_message_to_store
     ).await?;
@@ -298,13 +466,13 @@ pub async fn api_send_writing_message(
         Utc::now().naive_utc(),
         session_id
     )
-    .execute(&pool)
+    .execute(pool)
     .await
     .map_err(|_| Error::DatabaseError)?;
 
     // Retrieve chat history using the dedicated function
     println!("->> {:<12} - Retrieving chat history", "RAG FUNCTION");
-    let chat_history = retrieval::retrieve_chat_history(&pool, session_id).await?;
+    let chat_history = retrieval::retrieve_chat_history(pool, session_id).await?;
     println!("->> {:<12} - Retrieved {} messages from history", "RETRIEVAL", chat_history.messages.len());
     
     // Determine Project ID and Current Document Name for context retrieval
@@ -314,17 +482,19 @@ pub async fn api_send_writing_message(
     
     if let Some(doc_id) = session.document_id {
         current_doc_id = Some(doc_id);
-        // Fetch project ID and document name if document is linked
+        // Fetch project ID and document name if document is linked and still reachable by the user
         let doc_info = sqlx::query!(
             r#"
             SELECT dp.project_id, d.name 
             FROM documents d 
             LEFT JOIN document_projects dp ON d.id = dp.document_id 
             WHERE d.id = $1
+              AND EXISTS (SELECT 1 FROM document_access da WHERE da.document_id = d.id AND da.user_id = $2)
             "#,
-            doc_id
+            doc_id,
+            user_id
         )
-        .fetch_optional(&pool)
+        .fetch_optional(pool)
         .await
         .map_err(|_| Error::DatabaseError)?;
 
@@ -340,7 +510,8 @@ pub async fn api_send_writing_message(
     println!("->> {:<12} - Retrieving relevant chunks (k={}) for project_id: {:?}", "RETRIEVAL", k_value, project_id_for_context);
     
     let mut relevant_chunks = retrieval::semantic_search(
-        &pool, 
+        pool, 
+        user_id,
         project_id_for_context,
         &user_embedding,
         k_value
@@ -351,7 +522,6 @@ pub async fn api_send_writing_message(
     // Use construct_context_decision_prompt to determine if we need additional context
     println!("->> {:<12} - Determining context needs", "CONTEXT DECISION");
     let decision_prompt = construct_context_decision_prompt(&payload.content);
-    let query_model = QueryModel::new()?;
     let context_decision = query_model.query_model(&decision_prompt).await?;
     let context_decision = context_decision.trim().to_lowercase();
     
@@ -368,21 +538,19 @@ pub async fn api_send_writing_message(
                     SELECT name, content
                     FROM documents
                     WHERE id = $1 AND is_trashed = false
+                      AND EXISTS (SELECT 1 FROM document_access da WHERE da.document_id = documents.id AND da.user_id = $2)
                     "#,
-                    doc_id
+                    doc_id,
+                    user_id
                 )
-                .fetch_optional(&pool)
+                .fetch_optional(pool)
                 .await
                 .map_err(|_| Error::DatabaseError)?;
                 
                 if let Some(doc) = document {
                     if let Some(content) = doc.content {
                         let doc_name = doc.name;
-                        let doc_chunk = retrieval::RetrievedChunk {
-                            document_id: doc_id,
-                            document_name: doc_name,
-                            content,
-                        };
+                        let doc_chunk = retrieval::RetrievedChunk::whole_document(doc_id, doc_name, content);
                         println!("->> {:<12} - Added current document context: {} ({})", "CONTEXT ADDITION", doc_chunk.document_name, doc_chunk.content.len());
                         relevant_chunks.push(doc_chunk);
                     }
@@ -399,18 +567,20 @@ pub async fn api_send_writing_message(
                     content: Option<String>,
                 }
                 
-                // Fetch all documents in the project
+                // Fetch all documents in the project the user can read
                 let project_docs = sqlx::query_as!(DocumentContent,
                     r#"
                     SELECT id, name, content 
                     FROM documents 
                     WHERE id IN (SELECT document_id FROM document_projects WHERE project_id = $1)
                       AND is_trashed = false
+                      AND EXISTS (SELECT 1 FROM document_access da WHERE da.document_id = documents.id AND da.user_id = $2)
                     ORDER BY name ASC
                     "#,
-                    project_id
+                    project_id,
+                    user_id
                 )
-                .fetch_all(&pool)
+                .fetch_all(pool)
                 .await
                 .map_err(|_| Error::DatabaseError)?;
                 
@@ -423,11 +593,7 @@ pub async fn api_send_writing_message(
                         // Only add non-empty content
                         if !content.is_empty() {
                             let doc_name = doc.name.unwrap_or_else(|| "Untitled".to_string());
-                            let doc_chunk = retrieval::RetrievedChunk {
-                                document_id: doc.id,
-                                document_name: doc_name,
-                                content,
-                            };
+                            let doc_chunk = retrieval::RetrievedChunk::whole_document(doc.id, doc_name, content);
                             println!("->> {:<12} - Added project document: {} ({})", "CONTEXT ADDITION", doc_chunk.document_name, doc_chunk.content.len());
                             relevant_chunks.push(doc_chunk);
                         }
@@ -467,26 +633,24 @@ pub async fn api_send_writing_message(
     let estimated_tokens = final_prompt.split_whitespace().count();
     println!("->> {:<12} - Prompt constructed ({} tokens estimated):\n---\n{}\n---", "PROMPT", estimated_tokens, final_prompt);
 
-    // --- Query LLM --- 
-    println!("->> {:<12} - Querying LLM", "RAG FUNCTION");
-    // Query model was already initialized above
-    let llm_response_content = query_model.query_model(&final_prompt).await?;
-    println!("->> {:<12} - LLM response received: \"{}...\"", "RAG FUNCTION", llm_response_content.chars().take(70).collect::<String>());
-
-    // --- Embed and Store Assistant Response --- 
-    println!("->> {:<12} - Assistant response content: \"{}\"", "RAG FUNCTION", llm_response_content);
-    embed_and_store_assistant_message(
-        &embedding_model,
-        &pool,
-        session_id,
-        &llm_response_content // Pass LLM response content
-    ).await?;
+    // Include the passages used as context so the client can show and jump to sources
+    let sources: Vec<Value> = relevant_chunks
+        .iter()
+        .map(|chunk| json!({
+            "document_id": chunk.document_id,
+            "document_name": chunk.document_name,
+            "chunk_index": chunk.chunk_index,
+            "heading": chunk.heading,
+            "start_offset": chunk.start_offset,
+            "end_offset": chunk.end_offset
+        }))
+        .collect();
 
-    // --- Return Response --- 
-    println!("->> {:<12} - Sending response", "RAG FUNCTION");
-    let response_json = json!({ "role": "assistant", "content": llm_response_content });
-    println!("->> {:<12} - Response JSON: {:?}", "RES_MAPPER", response_json);
-    Ok(Json(response_json))
+    Ok(PreparedMessage {
+        embedding_model,
+        prompt: final_prompt,
+        sources,
+    })
 }
 
 /// DELETE handler for removing a writing session and all its messages.
@@ -496,14 +660,13 @@ pub async fn api_send_writing_message(
 /// This will automatically delete all associated messages due to CASCADE delete constraint.
 /// Only the owner of the session can delete it.
 pub async fn api_delete_writing_session(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Path(session_id): Path<i32>,
     Extension(pool): Extension<PgPool>,
 ) -> Result<Json<Value>> {
     println!("->> {:<12} - delete_writing_session", "HANDLER");
 
-    // Get user_id from cookies
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
 
     // Verify the session belongs to this user and delete it
     // All associated messages will be deleted automatically due to ON DELETE CASCADE
@@ -535,20 +698,14 @@ pub async fn api_delete_writing_session(
 /// Test: test_ai.rs/test_check_grammar_success()
 /// Frontend: ai.ts/check_grammar()
 pub async fn api_check_grammer(
-    cookies: Cookies,
+    auth_user: AuthUser,
     pool: Extension<PgPool>,
     Json(payload): Json<SelectedTextContext>
 ) -> Result<Json<Value>> {
     println!("->> {:<12} - api_check_grammer", "HANDLER");
 
-    // Get user_id and check/decrement credits
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
-    check_and_decrement_ai_credits(&pool, user_id).await?;
-
     let prompt = prompt::construct_grammar_check_prompt(&payload.content);
-    
-    let query_model = QueryModel::new()?;
-    let response = query_model.query_model(&prompt).await?;
+    let response = query_once(&pool, auth_user.user_id, AiOperation::Grammar, &prompt).await?;
 
     Ok(Json(json!({ "response": response })))
 }
@@ -558,18 +715,14 @@ pub async fn api_check_grammer(
 /// Test: test_ai.rs/test_summarize_success()
 /// Frontend: ai.ts/summarize_text()
 pub async fn api_summarize(
-    cookies: Cookies,
+    auth_user: AuthUser,
     pool: Extension<PgPool>,
     Json(payload): Json<SelectedTextContext>
 ) -> Result<Json<Value>> {
     println!("->> {:<12} - api_summarize", "HANDLER");
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
-    check_and_decrement_ai_credits(&pool, user_id).await?;
 
     let prompt = prompt::construct_summarize_prompt(&payload.content);
-    
-    let query_model = QueryModel::new()?;
-    let response = query_model.query_model(&prompt).await?;
+    let response = query_once(&pool, auth_user.user_id, AiOperation::Summarize, &prompt).await?;
 
     Ok(Json(json!({ "response": response })))
 }
@@ -579,18 +732,14 @@ pub async fn api_summarize(
 /// Test: test_ai.rs/test_rephrase_success()
 /// Frontend: ai.ts/rephrase_text()
 pub async fn api_rephrase(
-    cookies: Cookies,
+    auth_user: AuthUser,
     pool: Extension<PgPool>,
     Json(payload): Json<SelectedTextContext>
 ) -> Result<Json<Value>> {
     println!("->> {:<12} - api_rephrase", "HANDLER");
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
-    check_and_decrement_ai_credits(&pool, user_id).await?;
 
     let prompt = prompt::construct_rephrase_prompt(&payload.content);
-    
-    let query_model = QueryModel::new()?;
-    let response = query_model.query_model(&prompt).await?;
+    let response = query_once(&pool, auth_user.user_id, AiOperation::Rephrase, &prompt).await?;
 
     Ok(Json(json!({ "response": response })))
 }
@@ -600,18 +749,14 @@ pub async fn api_rephrase(
 /// Test: test_ai.rs/test_expand_success()
 /// Frontend: ai.ts/expand_text()
 pub async fn api_expand(
-    cookies: Cookies,
+    auth_user: AuthUser,
     pool: Extension<PgPool>,
     Json(payload): Json<SelectedTextContext>
 ) -> Result<Json<Value>> {
     println!("->> {:<12} - api_expand", "HANDLER");
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
-    check_and_decrement_ai_credits(&pool, user_id).await?;
 
     let prompt = prompt::construct_expand_prompt(&payload.content);
-    
-    let query_model = QueryModel::new()?;
-    let response = query_model.query_model(&prompt).await?;
+    let response = query_once(&pool, auth_user.user_id, AiOperation::Expand, &prompt).await?;
 
     Ok(Json(json!({ "response": response })))
 }
@@ -621,18 +766,14 @@ pub async fn api_expand(
 /// Test: test_ai.rs/test_shrink_success()
 /// Frontend: ai.ts/shrink_text()
 pub async fn api_shrink(
-    cookies: Cookies,
+    auth_user: AuthUser,
     pool: Extension<PgPool>,
     Json(payload): Json<SelectedTextContext>
 ) -> Result<Json<Value>> {
     println!("->> {:<12} - api_shrink", "HANDLER");
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
-    check_and_decrement_ai_credits(&pool, user_id).await?;
 
     let prompt = prompt::construct_shrink_prompt(&payload.content);
-    
-    let query_model = QueryModel::new()?;
-    let response = query_model.query_model(&prompt).await?;
+    let response = query_once(&pool, auth_user.user_id, AiOperation::Shrink, &prompt).await?;
 
     Ok(Json(json!({ "response": response })))
 }
@@ -642,18 +783,14 @@ pub async fn api_shrink(
 /// Test: test_ai.rs/test_rewrite_success()
 /// Frontend: ai.ts/rewrite_text_as()
 pub async fn api_rewrite(
-    cookies: Cookies,
+    auth_user: AuthUser,
     pool: Extension<PgPool>,
     Json(payload): Json<RewritePayload>,
 ) -> Result<Json<Value>> {
     println!("->> {:<12} - api_rewrite", "HANDLER");
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
-    check_and_decrement_ai_credits(&pool, user_id).await?;
 
     let prompt = prompt::construct_rewrite_prompt(&payload.content, &payload.style);
-    
-    let query_model = QueryModel::new()?;
-    let response = query_model.query_model(&prompt).await?;
+    let response = query_once(&pool, auth_user.user_id, AiOperation::Rewrite, &prompt).await?;
 
     Ok(Json(json!({ "response": response })))
 }
@@ -663,18 +800,14 @@ pub async fn api_rewrite(
 /// Test: test_ai.rs/test_fact_check_success()
 /// Frontend: ai.ts/fact_check_text()
 pub async fn api_fact_check(
-    cookies: Cookies,
+    auth_user: AuthUser,
     pool: Extension<PgPool>,
     Json(payload): Json<SelectedTextContext>,
 ) -> Result<Json<Value>> {
     println!("->> {:<12} - api_fact_check", "HANDLER");
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
-    check_and_decrement_ai_credits(&pool, user_id).await?;
 
     let prompt = prompt::construct_fact_check_prompt(&payload.content);
-    
-    let query_model = QueryModel::new()?;
-    let response = query_model.query_model(&prompt).await?;
+    let response = query_once(&pool, auth_user.user_id, AiOperation::FactCheck, &prompt).await?;
 
     Ok(Json(json!({ "response": response })))
 }
@@ -684,76 +817,63 @@ pub async fn api_fact_check(
 /// Test: test_ai.rs/test_spell_check_success()
 /// Frontend: ai.ts/check_spelling()
 pub async fn api_spell_check(
-    cookies: Cookies,
+    auth_user: AuthUser,
     pool: Extension<PgPool>,
     Json(payload): Json<SelectedTextContext>,
 ) -> Result<Json<Value>> {
     println!("->> {:<12} - api_spell_check", "HANDLER");
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
-    check_and_decrement_ai_credits(&pool, user_id).await?;
 
     let prompt = prompt::construct_spell_check_prompt(&payload.content);
-    
-    let query_model = QueryModel::new()?;
-    let response = query_model.query_model(&prompt).await?;
+    let response = query_once(&pool, auth_user.user_id, AiOperation::SpellCheck, &prompt).await?;
 
     Ok(Json(json!({ "response": response })))
 }
 
-/// Helper function to check and decrement AI credits
-async fn check_and_decrement_ai_credits(pool: &PgPool, user_id: i32) -> Result<()> {
-    // Fetch current credits
-    let user_credits = sqlx::query!(
-        "SELECT ai_credits FROM users WHERE id = $1",
-        user_id
-    )
-    .fetch_optional(pool)
-    .await
-    .map_err(|_| Error::DatabaseError)?
-    .ok_or(Error::UserNotFoundError { user_id })?
-    .ai_credits;
-
-    // Check if credits are sufficient
-    if user_credits <= 0 {
-        println!("->> {:<12} - User {} has insufficient AI credits ({})", "CREDIT_CHECK", user_id, user_credits);
-        return Err(Error::InsufficientAiCredits);
-    }
-
-    // Decrement credits
-    let update_result = sqlx::query!(
-        "UPDATE users SET ai_credits = ai_credits - 1 WHERE id = $1 AND ai_credits > 0",
-        user_id
-    )
-    .execute(pool)
-    .await
-    .map_err(|_| Error::DatabaseError)?;
-
-    // Check if the update actually happened (in case of race condition where credits became 0 between check and update)
-    if update_result.rows_affected() == 0 {
-        println!("->> {:<12} - Failed to decrement credits for user {} (possible race condition or already 0)", "CREDIT_CHECK", user_id);
-        return Err(Error::InsufficientAiCredits); // Treat as insufficient if update failed
-    }
+/// Send a single prompt as `operation`, charging the user for the tokens it used
+async fn query_once(pool: &PgPool, user_id: i32, operation: AiOperation, prompt: &str) -> Result<String> {
+    let query_model = QueryModel::new()?;
+    let credits = reserve_credits(pool, user_id, operation, CREDITS_PER_OPERATION).await?;
 
-    println!("->> {:<12} - Decremented AI credits for user {}. Remaining: {}", "CREDIT_CHECK", user_id, user_credits - 1);
-    Ok(())
+    let result = query_model.query_model(prompt).await;
+    settle_credits(pool, credits, query_model.usage(), result).await
 }
 
 /// POST handler for applying an AI suggestion to project documents.
+/// Each proposed change is stored as a pending suggestion on its document, to be accepted or rejected
+/// through /api/document/:id/suggestions. Only documents the user can read are shown to the model,
+/// and documents the user cannot suggest changes to are left out.
+/// The model returns find/replace edits rather than whole documents, see rag/edit.rs. Edits whose quoted
+/// text is not found exactly once in the current content are rejected along with the rest of that document,
+/// and the document is listed under rejected with the reason. If every document was rejected the answer is 409.
 /// Accessible via: POST /api/ai/writing-assistant/:id/apply-suggestion
-/// Test: TODO
+/// Test: test_ai.rs/test_apply_suggestion_success(), test_ai.rs/test_apply_suggestion_conflict()
+/// Frontend: ai.ts/apply_ai_suggestion()
 pub async fn api_apply_suggestion(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Path(session_id): Path<i32>,
     Extension(pool): Extension<PgPool>,
     Json(payload): Json<ApplySuggestionPayload>,
-) -> Result<Json<Vec<SuggestedDocumentChange>>> {
+) -> Result<Json<ApplySuggestionResponse>> {
     println!("->> {:<12} - api_apply_suggestion for session {}", "HANDLER", session_id);
 
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
+    let query_model = QueryModel::new()?;
+    let credits = reserve_credits(&pool, user_id, AiOperation::ApplySuggestion, CREDITS_PER_OPERATION).await?;
+
+    let result = apply_suggestion(&pool, &query_model, user_id, session_id, payload).await;
+    let response = settle_credits(&pool, credits, query_model.usage(), result).await?;
 
-    // Check and decrement credits before proceeding
-    check_and_decrement_ai_credits(&pool, user_id).await?;
+    Ok(Json(response))
+}
 
+// Asks the model for edits to the session's project and stores them as suggestions
+async fn apply_suggestion(
+    pool: &PgPool,
+    query_model: &QueryModel,
+    user_id: i32,
+    session_id: i32,
+    payload: ApplySuggestionPayload,
+) -> Result<ApplySuggestionResponse> {
     // 1. Fetch session to verify ownership and get linked document ID
     let session = sqlx::query_as!(
         WritingAssistantSession,
@@ -761,7 +881,7 @@ pub async fn api_apply_suggestion(
         session_id,
         user_id
     )
-    .fetch_optional(&pool) // Use optional as session might exist but not belong to user
+    .fetch_optional(pool) // Use optional as session might exist but not belong to user
     .await
     .map_err(|_| Error::DatabaseError)?
     .ok_or(Error::PermissionError)?; // Return permission error if session not found for user
@@ -777,7 +897,7 @@ pub async fn api_apply_suggestion(
         "SELECT project_id FROM document_projects WHERE document_id = $1",
         current_doc_id
     )
-    .fetch_optional(&pool)
+    .fetch_optional(pool)
     .await
     .map_err(|_| Error::DatabaseError)?;
 
@@ -790,17 +910,19 @@ pub async fn api_apply_suggestion(
     };
     println!("->> {:<12} - Found project_id {} for apply suggestion.", "HANDLER", project_id);
 
-    // 4. Fetch original content of all documents in the project
+    // 4. Fetch original content of the documents in the project the user can read
     struct OriginalDoc { id: i32, name: Option<String>, content: Option<String> }
     let original_docs = sqlx::query_as!(OriginalDoc,
         r#"
         SELECT id, name, content FROM documents 
         WHERE id IN (SELECT document_id FROM document_projects WHERE project_id = $1)
         AND is_trashed = false
+        AND EXISTS (SELECT 1 FROM document_access da WHERE da.document_id = documents.id AND da.user_id = $2)
         "#,
-        project_id
+        project_id,
+        user_id
     )
-    .fetch_all(&pool)
+    .fetch_all(pool)
     .await
     .map_err(|_| Error::DatabaseError)?;
 
@@ -820,12 +942,8 @@ pub async fn api_apply_suggestion(
         ))
         .collect();
 
-    // Store original content mapped by ID for later diff generation
-    let original_content_map: HashMap<i32, String> = original_docs
-        .into_iter() // Consume original_docs here
-        .map(|doc| (doc.id, doc.content.unwrap_or_default()))
-        .collect();
-
+    // Edits are only accepted for documents the model was shown
+    let project_document_ids: HashSet<i32> = original_docs.iter().map(|doc| doc.id).collect();
 
     // 5. Construct the prompt
     let final_prompt = prompt::construct_apply_suggestion_prompt(
@@ -839,61 +957,90 @@ pub async fn api_apply_suggestion(
 
     // 6. Query LLM
     println!("->> {:<12} - Querying LLM for apply suggestion.", "HANDLER");
-    let query_model = QueryModel::new()?;
-    let llm_response_str = query_model.query_model(&final_prompt).await?;
-    println!("->> {:<12} - LLM response received ({} chars).", "HANDLER", llm_response_str.len());
-
-    // Trim markdown fences if present
-    let trimmed_response = llm_response_str
-        .strip_prefix("```json\n")
-        .unwrap_or(&llm_response_str)
-        .strip_suffix("\n```")
-        .unwrap_or(&llm_response_str)
-        .trim(); // Also trim leading/trailing whitespace just in case
-
-    // 7. Parse LLM response (JSON array of LlmDocChange)
-    let llm_changes: Vec<LlmDocChange> = serde_json::from_str(trimmed_response)
-        .map_err(|e| {
-            eprintln!("Error parsing LLM response JSON: {:?}\nTrimmed Response: {}", e, trimmed_response);
-            Error::FailedApplyChanges
-        })?;
+
+    // 7. Parse LLM response (JSON array of LlmDocChange), retried once if it does not match the schema
+    let llm_changes: Vec<LlmDocChange> = query_model
+        .query_json(&final_prompt, &prompt::apply_suggestion_schema())
+        .await?;
     println!("->> {:<12} - Parsed {} changes from LLM response.", "HANDLER", llm_changes.len());
 
-    // 8. Construct final response (Vec<SuggestedDocumentChange>)
+    // 8. Apply each document's edits and store the result as a pending suggestion
     let mut suggested_changes: Vec<SuggestedDocumentChange> = Vec::new();
+    let mut rejected: Vec<RejectedDocumentChange> = Vec::new();
     for change in llm_changes {
-        if let Some(old_content) = original_content_map.get(&change.document_id) {
-            // Only include if the content actually changed
-            if old_content != &change.new_content {
-                 suggested_changes.push(SuggestedDocumentChange {
-                    document_id: change.document_id,
-                    old_content: old_content.clone(), // Clone original content
-                    new_content: change.new_content, // Use new content from LLM
-                });
-            } else {
-                 println!("->> {:<12} - LLM proposed no change for doc {}, skipping.", "HANDLER", change.document_id);
-            }
-        } else {
+        if !project_document_ids.contains(&change.document_id) {
             // LLM returned an ID not in the original set - log warning but ignore
-             println!("->> {:<12} - WARNING: LLM returned change for unknown document ID {}, ignoring.", "HANDLER", change.document_id);
+            println!("->> {:<12} - WARNING: LLM returned change for unknown document ID {}, ignoring.", "HANDLER", change.document_id);
+            continue;
+        }
+
+        if !check_document_permission(pool, user_id, change.document_id, Role::Commenter).await? {
+            println!("->> {:<12} - User {} cannot suggest changes to doc {}, skipping.", "HANDLER", user_id, change.document_id);
+            continue;
+        }
+
+        // Edits are placed in the content as it is now, which may have been saved while the model was working
+        let current = sqlx::query!(
+            "SELECT content, version FROM documents WHERE id = $1 AND is_trashed = false",
+            change.document_id
+        )
+        .fetch_optional(pool)
+        .await
+        .map_err(|_| Error::DatabaseError)?;
+        let Some(current) = current else { continue };
+        let old_content = current.content.unwrap_or_default();
+
+        let new_content = match apply_edits(&old_content, &change.edits) {
+            Ok(new_content) => new_content,
+            Err(reason) => {
+                println!("->> {:<12} - Rejected edits for doc {}: {}", "HANDLER", change.document_id, reason);
+                rejected.push(RejectedDocumentChange { document_id: change.document_id, reason });
+                continue;
+            }
+        };
+
+        let suggestion_id = create_suggestion(
+            pool,
+            change.document_id,
+            user_id,
+            SuggestionSource::Ai,
+            current.version,
+            &old_content,
+            &new_content,
+        )
+        .await?;
+
+        match suggestion_id {
+            Some(suggestion_id) => suggested_changes.push(SuggestedDocumentChange {
+                suggestion_id,
+                document_id: change.document_id,
+                hunks: diff_hunks(&old_content, &new_content),
+                old_content,
+                new_content,
+            }),
+            None => println!("->> {:<12} - LLM proposed no change for doc {}, skipping.", "HANDLER", change.document_id),
         }
     }
-     println!("->> {:<12} - Constructed {} SuggestedDocumentChange entries.", "HANDLER", suggested_changes.len());
+    println!("->> {:<12} - Constructed {} SuggestedDocumentChange entries.", "HANDLER", suggested_changes.len());
+
+    // Nothing could be applied because the quoted text no longer matches the documents
+    if let (true, Some(first)) = (suggested_changes.is_empty(), rejected.first()) {
+        return Err(Error::EditAnchorMismatchError { document_id: first.document_id });
+    }
 
-    // 9. Return the suggested changes
-    Ok(Json(suggested_changes))
+    // 9. Return the suggested changes and the documents that could not be changed
+    Ok(ApplySuggestionResponse { changes: suggested_changes, rejected })
 }
 
 /// POST handler for deciding if a diff should be proactively shown.
 /// Accessible via: POST /api/ai/writing-assistant/decide-proactive-diff
-/// This endpoint does NOT decrement AI credits as it's a meta-operation.
+/// Charged for its tokens and counted towards the token budget like the other operations.
 pub async fn api_decide_proactive_diff(
-    cookies: Cookies,
+    auth_user: AuthUser,
+    Extension(pool): Extension<PgPool>,
     Json(payload): Json<DecisionAgentPayload>,
 ) -> Result<Json<DecisionAgentResponse>> {
     println!("->> {:<12} - api_decide_proactive_diff", "HANDLER");
-    // Authenticate user via cookies
-    let _user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
 
     // Construct the prompt for the decision AI
     // Pass the document_content_snippet to the prompt construction function
@@ -906,11 +1053,7 @@ pub async fn api_decide_proactive_diff(
     println!("->> {:<12} - Decision Prompt: ...", "HANDLER"); // Avoid logging potentially large prompt for now
 
     // Query the LLM for a decision
-    let llm = QueryModel::new().map_err(|e| {
-        eprintln!("Error creating QueryModel for decision: {:?}", e);
-        Error::FailedApplyChanges
-    })?;
-    let llm_decision_str = llm.query_model(&decision_prompt).await?;
+    let llm_decision_str = query_once(&pool, auth_user.user_id, AiOperation::ProactiveDiff, &decision_prompt).await?;
     println!("->> {:<12} - LLM Decision Received: '{}'", "HANDLER", llm_decision_str);
 
     // Package and return the LLM's raw decision string
@@ -923,29 +1066,20 @@ pub async fn api_decide_proactive_diff(
 
 /// POST handler for sanitizing text by removing HTML and Markdown.
 /// Accessible via: POST /api/ai/writing-assistant/sanitize-text
-/// This endpoint does NOT decrement AI credits as it's a utility operation.
+/// Charged for its tokens and counted towards the token budget like the other operations.
 pub async fn api_sanitize_text(
-    cookies: Cookies,
+    auth_user: AuthUser,
+    Extension(pool): Extension<PgPool>,
     Json(payload): Json<SanitizeTextPayload>,
 ) -> Result<Json<SanitizeTextResponse>> {
     println!("->> {:<12} - api_sanitize_text", "HANDLER");
 
-    // Authenticate user - even if not billing, good for consistency and future use
-    let _user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
-
     // Construct the prompt for the sanitization AI
     let sanitize_prompt = prompt::construct_sanitize_text_prompt(&payload.text_to_sanitize);
     println!("->> {:<12} - Sanitize Prompt: ... (Brief)", "HANDLER"); // Avoid logging large text
 
     // Query the LLM for sanitization
-    let llm = QueryModel::new().map_err(|e| {
-        eprintln!("Error creating QueryModel for sanitization: {:?}", e);
-        Error::LlmQueryError // Use LlmQueryError for LLM initialization issues too
-    })?;
-    let sanitized_text_str = llm.query_model(&sanitize_prompt).await.map_err(|e| {
-        eprintln!("Error during LLM query for sanitization: {:?}", e);
-        Error::LlmQueryError // Use LlmQueryError for query failures
-    })?;
+    let sanitized_text_str = query_once(&pool, auth_user.user_id, AiOperation::SanitizeText, &sanitize_prompt).await?;
     println!("->> {:<12} - LLM Sanitized Text Received ({} chars): ...", "HANDLER", sanitized_text_str.len());
 
     // Package and return the sanitized text
@@ -964,6 +1098,7 @@ pub fn writing_assistant_routes() -> Router {
         .route("/:id", get(api_get_writing_session))
         .route("/:id", delete(api_delete_writing_session))
         .route("/:id/message", post(api_send_writing_message))
+        .route("/:id/message/stream", post(api_stream_writing_message))
         .route("/:id/apply-suggestion", post(api_apply_suggestion))
         .route("/grammer", post(api_check_grammer))
         .route("/spellcheck", post(api_spell_check))
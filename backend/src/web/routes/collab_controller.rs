@@ -0,0 +1,206 @@
+/*
+/ src/web/routes/collab_controller.rs
+/ Request Handlers
+/
+/ File containing the WebSocket endpoint for editing a document together in real time
+/
+/ API Summary:
+/ api_document_ws           GET     /:id/ws             - Join the Live Editing Session of a Document
+/
+/ Protocol (JSON text frames, positions count Unicode characters):
+/ server -> client  {"type":"init","client_id","version","content","read_only"}
+/ client -> server  {"type":"op","base_version":n,"ops":[{"type":"insert","pos","text"} | {"type":"delete","pos","len"}]}
+/ server -> client  {"type":"op","client_id","user_id","version","ops"}   - broadcast to everyone, including the sender
+/ client -> server  {"type":"resync"}                                     - answered with a fresh init
+/ server -> client  {"type":"error","message"}
+/
+/ Clients ignore ops with a version they already have, these can follow an init sent after falling behind.
+/ A fresh init is also sent when the document was saved outside the session, unsaved edits in the room are dropped.
+/ Access is checked again every few seconds. A changed role is sent as a fresh init with the new read_only,
+/ and once the user lost access or their account was disabled they get an error and the socket is closed.
+/
+*/
+
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::response::Response;
+use axum::routing::get;
+use axum::{
+    extract::{Extension, Path},
+    Router,
+};
+use futures_util::{SinkExt, StreamExt};
+use sqlx::PgPool;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc};
+use uuid::Uuid;
+
+use crate::collab::room::{ClientMessage, CollabHub, Room, ServerMessage};
+use crate::web::middleware::auth::AuthUser;
+use crate::auth::authorization::{active_document_role, document_role};
+use crate::models::role::Role;
+use crate::{Error, Result};
+
+/// How often an open connection checks that the user still has access to the document
+const ACCESS_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How long queued messages may take to reach a client before its socket is dropped without them
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// GET handler for joining the live editing session of a document over a WebSocket.
+/// Editors can send operations, viewers only receive them. The connection is closed once access is gone.
+/// Accessible via: GET /api/document/:id/ws
+/// Test: test_documents.rs/test_collaborative_editing(), test_documents.rs/test_collaborative_access_revoked()
+/// Frontend: // TODO: No frontend function implemented yet
+pub async fn api_document_ws(
+    auth_user: AuthUser,
+    Path(document_id): Path<i32>,
+    Extension(pool): Extension<PgPool>,
+    Extension(hub): Extension<CollabHub>,
+    ws: WebSocketUpgrade,
+) -> Result<Response> {
+    println!("->> {:<12} - document_ws", "HANDLER");
+
+    let user_id = auth_user.user_id;
+
+    let role = document_role(&pool, user_id, document_id)
+        .await?
+        .ok_or(Error::PermissionError)?;
+    let read_only = !role.allows(Role::Editor);
+
+    // Join only once upgraded, a failed upgrade must not leave a dangling connection in the room
+    Ok(ws.on_upgrade(move |socket| async move {
+        match hub.join(&pool, document_id).await {
+            Ok(room) => {
+                handle_socket(socket, &pool, &room, document_id, user_id, read_only).await;
+                hub.leave(&room);
+            }
+            Err(e) => println!("->> {:<12} - could not open document {}: {:?}", "COLLAB", document_id, e),
+        }
+    }))
+}
+
+async fn handle_socket(socket: WebSocket, pool: &PgPool, room: &Arc<Room>, document_id: i32, user_id: i32, read_only: bool) {
+    let client_id = Uuid::new_v4();
+    let (mut socket_sender, mut socket_receiver) = socket.split();
+    let read_only = Arc::new(AtomicBool::new(read_only));
+
+    // Everything for this client goes through one channel so replies and broadcasts keep their order
+    let (tx, mut rx) = mpsc::unbounded_channel::<ServerMessage>();
+
+    // Subscribe before taking the snapshot so no edit falls between the two
+    let mut updates = room.subscribe();
+    let _ = tx.send(room.init_message(client_id, read_only.load(Ordering::Relaxed)));
+
+    let mut writer = tokio::spawn(async move {
+        while let Some(message) = rx.recv().await {
+            let Ok(text) = serde_json::to_string(&message) else { continue };
+            if socket_sender.send(Message::Text(text)).await.is_err() {
+                return;
+            }
+        }
+        let _ = socket_sender.send(Message::Close(None)).await;
+    });
+
+    let broadcast_tx = tx.clone();
+    let broadcast_room = room.clone();
+    let broadcast_read_only = read_only.clone();
+    let forwarder = tokio::spawn(async move {
+        loop {
+            match updates.recv().await {
+                // The document was saved outside the room, start over from what was saved
+                Ok(ServerMessage::Reloaded) => {
+                    let init = broadcast_room.init_message(client_id, broadcast_read_only.load(Ordering::Relaxed));
+                    if broadcast_tx.send(init).is_err() {
+                        break;
+                    }
+                }
+                Ok(message) => {
+                    if broadcast_tx.send(message).is_err() {
+                        break;
+                    }
+                }
+                // Too slow to keep up, start over from the current text
+                Err(broadcast::error::RecvError::Lagged(_)) => {
+                    let init = broadcast_room.init_message(client_id, broadcast_read_only.load(Ordering::Relaxed));
+                    if broadcast_tx.send(init).is_err() {
+                        break;
+                    }
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+
+    // Access was checked when the socket was opened, the first tick is one interval from now
+    let mut access_check = tokio::time::interval(ACCESS_CHECK_INTERVAL);
+    access_check.reset();
+
+    loop {
+        let message = tokio::select! {
+            message = socket_receiver.next() => message,
+            _ = access_check.tick() => {
+                match active_document_role(pool, user_id, document_id).await {
+                    Ok(Some(role)) => {
+                        let now_read_only = !role.allows(Role::Editor);
+                        if read_only.swap(now_read_only, Ordering::Relaxed) != now_read_only
+                            && tx.send(room.init_message(client_id, now_read_only)).is_err()
+                        {
+                            break;
+                        }
+                    }
+                    Ok(None) => {
+                        println!("->> {:<12} - user {} lost access to document {}, closing", "COLLAB", user_id, document_id);
+                        let _ = tx.send(error_message("You no longer have access to this document"));
+                        break;
+                    }
+                    // Keep the connection, the check is retried on the next tick
+                    Err(e) => println!("->> {:<12} - could not check access to document {}: {:?}", "COLLAB", document_id, e),
+                }
+                continue;
+            }
+        };
+
+        let Some(Ok(message)) = message else { break };
+        let text = match message {
+            Message::Text(text) => text,
+            Message::Close(_) => break,
+            _ => continue,
+        };
+
+        let reply = match serde_json::from_str::<ClientMessage>(&text) {
+            Ok(ClientMessage::Op { .. }) if read_only.load(Ordering::Relaxed) => {
+                Some(error_message("You only have read access to this document"))
+            }
+            Ok(ClientMessage::Op { base_version, ops }) => room
+                .apply(client_id, user_id, base_version, ops)
+                .err()
+                .map(|_| error_message("Operation could not be applied, resync and try again")),
+            Ok(ClientMessage::Resync) => Some(room.init_message(client_id, read_only.load(Ordering::Relaxed))),
+            Err(_) => Some(error_message("Invalid message")),
+        };
+
+        if let Some(reply) = reply {
+            if tx.send(reply).is_err() {
+                break;
+            }
+        }
+    }
+
+    // Stop forwarding and let the writer send what is queued followed by a close frame
+    forwarder.abort();
+    let _ = forwarder.await;
+    drop(tx);
+    if tokio::time::timeout(CLOSE_TIMEOUT, &mut writer).await.is_err() {
+        writer.abort();
+    }
+}
+
+fn error_message(message: &str) -> ServerMessage {
+    ServerMessage::Error { message: message.to_string() }
+}
+
+pub fn collab_routes() -> Router {
+    Router::new().route("/:id/ws", get(api_document_ws))
+}
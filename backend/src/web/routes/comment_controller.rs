@@ -0,0 +1,314 @@
+/*
+/ src/web/routes/comment_controller.rs
+/ Request Handlers
+/
+/ File containing API Backend endpoints for discussing documents in comment threads
+/
+/ API Summary:
+/ api_get_comments          GET     /:id/comments                           - List Comment Threads of the Document
+/ api_create_comment        POST    /:id/comments                           - Start a Thread on a Range of the Document
+/ api_get_comment_thread    GET     /:id/comments/:comment_id               - Get the Thread a Comment Belongs To
+/ api_update_comment        PUT     /:id/comments/:comment_id               - Edit Your Own Comment
+/ api_delete_comment        DELETE  /:id/comments/:comment_id               - Delete a Comment or a Whole Thread
+/ api_reply_comment         POST    /:id/comments/:comment_id/replies       - Reply to a Thread
+/ api_resolve_comment       POST    /:id/comments/:comment_id/resolve       - Mark a Thread as Resolved
+/ api_unresolve_comment     POST    /:id/comments/:comment_id/unresolve     - Reopen a Resolved Thread
+/
+/ Viewers can read comments, posting, replying and resolving needs at least the commenter role.
+/
+*/
+
+use axum::routing::{delete, get, post, put};
+use axum::{
+    extract::{Extension, Json, Path, Query},
+    Router,
+};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+
+use crate::auth::authorization::{check_document_permission, document_role};
+use crate::db::comment::{
+    create_reply, create_thread, delete_comment, get_comment, group_threads, list_comments, set_resolved,
+    update_comment, validate_content,
+};
+use crate::models::comment::{
+    CommentContentPayload, CommentListQuery, CommentThread, CreateCommentPayload, DocumentComment,
+};
+use crate::models::role::Role;
+use crate::web::middleware::auth::AuthUser;
+use crate::{Error, Result};
+
+/// The thread containing `comment_id`, with all of its replies
+async fn load_thread(pool: &PgPool, document_id: i32, comment_id: i32) -> Result<CommentThread> {
+    let comment = get_comment(pool, document_id, comment_id).await?;
+    let thread_id = comment.thread_id.unwrap_or(comment.id);
+
+    let comments = list_comments(pool, document_id).await?;
+    group_threads(comments)
+        .into_iter()
+        .find(|thread| thread.comment.id == thread_id)
+        .ok_or(Error::CommentNotFoundError { comment_id })
+}
+
+/// GET handler for listing the comment threads of a document.
+/// Accessible via: GET /api/document/:id/comments?resolved=&mentioned=
+/// Test: test_documents.rs/test_comments()
+/// Frontend: // TODO: No frontend function implemented yet
+pub async fn api_get_comments(
+    auth_user: AuthUser,
+    Path(document_id): Path<i32>,
+    Query(params): Query<CommentListQuery>,
+    Extension(pool): Extension<PgPool>,
+) -> Result<Json<Vec<CommentThread>>> {
+    println!("->> {:<12} - get_comments", "HANDLER");
+
+    let user_id = auth_user.user_id;
+
+    if !check_document_permission(&pool, user_id, document_id, Role::Viewer).await? {
+        return Err(Error::PermissionError);
+    }
+
+    let comments = list_comments(&pool, document_id).await?;
+
+    let threads = group_threads(comments)
+        .into_iter()
+        .filter(|thread| {
+            params
+                .resolved
+                .is_none_or(|resolved| thread.comment.resolved_at.is_some() == resolved)
+        })
+        .filter(|thread| {
+            !params.mentioned.unwrap_or(false)
+                || thread.comment.mentions.contains(&user_id)
+                || thread.replies.iter().any(|reply| reply.mentions.contains(&user_id))
+        })
+        .collect();
+
+    Ok(Json(threads))
+}
+
+/// POST handler for starting a comment thread on a range of a document.
+/// Accessible via: POST /api/document/:id/comments
+/// Test: test_documents.rs/test_comments()
+/// Frontend: // TODO: No frontend function implemented yet
+pub async fn api_create_comment(
+    auth_user: AuthUser,
+    Path(document_id): Path<i32>,
+    Extension(pool): Extension<PgPool>,
+    Json(payload): Json<CreateCommentPayload>,
+) -> Result<Json<CommentThread>> {
+    println!("->> {:<12} - create_comment", "HANDLER");
+
+    let user_id = auth_user.user_id;
+
+    if !check_document_permission(&pool, user_id, document_id, Role::Commenter).await? {
+        return Err(Error::PermissionError);
+    }
+
+    let content = validate_content(&payload.content)?;
+
+    let comment_id = create_thread(
+        &pool,
+        document_id,
+        user_id,
+        &content,
+        (payload.anchor_start, payload.anchor_end),
+        &payload.quoted_text,
+        payload.mentions.as_deref().unwrap_or_default(),
+    )
+    .await?;
+
+    Ok(Json(load_thread(&pool, document_id, comment_id).await?))
+}
+
+/// GET handler for the thread a comment belongs to.
+/// Accessible via: GET /api/document/:id/comments/:comment_id
+/// Test: test_documents.rs/test_comments()
+/// Frontend: // TODO: No frontend function implemented yet
+pub async fn api_get_comment_thread(
+    auth_user: AuthUser,
+    Path((document_id, comment_id)): Path<(i32, i32)>,
+    Extension(pool): Extension<PgPool>,
+) -> Result<Json<CommentThread>> {
+    println!("->> {:<12} - get_comment_thread", "HANDLER");
+
+    if !check_document_permission(&pool, auth_user.user_id, document_id, Role::Viewer).await? {
+        return Err(Error::PermissionError);
+    }
+
+    Ok(Json(load_thread(&pool, document_id, comment_id).await?))
+}
+
+/// POST handler for replying to a comment thread.
+/// Replying to a resolved thread reopens it.
+/// Accessible via: POST /api/document/:id/comments/:comment_id/replies
+/// Test: test_documents.rs/test_comments()
+/// Frontend: // TODO: No frontend function implemented yet
+pub async fn api_reply_comment(
+    auth_user: AuthUser,
+    Path((document_id, comment_id)): Path<(i32, i32)>,
+    Extension(pool): Extension<PgPool>,
+    Json(payload): Json<CommentContentPayload>,
+) -> Result<Json<DocumentComment>> {
+    println!("->> {:<12} - reply_comment", "HANDLER");
+
+    let user_id = auth_user.user_id;
+
+    if !check_document_permission(&pool, user_id, document_id, Role::Commenter).await? {
+        return Err(Error::PermissionError);
+    }
+
+    let content = validate_content(&payload.content)?;
+    let parent = get_comment(&pool, document_id, comment_id).await?;
+    let thread_id = parent.thread_id.unwrap_or(parent.id);
+
+    let reply_id = create_reply(
+        &pool,
+        document_id,
+        &parent,
+        user_id,
+        &content,
+        payload.mentions.as_deref().unwrap_or_default(),
+    )
+    .await?;
+
+    set_resolved(&pool, document_id, thread_id, None).await?;
+
+    Ok(Json(get_comment(&pool, document_id, reply_id).await?))
+}
+
+/// PUT handler for editing a comment.
+/// Only the author can edit a comment, and only while they can still comment on the document.
+/// Accessible via: PUT /api/document/:id/comments/:comment_id
+/// Test: test_documents.rs/test_comments()
+/// Frontend: // TODO: No frontend function implemented yet
+pub async fn api_update_comment(
+    auth_user: AuthUser,
+    Path((document_id, comment_id)): Path<(i32, i32)>,
+    Extension(pool): Extension<PgPool>,
+    Json(payload): Json<CommentContentPayload>,
+) -> Result<Json<DocumentComment>> {
+    println!("->> {:<12} - update_comment", "HANDLER");
+
+    let user_id = auth_user.user_id;
+
+    if !check_document_permission(&pool, user_id, document_id, Role::Commenter).await? {
+        return Err(Error::PermissionError);
+    }
+
+    let comment = get_comment(&pool, document_id, comment_id).await?;
+    if comment.author_id != Some(user_id) {
+        return Err(Error::PermissionError);
+    }
+
+    let content = validate_content(&payload.content)?;
+
+    update_comment(
+        &pool,
+        document_id,
+        comment_id,
+        &content,
+        payload.mentions.as_deref().unwrap_or_default(),
+    )
+    .await?;
+
+    Ok(Json(get_comment(&pool, document_id, comment_id).await?))
+}
+
+/// DELETE handler for deleting a comment.
+/// Authors can delete their own comments, the document owner can delete any. Deleting the first comment
+/// of a thread deletes the whole thread.
+/// Accessible via: DELETE /api/document/:id/comments/:comment_id
+/// Test: test_documents.rs/test_comments()
+/// Frontend: // TODO: No frontend function implemented yet
+pub async fn api_delete_comment(
+    auth_user: AuthUser,
+    Path((document_id, comment_id)): Path<(i32, i32)>,
+    Extension(pool): Extension<PgPool>,
+) -> Result<Json<Value>> {
+    println!("->> {:<12} - delete_comment", "HANDLER");
+
+    let user_id = auth_user.user_id;
+    let role = document_role(&pool, user_id, document_id)
+        .await?
+        .ok_or(Error::PermissionError)?;
+
+    let comment = get_comment(&pool, document_id, comment_id).await?;
+
+    let is_author = comment.author_id == Some(user_id) && role.allows(Role::Commenter);
+    if !is_author && !role.allows(Role::Owner) {
+        return Err(Error::PermissionError);
+    }
+
+    delete_comment(&pool, document_id, comment_id).await?;
+
+    Ok(Json(json!({
+        "result": {
+            "success": true,
+            "message": "Comment deleted"
+        }
+    })))
+}
+
+/// Resolve or reopen the thread a comment belongs to
+async fn resolve_thread(
+    auth_user: AuthUser,
+    document_id: i32,
+    comment_id: i32,
+    pool: &PgPool,
+    resolved: bool,
+) -> Result<Json<CommentThread>> {
+    let user_id = auth_user.user_id;
+
+    if !check_document_permission(pool, user_id, document_id, Role::Commenter).await? {
+        return Err(Error::PermissionError);
+    }
+
+    let comment = get_comment(pool, document_id, comment_id).await?;
+    let thread_id = comment.thread_id.unwrap_or(comment.id);
+
+    set_resolved(pool, document_id, thread_id, resolved.then_some(user_id)).await?;
+
+    Ok(Json(load_thread(pool, document_id, thread_id).await?))
+}
+
+/// POST handler for resolving a comment thread.
+/// Accessible via: POST /api/document/:id/comments/:comment_id/resolve
+/// Test: test_documents.rs/test_comments()
+/// Frontend: // TODO: No frontend function implemented yet
+pub async fn api_resolve_comment(
+    auth_user: AuthUser,
+    Path((document_id, comment_id)): Path<(i32, i32)>,
+    Extension(pool): Extension<PgPool>,
+) -> Result<Json<CommentThread>> {
+    println!("->> {:<12} - resolve_comment", "HANDLER");
+
+    resolve_thread(auth_user, document_id, comment_id, &pool, true).await
+}
+
+/// POST handler for reopening a resolved comment thread.
+/// Accessible via: POST /api/document/:id/comments/:comment_id/unresolve
+/// Test: test_documents.rs/test_comments()
+/// Frontend: // TODO: No frontend function implemented yet
+pub async fn api_unresolve_comment(
+    auth_user: AuthUser,
+    Path((document_id, comment_id)): Path<(i32, i32)>,
+    Extension(pool): Extension<PgPool>,
+) -> Result<Json<CommentThread>> {
+    println!("->> {:<12} - unresolve_comment", "HANDLER");
+
+    resolve_thread(auth_user, document_id, comment_id, &pool, false).await
+}
+
+/// Comment threads, merged into the document router
+pub fn comment_routes() -> Router {
+    Router::new()
+        .route("/:id/comments", get(api_get_comments))
+        .route("/:id/comments", post(api_create_comment))
+        .route("/:id/comments/:comment_id", get(api_get_comment_thread))
+        .route("/:id/comments/:comment_id", put(api_update_comment))
+        .route("/:id/comments/:comment_id", delete(api_delete_comment))
+        .route("/:id/comments/:comment_id/replies", post(api_reply_comment))
+        .route("/:id/comments/:comment_id/resolve", post(api_resolve_comment))
+        .route("/:id/comments/:comment_id/unresolve", post(api_unresolve_comment))
+}
@@ -5,22 +5,29 @@
 / File containing various API Backend endpoints for manipulating the database and environment
 /
 / API Summary:
-/ api_test_db   GET    /test    - Test The Database Connection
-/ api_wipe_db   GET    /wipe    - Wipe The Database If Secret Code Matches
+/ api_db_test    GET    /test    - Test The Database Connection
+/ api_db_reset   POST   /reset   - Reset The Database, Only In Test Mode (ALLOW_DB_RESET=true) With DB_RESET_SECRET
 /
 */
 use axum::{
-    extract::{Extension, Query},
+    extract::{ConnectInfo, Extension},
     response::Json,
-    routing::get,
+    routing::{get, post},
     Router,
 };
 use serde_json::{json, Value};
-use std::{fs, path::PathBuf, time::Duration};
+use sha2::{Digest, Sha256};
+use sqlx::Executor;
+use std::env;
+use std::net::SocketAddr;
+use std::time::Duration;
 use tokio::time;
 use reqwest::Client;
 
+use crate::db::migrate::{load_fixtures, run_migrations};
+use crate::log::log_audit_event;
 use crate::models::db::WipeParams;
+use crate::web::middleware::auth::AuthUser;
 use crate::{Error, Result};
 
 /// GET handler for testing the database connection.
@@ -51,40 +58,31 @@ pub async fn api_db_test(Extension(pool): Extension<sqlx::PgPool>) -> Result<Jso
     }
 }
 
-/// GET handler for resetting the database with a secret key.
-/// Accessible via: GET /api/db/wipe?secret=secret_key
-/// Test: test_environment.rs/test_reset_db()
+/// POST handler for resetting the database to the migrations and fixtures.
+/// Only available when the server runs in test mode (ALLOW_DB_RESET=true) and the body carries DB_RESET_SECRET.
+/// Every call is written to the audit log, whether it went through or not.
+/// Accessible via: POST /api/db/reset
+/// Test: test_environment.rs/test_reset_db_wrong_secret()
 /// Frontend: Not directly called from frontend
 async fn api_db_reset(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    auth_user: Option<AuthUser>,
     Extension(pool): Extension<sqlx::PgPool>,
-    Query(params): Query<WipeParams>,
+    params: Option<Json<WipeParams>>,
 ) -> Result<Json<Value>> {
-    println!("->> {:<12} - wipe_db_method", "HANDLER");
+    println!("->> {:<12} - reset_db", "HANDLER");
 
-    // Check for secret key needed to wipe db
-    if params.secret != Some("secret_key".to_string()) {
-        return Err(Error::MigrationKeyError);
-    }
+    let user_id = auth_user.map(|u| u.user_id);
+    let secret = params.and_then(|Json(params)| params.secret);
+    let result = reset_db(&pool, secret.as_deref()).await;
 
-    // Read the migration script
-    let migration_path = PathBuf::from("migrations/01_migration_script.sql");
-    let migration_sql = fs::read_to_string(migration_path).map_err(|e| {
-        println!("Error reading migration file: {:?}", e);
-        Error::MigrationExecutionError
-    })?;
-
-    // Execute each statement in the migration script
-    let statements: Vec<&str> = migration_sql
-        .split(';')
-        .filter(|s| !s.trim().is_empty())
-        .collect();
-
-    for (i, statement) in statements.iter().enumerate() {
-        sqlx::query(statement).execute(&pool).await.map_err(|e| {
-            println!("Error executing statement {}: {:?}", i + 1, e);
-            Error::MigrationExecutionError
-        })?;
-    }
+    let outcome = match &result {
+        Ok(()) => "success".to_string(),
+        Err(e) => e.as_ref().to_string(),
+    };
+    log_audit_event("db_reset", user_id, addr.ip(), &outcome);
+
+    result?;
 
     Ok(Json(json!({
         "result": {
@@ -94,6 +92,43 @@ async fn api_db_reset(
     })))
 }
 
+async fn reset_db(pool: &sqlx::PgPool, secret: Option<&str>) -> Result<()> {
+    // Outside of test mode the endpoint does not exist as far as callers can tell
+    let expected = reset_secret().ok_or(Error::DbResetDisabledError)?;
+
+    if !secret.is_some_and(|secret| secrets_match(secret, &expected)) {
+        return Err(Error::MigrationKeyError);
+    }
+
+    // Start from an empty schema, then rebuild it the same way a fresh deployment would
+    pool.execute("DROP SCHEMA public CASCADE; CREATE SCHEMA public;")
+        .await
+        .map_err(|e| {
+            println!("Error wiping database: {:?}", e);
+            Error::MigrationExecutionError
+        })?;
+
+    run_migrations(pool).await?;
+    load_fixtures(pool).await?;
+
+    Ok(())
+}
+
+// The reset secret, only set when the server runs in test mode with a secret configured
+fn reset_secret() -> Option<String> {
+    if env::var("ALLOW_DB_RESET").map_or(true, |v| v != "true") {
+        return None;
+    }
+    env::var("DB_RESET_SECRET").ok().filter(|secret| !secret.is_empty())
+}
+
+// Compare the digests so the time taken reveals neither the matching prefix nor the secret's length
+fn secrets_match(given: &str, expected: &str) -> bool {
+    let given = Sha256::digest(given.as_bytes());
+    let expected = Sha256::digest(expected.as_bytes());
+    given.iter().zip(expected.iter()).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
 async fn send_periodic_request(pool: sqlx::PgPool) {
     let client = Client::new();
     loop {
@@ -128,7 +163,11 @@ pub fn db_routes(pool: sqlx::PgPool) -> Router {
     let pool_clone = pool.clone(); // Clone the pool to pass to the task
     tokio::spawn(send_periodic_request(pool_clone));
 
+    if reset_secret().is_some() {
+        println!("->> {:<12} - database reset endpoint is enabled, never run like this in production", "STARTUP");
+    }
+
     Router::new()
         .route("/test", get(api_db_test))
-        .route("/reset", get(api_db_reset))
+        .route("/reset", post(api_db_reset))
 }
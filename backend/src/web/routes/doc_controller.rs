@@ -23,20 +23,22 @@ use axum::{
 };
 use serde_json::{json, Value};
 use sqlx::PgPool;
-use tower_cookies::Cookies;
 
 use crate::models::document::{CreateDocumentPayload, Document, UpdateDocumentPayload};
 use crate::models::permission::{
     CreatePermissionPayload, DocumentPermission, UpdatePermissionPayload, UserPermissions,
 };
+use crate::models::revision::RevisionOrigin;
 use crate::models::storage::StorageManager;
-use crate::web::middleware::middleware::check_document_permission;
+use crate::db::revision::{enforce_storage_quota, get_document_owner, record_revision};
+use crate::auth::authorization::check_document_permission;
+use crate::models::role::Role;
 use crate::{Error, Result};
 
-use backend::get_user_id_from_cookie;
+use crate::web::middleware::auth::AuthUser;
 
 // Import necessary items for embedding
-use crate::rag::embed::EmbeddingModel;
+use crate::rag::embed::{index_document_chunks, reindex_document, EmbeddingModel};
 use chrono::{Utc, Duration};
 
 /// GET handler for retrieving a document by ID.
@@ -44,17 +46,16 @@ use chrono::{Utc, Duration};
 /// Test: test_documents.rs/test_get_document()
 /// Frontend: document.ts/get_document()
 pub async fn api_get_document(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Path(document_id): Path<i32>,
     Extension(pool): Extension<PgPool>,
 ) -> Result<Json<Document>> {
     println!("->> {:<12} - get_document", "HANDLER");
 
-    // get user_id from cookies
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
 
     // need to ensure the user has permissions to view this document
-    let has_permission = check_document_permission(&pool, user_id, document_id, "editor").await?;
+    let has_permission = check_document_permission(&pool, user_id, document_id, Role::Editor).await?;
 
     if has_permission {
     let result = sqlx::query_as!(
@@ -67,7 +68,8 @@ pub async fn api_get_document(
                 updated_at, 
                 user_id,
                 is_starred,
-                is_trashed
+                is_trashed,
+                version
             FROM documents WHERE id = $1"#,
         document_id
     )
@@ -88,21 +90,20 @@ pub async fn api_get_document(
 /// Test: test_documents.rs/test_get_all_documents()
 /// Frontend: document.ts/get_all_documents()
 pub async fn api_get_all_documents(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Extension(pool): Extension<PgPool>,
 ) -> Result<Json<Vec<Document>>> {
     println!("->> {:<12} - get_all_documents", "HANDLER");
 
-    // get user_id from cookies
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
 
-    // Get all documents where the user has any permission
+    // Get all documents the user can access, directly or through a project
     let result = sqlx::query_as!(
         Document,
-        r#"SELECT d.id, d.name, d.content, d.created_at, d.updated_at, d.user_id, is_starred, is_trashed
+        r#"SELECT d.id, d.name, d.content, d.created_at, d.updated_at, d.user_id, is_starred, is_trashed, d.version
            FROM documents d
-           JOIN document_permissions dp ON d.id = dp.document_id
-           WHERE dp.user_id = $1"#,
+           JOIN document_access da ON d.id = da.document_id
+           WHERE da.user_id = $1"#,
         user_id
     )
     .fetch_all(&pool)
@@ -117,14 +118,13 @@ pub async fn api_get_all_documents(
 /// Test: test_documents.rs/test_create_document()
 /// Frontend: document.ts/create_document()
 pub async fn api_create_document(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Extension(pool): Extension<PgPool>,
     Json(payload): Json<CreateDocumentPayload>,
 ) -> Result<Json<Document>> {
     println!("->> {:<12} - create_document", "HANDLER");
     
-    // get user_id from cookies
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
 
     // Check if user has reached their document limit
     let user_docs_count = sqlx::query!(
@@ -137,10 +137,11 @@ pub async fn api_create_document(
     .await
     .map_err(|_| Error::DatabaseError)?;
     
-    // Use hardcoded default limit
-    let max_documents = 10;
+    let limits = StorageManager::get_user_limits(&pool, user_id)
+        .await
+        .map_err(|_| Error::DatabaseError)?;
     
-    if user_docs_count.count.unwrap_or(0) as i32 >= max_documents {
+    if user_docs_count.count.unwrap_or(0) as i32 >= limits.max_documents {
         return Err(Error::LimitExceededError { message: "Document limit reached".to_string() });
     }
 
@@ -148,27 +149,21 @@ pub async fn api_create_document(
     let content_length = payload.content.as_ref().map_or(0, |s| s.len() as i64);
     
     // Check if this would exceed the user's storage limit (using dynamic storage management)
-    let max_storage_bytes = StorageManager::get_user_quota();
+    let max_storage_bytes = limits.max_storage_bytes;
     
-    // Get user's current storage usage
-    let current_storage = sqlx::query!(
-        r#"SELECT COALESCE(SUM(LENGTH(COALESCE(d.content, ''))), 0) as total_bytes
-           FROM documents d
-           JOIN document_permissions dp ON d.id = dp.document_id
-           WHERE dp.user_id = $1 AND dp.role = 'owner'"#,
-        user_id
-    )
-    .fetch_one(&pool)
-    .await
-    .map_err(|_| Error::DatabaseError)?;
+    // Get user's current storage usage, including revision history
+    let current_storage = StorageManager::get_user_storage_used(&pool, user_id)
+        .await
+        .map_err(|_| Error::DatabaseError)?;
     
-    let new_total_storage = current_storage.total_bytes.unwrap_or(0) + content_length;
+    // The first revision stores a second copy of the content
+    let new_total_storage = current_storage + content_length * 2;
     
     if new_total_storage > max_storage_bytes {
         return Err(Error::LimitExceededError { 
             message: format!("Storage limit of {}MB exceeded. Current usage: {}MB, This document: {}MB", 
                             max_storage_bytes / 1024 / 1024,
-                            current_storage.total_bytes.unwrap_or(0) / 1024 / 1024,
+                            current_storage / 1024 / 1024,
                             content_length / 1024 / 1024)
         });
     }
@@ -199,10 +194,18 @@ pub async fn api_create_document(
             .execute(&pool)
             .await;
 
-            if let Err(_) = permissions {
+            if permissions.is_err() {
                 return Err(Error::PermissionCreationError);
             }
 
+            // Start the revision history with the initial content
+            // and make it searchable right away instead of after its first update
+            if let Some(content) = payload.content.as_deref().filter(|c| !c.is_empty()) {
+                let mut conn = pool.acquire().await.map_err(|_| Error::DatabaseError)?;
+                record_revision(&mut conn, record.id, user_id, content, RevisionOrigin::Manual).await?;
+                reindex_document(&pool, record.id, content).await;
+            }
+
             // Then fetch the document by id
             let document = sqlx::query_as!(
                 Document,
@@ -214,7 +217,8 @@ pub async fn api_create_document(
                     updated_at,
                     user_id,
                     is_starred,
-                    is_trashed
+                    is_trashed,
+                    version
                 FROM documents WHERE id = $1"#,
                 record.id
             )
@@ -237,20 +241,22 @@ pub async fn api_create_document(
 }
 
 /// PUT handler for updating a document.
+/// Every save carries the version it is based on. A save without one is refused with 422,
+/// one carrying a version older than the stored one is rejected with 409 Conflict.
 /// Accessible via: PUT /api/document/:id
-/// Test: test_documents.rs/test_update_document()
+/// Test: test_documents.rs/test_update_document(), test_documents.rs/test_update_document_conflict()
 /// Frontend: document.ts/update_document()
 pub async fn api_update_document(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Path(document_id): Path<i32>,
     Extension(pool): Extension<PgPool>,
     Json(payload): Json<UpdateDocumentPayload>,
 ) -> Result<Json<Value>> {
     println!("->> {:<12} - update_document", "HANDLER");
 
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
 
-    let has_permission = check_document_permission(&pool, user_id, document_id, "editor").await?;
+    let has_permission = check_document_permission(&pool, user_id, document_id, Role::Editor).await?;
     if !has_permission {
         return Err(Error::PermissionError);
     }
@@ -259,7 +265,7 @@ pub async fn api_update_document(
     // Fetch old content and embedding timestamp BEFORE updating
     let old_data = sqlx::query!(
         r#"
-        SELECT content, embedding_updated_at
+        SELECT content, embedding_updated_at, version
         FROM documents
         WHERE id = $1
         "#,
@@ -273,67 +279,64 @@ pub async fn api_update_document(
     let old_embedding_time = old_data.as_ref().and_then(|d| d.embedding_updated_at);
     // --- Embedding Logic End ---
 
-    // Calculate the difference in content size
-    let old_content_len = old_content.len() as i64;
-    let new_content_len = payload.content.as_ref().map_or(0, |s| s.len() as i64);
-    let size_diff = new_content_len - old_content_len;
-
-    // If content is growing, check storage limits
-    if size_diff > 0 {
-        // Check if this would exceed the user's storage limit (using dynamic storage management)
-        let max_storage_bytes = StorageManager::get_user_quota();
-        
-        // Get document owner
-        let owner = sqlx::query!(
-            "SELECT user_id FROM document_permissions 
-             WHERE document_id = $1 AND role = 'owner'",
-            document_id
-        )
-        .fetch_one(&pool)
-        .await
-        .map_err(|_| Error::DatabaseError)?;
-        
-        // Get owner's current storage usage
-        let current_storage = sqlx::query!(
-            r#"SELECT COALESCE(SUM(LENGTH(COALESCE(d.content, ''))), 0) as total_bytes
-               FROM documents d
-               JOIN document_permissions dp ON d.id = dp.document_id
-               WHERE dp.user_id = $1 AND dp.role = 'owner'"#,
-            owner.user_id
-        )
-        .fetch_one(&pool)
-        .await
-        .map_err(|_| Error::DatabaseError)?;
-        
-        let new_total_storage = current_storage.total_bytes.unwrap_or(0) + size_diff;
-        
-        if new_total_storage > max_storage_bytes {
-            return Err(Error::LimitExceededError { 
-                message: format!("Storage limit of {}MB exceeded. Current usage: {}MB, Additional storage needed: {}MB", 
-                                max_storage_bytes / 1024 / 1024,
-                                current_storage.total_bytes.unwrap_or(0) / 1024 / 1024,
-                                size_diff / 1024 / 1024)
-            });
+    // Reject a stale save up front
+    if let Some(current) = old_data.as_ref() {
+        if payload.version != current.version {
+            return Err(Error::DocumentConflictError { document_id, current_version: current.version });
         }
     }
 
-    // Proceed with the main update
+    // The update, its revision and any pruning of old revisions to stay within quota commit together
+    let owner_id = get_document_owner(&pool, document_id).await?;
+    let mut tx = pool.begin().await.map_err(|_| Error::DatabaseError)?;
+
+    // Proceed with the main update, only if nobody else saved since the client loaded the document
     let update_result = sqlx::query!(
         "UPDATE documents
-        SET name = $1, content = $2, updated_at = $3
-        WHERE id = $4",
+        SET name = $1, content = $2, updated_at = $3, version = version + 1
+        WHERE id = $4 AND version = $5
+        RETURNING version",
         payload.name,
         payload.content,
         payload.updated_at,
-        document_id
+        document_id,
+        payload.version
     )
-    .execute(&pool)
-    .await;
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|_| Error::DocumentUpdateError { document_id })?;
+
+    let new_version = match update_result {
+        Some(record) => record.version,
+        None => {
+            // Either the document is gone or the client's version is stale
+            let current = sqlx::query!("SELECT version FROM documents WHERE id = $1", document_id)
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(|_| Error::DatabaseError)?;
+
+            return match current {
+                Some(current) => Err(Error::DocumentConflictError {
+                    document_id,
+                    current_version: current.version,
+                }),
+                None => Err(Error::DocumentUpdateError { document_id }),
+            };
+        }
+    };
 
-    // Check if the main update failed
-    if update_result.is_err() || update_result.unwrap().rows_affected() == 0 {
-        return Err(Error::DocumentUpdateError { document_id });
-    }
+    // Snapshot the saved content, only AI applies can be marked by the client
+    let origin = match payload.origin {
+        Some(RevisionOrigin::AiApply) => RevisionOrigin::AiApply,
+        _ => RevisionOrigin::Manual,
+    };
+    let revision_number = match payload.content.as_deref() {
+        Some(content) => Some(record_revision(&mut tx, document_id, user_id, content, origin).await?),
+        None => None,
+    };
+
+    enforce_storage_quota(&mut tx, owner_id, document_id).await?;
+    tx.commit().await.map_err(|_| Error::DatabaseError)?;
 
     let should_update_embedding = match old_embedding_time {
         Some(timestamp) => {
@@ -356,25 +359,11 @@ pub async fn api_update_document(
 
         // Handle Option<String> for content before embedding
         if let Some(content_str) = payload.content.as_deref() {
-            // Generate new embedding only if content exists
-            let new_embedding = embedding_model.embed_document(content_str).await?;
-            
-            // Update the embedding and timestamp in the database
-            let embed_update_result = sqlx::query!(
-                r#"
-                UPDATE documents
-                SET embedding = $1, embedding_updated_at = $2
-                WHERE id = $3
-                "#,
-                new_embedding as _,
-                Utc::now(),
-                document_id
-            )
-            .execute(&pool)
-            .await;
+            // Re-chunk the document and embed each chunk, this also updates the embedding timestamp
+            let index_result = index_document_chunks(&embedding_model, &pool, document_id, content_str).await;
 
-            if embed_update_result.is_err() {
-                println!("->> {:<12} - Failed to update embedding for document {}: {:?}", "ERROR", document_id, embed_update_result.err());
+            if index_result.is_err() {
+                println!("->> {:<12} - Failed to update embedding for document {}: {:?}", "ERROR", document_id, index_result.err());
             }
         } else {
             println!("->> {:<12} - Skipping embedding update for document {} as content is None", "INFO", document_id);
@@ -385,7 +374,9 @@ pub async fn api_update_document(
     Ok(Json(json!({
         "result": {
             "success": true
-        }
+        },
+        "version": new_version,
+        "revision_number": revision_number
     })))
 }
 
@@ -394,15 +385,14 @@ pub async fn api_update_document(
 /// Test: test_documents.rs/test_delete_document()
 /// Frontend: document.ts/delete_document()
 async fn api_delete_document(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Path(document_id): Path<i32>,
     Extension(pool): Extension<PgPool>,
 ) -> Result<Json<Value>> {
     // First check if the current user has owner permission
-    // get user_id from cookies
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
 
-    let has_permission = check_document_permission(&pool, user_id, document_id, "owner").await?;
+    let has_permission = check_document_permission(&pool, user_id, document_id, Role::Owner).await?;
 
     if !has_permission {
         return Err(Error::PermissionError);
@@ -450,11 +440,11 @@ async fn api_delete_document(
     // Note: We're skipping updating storage_bytes in this version
 
     // otherwise its success
-    return Ok(Json(json!({
+    Ok(Json(json!({
         "result": {
             "success": true
         }
-    })));
+    })))
 }
 
 /// GET handler for getting a project given a document id
@@ -462,17 +452,16 @@ async fn api_delete_document(
 /// Test: test_documents.rs/test_get_project_from_document()
 /// Frontend: document.ts/get_project_from_document()
 pub async fn api_get_project_from_document(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Path(document_id): Path<i32>,
     Extension(pool): Extension<PgPool>,
 ) -> Result<Json<Value>> {
     println!("->> {:<12} - get_project_from_document", "HANDLER");
 
-    // Get user ID from cookie
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
 
     // Check if user has at least viewer permission for the document
-    let has_permission = check_document_permission(&pool, user_id, document_id, "viewer").await?;
+    let has_permission = check_document_permission(&pool, user_id, document_id, Role::Viewer).await?;
 
     if !has_permission {
         return Err(Error::PermissionError);
@@ -509,11 +498,12 @@ pub async fn api_get_project_from_document(
 }
 
 /// POST handler for granting permission to a user for a document.
+/// The permission overrides any role the user inherits from the document's projects, in either direction.
 /// Accessible via: POST /api/document/:id/permissions
 /// Test: test_documents.rs/test_add_permissions()
 /// Frontend: document.ts/add_document_permissions()
 pub async fn api_add_permissions(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Path(document_id): Path<i32>,
     Extension(pool): Extension<PgPool>,
     Json(payload): Json<CreatePermissionPayload>,
@@ -521,10 +511,9 @@ pub async fn api_add_permissions(
     println!("->> {:<12} - grant_document_permission", "HANDLER");
 
     // First check if the current user has owner permission
-    // get user_id from cookies
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
 
-    let has_permission = check_document_permission(&pool, user_id, document_id, "owner").await?;
+    let has_permission = check_document_permission(&pool, user_id, document_id, Role::Owner).await?;
 
     if !has_permission {
         return Err(Error::PermissionError);
@@ -533,14 +522,14 @@ pub async fn api_add_permissions(
     // Insert the permission
     let result = sqlx::query_as!(
         DocumentPermission,
-        "INSERT INTO document_permissions (document_id, user_id, role)
+        r#"INSERT INTO document_permissions (document_id, user_id, role)
         VALUES ($1, $2, $3)
         ON CONFLICT (document_id, user_id) 
         DO UPDATE SET role = $3
-        RETURNING document_id, user_id, role, created_at",
+        RETURNING document_id, user_id, role as "role: Role", created_at"#,
         document_id,
         payload.user_id,
-        payload.role
+        payload.role as Role
     )
     .fetch_one(&pool)
     .await;
@@ -552,30 +541,30 @@ pub async fn api_add_permissions(
 }
 
 /// GET handler for retrieving all users with access to a document.
+/// Users who only have access through one of the document's projects are marked as inherited.
 /// Accessible via: GET /api/document/:id/permissions
 /// Test: test_documents.rs/test_get_permissions()
 /// Frontend: document.ts/get_document_permissions()
 pub async fn api_get_permissions(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Path(document_id): Path<i32>,
     Extension(pool): Extension<PgPool>,
 ) -> Result<Json<Vec<UserPermissions>>> {
     println!("->> {:<12} - get_document_users", "HANDLER");
 
-    // get user_id from cookies
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
 
-    let permissions = check_document_permission(&pool, user_id, document_id, "viewer").await?;
+    let permissions = check_document_permission(&pool, user_id, document_id, Role::Viewer).await?;
 
     if !permissions {
         return Err(Error::PermissionError);
     }
     let result = sqlx::query_as!(
         UserPermissions,
-        r#"SELECT dp.user_id, u.name, u.email, dp.role 
-           FROM document_permissions dp
-           JOIN users u ON dp.user_id = u.id
-           WHERE dp.document_id = $1"#,
+        r#"SELECT da.user_id as "user_id!", u.name, u.email, da.role as "role!: Role", da.inherited as "inherited!"
+           FROM document_access da
+           JOIN users u ON da.user_id = u.id
+           WHERE da.document_id = $1"#,
         document_id
     )
     .fetch_all(&pool)
@@ -592,28 +581,27 @@ pub async fn api_get_permissions(
 /// Test: test_documents.rs/test_update_permission()
 /// Frontend: document.ts/update_document_permissions()
 pub async fn api_update_permission(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Path(document_id): Path<i32>,
     Extension(pool): Extension<PgPool>,
     Json(payload): Json<UpdatePermissionPayload>,
 ) -> Result<Json<Value>> {
     println!("->> {:<12} - update_document_permission", "HANDLER");
 
-    // get user_id from cookies
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
 
     // Check if user has owner permission
-    let has_permission = check_document_permission(&pool, user_id, document_id, "owner").await?;
+    let has_permission = check_document_permission(&pool, user_id, document_id, Role::Owner).await?;
 
     if !has_permission {
         return Err(Error::PermissionError);
     }
 
     // Check if this is an ownership transfer
-    if payload.role == "owner" {
+    if payload.role == Role::Owner {
         // Get the current owner's role
         let current_owner = sqlx::query!(
-            "SELECT user_id, role FROM document_permissions 
+            "SELECT user_id FROM document_permissions 
              WHERE document_id = $1 AND role = 'owner'",
             document_id
         )
@@ -637,12 +625,13 @@ pub async fn api_update_permission(
         }
     }
 
-    // Update the permission
+    // Update the permission, a user who only inherited access from a project gets an override
     let result = sqlx::query!(
-        "UPDATE document_permissions 
-         SET role = $1
-         WHERE document_id = $2 AND user_id = $3",
-        payload.role,
+        "INSERT INTO document_permissions (document_id, user_id, role)
+         VALUES ($2, $3, $1)
+         ON CONFLICT (document_id, user_id)
+         DO UPDATE SET role = $1",
+        payload.role as Role,
         document_id,
         payload.user_id
     )
@@ -663,21 +652,21 @@ pub async fn api_update_permission(
 }
 
 /// DELETE handler for removing a user's permission for a document.
+/// A user who is also on one of the document's projects falls back to the role inherited from it.
 /// Accessible via: DELETE /api/document/:id/permissions/:user_id
 /// Test: test_documents.rs/test_remove_permissions()
 /// Frontend: document.ts/delete_document_permissions()
 pub async fn api_remove_permissions(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Path((document_id, target_id)): Path<(i32, i32)>,
     Extension(pool): Extension<PgPool>,
 ) -> Result<Json<Value>> {
     println!("->> {:<12} - remove_document_permission", "HANDLER");
 
-    // get user_id from cookies
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
 
     // Check if user has owner permission
-    let has_permission = check_document_permission(&pool, user_id, document_id, "owner").await?;
+    let has_permission = check_document_permission(&pool, user_id, document_id, Role::Owner).await?;
 
     if !has_permission {
         return Err(Error::PermissionError);
@@ -709,17 +698,16 @@ pub async fn api_remove_permissions(
 /// Test: TODO: test_documents.rs/test_toggle_star_document()
 /// Frontend: document.ts/toggle_star_document()
 pub async fn api_toggle_star_document(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Path(document_id): Path<i32>,
     Extension(pool): Extension<PgPool>,
 ) -> Result<Json<Value>> {
     println!("->> {:<12} - api_toggle_star_document", "HANDLER");
 
-    // Get user ID from cookie
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
 
     // Check if user has at least editor permission
-    let has_permission = check_document_permission(&pool, user_id, document_id, "editor").await?;
+    let has_permission = check_document_permission(&pool, user_id, document_id, Role::Editor).await?;
 
     if !has_permission {
         return Err(Error::PermissionError);
@@ -769,17 +757,16 @@ pub async fn api_toggle_star_document(
 /// Test: TODO: test_documents.rs/test_trash_document()
 /// Frontend: document.ts/trash_document()
 pub async fn api_trash_document(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Path(document_id): Path<i32>,
     Extension(pool): Extension<PgPool>,
 ) -> Result<Json<Value>> {
     println!("->> {:<12} - api_trash_document", "HANDLER");
 
-    // Get user ID from cookie
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
 
     // Check if user has owner permission (changed from editor)
-    let has_permission = check_document_permission(&pool, user_id, document_id, "owner").await?;
+    let has_permission = check_document_permission(&pool, user_id, document_id, Role::Owner).await?;
 
     if !has_permission {
         return Err(Error::PermissionError);
@@ -811,17 +798,16 @@ pub async fn api_trash_document(
 /// Test: TODO: test_documents.rs/test_restore_document()
 /// Frontend: document.ts/restore_document()
 pub async fn api_restore_document(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Path(document_id): Path<i32>,
     Extension(pool): Extension<PgPool>,
 ) -> Result<Json<Value>> {
     println!("->> {:<12} - api_restore_document", "HANDLER");
 
-    // Get user ID from cookie
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
 
     // Check if user has owner permission (changed from editor for consistency)
-    let has_permission = check_document_permission(&pool, user_id, document_id, "owner").await?;
+    let has_permission = check_document_permission(&pool, user_id, document_id, Role::Owner).await?;
 
     if !has_permission {
         return Err(Error::PermissionError);
@@ -853,22 +839,21 @@ pub async fn api_restore_document(
 /// Test: TODO: test_documents.rs/test_get_starred_documents()
 /// Frontend: document.ts/get_starred_documents()
 pub async fn api_get_starred_documents(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Extension(pool): Extension<PgPool>,
 ) -> Result<Json<Vec<Document>>> {
     println!("->> {:<12} - api_get_starred_documents", "HANDLER");
 
-    // Get user ID from cookie
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
 
     // Get all starred documents for this user
     let documents = sqlx::query_as!(
         Document,
         r#"
-        SELECT d.id, d.name, d.content, d.created_at, d.updated_at, d.user_id, d.is_starred, d.is_trashed
+        SELECT d.id, d.name, d.content, d.created_at, d.updated_at, d.user_id, d.is_starred, d.is_trashed, d.version
         FROM documents d
-        JOIN document_permissions dp ON d.id = dp.document_id
-        WHERE dp.user_id = $1 AND d.is_starred = true AND d.is_trashed = false
+        JOIN document_access da ON d.id = da.document_id
+        WHERE da.user_id = $1 AND d.is_starred = true AND d.is_trashed = false
         "#,
         user_id
     )
@@ -884,22 +869,21 @@ pub async fn api_get_starred_documents(
 /// Test: TODO: test_documents.rs/test_get_trashed_documents()
 /// Frontend: document.ts/get_trashed_documents()
 pub async fn api_get_trashed_documents(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Extension(pool): Extension<PgPool>,
 ) -> Result<Json<Vec<Document>>> {
     println!("->> {:<12} - api_get_trashed_documents", "HANDLER");
 
-    // Get user ID from cookie
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
 
     // Get all trashed documents for this user
     let documents = sqlx::query_as!(
         Document,
         r#"
-        SELECT d.id, d.name, d.content, d.created_at, d.updated_at, d.user_id, d.is_starred, d.is_trashed
+        SELECT d.id, d.name, d.content, d.created_at, d.updated_at, d.user_id, d.is_starred, d.is_trashed, d.version
         FROM documents d
-        JOIN document_permissions dp ON d.id = dp.document_id
-        WHERE dp.user_id = $1 AND d.is_trashed = true
+        JOIN document_access da ON d.id = da.document_id
+        WHERE da.user_id = $1 AND d.is_trashed = true
         "#,
         user_id
     )
@@ -910,27 +894,26 @@ pub async fn api_get_trashed_documents(
     Ok(Json(documents))
 }
 
-/// GET handler for retrieving all shared documents for a user (where user is not owner but has viewer/editor permissions).
+/// GET handler for retrieving all shared documents for a user (where user has a permission other than owner).
 /// Accessible via: GET /api/document/shared
 /// Test: TODO: test_documents.rs/test_get_shared_documents()
 /// Frontend: document.ts/get_shared_documents()
 pub async fn api_get_shared_documents(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Extension(pool): Extension<PgPool>,
 ) -> Result<Json<Vec<Document>>> {
     println!("->> {:<12} - api_get_shared_documents", "HANDLER");
 
-    // Get user ID from cookie
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
 
-    // Get all documents where user has viewer/editor permissions but is not the owner
+    // Get all documents where user has a non-owner role, directly or through a project, and is not the owner
     let result = sqlx::query_as!(
         Document,
-        r#"SELECT DISTINCT d.id, d.name, d.content, d.created_at, d.updated_at, d.user_id, d.is_starred, d.is_trashed
+        r#"SELECT DISTINCT d.id, d.name, d.content, d.created_at, d.updated_at, d.user_id, d.is_starred, d.is_trashed, d.version
            FROM documents d
-           JOIN document_permissions dp ON d.id = dp.document_id
-           WHERE dp.user_id = $1 
-           AND dp.role IN ('viewer', 'editor')
+           JOIN document_access da ON d.id = da.document_id
+           WHERE da.user_id = $1 
+           AND da.role <> 'owner'
            AND d.user_id != $1"#,
         user_id
     )
@@ -18,26 +18,23 @@ use axum::{
     Router,
 };
 use sqlx::PgPool;
-use tower_cookies::Cookies;
 use serde_json::{Value, json};
 
 use crate::models::commands::{Command, UserKeybinding, UpdateKeybindingPayload};
 use crate::{Error, Result};
 
-use backend::get_user_id_from_cookie;
+use crate::web::middleware::auth::AuthUser;
 
 /// GET handler for retrieving all database-registered commands
 /// Accessible via: GET /api/command/default
 /// Test: test_documents.rs/test_get_all_commands()
 /// Frontend: document.ts/get_all_commands()
 pub async fn api_get_all_commands(
-    cookies: Cookies,
+    _auth_user: AuthUser,
     Extension(pool): Extension<PgPool>
 ) -> Result<Json<Vec<Command>>> {
     println!("->> {:<12} - get_all_commands", "HANDLER");
     
-    // get user_id from cookies
-    let _user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
 
     // Return all commands from database
     let commands = sqlx::query_as!(
@@ -56,13 +53,12 @@ pub async fn api_get_all_commands(
 /// Test: test_documents.rs/test_get_all_keybindings()
 /// Frontend: document.ts/get_all_keybindings()
 pub async fn api_get_all_keybindings(
-    cookies: Cookies, 
+    auth_user: AuthUser,
     Extension(pool): Extension<PgPool>
 ) -> Result<Json<Vec<UserKeybinding>>> {
     println!("->> {:<12} - get_all_keybindings", "HANDLER");
 
-    // get user_id from cookies
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
 
     // Return all custom keybindings owned by user in user keybindings table
     let keybindings = sqlx::query_as!(
@@ -82,15 +78,14 @@ pub async fn api_get_all_keybindings(
 /// Test: test_documents.rs/test_add_update_keybinding()
 /// Frontend: document.ts/add_update_keybinding()
 pub async fn api_add_update_keybinding(
-    cookies: Cookies, 
+    auth_user: AuthUser,
     Path(command_id): Path<i32>,
     Extension(pool): Extension<PgPool>,
     Json(payload): Json<UpdateKeybindingPayload>
 ) -> Result<Json<UserKeybinding>> {
     println!("->> {:<12} - add_update_keybinding", "HANDLER");
 
-    // get user_id from cookies
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
 
     // Upsert the keybinding (insert or update)
     let keybinding = sqlx::query_as!(
@@ -116,14 +111,13 @@ pub async fn api_add_update_keybinding(
 /// Test: test_documents.rs/test_delete_keybinding()
 /// Frontend: document.ts/delete_keybinding()
 pub async fn api_delete_keybinding(
-    cookies: Cookies, 
+    auth_user: AuthUser,
     Path(command_id): Path<i32>, 
     Extension(pool): Extension<PgPool>
 ) -> Result<Json<Command>> {
     println!("->> {:<12} - delete_keybinding", "HANDLER");
 
-    // get user_id from cookies
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
 
     // Delete from user keybindings table
     sqlx::query!(
@@ -153,13 +147,12 @@ pub async fn api_delete_keybinding(
 /// DELETE handler for resetting all user keybindings to default.
 /// Accessible via: DELETE /api/command/reset
 pub async fn api_reset_all_keybindings(
-    cookies: Cookies, 
+    auth_user: AuthUser,
     Extension(pool): Extension<PgPool>
 ) -> Result<Json<Value>> {
     println!("->> {:<12} - reset_all_keybindings", "HANDLER");
 
-    // Get user_id from cookies
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
 
     // Delete all user keybindings
     sqlx::query!(
@@ -4,4 +4,11 @@ pub mod proj_controller;
 pub mod doc_controller;
 pub mod key_controller;
 pub mod ai_controller;
-pub mod pref_controller;
\ No newline at end of file
+pub mod pref_controller;
+pub mod revision_controller;
+pub mod collab_controller;
+pub mod share_controller;
+pub mod publish_controller;
+pub mod comment_controller;
+pub mod suggestion_controller;
+pub mod admin_controller;
\ No newline at end of file
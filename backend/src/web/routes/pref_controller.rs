@@ -26,9 +26,8 @@ use axum::{
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use sqlx::PgPool;
-use tower_cookies::Cookies;
 use crate::{Error, Result};
-use backend::get_user_id_from_cookie;
+use crate::web::middleware::auth::AuthUser;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Preference {
@@ -54,13 +53,12 @@ fn get_default_background_image() -> (Vec<u8>, &'static str) {
 /// Test: test_preferences.rs/test_get_all_preferences()
 /// Frontend: account.ts/get_all_preferences()
 pub async fn api_get_all_preferences(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Extension(pool): Extension<PgPool>,
 ) -> Result<Json<Vec<Preference>>> {
     println!("->> {:<12} - get_all_preferences", "HANDLER");
 
-    // Get user_id from cookies
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
 
     // Get all preferences for the user, including defaults where user preferences don't exist
     let preferences = sqlx::query_as!(
@@ -90,14 +88,13 @@ pub async fn api_get_all_preferences(
 /// Test: test_preferences.rs/test_get_specific_preference()
 /// Frontend: account.ts/get_all_preferences() (accessed indirectly)
 pub async fn api_get_preference(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Path(preference_id): Path<i32>,
     Extension(pool): Extension<PgPool>,
 ) -> Result<Json<Preference>> {
     println!("->> {:<12} - get_preference", "HANDLER");
 
-    // Get user_id from cookies
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
 
     // Get the specific preference for the user, or the default if user preference doesn't exist
     let preference = sqlx::query_as!(
@@ -131,15 +128,14 @@ pub async fn api_get_preference(
 /// Test: test_preferences.rs/test_update_specific_preference()
 /// Frontend: account.ts/update_preference()
 pub async fn api_update_preference(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Path(preference_id): Path<i32>,
     Extension(pool): Extension<PgPool>,
     Json(payload): Json<UpdatePreferencePayload>,
 ) -> Result<Json<Value>> {
     println!("->> {:<12} - update_preference", "HANDLER");
 
-    // Get user_id from cookies
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
 
     // Check if the preference exists
     let preference_exists = sqlx::query!(
@@ -183,14 +179,13 @@ pub async fn api_update_preference(
 /// Test: test_preferences.rs/test_reset_specific_preference()
 /// Frontend: account.ts/reset_preference()
 pub async fn api_reset_preference(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Path(preference_id): Path<i32>,
     Extension(pool): Extension<PgPool>,
 ) -> Result<Json<Value>> {
     println!("->> {:<12} - reset_preference", "HANDLER");
 
-    // Get user_id from cookies
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
 
     // Check if the preference exists
     let preference_exists = sqlx::query!(
@@ -228,13 +223,12 @@ pub async fn api_reset_preference(
 /// Test: test_preferences.rs/test_reset_all_preferences()
 /// Frontend: account.ts/reset_all_preferences()
 pub async fn api_reset_all_preferences(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Extension(pool): Extension<PgPool>,
 ) -> Result<Json<Value>> {
     println!("->> {:<12} - reset_all_preferences", "HANDLER");
 
-    // Get user_id from cookies
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
 
     // Delete all user preferences to revert to defaults
     sqlx::query!(
@@ -258,13 +252,13 @@ pub async fn api_reset_all_preferences(
 /// Test: test_preferences.rs/test_upload_background_image()
 /// Frontend: account.ts/upload_background_image()
 pub async fn api_upload_background_image(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Extension(pool): Extension<PgPool>,
     mut multipart: Multipart,
 ) -> Result<Json<Value>> {
     println!("->> {:<12} - upload_background_image", "HANDLER");
 
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
 
     let mut background_image_data = Vec::new();
     let mut content_type = String::new();
@@ -311,12 +305,12 @@ pub async fn api_upload_background_image(
 /// Test: test_preferences.rs/test_get_background_image()
 /// Frontend: account.ts/check_background_image()
 pub async fn api_get_background_image(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Extension(pool): Extension<PgPool>
 ) -> Result<impl axum::response::IntoResponse> {
     println!("->> {:<12} - get_background_image", "HANDLER");
 
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
 
     let background_data = sqlx::query!(
         "SELECT image_data, content_type FROM user_backgrounds WHERE user_id = $1",
@@ -351,13 +345,12 @@ pub async fn api_get_background_image(
 /// Test: test_preferences.rs/test_delete_background_image()
 /// Frontend: account.ts/reset_background_image()
 pub async fn api_delete_background_image(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Extension(pool): Extension<PgPool>,
 ) -> Result<Json<Value>> {
     println!("->> {:<12} - delete_background_image", "HANDLER");
 
-    // Get user_id from cookies
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
 
     // Delete background from database
     sqlx::query!(
@@ -28,29 +28,29 @@ use axum::{
 };
 use serde_json::{json, Value};
 use sqlx::PgPool;
-use tower_cookies::Cookies;
 
 use crate::models::project::{CreateProjectPayload, Project, UpdateProjectPayload};
 use crate::models::project_permission::{
     CreateProjectPermissionPayload, ProjectPermission, UpdateProjectPermissionPayload,
     UserProjectPermissions,
 };
-use crate::web::middleware::middleware::check_project_permission;
+use crate::auth::authorization::{check_document_permission, check_project_permission};
+use crate::models::role::Role;
 use crate::{Error, Result};
 
 use crate::models::document::Document;
-use backend::get_user_id_from_cookie;
+use crate::models::storage::StorageManager;
+use crate::web::middleware::auth::AuthUser;
 
 /// GET handler for retrieving all projects for a user.
 /// Accessible via: GET /api/project
 /// Test: test_projects.rs/test_get_all_projects()
 /// Frontend: project.ts/get_all_projects()
 async fn api_get_all_projects(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Extension(pool): Extension<PgPool>,
 ) -> Result<Json<Vec<Project>>> {
-    // get user_id from cookies
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
 
     // Get all projects where the user has any permission
     let result = sqlx::query_as!(
@@ -75,17 +75,16 @@ async fn api_get_all_projects(
 /// Test: test_projects.rs/test_get_project()
 /// Frontend: project.ts/get_project()
 async fn api_get_project(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Path(id): Path<i32>,
     Extension(pool): Extension<PgPool>,
 ) -> Result<Json<Project>> {
     println!("->> {:<12} - api_get_project", "HANDLER");
 
-    // get user_id from cookies
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
 
     // Check if user has at least viewer permission
-    let has_permission = check_project_permission(&pool, user_id, id, "viewer").await?;
+    let has_permission = check_project_permission(&pool, user_id, id, Role::Viewer).await?;
 
     if !has_permission {
         return Err(Error::PermissionError);
@@ -103,7 +102,7 @@ async fn api_get_project(
 
     match result {
         Ok(project) => Ok(Json(project)),
-        Err(_) => return Err(Error::ProjectNotFoundError {project_id: id}),
+        Err(_) => Err(Error::ProjectNotFoundError {project_id: id}),
     }
 }
 
@@ -112,14 +111,13 @@ async fn api_get_project(
 /// Test: test_projects.rs/test_create_project()
 /// Frontend: project.ts/create_project()
 async fn api_create_project(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Extension(pool): Extension<PgPool>,
     Json(payload): Json<CreateProjectPayload>,
 ) -> Result<Json<Project>> {
     println!("->> {:<12} - api_create_project", "HANDLER");
 
-    // Get user ID from cookie
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
 
     // Check if user has reached their project limit
     let user_projects_count = sqlx::query!(
@@ -132,10 +130,11 @@ async fn api_create_project(
     .await
     .map_err(|_| Error::DatabaseError)?;
     
-    // Use hardcoded default limit
-    let max_projects = 3;
+    let limits = StorageManager::get_user_limits(&pool, user_id)
+        .await
+        .map_err(|_| Error::DatabaseError)?;
     
-    if user_projects_count.count.unwrap_or(0) as i32 >= max_projects {
+    if user_projects_count.count.unwrap_or(0) as i32 >= limits.max_projects {
         return Err(Error::LimitExceededError { message: "Project limit reached".to_string() });
     }
 
@@ -165,7 +164,7 @@ async fn api_create_project(
             .execute(&pool)
             .await;
 
-            if let Err(_) = permissions {
+            if permissions.is_err() {
                 return Err(Error::PermissionCreationError);
             }
 
@@ -180,18 +179,17 @@ async fn api_create_project(
 /// Test: test_projects.rs/test_update_project()
 /// Frontend: project.ts/update_project()
 async fn api_update_project(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Path(id): Path<i32>,
     Extension(pool): Extension<PgPool>,
     Json(payload): Json<UpdateProjectPayload>,
 ) -> Result<Json<Project>> {
     println!("->> {:<12} - api_update_project", "HANDLER");
 
-    // Get user ID from cookie
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
 
     // Check if user has editor or owner permission
-    let has_permission = check_project_permission(&pool, user_id, id, "editor").await?;
+    let has_permission = check_project_permission(&pool, user_id, id, Role::Editor).await?;
 
     if !has_permission {
         return Err(Error::PermissionError);
@@ -214,7 +212,7 @@ async fn api_update_project(
 
     match result {
         Ok(project) => Ok(Json(project)),
-        Err(_) => return Err(Error::ProjectNotFoundError { project_id: id }),
+        Err(_) => Err(Error::ProjectNotFoundError { project_id: id }),
     }
 }
 
@@ -223,17 +221,16 @@ async fn api_update_project(
 /// Test: test_projects.rs/test_delete_project()
 /// Frontend: project.ts/delete_project()
 async fn api_delete_project(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Path(id): Path<i32>,
     Extension(pool): Extension<PgPool>,
 ) -> Result<Json<Project>> {
     println!("->> {:<12} - api_delete_project", "HANDLER");
 
-    // Get user ID from cookie
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
 
     // Check if user has owner permission
-    let has_permission = check_project_permission(&pool, user_id, id, "owner").await?;
+    let has_permission = check_project_permission(&pool, user_id, id, Role::Owner).await?;
 
     if !has_permission {
         return Err(Error::PermissionError);
@@ -271,18 +268,17 @@ async fn api_delete_project(
 /// Test: test_projects.rs/test_add_permissions()
 /// Frontend: project.ts/add_project_permissions()
 async fn api_add_permissions(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Path(project_id): Path<i32>,
     Extension(pool): Extension<PgPool>,
     Json(payload): Json<CreateProjectPermissionPayload>,
 ) -> Result<Json<ProjectPermission>> {
     println!("->> {:<12} - grant_project_permission", "HANDLER");
 
-    // Get user ID from cookie
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
 
     // Check if user has owner permission
-    let has_permission = check_project_permission(&pool, user_id, project_id, "owner").await?;
+    let has_permission = check_project_permission(&pool, user_id, project_id, Role::Owner).await?;
 
     if !has_permission {
         return Err(Error::PermissionError);
@@ -291,59 +287,21 @@ async fn api_add_permissions(
     // Insert the project permission
     let result = sqlx::query_as!(
         ProjectPermission,
-        "INSERT INTO project_permissions (project_id, user_id, role)
+        r#"INSERT INTO project_permissions (project_id, user_id, role)
         VALUES ($1, $2, $3)
         ON CONFLICT (project_id, user_id) 
         DO UPDATE SET role = $3
-        RETURNING project_id, user_id, role, created_at",
+        RETURNING project_id, user_id, role as "role: Role", created_at"#,
         project_id,
         payload.user_id,
-        payload.role
+        payload.role as Role
             )
             .fetch_one(&pool)
             .await;
 
             match result {
-        Ok(permission) => {
-            // Get all documents in the project
-            let documents = sqlx::query!(
-                "SELECT document_id FROM document_projects WHERE project_id = $1",
-                project_id
-            )
-            .fetch_all(&pool)
-            .await
-            .map_err(|_| Error::DatabaseError)?;
-
-            // For each document, add document permissions
-            for doc in documents {
-                // Check if permission already exists
-                let existing = sqlx::query!(
-                    "SELECT 1 as exists FROM document_permissions 
-                     WHERE document_id = $1 AND user_id = $2",
-                    doc.document_id,
-                    payload.user_id
-                )
-                .fetch_optional(&pool)
-                .await
-                .map_err(|_| Error::DatabaseError)?;
-
-                if existing.is_none() {
-                    // Add document permission with the same role as project permission
-                    sqlx::query!(
-                        "INSERT INTO document_permissions (document_id, user_id, role)
-                         VALUES ($1, $2, $3)",
-                        doc.document_id,
-                        payload.user_id,
-                        payload.role
-                    )
-                    .execute(&pool)
-                    .await
-                    .map_err(|_| Error::DatabaseError)?;
-                }
-            }
-
-            Ok(Json(permission))
-        }
+        // Documents in the project are reached through the project permission, nothing to copy
+        Ok(permission) => Ok(Json(permission)),
         Err(_) => Err(Error::PermissionError)
     }
 }
@@ -353,17 +311,16 @@ async fn api_add_permissions(
 /// Test: test_projects.rs/test_get_permissions()
 /// Frontend: project.ts/get_project_permissions()
 async fn api_get_permissions(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Path(project_id): Path<i32>,
     Extension(pool): Extension<PgPool>,
 ) -> Result<Json<Vec<UserProjectPermissions>>> {
     println!("->> {:<12} - get_project_users", "HANDLER");
 
-    // Get user ID from cookie
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
 
     // Check if user has at least viewer permission
-    let has_permission = check_project_permission(&pool, user_id, project_id, "viewer").await?;
+    let has_permission = check_project_permission(&pool, user_id, project_id, Role::Viewer).await?;
 
     if !has_permission {
         return Err(Error::PermissionError);
@@ -371,7 +328,7 @@ async fn api_get_permissions(
 
     let result = sqlx::query_as!(
         UserProjectPermissions,
-        r#"SELECT pp.user_id, u.name, u.email, pp.role 
+        r#"SELECT pp.user_id, u.name, u.email, pp.role as "role: Role"
            FROM project_permissions pp
            JOIN users u ON pp.user_id = u.id
            WHERE pp.project_id = $1"#,
@@ -391,28 +348,27 @@ async fn api_get_permissions(
 /// Test: test_projects.rs/test_update_permission()
 /// Frontend: project.ts/update_project_permission()
 async fn api_update_permission(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Path(project_id): Path<i32>,
     Extension(pool): Extension<PgPool>,
     Json(payload): Json<UpdateProjectPermissionPayload>,
 ) -> Result<Json<Value>> {
     println!("->> {:<12} - update_project_permission", "HANDLER");
 
-    // Get user ID from cookie
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
 
     // Check if user has owner permission
-    let has_permission = check_project_permission(&pool, user_id, project_id, "owner").await?;
+    let has_permission = check_project_permission(&pool, user_id, project_id, Role::Owner).await?;
 
     if !has_permission {
         return Err(Error::PermissionError);
     }
 
     // Check if this is an ownership transfer
-    if payload.role == "owner" {
+    if payload.role == Role::Owner {
         // Get the current owner's role
         let current_owner = sqlx::query!(
-            "SELECT user_id, role FROM project_permissions 
+            "SELECT user_id FROM project_permissions 
              WHERE project_id = $1 AND role = 'owner'",
             project_id
         )
@@ -433,28 +389,6 @@ async fn api_update_permission(
             .execute(&pool)
             .await
             .map_err(|_| Error::DatabaseError)?;
-
-            // Update all document permissions for the current owner
-            let documents = sqlx::query!(
-                "SELECT document_id FROM document_projects WHERE project_id = $1",
-                project_id
-            )
-            .fetch_all(&pool)
-            .await
-            .map_err(|_| Error::DatabaseError)?;
-
-            for doc in documents {
-                sqlx::query!(
-                    "UPDATE document_permissions 
-                     SET role = 'editor'
-                     WHERE document_id = $1 AND user_id = $2",
-                    doc.document_id,
-                    current_owner.user_id
-                )
-                .execute(&pool)
-                .await
-                .map_err(|_| Error::DatabaseError)?;
-            }
         }
     }
 
@@ -463,7 +397,7 @@ async fn api_update_permission(
         "UPDATE project_permissions 
          SET role = $1
          WHERE project_id = $2 AND user_id = $3",
-        payload.role,
+        payload.role as Role,
         project_id,
         payload.user_id
     )
@@ -471,51 +405,12 @@ async fn api_update_permission(
     .await;
 
     match result {
-        Ok(_) => {
-            // Get all documents in the project
-            let documents = sqlx::query!(
-                "SELECT document_id FROM document_projects WHERE project_id = $1",
-                project_id
-            )
-            .fetch_all(&pool)
-            .await
-            .map_err(|_| Error::DatabaseError)?;
-
-            // Update permissions for all documents
-            for doc in documents {
-                // Check if permission exists
-                let existing = sqlx::query!(
-                    "SELECT 1 as exists FROM document_permissions 
-                     WHERE document_id = $1 AND user_id = $2",
-                    doc.document_id,
-                    payload.user_id
-                )
-                .fetch_optional(&pool)
-                .await
-                .map_err(|_| Error::DatabaseError)?;
-
-                if existing.is_some() {
-                    // Update existing document permission
-                    sqlx::query!(
-                        "UPDATE document_permissions 
-                         SET role = $1
-                         WHERE document_id = $2 AND user_id = $3",
-                        payload.role,
-                        doc.document_id,
-                        payload.user_id
-                    )
-                    .execute(&pool)
-                    .await
-                    .map_err(|_| Error::DatabaseError)?;
-                }
+        // Documents in the project pick up the new role through the project permission
+        Ok(_) => Ok(Json(json!({
+            "result": {
+                "success": true,
             }
-
-            Ok(Json(json!({
-                "result": {
-                    "success": true,
-                }
-            })))
-        }
+        }))),
         Err(e) => {
             println!("Error updating permission: {:?}", e);
             Err(Error::PermissionError)
@@ -528,17 +423,16 @@ async fn api_update_permission(
 /// Test: test_projects.rs/test_remove_permissions()
 /// Frontend: project.ts/remove_project_permissions()
 async fn api_delete_permissions(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Path((project_id, target_id)): Path<(i32, i32)>,
     Extension(pool): Extension<PgPool>,
 ) -> Result<Json<Value>> {
     println!("->> {:<12} - remove_project_permission", "HANDLER");
 
-    // Get user ID from cookie
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
     
     // Check if user has owner permission
-    let has_permission = check_project_permission(&pool, user_id, project_id, "owner").await?;
+    let has_permission = check_project_permission(&pool, user_id, project_id, Role::Owner).await?;
 
     if !has_permission {
         return Err(Error::PermissionError);
@@ -554,8 +448,8 @@ async fn api_delete_permissions(
     .await;
 
     let is_target_owner = sqlx::query!(
-        "SELECT role FROM project_permissions 
-         WHERE project_id = $1 AND user_id = $2",
+        r#"SELECT role as "role: Role" FROM project_permissions 
+           WHERE project_id = $1 AND user_id = $2"#,
         project_id,
         target_id
     )
@@ -564,34 +458,12 @@ async fn api_delete_permissions(
 
     // If we're removing an owner and there's only one owner, prevent it
     if let (Ok(owners_count), Ok(Some(record))) = (&owners_count_result, &is_target_owner) {
-        if record.role == "owner" && owners_count.count.unwrap_or(0) <= 1 {
+        if record.role == Role::Owner && owners_count.count.unwrap_or(0) <= 1 {
             return Err(Error::PermissionError);
         }
     }
 
-    // Get all documents in the project
-    let documents = sqlx::query!(
-        "SELECT document_id FROM document_projects WHERE project_id = $1",
-        project_id
-    )
-    .fetch_all(&pool)
-    .await
-    .map_err(|_| Error::DatabaseError)?;
-
-    // Remove permissions from all documents
-    for doc in documents {
-        sqlx::query!(
-            "DELETE FROM document_permissions 
-             WHERE document_id = $1 AND user_id = $2",
-            doc.document_id,
-            target_id
-        )
-        .execute(&pool)
-        .await
-        .map_err(|_| Error::DatabaseError)?;
-    }
-
-    // Remove the project permission
+    // Remove the project permission, this also ends the access it gave to the project's documents
     let result = sqlx::query!(
         "DELETE FROM project_permissions 
          WHERE project_id = $1 AND user_id = $2",
@@ -617,17 +489,16 @@ async fn api_delete_permissions(
 /// Test: test_projects.rs/test_force_delete_project()
 /// Frontend: project.ts/force_delete_project()
 async fn api_force_delete_project(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Path(id): Path<i32>,
     Extension(pool): Extension<PgPool>,
 ) -> Result<Json<Value>> {
     println!("->> {:<12} - api_force_delete_project", "HANDLER");
 
-    // Get user ID from cookie
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
 
     // Check if user has owner permission
-    let has_permission = check_project_permission(&pool, user_id, id, "owner").await?;
+    let has_permission = check_project_permission(&pool, user_id, id, Role::Owner).await?;
 
     if !has_permission {
         return Err(Error::PermissionError);
@@ -687,33 +558,23 @@ async fn api_force_delete_project(
 /// Test: test_projects.rs/test_get_project_documents()
 /// Frontend: project.ts/get_project_documents()
 async fn api_get_documents(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Path(project_id): Path<i32>,
     Extension(pool): Extension<PgPool>,
 ) -> Result<Json<Vec<Document>>> {
     println!("->> {:<12} - api_get_documents", "HANDLER");
 
-    // Get user ID from cookie
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
 
-    // Get all documents in the project that the user has at least viewer access to
+    // Get all documents in the project that the user can access, directly or through a project
     let documents = sqlx::query_as!(
         Document,
-        r#"SELECT DISTINCT d.id, d.name, d.content, d.created_at, d.updated_at, d.user_id, d.is_trashed, d.is_starred
+        r#"SELECT d.id, d.name, d.content, d.created_at, d.updated_at, d.user_id, d.is_trashed, d.is_starred, d.version
         FROM documents d
         JOIN document_projects dp ON d.id = dp.document_id
-        LEFT JOIN document_permissions dp2 ON d.id = dp2.document_id
+        JOIN document_access da ON d.id = da.document_id
         WHERE dp.project_id = $1
-        AND (
-            d.user_id = $2  -- User is the owner
-            OR dp2.user_id = $2  -- User has direct document permissions
-            OR EXISTS (  -- User has project permissions
-                SELECT 1 FROM project_permissions pp 
-                WHERE pp.project_id = $1 
-                AND pp.user_id = $2 
-                AND pp.role IN ('owner', 'editor', 'viewer')
-            )
-        )
+        AND da.user_id = $2
         ORDER BY d.id"#,
         project_id,
         user_id
@@ -730,35 +591,27 @@ async fn api_get_documents(
 /// Test: test_projects.rs/test_add_document_to_project()
 /// Frontend: project.ts/add_document_to_project()
 async fn api_add_document(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Path((project_id, document_id)): Path<(i32, i32)>,
     Extension(pool): Extension<PgPool>,
 ) -> Result<Json<Value>> {
     println!("->> {:<12} - api_add_document", "HANDLER");
 
-    // Get user ID from cookie
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
 
     // Check if user has at least editor permission on the project
     let has_project_permission =
-        check_project_permission(&pool, user_id, project_id, "editor").await?;
+        check_project_permission(&pool, user_id, project_id, Role::Editor).await?;
 
     if !has_project_permission {
         return Err(Error::PermissionError);
     }
 
     // Check if user has at least editor permission on the document
-    let has_document_permission = sqlx::query!(
-        "SELECT role FROM document_permissions 
-         WHERE document_id = $1 AND user_id = $2 AND role IN ('editor', 'owner')",
-        document_id,
-        user_id
-    )
-    .fetch_optional(&pool)
-    .await
-    .map_err(|_| Error::PermissionError)?;
+    let has_document_permission =
+        check_document_permission(&pool, user_id, document_id, Role::Editor).await?;
 
-    if has_document_permission.is_none() {
+    if !has_document_permission {
         return Err(Error::PermissionError);
     }
 
@@ -808,17 +661,16 @@ async fn api_add_document(
 /// Test: test_projects.rs/test_remove_document_from_project()
 /// Frontend: project.ts/remove_document_from_project()
 async fn api_remove_document(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Path((project_id, document_id)): Path<(i32, i32)>,
     Extension(pool): Extension<PgPool>,
 ) -> Result<Json<Value>> {
     println!("->> {:<12} - api_remove_document", "HANDLER");
 
-    // Get user ID from cookie
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
 
     // Check if user has at least editor permission
-    let has_permission = check_project_permission(&pool, user_id, project_id, "editor").await?;
+    let has_permission = check_project_permission(&pool, user_id, project_id, Role::Editor).await?;
 
     if !has_permission {
         return Err(Error::PermissionError);
@@ -850,17 +702,16 @@ async fn api_remove_document(
 /// Test: TODO: test_projects.rs/test_toggle_star_project()
 /// Frontend: drive.ts/toggle_star_project()
 async fn api_toggle_star_project(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Path(id): Path<i32>,
     Extension(pool): Extension<PgPool>,
 ) -> Result<Json<Value>> {
     println!("->> {:<12} - api_toggle_star_project", "HANDLER");
 
-    // Get user ID from cookie
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
 
     // Check if user has at least editor permission
-    let has_permission = check_project_permission(&pool, user_id, id, "editor").await?;
+    let has_permission = check_project_permission(&pool, user_id, id, Role::Editor).await?;
 
     if !has_permission {
         return Err(Error::PermissionError);
@@ -910,17 +761,16 @@ async fn api_toggle_star_project(
 /// Test: TODO: test_projects.rs/test_trash_project()
 /// Frontend: drive.ts/trash_project()
 async fn api_trash_project(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Path(id): Path<i32>,
     Extension(pool): Extension<PgPool>,
 ) -> Result<Json<Value>> {
     println!("->> {:<12} - api_trash_project", "HANDLER");
 
-    // Get user ID from cookie
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
 
     // Check if user has owner permission
-    let has_permission = check_project_permission(&pool, user_id, id, "owner").await?;
+    let has_permission = check_project_permission(&pool, user_id, id, Role::Owner).await?;
 
     if !has_permission {
         return Err(Error::PermissionError);
@@ -952,17 +802,16 @@ async fn api_trash_project(
 /// Test: TODO: test_projects.rs/test_restore_project()
 /// Frontend: drive.ts/restore_project()
 async fn api_restore_project(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Path(id): Path<i32>,
     Extension(pool): Extension<PgPool>,
 ) -> Result<Json<Value>> {
     println!("->> {:<12} - api_restore_project", "HANDLER");
 
-    // Get user ID from cookie
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
 
     // Check if user has owner permission
-    let has_permission = check_project_permission(&pool, user_id, id, "owner").await?;
+    let has_permission = check_project_permission(&pool, user_id, id, Role::Owner).await?;
 
     if !has_permission {
         return Err(Error::PermissionError);
@@ -994,13 +843,12 @@ async fn api_restore_project(
 /// Test: TODO: test_projects.rs/test_get_starred_projects()
 /// Frontend: drive.ts/get_starred_projects()
 async fn api_get_starred_projects(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Extension(pool): Extension<PgPool>,
 ) -> Result<Json<Vec<Project>>> {
     println!("->> {:<12} - api_get_starred_projects", "HANDLER");
 
-    // Get user ID from cookie
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
 
     // Get all starred projects for this user
     let result = sqlx::query_as!(
@@ -1025,13 +873,12 @@ async fn api_get_starred_projects(
 /// Test: TODO: test_projects.rs/test_get_trashed_projects()
 /// Frontend: drive.ts/get_trashed_projects()
 async fn api_get_trashed_projects(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Extension(pool): Extension<PgPool>,
 ) -> Result<Json<Vec<Project>>> {
     println!("->> {:<12} - api_get_trashed_projects", "HANDLER");
 
-    // Get user ID from cookie
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
 
     // Get all trashed projects for this user
     let result = sqlx::query_as!(
@@ -1051,27 +898,26 @@ async fn api_get_trashed_projects(
     }
 }
 
-/// GET handler for retrieving all shared projects for a user (where user is not owner but has viewer/editor permissions).
+/// GET handler for retrieving all shared projects for a user (where user has a permission other than owner).
 /// Accessible via: GET /api/project/shared
 /// Test: TODO: test_projects.rs/test_get_shared_projects()
 /// Frontend: drive.ts/get_shared_projects()
 async fn api_get_shared_projects(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Extension(pool): Extension<PgPool>,
 ) -> Result<Json<Vec<Project>>> {
     println!("->> {:<12} - api_get_shared_projects", "HANDLER");
 
-    // Get user ID from cookie
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
 
-    // Get all projects where the user has editor/viewer permissions but is not the owner
+    // Get all projects where the user has a non-owner permission
     let result = sqlx::query_as!(
         Project,
         r#"SELECT p.id, p.name, p.user_id, p.created_at, p.updated_at, is_trashed, is_starred
            FROM projects p
            JOIN project_permissions pp ON p.id = pp.project_id
            WHERE pp.user_id = $1 
-           AND pp.role IN ('editor', 'viewer')
+           AND pp.role <> 'owner'
            AND COALESCE(p.is_trashed, false) = false"#,
         user_id
     )
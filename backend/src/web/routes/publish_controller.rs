@@ -0,0 +1,275 @@
+/*
+/ src/web/routes/publish_controller.rs
+/ Request Handlers
+/
+/ File containing API Backend endpoints for publishing documents to anonymous readers
+/
+/ API Summary:
+/ api_publish_document      POST    /api/document/:id/publish   - Publish the Current Content of the Document
+/ api_get_publication       GET     /api/document/:id/publish   - Get the Publication Status of the Document
+/ api_unpublish_document    DELETE  /api/document/:id/publish   - Stop Serving the Document, Keeping Its Slug
+/ public_get_document       GET     /p/:slug                    - Read a Published Document as HTML (No Login)
+/ public_get_document_json  GET     /p/:slug/json               - Read a Published Document as JSON (No Login)
+/
+*/
+
+use axum::response::Html;
+use axum::routing::{delete, get, post};
+use axum::{
+    extract::{Extension, Json, Path},
+    Router,
+};
+use http::header;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::auth::authorization::check_document_permission;
+use crate::models::publication::DocumentPublication;
+use crate::models::role::Role;
+use crate::rag::chunk::html_to_text;
+use crate::web::middleware::auth::AuthUser;
+use crate::web::sanitize::{escape_html, sanitize_html};
+use crate::{Error, Result};
+
+/// Published pages can only show what is in them, no scripts, frames or outside resources
+const PUBLIC_CONTENT_SECURITY_POLICY: &str = "default-src 'none'; style-src 'unsafe-inline'; img-src https: data:";
+
+/// Readable slug from the document name with a random suffix so it cannot be guessed
+fn make_slug(name: &str) -> String {
+    let mut base = String::new();
+    for c in name.to_lowercase().chars() {
+        if c.is_ascii_alphanumeric() {
+            base.push(c);
+        } else if !base.ends_with('-') && !base.is_empty() {
+            base.push('-');
+        }
+    }
+    let base: String = base.trim_end_matches('-').chars().take(60).collect();
+    let suffix = &Uuid::new_v4().simple().to_string()[..10];
+
+    if base.is_empty() {
+        suffix.to_string()
+    } else {
+        format!("{}-{}", base.trim_end_matches('-'), suffix)
+    }
+}
+
+fn publication_json(publication: &DocumentPublication) -> Value {
+    json!({
+        "published": publication.is_published,
+        "slug": publication.slug,
+        "url": format!("/p/{}", publication.slug),
+        "title": publication.title,
+        "revision_number": publication.revision_number,
+        "published_at": publication.published_at,
+        "view_count": publication.view_count
+    })
+}
+
+/// POST handler for publishing a document.
+/// Snapshots the current content, later edits stay private until the document is published again.
+/// Accessible via: POST /api/document/:id/publish
+/// Test: test_documents.rs/test_publish_document()
+/// Frontend: // TODO: No frontend function implemented yet
+pub async fn api_publish_document(
+    auth_user: AuthUser,
+    Path(document_id): Path<i32>,
+    Extension(pool): Extension<PgPool>,
+) -> Result<Json<Value>> {
+    println!("->> {:<12} - publish_document", "HANDLER");
+
+    let user_id = auth_user.user_id;
+
+    if !check_document_permission(&pool, user_id, document_id, Role::Owner).await? {
+        return Err(Error::PermissionError);
+    }
+
+    let document = sqlx::query!(
+        r#"SELECT d.name, d.content,
+                  (SELECT MAX(revision_number) FROM document_revisions WHERE document_id = d.id) as "revision_number?"
+           FROM documents d WHERE d.id = $1"#,
+        document_id
+    )
+    .fetch_optional(&pool)
+    .await
+    .map_err(|_| Error::DatabaseError)?
+    .ok_or(Error::DocumentNotFoundError { document_id })?;
+
+    // The slug is only generated the first time, republishing keeps the URL readers already have
+    let publication = sqlx::query_as!(
+        DocumentPublication,
+        r#"INSERT INTO document_publications (document_id, slug, title, content, revision_number, published_by)
+           VALUES ($1, $2, $3, $4, $5, $6)
+           ON CONFLICT (document_id) DO UPDATE
+           SET title = EXCLUDED.title,
+               content = EXCLUDED.content,
+               revision_number = EXCLUDED.revision_number,
+               published_by = EXCLUDED.published_by,
+               published_at = CURRENT_TIMESTAMP,
+               is_published = TRUE
+           RETURNING document_id, slug, title, content, revision_number, is_published, published_by,
+                     published_at, view_count"#,
+        document_id,
+        make_slug(&document.name),
+        document.name,
+        document.content.unwrap_or_default(),
+        document.revision_number,
+        user_id
+    )
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| {
+        println!("->> {:<12} - failed to publish document {}: {:?}", "ERROR", document_id, e);
+        Error::DocumentUpdateError { document_id }
+    })?;
+
+    Ok(Json(publication_json(&publication)))
+}
+
+/// GET handler for the publication status of a document.
+/// Accessible via: GET /api/document/:id/publish
+/// Test: test_documents.rs/test_publish_document()
+/// Frontend: // TODO: No frontend function implemented yet
+pub async fn api_get_publication(
+    auth_user: AuthUser,
+    Path(document_id): Path<i32>,
+    Extension(pool): Extension<PgPool>,
+) -> Result<Json<Value>> {
+    println!("->> {:<12} - get_publication", "HANDLER");
+
+    if !check_document_permission(&pool, auth_user.user_id, document_id, Role::Viewer).await? {
+        return Err(Error::PermissionError);
+    }
+
+    let publication = sqlx::query_as!(
+        DocumentPublication,
+        "SELECT document_id, slug, title, content, revision_number, is_published, published_by,
+                published_at, view_count
+         FROM document_publications WHERE document_id = $1",
+        document_id
+    )
+    .fetch_optional(&pool)
+    .await
+    .map_err(|_| Error::DatabaseError)?;
+
+    match publication {
+        Some(publication) => Ok(Json(publication_json(&publication))),
+        None => Ok(Json(json!({ "published": false }))),
+    }
+}
+
+/// DELETE handler for unpublishing a document.
+/// The slug and view count are kept for when the document is published again.
+/// Accessible via: DELETE /api/document/:id/publish
+/// Test: test_documents.rs/test_publish_document()
+/// Frontend: // TODO: No frontend function implemented yet
+pub async fn api_unpublish_document(
+    auth_user: AuthUser,
+    Path(document_id): Path<i32>,
+    Extension(pool): Extension<PgPool>,
+) -> Result<Json<Value>> {
+    println!("->> {:<12} - unpublish_document", "HANDLER");
+
+    if !check_document_permission(&pool, auth_user.user_id, document_id, Role::Owner).await? {
+        return Err(Error::PermissionError);
+    }
+
+    sqlx::query!(
+        "UPDATE document_publications SET is_published = FALSE WHERE document_id = $1",
+        document_id
+    )
+    .execute(&pool)
+    .await
+    .map_err(|_| Error::DatabaseError)?;
+
+    Ok(Json(json!({
+        "result": {
+            "success": true,
+            "message": "Document unpublished"
+        }
+    })))
+}
+
+/// Look up a published snapshot by slug and count the view.
+/// This is the only way anonymous readers reach document content, and it never reads the live document.
+async fn view_publication(pool: &PgPool, slug: &str) -> Result<DocumentPublication> {
+    sqlx::query_as!(
+        DocumentPublication,
+        "UPDATE document_publications p SET view_count = view_count + 1
+         FROM documents d
+         WHERE p.slug = $1 AND p.is_published = TRUE
+           AND d.id = p.document_id AND COALESCE(d.is_trashed, false) = false
+         RETURNING p.document_id, p.slug, p.title, p.content, p.revision_number, p.is_published,
+                   p.published_by, p.published_at, p.view_count",
+        slug
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|_| Error::DatabaseError)?
+    .ok_or(Error::PublicationNotFoundError { slug: slug.to_string() })
+}
+
+/// GET handler for reading a published document as a standalone HTML page.
+/// Accessible via: GET /p/:slug (no login required)
+/// Test: test_documents.rs/test_publish_document()
+/// Frontend: Not directly called from frontend, opened by readers
+pub async fn public_get_document(
+    Path(slug): Path<String>,
+    Extension(pool): Extension<PgPool>,
+) -> Result<([(header::HeaderName, &'static str); 1], Html<String>)> {
+    println!("->> {:<12} - public_get_document {}", "HANDLER", slug);
+
+    let publication = view_publication(&pool, &slug).await?;
+    let title = escape_html(&publication.title);
+
+    let page = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{title}</title>\n\
+         <style>body{{max-width:46rem;margin:3rem auto;padding:0 1rem;font-family:Georgia,serif;line-height:1.6;color:#222}}</style>\n\
+         </head>\n<body>\n<article>\n<h1>{title}</h1>\n{content}\n</article>\n</body>\n</html>\n",
+        title = title,
+        content = sanitize_html(&publication.content),
+    );
+
+    Ok(([(header::CONTENT_SECURITY_POLICY, PUBLIC_CONTENT_SECURITY_POLICY)], Html(page)))
+}
+
+/// GET handler for reading a published document as JSON.
+/// Accessible via: GET /p/:slug/json (no login required)
+/// Test: test_documents.rs/test_publish_document()
+/// Frontend: Not directly called from frontend, opened by readers
+pub async fn public_get_document_json(
+    Path(slug): Path<String>,
+    Extension(pool): Extension<PgPool>,
+) -> Result<Json<Value>> {
+    println!("->> {:<12} - public_get_document_json {}", "HANDLER", slug);
+
+    let publication = view_publication(&pool, &slug).await?;
+
+    Ok(Json(json!({
+        "slug": publication.slug,
+        "title": publication.title,
+        "content": sanitize_html(&publication.content),
+        "text": html_to_text(&publication.content),
+        "revision_number": publication.revision_number,
+        "published_at": publication.published_at,
+        "view_count": publication.view_count
+    })))
+}
+
+/// Publishing controls, merged into the document router
+pub fn publish_routes() -> Router {
+    Router::new()
+        .route("/:id/publish", post(api_publish_document))
+        .route("/:id/publish", get(api_get_publication))
+        .route("/:id/publish", delete(api_unpublish_document))
+}
+
+/// Anonymous read-only access, nested under /p
+pub fn public_routes() -> Router {
+    Router::new()
+        .route("/:slug", get(public_get_document))
+        .route("/:slug/json", get(public_get_document_json))
+}
@@ -0,0 +1,249 @@
+/*
+/ src/web/routes/revision_controller.rs
+/ Request Handlers
+/
+/ File containing API Backend endpoints for browsing and restoring a document's revision history
+/
+/ API Summary:
+/ api_get_revisions         GET     /:id/revisions                      - List Revisions of the Document, Newest First
+/ api_diff_revisions        GET     /:id/revisions/diff?from=&to=       - Word Diff Between Two Revisions
+/ api_diff_document         GET     /:id/diff?from=&to=                 - Changed Ranges Since a Revision
+/ api_get_revision          GET     /:id/revisions/:number              - Get a Single Revision With Its Content
+/ api_restore_revision      POST    /:id/revisions/:number/restore      - Restore the Document to a Revision
+/
+*/
+
+use axum::routing::{get, post};
+use axum::{
+    extract::{Extension, Json, Path, Query},
+    Router,
+};
+use chrono::Utc;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+
+use crate::db::revision::{enforce_storage_quota, get_document_owner, record_revision};
+use crate::diff::{diff_hunks, diff_runs};
+use crate::models::revision::{DocumentDiffQuery, DocumentRevision, RevisionDiffQuery, RevisionOrigin, RevisionSummary};
+use crate::rag::chunk::html_to_text;
+use crate::rag::embed::reindex_document;
+use crate::web::middleware::auth::AuthUser;
+use crate::auth::authorization::check_document_permission;
+use crate::models::role::Role;
+use crate::{Error, Result};
+
+/// GET handler for listing the revisions of a document.
+/// Accessible via: GET /api/document/:id/revisions
+/// Test: test_documents.rs/test_get_revisions()
+/// Frontend: // TODO: No frontend function implemented yet
+pub async fn api_get_revisions(
+    auth_user: AuthUser,
+    Path(document_id): Path<i32>,
+    Extension(pool): Extension<PgPool>,
+) -> Result<Json<Vec<RevisionSummary>>> {
+    println!("->> {:<12} - get_revisions", "HANDLER");
+
+    let user_id = auth_user.user_id;
+
+    if !check_document_permission(&pool, user_id, document_id, Role::Viewer).await? {
+        return Err(Error::PermissionError);
+    }
+
+    let revisions = sqlx::query_as!(
+        RevisionSummary,
+        r#"SELECT r.id, r.revision_number, r.author_id, u.name as "author_name?",
+                  r.origin as "origin: RevisionOrigin", r.created_at,
+                  LENGTH(r.content)::BIGINT as "size_bytes!"
+           FROM document_revisions r
+           LEFT JOIN users u ON r.author_id = u.id
+           WHERE r.document_id = $1
+           ORDER BY r.revision_number DESC"#,
+        document_id
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(|_| Error::DatabaseError)?;
+
+    Ok(Json(revisions))
+}
+
+/// GET handler for retrieving a single revision including its content.
+/// Accessible via: GET /api/document/:id/revisions/:number
+/// Test: test_documents.rs/test_get_revisions()
+/// Frontend: // TODO: No frontend function implemented yet
+pub async fn api_get_revision(
+    auth_user: AuthUser,
+    Path((document_id, revision_number)): Path<(i32, i32)>,
+    Extension(pool): Extension<PgPool>,
+) -> Result<Json<DocumentRevision>> {
+    println!("->> {:<12} - get_revision", "HANDLER");
+
+    let user_id = auth_user.user_id;
+
+    if !check_document_permission(&pool, user_id, document_id, Role::Viewer).await? {
+        return Err(Error::PermissionError);
+    }
+
+    let revision = fetch_revision(&pool, document_id, revision_number).await?;
+
+    Ok(Json(revision))
+}
+
+/// GET handler for comparing two revisions of a document word by word.
+/// `changes` covers the whole text for reading, `hunks` are the changed ranges of the HTML content.
+/// Accessible via: GET /api/document/:id/revisions/diff?from=&to=
+/// Test: test_documents.rs/test_diff_revisions()
+/// Frontend: // TODO: No frontend function implemented yet
+pub async fn api_diff_revisions(
+    auth_user: AuthUser,
+    Path(document_id): Path<i32>,
+    Query(params): Query<RevisionDiffQuery>,
+    Extension(pool): Extension<PgPool>,
+) -> Result<Json<Value>> {
+    println!("->> {:<12} - diff_revisions", "HANDLER");
+
+    let user_id = auth_user.user_id;
+
+    if !check_document_permission(&pool, user_id, document_id, Role::Viewer).await? {
+        return Err(Error::PermissionError);
+    }
+
+    let from = fetch_revision(&pool, document_id, params.from).await?;
+    let to = fetch_revision(&pool, document_id, params.to).await?;
+
+    let changes = diff_runs(&html_to_text(&from.content), &html_to_text(&to.content));
+    let hunks = diff_hunks(&from.content, &to.content);
+
+    Ok(Json(json!({
+        "from": from.revision_number,
+        "to": to.revision_number,
+        "changes": changes,
+        "hunks": hunks
+    })))
+}
+
+/// GET handler for the changed ranges between a revision and another revision or the current content.
+/// Without `to` the revision is compared with the document as it is now.
+/// Accessible via: GET /api/document/:id/diff?from=&to=
+/// Test: test_documents.rs/test_diff_document()
+/// Frontend: // TODO: No frontend function implemented yet
+pub async fn api_diff_document(
+    auth_user: AuthUser,
+    Path(document_id): Path<i32>,
+    Query(params): Query<DocumentDiffQuery>,
+    Extension(pool): Extension<PgPool>,
+) -> Result<Json<Value>> {
+    println!("->> {:<12} - diff_document", "HANDLER");
+
+    if !check_document_permission(&pool, auth_user.user_id, document_id, Role::Viewer).await? {
+        return Err(Error::PermissionError);
+    }
+
+    let from = fetch_revision(&pool, document_id, params.from).await?;
+
+    let (to_revision, to_content) = match params.to {
+        Some(revision_number) => {
+            let to = fetch_revision(&pool, document_id, revision_number).await?;
+            (Some(to.revision_number), to.content)
+        }
+        None => {
+            let current = sqlx::query!("SELECT content FROM documents WHERE id = $1", document_id)
+                .fetch_optional(&pool)
+                .await
+                .map_err(|_| Error::DatabaseError)?
+                .ok_or(Error::DocumentNotFoundError { document_id })?;
+            (None, current.content.unwrap_or_default())
+        }
+    };
+
+    let hunks = diff_hunks(&from.content, &to_content);
+
+    Ok(Json(json!({
+        "from": from.revision_number,
+        "to": to_revision, // null for the current content
+        "hunks": hunks
+    })))
+}
+
+/// POST handler for restoring a document to an earlier revision.
+/// The restore is recorded as a new revision, so it can be undone like any other save.
+/// Accessible via: POST /api/document/:id/revisions/:number/restore
+/// Test: test_documents.rs/test_restore_revision()
+/// Frontend: // TODO: No frontend function implemented yet
+pub async fn api_restore_revision(
+    auth_user: AuthUser,
+    Path((document_id, revision_number)): Path<(i32, i32)>,
+    Extension(pool): Extension<PgPool>,
+) -> Result<Json<Value>> {
+    println!("->> {:<12} - restore_revision", "HANDLER");
+
+    let user_id = auth_user.user_id;
+
+    if !check_document_permission(&pool, user_id, document_id, Role::Editor).await? {
+        return Err(Error::PermissionError);
+    }
+
+    let revision = fetch_revision(&pool, document_id, revision_number).await?;
+
+    let owner_id = get_document_owner(&pool, document_id).await?;
+    let mut tx = pool.begin().await.map_err(|_| Error::DatabaseError)?;
+
+    let update_result = sqlx::query!(
+        "UPDATE documents
+         SET content = $1, updated_at = $2, version = version + 1
+         WHERE id = $3",
+        revision.content,
+        Utc::now().naive_utc(),
+        document_id
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|_| Error::DocumentUpdateError { document_id })?;
+
+    if update_result.rows_affected() == 0 {
+        return Err(Error::DocumentUpdateError { document_id });
+    }
+
+    let new_revision_number =
+        record_revision(&mut tx, document_id, user_id, &revision.content, RevisionOrigin::Restore).await?;
+
+    enforce_storage_quota(&mut tx, owner_id, document_id).await?;
+    tx.commit().await.map_err(|_| Error::DatabaseError)?;
+
+    // Search the restored content right away, not the content it replaced
+    reindex_document(&pool, document_id, &revision.content).await;
+
+    Ok(Json(json!({
+        "result": {
+            "success": true
+        },
+        "restored_from": revision.revision_number,
+        "revision_number": new_revision_number
+    })))
+}
+
+async fn fetch_revision(pool: &PgPool, document_id: i32, revision_number: i32) -> Result<DocumentRevision> {
+    sqlx::query_as!(
+        DocumentRevision,
+        r#"SELECT r.id, r.document_id, r.revision_number, r.author_id, u.name as "author_name?",
+                  r.content, r.origin as "origin: RevisionOrigin", r.created_at
+           FROM document_revisions r
+           LEFT JOIN users u ON r.author_id = u.id
+           WHERE r.document_id = $1 AND r.revision_number = $2"#,
+        document_id,
+        revision_number
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|_| Error::DatabaseError)?
+    .ok_or(Error::RevisionNotFoundError { document_id, revision_number })
+}
+
+pub fn revision_routes() -> Router {
+    Router::new()
+        .route("/:id/revisions", get(api_get_revisions))
+        .route("/:id/revisions/diff", get(api_diff_revisions))
+        .route("/:id/revisions/:number", get(api_get_revision))
+        .route("/:id/revisions/:number/restore", post(api_restore_revision))
+        .route("/:id/diff", get(api_diff_document))
+}
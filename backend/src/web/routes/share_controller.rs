@@ -0,0 +1,236 @@
+/*
+/ src/web/routes/share_controller.rs
+/ Request Handlers
+/
+/ File containing API Backend endpoints for sharing documents and projects through links
+/
+/ API Summary:
+/ api_create_document_link  POST    /document/:id/links             - Create a Share Link for the Document
+/ api_get_document_links    GET     /document/:id/links             - List the Document's Active Share Links
+/ api_revoke_document_link  DELETE  /document/:id/links/:link_id    - Revoke a Document Share Link
+/ api_create_project_link   POST    /project/:id/links              - Create a Share Link for the Project
+/ api_get_project_links     GET     /project/:id/links              - List the Project's Active Share Links
+/ api_revoke_project_link   DELETE  /project/:id/links/:link_id     - Revoke a Project Share Link
+/ api_get_share_link        GET     /share/:token                   - Preview What a Share Link Grants
+/ api_accept_share_link     POST    /share/:token/accept            - Accept a Share Link as the Current User
+/
+*/
+
+use axum::routing::{delete, get, post};
+use axum::{
+    extract::{Extension, Json, Path},
+    Router,
+};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+
+use crate::auth::authorization::{check_document_permission, check_project_permission};
+use crate::db::share_link::{accept_link, create_link, find_active_link, list_links, revoke_link, ShareTarget};
+use crate::models::role::Role;
+use crate::models::share_link::{AcceptShareLinkPayload, CreateShareLinkPayload, ShareLinkInfo};
+use crate::web::middleware::auth::AuthUser;
+use crate::{Error, Result};
+
+/// POST handler for creating a share link for a document.
+/// Accessible via: POST /api/document/:id/links
+/// Test: test_documents.rs/test_share_links()
+/// Frontend: // TODO: No frontend function implemented yet
+pub async fn api_create_document_link(
+    auth_user: AuthUser,
+    Path(document_id): Path<i32>,
+    Extension(pool): Extension<PgPool>,
+    Json(payload): Json<CreateShareLinkPayload>,
+) -> Result<Json<ShareLinkInfo>> {
+    println!("->> {:<12} - create_document_link", "HANDLER");
+
+    if !check_document_permission(&pool, auth_user.user_id, document_id, Role::Owner).await? {
+        return Err(Error::PermissionError);
+    }
+
+    let link = create_link(&pool, ShareTarget::Document(document_id), auth_user.user_id, &payload).await?;
+
+    Ok(Json(link.into()))
+}
+
+/// GET handler for listing the active share links of a document.
+/// Accessible via: GET /api/document/:id/links
+/// Test: test_documents.rs/test_share_links()
+/// Frontend: // TODO: No frontend function implemented yet
+pub async fn api_get_document_links(
+    auth_user: AuthUser,
+    Path(document_id): Path<i32>,
+    Extension(pool): Extension<PgPool>,
+) -> Result<Json<Vec<ShareLinkInfo>>> {
+    println!("->> {:<12} - get_document_links", "HANDLER");
+
+    if !check_document_permission(&pool, auth_user.user_id, document_id, Role::Owner).await? {
+        return Err(Error::PermissionError);
+    }
+
+    let links = list_links(&pool, ShareTarget::Document(document_id)).await?;
+
+    Ok(Json(links.into_iter().map(ShareLinkInfo::from).collect()))
+}
+
+/// DELETE handler for revoking a share link of a document.
+/// Permissions already granted through the link are kept.
+/// Accessible via: DELETE /api/document/:id/links/:link_id
+/// Test: test_documents.rs/test_share_links()
+/// Frontend: // TODO: No frontend function implemented yet
+pub async fn api_revoke_document_link(
+    auth_user: AuthUser,
+    Path((document_id, link_id)): Path<(i32, i32)>,
+    Extension(pool): Extension<PgPool>,
+) -> Result<Json<Value>> {
+    println!("->> {:<12} - revoke_document_link", "HANDLER");
+
+    if !check_document_permission(&pool, auth_user.user_id, document_id, Role::Owner).await? {
+        return Err(Error::PermissionError);
+    }
+
+    revoke_link(&pool, ShareTarget::Document(document_id), link_id).await?;
+
+    Ok(Json(json!({
+        "result": {
+            "success": true,
+            "message": "Share link revoked"
+        }
+    })))
+}
+
+/// POST handler for creating a share link for a project.
+/// Accessible via: POST /api/project/:id/links
+/// Test: test_projects.rs/test_share_links()
+/// Frontend: // TODO: No frontend function implemented yet
+pub async fn api_create_project_link(
+    auth_user: AuthUser,
+    Path(project_id): Path<i32>,
+    Extension(pool): Extension<PgPool>,
+    Json(payload): Json<CreateShareLinkPayload>,
+) -> Result<Json<ShareLinkInfo>> {
+    println!("->> {:<12} - create_project_link", "HANDLER");
+
+    if !check_project_permission(&pool, auth_user.user_id, project_id, Role::Owner).await? {
+        return Err(Error::PermissionError);
+    }
+
+    let link = create_link(&pool, ShareTarget::Project(project_id), auth_user.user_id, &payload).await?;
+
+    Ok(Json(link.into()))
+}
+
+/// GET handler for listing the active share links of a project.
+/// Accessible via: GET /api/project/:id/links
+/// Test: test_projects.rs/test_share_links()
+/// Frontend: // TODO: No frontend function implemented yet
+pub async fn api_get_project_links(
+    auth_user: AuthUser,
+    Path(project_id): Path<i32>,
+    Extension(pool): Extension<PgPool>,
+) -> Result<Json<Vec<ShareLinkInfo>>> {
+    println!("->> {:<12} - get_project_links", "HANDLER");
+
+    if !check_project_permission(&pool, auth_user.user_id, project_id, Role::Owner).await? {
+        return Err(Error::PermissionError);
+    }
+
+    let links = list_links(&pool, ShareTarget::Project(project_id)).await?;
+
+    Ok(Json(links.into_iter().map(ShareLinkInfo::from).collect()))
+}
+
+/// DELETE handler for revoking a share link of a project.
+/// Permissions already granted through the link are kept.
+/// Accessible via: DELETE /api/project/:id/links/:link_id
+/// Test: test_projects.rs/test_share_links()
+/// Frontend: // TODO: No frontend function implemented yet
+pub async fn api_revoke_project_link(
+    auth_user: AuthUser,
+    Path((project_id, link_id)): Path<(i32, i32)>,
+    Extension(pool): Extension<PgPool>,
+) -> Result<Json<Value>> {
+    println!("->> {:<12} - revoke_project_link", "HANDLER");
+
+    if !check_project_permission(&pool, auth_user.user_id, project_id, Role::Owner).await? {
+        return Err(Error::PermissionError);
+    }
+
+    revoke_link(&pool, ShareTarget::Project(project_id), link_id).await?;
+
+    Ok(Json(json!({
+        "result": {
+            "success": true,
+            "message": "Share link revoked"
+        }
+    })))
+}
+
+/// GET handler for previewing a share link before accepting it.
+/// Only says what kind of access the link grants, the target is revealed once it is accepted.
+/// Accessible via: GET /api/share/:token
+/// Test: test_documents.rs/test_share_links()
+/// Frontend: // TODO: No frontend function implemented yet
+pub async fn api_get_share_link(
+    _auth_user: AuthUser,
+    Path(token): Path<String>,
+    Extension(pool): Extension<PgPool>,
+) -> Result<Json<Value>> {
+    println!("->> {:<12} - get_share_link", "HANDLER");
+
+    let link = find_active_link(&pool, &token).await?;
+    let target_type = if link.document_id.is_some() { "document" } else { "project" };
+
+    Ok(Json(json!({
+        "target_type": target_type,
+        "role": link.role,
+        "requires_password": link.password_hash.is_some(),
+        "expires_at": link.expires_at
+    })))
+}
+
+/// POST handler for accepting a share link.
+/// Grants the link's role to the current user, access they already have is never reduced.
+/// Too many wrong passwords in a row lock the link for a while, it answers 429 until then.
+/// Accessible via: POST /api/share/:token/accept
+/// Test: test_documents.rs/test_share_links(), test_documents.rs/test_share_link_limits(), test_projects.rs/test_share_links()
+/// Frontend: // TODO: No frontend function implemented yet
+pub async fn api_accept_share_link(
+    auth_user: AuthUser,
+    Path(token): Path<String>,
+    Extension(pool): Extension<PgPool>,
+    Json(payload): Json<AcceptShareLinkPayload>,
+) -> Result<Json<Value>> {
+    println!("->> {:<12} - accept_share_link", "HANDLER");
+
+    let (target, role) = accept_link(&pool, &token, auth_user.user_id, payload.password.as_deref()).await?;
+
+    let (document_id, project_id) = target.ids();
+
+    Ok(Json(json!({
+        "document_id": document_id,
+        "project_id": project_id,
+        "role": role
+    })))
+}
+
+/// Link management for documents, merged into the document router
+pub fn document_link_routes() -> Router {
+    Router::new()
+        .route("/:id/links", post(api_create_document_link))
+        .route("/:id/links", get(api_get_document_links))
+        .route("/:id/links/:link_id", delete(api_revoke_document_link))
+}
+
+/// Link management for projects, merged into the project router
+pub fn project_link_routes() -> Router {
+    Router::new()
+        .route("/:id/links", post(api_create_project_link))
+        .route("/:id/links", get(api_get_project_links))
+        .route("/:id/links/:link_id", delete(api_revoke_project_link))
+}
+
+pub fn share_routes() -> Router {
+    Router::new()
+        .route("/:token", get(api_get_share_link))
+        .route("/:token/accept", post(api_accept_share_link))
+}
@@ -0,0 +1,192 @@
+/*
+/ src/web/routes/suggestion_controller.rs
+/ Request Handlers
+/
+/ File containing API Backend endpoints for suggesting changes to documents and reviewing them
+/
+/ API Summary:
+/ api_get_suggestions       GET     /:id/suggestions?status=                    - List Suggestions on the Document
+/ api_create_suggestion     POST    /:id/suggestions                            - Suggest a Change Instead of Saving It
+/ api_get_suggestion        GET     /:id/suggestions/:suggestion_id             - Get a Single Suggestion
+/ api_accept_suggestion     POST    /:id/suggestions/:suggestion_id/accept      - Apply the Suggestion as a New Revision
+/ api_reject_suggestion     POST    /:id/suggestions/:suggestion_id/reject      - Close the Suggestion Without Applying It
+/
+/ Commenters can suggest, editors review. Authors can withdraw their own suggestions by rejecting them.
+/
+*/
+
+use axum::routing::{get, post};
+use axum::{
+    extract::{Extension, Json, Path, Query},
+    Router,
+};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+
+use crate::auth::authorization::{check_document_permission, document_role};
+use crate::db::suggestion::{
+    accept_suggestion, create_suggestion, get_suggestion, list_suggestions, reject_suggestion,
+};
+use crate::models::role::Role;
+use crate::models::suggestion::{
+    CreateSuggestionPayload, DocumentSuggestion, SuggestionListQuery, SuggestionSource,
+};
+use crate::web::middleware::auth::AuthUser;
+use crate::{Error, Result};
+
+/// GET handler for listing the suggestions on a document.
+/// Accessible via: GET /api/document/:id/suggestions?status=
+/// Test: test_documents.rs/test_suggestions()
+/// Frontend: // TODO: No frontend function implemented yet
+pub async fn api_get_suggestions(
+    auth_user: AuthUser,
+    Path(document_id): Path<i32>,
+    Query(params): Query<SuggestionListQuery>,
+    Extension(pool): Extension<PgPool>,
+) -> Result<Json<Vec<DocumentSuggestion>>> {
+    println!("->> {:<12} - get_suggestions", "HANDLER");
+
+    if !check_document_permission(&pool, auth_user.user_id, document_id, Role::Viewer).await? {
+        return Err(Error::PermissionError);
+    }
+
+    let suggestions = list_suggestions(&pool, document_id, params.status).await?;
+
+    Ok(Json(suggestions))
+}
+
+/// POST handler for suggesting a change to a document.
+/// The suggestion is made against the version the editor has open, which must still be the current one.
+/// Accessible via: POST /api/document/:id/suggestions
+/// Test: test_documents.rs/test_suggestions()
+/// Frontend: // TODO: No frontend function implemented yet
+pub async fn api_create_suggestion(
+    auth_user: AuthUser,
+    Path(document_id): Path<i32>,
+    Extension(pool): Extension<PgPool>,
+    Json(payload): Json<CreateSuggestionPayload>,
+) -> Result<Json<DocumentSuggestion>> {
+    println!("->> {:<12} - create_suggestion", "HANDLER");
+
+    let user_id = auth_user.user_id;
+
+    if !check_document_permission(&pool, user_id, document_id, Role::Commenter).await? {
+        return Err(Error::PermissionError);
+    }
+
+    let current = sqlx::query!("SELECT content, version FROM documents WHERE id = $1", document_id)
+        .fetch_optional(&pool)
+        .await
+        .map_err(|_| Error::DatabaseError)?
+        .ok_or(Error::DocumentNotFoundError { document_id })?;
+
+    if current.version != payload.base_version {
+        return Err(Error::DocumentConflictError { document_id, current_version: current.version });
+    }
+
+    let suggestion_id = create_suggestion(
+        &pool,
+        document_id,
+        user_id,
+        SuggestionSource::User,
+        current.version,
+        &current.content.unwrap_or_default(),
+        &payload.new_content,
+    )
+    .await?
+    .ok_or(Error::InvalidRequestFormatError)?; // Nothing to suggest
+
+    Ok(Json(get_suggestion(&pool, document_id, suggestion_id).await?))
+}
+
+/// GET handler for a single suggestion.
+/// Accessible via: GET /api/document/:id/suggestions/:suggestion_id
+/// Test: test_documents.rs/test_suggestions()
+/// Frontend: // TODO: No frontend function implemented yet
+pub async fn api_get_suggestion(
+    auth_user: AuthUser,
+    Path((document_id, suggestion_id)): Path<(i32, i32)>,
+    Extension(pool): Extension<PgPool>,
+) -> Result<Json<DocumentSuggestion>> {
+    println!("->> {:<12} - get_suggestion", "HANDLER");
+
+    if !check_document_permission(&pool, auth_user.user_id, document_id, Role::Viewer).await? {
+        return Err(Error::PermissionError);
+    }
+
+    Ok(Json(get_suggestion(&pool, document_id, suggestion_id).await?))
+}
+
+/// POST handler for accepting a suggestion.
+/// The changed ranges are applied to the current content, edits made since the suggestion are kept.
+/// The document is indexed for search again right away.
+/// Accessible via: POST /api/document/:id/suggestions/:suggestion_id/accept
+/// Test: test_documents.rs/test_suggestions()
+/// Frontend: // TODO: No frontend function implemented yet
+pub async fn api_accept_suggestion(
+    auth_user: AuthUser,
+    Path((document_id, suggestion_id)): Path<(i32, i32)>,
+    Extension(pool): Extension<PgPool>,
+) -> Result<Json<Value>> {
+    println!("->> {:<12} - accept_suggestion", "HANDLER");
+
+    let user_id = auth_user.user_id;
+
+    if !check_document_permission(&pool, user_id, document_id, Role::Editor).await? {
+        return Err(Error::PermissionError);
+    }
+
+    let suggestion = get_suggestion(&pool, document_id, suggestion_id).await?;
+    let revision_number = accept_suggestion(&pool, &suggestion, user_id).await?;
+
+    Ok(Json(json!({
+        "result": {
+            "success": true
+        },
+        "suggestion_id": suggestion_id,
+        "revision_number": revision_number
+    })))
+}
+
+/// POST handler for rejecting a suggestion.
+/// Accessible via: POST /api/document/:id/suggestions/:suggestion_id/reject
+/// Test: test_documents.rs/test_suggestions()
+/// Frontend: // TODO: No frontend function implemented yet
+pub async fn api_reject_suggestion(
+    auth_user: AuthUser,
+    Path((document_id, suggestion_id)): Path<(i32, i32)>,
+    Extension(pool): Extension<PgPool>,
+) -> Result<Json<Value>> {
+    println!("->> {:<12} - reject_suggestion", "HANDLER");
+
+    let user_id = auth_user.user_id;
+    let role = document_role(&pool, user_id, document_id)
+        .await?
+        .ok_or(Error::PermissionError)?;
+
+    let suggestion = get_suggestion(&pool, document_id, suggestion_id).await?;
+
+    let is_author = suggestion.author_id == Some(user_id) && role.allows(Role::Commenter);
+    if !is_author && !role.allows(Role::Editor) {
+        return Err(Error::PermissionError);
+    }
+
+    reject_suggestion(&pool, &suggestion, user_id).await?;
+
+    Ok(Json(json!({
+        "result": {
+            "success": true,
+            "message": "Suggestion rejected"
+        }
+    })))
+}
+
+/// Suggestion review, merged into the document router
+pub fn suggestion_routes() -> Router {
+    Router::new()
+        .route("/:id/suggestions", get(api_get_suggestions))
+        .route("/:id/suggestions", post(api_create_suggestion))
+        .route("/:id/suggestions/:suggestion_id", get(api_get_suggestion))
+        .route("/:id/suggestions/:suggestion_id/accept", post(api_accept_suggestion))
+        .route("/:id/suggestions/:suggestion_id/reject", post(api_reject_suggestion))
+}
@@ -11,12 +11,19 @@
 / api_login             POST    /login          - Attempt Login And Set Cookies
 / api_logout            GET     /logout         - Logout By Wiping Cookies
 / api_check_auth        GET     /check-auth     - Check User Authentication
+/ api_get_sessions      GET     /sessions       - List Active Sessions For Current User
+/ api_revoke_session    DELETE  /sessions/:id   - Revoke One Session
+/ api_revoke_all_sessions DELETE /sessions      - Revoke Every Session (Sign Out Everywhere)
+/ api_get_ai_usage      GET     /ai-usage       - Plan, Token Usage This Month And AI Operation History
+/ api_get_plans         GET     /plans          - List The Available Plans (Public)
+/ api_select_plan       PUT     /plan           - Move The Current User To Another Free Plan
 /
 */
 
-use axum::routing::{get, post, put};
+use axum::routing::{delete, get, post, put};
 use axum::{
-    extract::{Extension, Json, Path},
+    extract::{ConnectInfo, Extension, Json, Path, Query},
+    http::HeaderMap,
     Router,
 };
 use serde_json::{json, Value};
@@ -28,12 +35,20 @@ use argon2::{
     password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
     Argon2
 };
+use std::env;
+use std::net::{IpAddr, SocketAddr};
 use std::sync::OnceLock;
+use uuid::Uuid;
 
 use crate::models::user::{CreateUserPayload, LoginUserPayload, UpdateUserPayload, User};
+use crate::models::credit::{AiUsageQuery, AiUsageSummary, Plan, SelectPlanPayload};
+use crate::db::credit::{list_plans, set_user_plan, usage_summary};
+use crate::models::session::SessionInfo;
 use crate::models::storage::StorageManager;
 use crate::{Error, Result};
-use backend::get_user_id_from_cookie;
+use crate::auth::session::{create_session, list_sessions, revoke_all_sessions, revoke_session};
+use crate::auth::token::{AuthToken, AUTH_COOKIE_NAME, TOKEN_TTL_DAYS};
+use crate::web::middleware::auth::AuthUser;
 
 // Define a static variable to hold the default profile image data
 static DEFAULT_PROFILE_IMAGE: OnceLock<(Vec<u8>, String)> = OnceLock::new();
@@ -55,6 +70,7 @@ fn get_default_profile_image() -> (Vec<u8>, String) {
 /// Test: test_users.rs/test_get_user()
 /// Frontend: // TODO: No direct frontend call, user info usually fetched via /current
 pub async fn api_get_user(
+    _auth_user: AuthUser,
     Path(id): Path<i32>,
     Extension(pool): Extension<PgPool>,
 ) -> Result<Json<User>> {
@@ -189,19 +205,13 @@ pub async fn api_create_user(
 /// Test: test_users.rs/test_update_user()
 /// Frontend: user.ts/update_user()
 pub async fn api_update_user(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Extension(pool): Extension<PgPool>,
     Json(payload): Json<UpdateUserPayload>,
 ) -> Result<Json<Value>> {
     println!("->> {:<12} - update_user", "HANDLER");
 
-    // Need to check cookie here to get user id
-    let user_id = get_user_id_from_cookie(&cookies);
-
-    // if we cant parse a user's id
-    if user_id == None {
-        return Err(Error::UserIdUpdateError);
-    }
+    let user_id = auth_user.user_id;
     
     // Validate password complexity
     if payload.password.is_none() || payload.password.as_ref().unwrap().is_empty() {
@@ -219,7 +229,7 @@ pub async fn api_update_user(
 
         // if the update doesnt affect any rows it failed
         if result.unwrap().rows_affected() == 0 {
-            return Err(Error::UserNotFoundError { user_id: user_id.unwrap() });
+            return Err(Error::UserNotFoundError { user_id });
         }
 
         // otherwise it passes
@@ -263,7 +273,7 @@ pub async fn api_update_user(
     let password_hash = argon2.hash_password(password.as_bytes(), &salt)
         .map_err(|e| {
             println!("->> {:<12} - password hashing error: {:?}", "ERROR", e);
-            Error::UserUpdateError { user_id: user_id.unwrap() }
+            Error::UserUpdateError { user_id }
         })?
         .to_string();
 
@@ -282,7 +292,7 @@ pub async fn api_update_user(
 
     // if the update doesnt affect any rows it failed
     if result.unwrap().rows_affected() == 0 {
-        return Err(Error::UserNotFoundError { user_id: user_id.unwrap() });
+        return Err(Error::UserNotFoundError { user_id });
     }
 
     // otherwise it passes
@@ -299,6 +309,8 @@ pub async fn api_update_user(
 /// Frontend: user.ts/attempt_login()
 pub async fn api_login(
     cookies: Cookies,
+    headers: HeaderMap,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
     Extension(pool): Extension<PgPool>,
     Json(payload): Json<LoginUserPayload>,
 ) -> Result<Json<Value>> {
@@ -307,7 +319,7 @@ pub async fn api_login(
 
     // Get user from database
     let result = sqlx::query!(
-        "SELECT id, email, password
+        "SELECT id, email, password, disabled_at
          FROM users
          WHERE email = $1;",
         payload.email
@@ -333,20 +345,34 @@ pub async fn api_login(
             if password_verified {
                 println!("Password verified for user: {}", record.email);
 
+                // Only someone who knows the password learns that the account is disabled
+                if record.disabled_at.is_some() {
+                    println!("Account is disabled for user: {}", record.email);
+                    return Err(Error::AccountDisabledError);
+                }
+
                 // Create token and set cookie as before
                 let _domain = option_env!("DOMAIN").unwrap_or("localhost");
                 let app_env = option_env!("APP_ENV").unwrap_or("development");
                 let on_production = app_env == "production";
 
-                // Create a token value (in a real app, this would be a JWT or similar)
-                let token_value = format!("user-{}.exp.sign", record.id);
+                // Create a signed token carrying the user id, issue/expiry time and a session id
+                let token = AuthToken::new(record.id);
+                let token_value = token.encode();
                 let token_for_cookie = token_value.clone();
 
-                println!("Generated token value: {}", token_value);
+                println!("Generated token for session: {}", token.session_id);
                 println!("Production is: {}", on_production);
 
+                // Record the session so it can be listed and revoked later
+                let user_agent = headers
+                    .get(http::header::USER_AGENT)
+                    .and_then(|v| v.to_str().ok())
+                    .map(|v| v.to_string());
+                create_session(&pool, &token, user_agent, Some(client_ip(&headers, &addr))).await?;
+
                 // Build the cookie with enhanced security
-                let cookie = Cookie::build("auth-token", token_value)
+                let cookie = Cookie::build(AUTH_COOKIE_NAME, token_value)
                     //.domain(domain.to_string())
                     .path("/")
                     .secure(on_production)
@@ -356,29 +382,29 @@ pub async fn api_login(
                     } else { 
                         SameSite::Lax   // For local development
                     })
-                    .max_age(Duration::days(3))
+                    .max_age(Duration::days(TOKEN_TTL_DAYS))
                     .finish();
 
                 // Add the cookie
                 cookies.add(cookie);
 
                 // Return success
-                return Ok(Json(json!({
+                Ok(Json(json!({
                     "result": {
                         "success": true,
                         "user_id": record.id,
                         "token": token_for_cookie
                     }
-                })));
+                })))
 
             } else {
                 println!("Password verification failed for user: {}", record.email);
-                return Err(Error::LoginFailError);
+                Err(Error::LoginFailError)
             }
         }
         Err(_) => {
             println!("No user found with email: {}", payload.email);
-            return Err(Error::LoginFailError);
+            Err(Error::LoginFailError)
         },
     }
 }
@@ -387,15 +413,26 @@ pub async fn api_login(
 /// Accessible via: GET /api/users/logout
 /// Test: test_users.rs/test_logout()
 /// Frontend: user.ts/logout()
-pub async fn api_logout(cookies: Cookies) -> Result<Json<Value>> {
+pub async fn api_logout(
+    auth_user: Option<AuthUser>,
+    cookies: Cookies,
+    Extension(pool): Extension<PgPool>,
+) -> Result<Json<Value>> {
     println!("->> {:<12} - logout", "HANDLER");
+
+    // Revoke the server-side session so the token stops working even if it was copied
+    if let Some(auth_user) = auth_user {
+        // The session may already be gone (revoked elsewhere), which is fine here
+        let _ = revoke_session(&pool, auth_user.user_id, auth_user.session_id).await;
+    }
+
     // Get environment variables with fallbacks for development
     let _domain = option_env!("DOMAIN").unwrap_or("localhost");
     let app_env = option_env!("APP_ENV").unwrap_or("development");
     let on_production = app_env == "production";
 
     // Build a cookie with the same properties as the login cookie
-    let cookie = Cookie::build("auth-token", "")
+    let cookie = Cookie::build(AUTH_COOKIE_NAME, "")
         //.domain(domain.to_string())
         .path("/")
         .secure(on_production)
@@ -411,11 +448,11 @@ pub async fn api_logout(cookies: Cookies) -> Result<Json<Value>> {
     // Remove the private cookie
     cookies.remove(cookie);
 
-    return Ok(Json(json!({
+    Ok(Json(json!({
         "result": {
             "success": true
         }
-    })));
+    })))
 }
 
 /// GET handler to check if user is authenticated via cookie.
@@ -423,16 +460,12 @@ pub async fn api_logout(cookies: Cookies) -> Result<Json<Value>> {
 /// Test: TODO: test_users.rs/test_check_auth()
 /// Frontend: user.ts/check_auth()
 pub async fn api_check_auth(
-    cookies: Cookies,
-    Extension(_pool): Extension<PgPool>,
+    auth_user: Option<AuthUser>,
 ) -> Result<Json<Value>> {
     println!("->> {:<12} - check_auth", "HANDLER");
 
-    // Try to get user_id from cookie
-    let user_id = get_user_id_from_cookie(&cookies);
-    
     // Return JSON with auth status
-    match user_id {
+    match auth_user {
         Some(_) => Ok(Json(json!({ "authenticated": true }))),
         None => Ok(Json(json!({ "authenticated": false }))),
     }
@@ -449,17 +482,13 @@ pub async fn api_check_auth(
 /// 
 /// Returns a JSON response with success status and message.
 pub async fn api_upload_profile_image(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Extension(pool): Extension<PgPool>,
     mut multipart: axum::extract::Multipart,
 ) -> Result<Json<Value>> {
     println!("->> {:<12} - upload_profile_image", "HANDLER");
     
-    // Get user id from cookie
-    let user_id = match get_user_id_from_cookie(&cookies) {
-        Some(id) => id,
-        None => return Err(Error::UserIdUpdateError),
-    };
+    let user_id = auth_user.user_id;
     
     // Process the multipart form data with better error handling
     let mut image_data = Vec::new();
@@ -620,6 +649,7 @@ pub async fn api_get_profile_image(
 /// Frontend: // TODO: No frontend function implemented yet
 /// Returns a list of users matching the search term.
 pub async fn api_search_users(
+    _auth_user: AuthUser,
     Extension(pool): Extension<PgPool>,
     axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
 ) -> Result<Json<Vec<User>>> {
@@ -655,13 +685,12 @@ pub async fn api_search_users(
 /// Test: test_users.rs/test_get_current_user()
 /// Frontend: user.ts/get_current_user()
 pub async fn api_get_current_user(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Extension(pool): Extension<PgPool>,
 ) -> Result<Json<User>> {
     println!("->> {:<12} - get_current_user", "HANDLER");
 
-    // Get user ID from cookie
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::UserIdUpdateError)?;
+    let user_id = auth_user.user_id;
 
     let result = sqlx::query_as!(
         User,
@@ -685,13 +714,12 @@ pub async fn api_get_current_user(
 /// Accessible via: GET /api/users/storage
 /// Frontend: user.ts/get_storage_usage()
 pub async fn api_get_storage_usage(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Extension(pool): Extension<PgPool>,
 ) -> Result<Json<Value>> {
     println!("->> {:<12} - get_storage_usage", "HANDLER");
 
-    // Get user ID from cookie
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::UserIdUpdateError)?;
+    let user_id = auth_user.user_id;
     
     // Calculate document storage
     // We'll count characters in content as a proxy for storage space (1 char = ~1-4 bytes)
@@ -760,13 +788,12 @@ pub async fn api_get_storage_usage(
 /// Accessible via: GET /api/user/storage
 /// Frontend: user.ts/get_user_storage()
 pub async fn api_get_user_storage(
-    cookies: Cookies,
+    auth_user: AuthUser,
     Extension(pool): Extension<PgPool>,
 ) -> Result<Json<Value>> {
     println!("->> {:<12} - get_user_storage", "HANDLER");
 
-    // Get user ID from cookie
-    let user_id = get_user_id_from_cookie(&cookies).ok_or(Error::PermissionError)?;
+    let user_id = auth_user.user_id;
 
     // Get project and document counts
     let project_count = sqlx::query!(
@@ -789,22 +816,17 @@ pub async fn api_get_user_storage(
     .await
     .map_err(|_| Error::UserNotFoundError { user_id })?;
     
-    // Calculate storage bytes (sum of document content lengths) - with precise character counting
-    let storage_bytes = sqlx::query!(
-        r#"SELECT COALESCE(SUM(LENGTH(COALESCE(d.content, ''))), 0) as total_bytes
-           FROM documents d
-           JOIN document_permissions dp ON d.id = dp.document_id
-           WHERE dp.user_id = $1 AND dp.role = 'owner'"#,
-        user_id
-    )
-    .fetch_one(&pool)
-    .await
-    .map_err(|_| Error::DatabaseError)?;
+    // Calculate storage bytes (sum of document content lengths plus revision history) - with precise character counting
+    let storage_bytes = StorageManager::get_user_storage_used(&pool, user_id)
+        .await
+        .map_err(|_| Error::DatabaseError)?;
 
-    // Get dynamic storage limits
-    let max_projects = 3; // Project limit remains fixed
-    let max_documents = 10; // Document limit remains fixed
-    let max_storage_bytes = StorageManager::get_user_quota();
+    // Get the user's limits, set per user by admins
+    let limits = StorageManager::get_user_limits(&pool, user_id)
+        .await
+        .map_err(|_| Error::UserNotFoundError { user_id })?;
+    let (max_projects, max_documents, max_storage_bytes) =
+        (limits.max_projects, limits.max_documents, limits.max_storage_bytes);
     
     // Get overall database statistics
     let db_size = StorageManager::get_db_size(&pool).await.unwrap_or(0);
@@ -812,14 +834,14 @@ pub async fn api_get_user_storage(
     let db_usage_percentage = StorageManager::get_db_usage_percentage(&pool).await.unwrap_or(0.0);
     
     // Calculate ultra-precise percentages
-    let storage_percentage = (storage_bytes.total_bytes.unwrap_or(0) as f64 / max_storage_bytes as f64) * 100.0;
+    let storage_percentage = (storage_bytes as f64 / max_storage_bytes as f64) * 100.0;
     let projects_percentage = (project_count.count.unwrap_or(0) as f64 / max_projects as f64) * 100.0;
     let documents_percentage = (document_count.count.unwrap_or(0) as f64 / max_documents as f64) * 100.0;
     
     // Return the storage usage information with detailed byte-level precision
     Ok(Json(json!({
         // Raw byte counts for maximum precision
-        "storage_bytes": storage_bytes.total_bytes,
+        "storage_bytes": storage_bytes,
         "max_storage_bytes": max_storage_bytes,
         
         // Database overview
@@ -832,10 +854,10 @@ pub async fn api_get_user_storage(
         
         // Formatted values for different units
         "storage_bytes_formatted": {
-            "bytes": storage_bytes.total_bytes.unwrap_or(0),
-            "kb": format!("{:.10}", storage_bytes.total_bytes.unwrap_or(0) as f64 / 1024.0),
-            "mb": format!("{:.10}", storage_bytes.total_bytes.unwrap_or(0) as f64 / (1024.0 * 1024.0)),
-            "gb": format!("{:.10}", storage_bytes.total_bytes.unwrap_or(0) as f64 / (1024.0 * 1024.0 * 1024.0))
+            "bytes": storage_bytes,
+            "kb": format!("{:.10}", storage_bytes as f64 / 1024.0),
+            "mb": format!("{:.10}", storage_bytes as f64 / (1024.0 * 1024.0)),
+            "gb": format!("{:.10}", storage_bytes as f64 / (1024.0 * 1024.0 * 1024.0))
         },
         
         // Counts and limits
@@ -851,6 +873,161 @@ pub async fn api_get_user_storage(
     })))
 }
 
+/// GET handler for listing the current user's active sessions.
+/// Accessible via: GET /api/users/sessions
+/// Test: test_users.rs/test_get_sessions()
+/// Frontend: // TODO: No frontend function implemented yet
+pub async fn api_get_sessions(
+    auth_user: AuthUser,
+    Extension(pool): Extension<PgPool>,
+) -> Result<Json<Vec<SessionInfo>>> {
+    println!("->> {:<12} - get_sessions", "HANDLER");
+
+    let sessions = list_sessions(&pool, auth_user.user_id)
+        .await?
+        .into_iter()
+        .map(|session| SessionInfo {
+            is_current: session.id == auth_user.session_id,
+            id: session.id,
+            created_at: session.created_at,
+            last_seen: session.last_seen,
+            expires_at: session.expires_at,
+            user_agent: session.user_agent,
+            ip_address: session.ip_address,
+        })
+        .collect();
+
+    Ok(Json(sessions))
+}
+
+/// DELETE handler for revoking one of the current user's sessions.
+/// Accessible via: DELETE /api/users/sessions/:id
+/// Test: test_users.rs/test_revoke_session()
+/// Frontend: // TODO: No frontend function implemented yet
+pub async fn api_revoke_session(
+    auth_user: AuthUser,
+    Path(session_id): Path<Uuid>,
+    Extension(pool): Extension<PgPool>,
+) -> Result<Json<Value>> {
+    println!("->> {:<12} - revoke_session", "HANDLER");
+
+    revoke_session(&pool, auth_user.user_id, session_id).await?;
+
+    Ok(Json(json!({
+        "result": {
+            "success": true,
+            "message": "Session revoked"
+        }
+    })))
+}
+
+/// DELETE handler for revoking every session of the current user, including this one.
+/// Accessible via: DELETE /api/users/sessions
+/// Test: test_users.rs/test_revoke_all_sessions()
+/// Frontend: // TODO: No frontend function implemented yet
+pub async fn api_revoke_all_sessions(
+    auth_user: AuthUser,
+    cookies: Cookies,
+    Extension(pool): Extension<PgPool>,
+) -> Result<Json<Value>> {
+    println!("->> {:<12} - revoke_all_sessions", "HANDLER");
+
+    let revoked = revoke_all_sessions(&pool, auth_user.user_id).await?;
+
+    // This browser's session is gone too, so drop its cookie
+    cookies.remove(Cookie::build(AUTH_COOKIE_NAME, "").path("/").finish());
+
+    Ok(Json(json!({
+        "result": {
+            "success": true,
+            "revoked": revoked
+        }
+    })))
+}
+
+// Proxies allowed to report the client address in x-forwarded-for, from TRUSTED_PROXIES (comma separated IPs)
+static TRUSTED_PROXIES: OnceLock<Vec<IpAddr>> = OnceLock::new();
+
+fn trusted_proxies() -> &'static [IpAddr] {
+    TRUSTED_PROXIES.get_or_init(|| {
+        env::var("TRUSTED_PROXIES")
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|ip| ip.trim().parse().ok())
+            .collect()
+    })
+}
+
+// Get the client address. The proxy header is only believed when the request came from a trusted proxy,
+// otherwise anyone could set it. Entries are read from the right, skipping the proxies themselves.
+fn client_ip(headers: &HeaderMap, addr: &SocketAddr) -> String {
+    let proxies = trusted_proxies();
+    if !proxies.contains(&addr.ip()) {
+        return addr.ip().to_string();
+    }
+
+    headers
+        .get("x-forwarded-for")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| {
+            v.rsplit(',')
+                .filter_map(|ip| ip.trim().parse::<IpAddr>().ok())
+                .find(|ip| !proxies.contains(ip))
+        })
+        .unwrap_or_else(|| addr.ip())
+        .to_string()
+}
+
+/// Entries of the AI usage history returned when no limit is given, and the most that can be asked for
+const DEFAULT_USAGE_PAGE: i64 = 50;
+const MAX_USAGE_PAGE: i64 = 200;
+
+/// GET handler for the current user's AI usage: their plan, credits, tokens used this month and
+/// the latest operations with the tokens and credits each one took, newest first.
+/// Accessible via: GET /api/users/ai-usage?limit=&before=
+/// Test: test_users.rs/test_get_ai_usage()
+/// Frontend: user.ts/get_ai_usage()
+pub async fn api_get_ai_usage(
+    auth_user: AuthUser,
+    Extension(pool): Extension<PgPool>,
+    Query(params): Query<AiUsageQuery>,
+) -> Result<Json<AiUsageSummary>> {
+    println!("->> {:<12} - get_ai_usage", "HANDLER");
+
+    let limit = params.limit.unwrap_or(DEFAULT_USAGE_PAGE).clamp(1, MAX_USAGE_PAGE);
+    let summary = usage_summary(&pool, auth_user.user_id, params.before, limit).await?;
+
+    Ok(Json(summary))
+}
+
+/// GET handler for the available plans, cheapest first. Public so they can be shown before signing up.
+/// Accessible via: GET /api/users/plans
+/// Test: test_users.rs/test_get_ai_usage()
+/// Frontend: user.ts/get_plans()
+pub async fn api_get_plans(
+    Extension(pool): Extension<PgPool>,
+) -> Result<Json<Vec<Plan>>> {
+    println!("->> {:<12} - get_plans", "HANDLER");
+
+    Ok(Json(list_plans(&pool).await?))
+}
+
+/// PUT handler for moving the current user to another free plan. The new token budget applies right away,
+/// the balance is topped up to the new plan's credits at the next refill. Unknown plans are refused with 400,
+/// paid plans with 403, those are set by an admin through PUT /api/admin/users/:id/limits.
+/// Accessible via: PUT /api/users/plan
+/// Test: test_users.rs/test_select_plan()
+/// Frontend: user.ts/select_plan()
+pub async fn api_select_plan(
+    auth_user: AuthUser,
+    Extension(pool): Extension<PgPool>,
+    Json(payload): Json<SelectPlanPayload>,
+) -> Result<Json<Plan>> {
+    println!("->> {:<12} - select_plan", "HANDLER");
+
+    Ok(Json(set_user_plan(&pool, auth_user.user_id, &payload.plan).await?))
+}
+
 // Combine user-related routes into one Router instance.
 pub fn user_routes() -> Router {
     Router::new()
@@ -864,7 +1041,13 @@ pub fn user_routes() -> Router {
         .route("/profile-image", post(api_upload_profile_image))
         .route("/current", get(api_get_current_user))
         .route("/storage", get(api_get_storage_usage))
+        .route("/ai-usage", get(api_get_ai_usage))
+        .route("/plans", get(api_get_plans))
+        .route("/plan", put(api_select_plan))
         .route("/user-storage", get(api_get_user_storage))
         .route("/search", get(api_search_users))
         .route("/update", put(api_update_user))
+        .route("/sessions", get(api_get_sessions))
+        .route("/sessions", delete(api_revoke_all_sessions))
+        .route("/sessions/:id", delete(api_revoke_session))
 }
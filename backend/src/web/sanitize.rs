@@ -0,0 +1,135 @@
+// src/web/sanitize.rs
+//
+// HTML sanitizer for content served to anonymous readers.
+// Only an allow-list of formatting tags survives, every attribute is dropped except safe link targets,
+// and the contents of script-like elements are removed entirely. Anything else is escaped as text.
+
+use regex::Regex;
+use std::sync::OnceLock;
+
+/// Tags kept as-is, without attributes
+const ALLOWED_TAGS: &[&str] = &[
+    "p", "br", "hr", "h1", "h2", "h3", "h4", "h5", "h6", "strong", "b", "em", "i", "u", "s", "strike",
+    "sub", "sup", "mark", "blockquote", "pre", "code", "ul", "ol", "li", "span", "div", "a", "table",
+    "thead", "tbody", "tr", "th", "td",
+];
+
+/// Tags that never get a closing tag
+const VOID_TAGS: &[&str] = &["br", "hr"];
+
+/// Tags whose whole content is dropped along with them
+const DROPPED_TAGS: &[&str] = &[
+    "script", "style", "iframe", "object", "embed", "noscript", "template", "textarea", "svg", "math",
+];
+
+fn comment_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?s)<!--.*?(-->|$)").expect("valid comment regex"))
+}
+
+fn tag_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"<(/?)([a-zA-Z][a-zA-Z0-9]*)([^>]*)>").expect("valid tag regex"))
+}
+
+fn href_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r#"(?i)\bhref\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'>]+))"#).expect("valid href regex")
+    })
+}
+
+/// Escape text for use in HTML content or a quoted attribute.
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Text between tags is already HTML from the editor, only stray angle brackets need escaping
+fn escape_text(text: &str) -> String {
+    text.replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// Only web, mail and same-site links, so a link can never run script
+fn safe_href(attributes: &str) -> Option<String> {
+    let captures = href_regex().captures(attributes)?;
+    let raw = captures.get(1).or(captures.get(2)).or(captures.get(3))?.as_str();
+
+    // Browsers ignore whitespace and control characters inside the scheme
+    let href: String = raw
+        .replace("&amp;", "&")
+        .chars()
+        .filter(|c| !c.is_whitespace() && !c.is_control())
+        .collect();
+    let lower = href.to_lowercase();
+
+    let allowed = lower.starts_with("http://")
+        || lower.starts_with("https://")
+        || lower.starts_with("mailto:")
+        || lower.starts_with('#')
+        || (lower.starts_with('/') && !lower.starts_with("//"));
+
+    allowed.then(|| escape_html(&href))
+}
+
+/// Reduce document HTML to safe formatting.
+pub fn sanitize_html(html: &str) -> String {
+    let html = comment_regex().replace_all(html, "");
+    let mut output = String::with_capacity(html.len());
+    let mut last = 0;
+    let mut dropping: Option<String> = None; // Inside a dropped element, waiting for its closing tag
+
+    for captures in tag_regex().captures_iter(&html) {
+        let tag = captures.get(0).expect("whole match");
+        let is_closing = !captures[1].is_empty();
+        let name = captures[2].to_lowercase();
+        let attributes = &captures[3];
+
+        if let Some(dropped) = &dropping {
+            if is_closing && *dropped == name {
+                dropping = None;
+            }
+            last = tag.end();
+            continue;
+        }
+
+        output.push_str(&escape_text(&html[last..tag.start()]));
+        last = tag.end();
+
+        if DROPPED_TAGS.contains(&name.as_str()) {
+            if !is_closing && !attributes.trim_end().ends_with('/') {
+                dropping = Some(name);
+            }
+            continue;
+        }
+
+        if !ALLOWED_TAGS.contains(&name.as_str()) {
+            continue;
+        }
+
+        if is_closing {
+            if !VOID_TAGS.contains(&name.as_str()) {
+                output.push_str(&format!("</{}>", name));
+            }
+        } else if name == "a" {
+            match safe_href(attributes) {
+                Some(href) => output.push_str(&format!(
+                    "<a href=\"{}\" rel=\"noopener noreferrer nofollow\" target=\"_blank\">",
+                    href
+                )),
+                None => output.push_str("<a>"),
+            }
+        } else {
+            output.push_str(&format!("<{}>", name));
+        }
+    }
+
+    if dropping.is_none() {
+        output.push_str(&escape_text(&html[last..]));
+    }
+
+    output
+}
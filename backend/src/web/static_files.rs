@@ -0,0 +1,59 @@
+// src/web/static_files.rs
+//
+// Serves the built frontend for every request no API route handled.
+// Files come from STATIC_DIR (default ../frontend/build), client side routes get its index.html,
+// unknown /api paths get a JSON 404 and dotfiles are never served.
+
+use axum::body::{boxed, Body};
+use axum::response::{IntoResponse, Response};
+use http::Request;
+use std::env;
+use std::path::PathBuf;
+use tower_http::services::{ServeDir, ServeFile};
+
+use crate::{Error, Result};
+
+const DEFAULT_STATIC_DIR: &str = "../frontend/build";
+
+fn static_root() -> PathBuf {
+    PathBuf::from(env::var("STATIC_DIR").unwrap_or_else(|_| DEFAULT_STATIC_DIR.to_string()))
+}
+
+/// Fallback handler for any request that did not match a route
+pub async fn static_fallback(request: Request<Body>) -> Result<Response> {
+    let path = request.uri().path().to_string();
+    println!("->> {:<12} - static_fallback {}", "FALLBACK HIT", path);
+
+    // A missing API route is an API error, never the frontend's index page
+    if path == "/api" || path.starts_with("/api/") {
+        return Err(Error::RouteNotFoundError { path });
+    }
+
+    // Covers .env, .git and anything else hidden that ends up next to the build.
+    // ServeDir decodes the path itself, so an encoded dot counts as a dot.
+    let decoded_dots = path.replace("%2e", ".").replace("%2E", ".");
+    if decoded_dots.split('/').any(|segment| segment.starts_with('.')) {
+        return Err(Error::RouteNotFoundError { path });
+    }
+
+    let root = static_root();
+    let last_segment = path.rsplit('/').next().unwrap_or_default();
+
+    // Paths that look like files are assets and 404 when missing, anything else is a client route
+    let response = if last_segment.contains('.') {
+        ServeDir::new(&root).try_call(request).await
+    } else {
+        ServeDir::new(&root)
+            .fallback(ServeFile::new(root.join("index.html")))
+            .try_call(request)
+            .await
+    };
+
+    match response {
+        Ok(response) => Ok(response.map(boxed)),
+        Err(e) => {
+            println!("->> {:<12} - could not serve {}: {:?}", "FALLBACK HIT", path, e);
+            Ok(http::StatusCode::INTERNAL_SERVER_ERROR.into_response())
+        }
+    }
+}
@@ -3,4 +3,8 @@ mod test_documents;
 mod test_environment;
 mod test_projects;
 mod test_keybindings;
-mod test_ai;
\ No newline at end of file
+mod test_ai;
+mod test_admin;
+mod test_text;
+mod test_providers;
+mod test_diff;
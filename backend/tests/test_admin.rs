@@ -0,0 +1,252 @@
+#![allow(unused)]
+
+use anyhow::{anyhow, Result};
+use backend::result_to_string;
+use httpc_test::Client;
+use serde_json::json;
+
+#[tokio::test]
+async fn test_admin() -> Result<()> {
+    let hc = httpc_test::new_client("http://localhost:3001")?;
+
+    println!("\n===== RUNNING ADMIN API TESTS =====\n");
+
+    // User 1 is an admin in the fixtures, user 2 is not
+    let admin_login_result = login(&hc, "CFdefence@gmail.com", "MyPassword").await;
+    let other = httpc_test::new_client("http://localhost:3001")?;
+    let other_login_result = login(&other, "MarkoP@gmail.com", "MarkosPassword").await;
+
+    // Run all tests and collect results
+    let non_admin_result = test_non_admin_rejected(&other).await;
+    let list_users_result = test_list_users(&hc).await;
+    let adjust_credits_result = test_adjust_credits(&hc).await;
+    let update_limits_result = test_update_limits(&hc, &other).await;
+    let disable_user_result = test_disable_user(&hc, &other).await;
+    let get_storage_result = test_get_storage(&hc).await;
+    let audit_log_result = test_get_audit_log(&hc).await;
+    let reset_db_result = backend::test_reset_db(&hc).await;
+
+    // Print summary
+    println!("\n==== TEST RESULTS ====");
+    println!("Login as Admin:\t\t{}", result_to_string(&admin_login_result));
+    println!("Login as User 2:\t{}", result_to_string(&other_login_result));
+    println!("Non Admin Rejected:\t{}", result_to_string(&non_admin_result));
+    println!("List Users:\t\t{}", result_to_string(&list_users_result));
+    println!("Adjust Credits:\t\t{}", result_to_string(&adjust_credits_result));
+    println!("Update Limits:\t\t{}", result_to_string(&update_limits_result));
+    println!("Disable User:\t\t{}", result_to_string(&disable_user_result));
+    println!("Get Storage:\t\t{}", result_to_string(&get_storage_result));
+    println!("Audit Log:\t\t{}", result_to_string(&audit_log_result));
+    println!("Reset Database:\t\t{}", result_to_string(&reset_db_result));
+    println!("======================\n");
+
+    Ok(())
+}
+
+async fn login(hc: &Client, email: &str, password: &str) -> Result<()> {
+    println!("TEST - Login as {}", email);
+    let response = hc
+        .do_post("/api/users/login", json!({ "email": email, "password": password }))
+        .await?;
+    response.print().await?;
+
+    if !response.status().is_success() {
+        return Err(anyhow!("Login failed with status: {}", response.status()));
+    }
+
+    Ok(())
+}
+
+async fn test_non_admin_rejected(other: &Client) -> Result<()> {
+    println!("TEST - Non Admin Rejected");
+
+    let response = other.do_get("/api/admin/users").await?;
+    response.print().await?;
+
+    if response.status().as_u16() != 403 {
+        return Err(anyhow!("Expected 403 for a non admin, got: {}", response.status()));
+    }
+
+    Ok(())
+}
+
+async fn test_list_users(hc: &Client) -> Result<()> {
+    println!("TEST - List Users");
+
+    let response = hc.do_get("/api/admin/users?q=markop").await?;
+    response.print().await?;
+
+    if !response.status().is_success() {
+        return Err(anyhow!("List users failed with status: {}", response.status()));
+    }
+
+    let users = response.json_body()?;
+    let users = users.as_array().ok_or(anyhow!("Users are not a list"))?;
+    if users.len() != 1 || users[0]["email"] != "MarkoP@gmail.com" || users[0]["max_documents"] != 10 {
+        return Err(anyhow!("Search did not return user 2 with the default limits"));
+    }
+
+    Ok(())
+}
+
+async fn test_adjust_credits(hc: &Client) -> Result<()> {
+    println!("TEST - Adjust Credits");
+
+    let before = hc.do_get("/api/admin/users/2").await?.json_body()?["ai_credits"]
+        .as_i64()
+        .ok_or(anyhow!("User has no ai_credits"))?;
+
+    let response = hc
+        .do_post("/api/admin/users/2/credits", json!({ "delta": 5, "reason": "Support refund" }))
+        .await?;
+    response.print().await?;
+
+    if !response.status().is_success() || response.json_body()?["ai_credits"] != before + 5 {
+        return Err(anyhow!("Adjust credits failed with status: {}", response.status()));
+    }
+
+    // The balance cannot go below zero
+    let response = hc
+        .do_post("/api/admin/users/2/credits", json!({ "delta": -(before + 6) }))
+        .await?;
+
+    if response.status().as_u16() != 400 {
+        return Err(anyhow!("Expected 400 for a negative balance, got: {}", response.status()));
+    }
+
+    Ok(())
+}
+
+async fn test_update_limits(hc: &Client, other: &Client) -> Result<()> {
+    println!("TEST - Update Limits");
+
+    let response = hc
+        .do_put(
+            "/api/admin/users/2/limits",
+            json!({ "plan": "pro", "max_documents": 0, "storage_quota_bytes": 1048576 }),
+        )
+        .await?;
+    response.print().await?;
+
+    let user = response.json_body()?;
+    if !response.status().is_success()
+        || user["plan"] != "pro"
+        || user["max_documents"] != 0
+        || user["max_projects"] != 3
+        || user["storage_quota_bytes"] != 1048576
+    {
+        return Err(anyhow!("Update limits failed with status: {}", response.status()));
+    }
+
+    // The new document limit applies right away
+    let create_response = other
+        .do_post("/api/document", json!({ "name": "Over The Limit", "content": "Should not be created" }))
+        .await?;
+
+    if create_response.status().is_success() {
+        return Err(anyhow!("Document was created past the user's document limit"));
+    }
+
+    // null goes back to the default quota, the other limits are kept
+    let response = hc
+        .do_put("/api/admin/users/2/limits", json!({ "storage_quota_bytes": null }))
+        .await?;
+    let user = response.json_body()?;
+    if !user["storage_quota_bytes"].is_null() || user["max_documents"] != 0 {
+        return Err(anyhow!("Resetting the storage quota changed the wrong limits"));
+    }
+
+    // Plans that do not exist are refused
+    let response = hc
+        .do_put("/api/admin/users/2/limits", json!({ "plan": "no-such-plan" }))
+        .await?;
+    if response.status().as_u16() != 400 {
+        return Err(anyhow!("Expected 400 for an unknown plan, got: {}", response.status()));
+    }
+
+    Ok(())
+}
+
+async fn test_disable_user(hc: &Client, other: &Client) -> Result<()> {
+    println!("TEST - Disable User");
+
+    let response = hc
+        .do_post("/api/admin/users/2/disable", json!({ "reason": "Abuse report" }))
+        .await?;
+    response.print().await?;
+
+    if !response.status().is_success() || response.json_body()?["disabled_at"].is_null() {
+        return Err(anyhow!("Disable user failed with status: {}", response.status()));
+    }
+
+    // The open session stops working and the user cannot sign in again
+    let current_response = other.do_get("/api/users/current").await?;
+    if current_response.status().as_u16() != 401 {
+        return Err(anyhow!("Expected 401 for a disabled user's session, got: {}", current_response.status()));
+    }
+
+    let login_response = other
+        .do_post("/api/users/login", json!({ "email": "MarkoP@gmail.com", "password": "MarkosPassword" }))
+        .await?;
+    if login_response.status().as_u16() != 403 {
+        return Err(anyhow!("Expected 403 for a disabled user's login, got: {}", login_response.status()));
+    }
+
+    // Admins cannot lock themselves out
+    let self_response = hc.do_post("/api/admin/users/1/disable", json!({})).await?;
+    if self_response.status().as_u16() != 400 {
+        return Err(anyhow!("Expected 400 when disabling yourself, got: {}", self_response.status()));
+    }
+
+    let enable_response = hc.do_post("/api/admin/users/2/enable", json!({})).await?;
+    if !enable_response.status().is_success() || !enable_response.json_body()?["disabled_at"].is_null() {
+        return Err(anyhow!("Enable user failed with status: {}", enable_response.status()));
+    }
+
+    login(other, "MarkoP@gmail.com", "MarkosPassword").await
+}
+
+async fn test_get_storage(hc: &Client) -> Result<()> {
+    println!("TEST - Get Storage");
+
+    let response = hc.do_get("/api/admin/storage").await?;
+    response.print().await?;
+
+    if !response.status().is_success() {
+        return Err(anyhow!("Get storage failed with status: {}", response.status()));
+    }
+
+    let storage = response.json_body()?;
+    if !storage["database"]["used_bytes"].is_i64() || storage["users"]["admins"] != 1 {
+        return Err(anyhow!("Storage overview is missing the database size or user counts"));
+    }
+
+    Ok(())
+}
+
+async fn test_get_audit_log(hc: &Client) -> Result<()> {
+    println!("TEST - Get Audit Log");
+
+    let response = hc.do_get("/api/admin/audit?target_user_id=2").await?;
+    response.print().await?;
+
+    if !response.status().is_success() {
+        return Err(anyhow!("Get audit log failed with status: {}", response.status()));
+    }
+
+    // Newest first, refused changes are not recorded
+    let actions: Vec<String> = response
+        .json_body()?
+        .as_array()
+        .ok_or(anyhow!("Audit log is not a list"))?
+        .iter()
+        .filter_map(|entry| entry["action"].as_str().map(str::to_string))
+        .collect();
+
+    let expected = ["enable_user", "disable_user", "update_limits", "update_limits", "adjust_credits"];
+    if actions != expected {
+        return Err(anyhow!("Unexpected audit log: {:?}", actions));
+    }
+
+    Ok(())
+}
@@ -18,6 +18,7 @@ async fn test_writing_assistant() -> Result<()> {
     let get_all_sessions = test_get_all_writing_sessions_success(&hc).await;
     let get_session = test_get_writing_session_success(&hc).await;
     let send_message = test_send_writing_message_success(&hc).await;
+    let stream_message = test_stream_writing_message_success(&hc).await;
     let check_grammar = test_check_grammar_success(&hc).await;
     let spell_check = test_spell_check_success(&hc).await;
     let summarize = test_summarize_success(&hc).await;
@@ -27,9 +28,15 @@ async fn test_writing_assistant() -> Result<()> {
     let rewrite = test_rewrite_success(&hc).await;
     let fact_check = test_fact_check_success(&hc).await;
     let apply_suggestion = test_apply_suggestion_success(&hc).await;
+    let apply_conflict = test_apply_suggestion_conflict(&hc).await;
+    let credit_refund = test_credits_refunded_on_failure(&hc).await;
     let decide_proactive = test_decide_proactive_diff_success(&hc).await;
     let sanitize_text = test_sanitize_text_success(&hc).await;
     let delete_session = test_delete_writing_session_success(&hc).await;
+    let search_isolation = test_semantic_search_user_isolation(&hc).await;
+    let session_isolation = test_linked_session_user_isolation(&hc).await;
+    let tokens_charged = test_tokens_charged(&hc).await;
+    let token_budget = test_token_budget_exceeded(&hc).await;
     let reset_db = backend::test_reset_db(&hc).await;
 
     // Print summary
@@ -39,6 +46,7 @@ async fn test_writing_assistant() -> Result<()> {
     println!("Get All Sessions\t\t{}", result_to_string(&get_all_sessions));
     println!("Get Session\t\t{}", result_to_string(&get_session));
     println!("Send Message\t\t{}", result_to_string(&send_message));
+    println!("Stream Message\t\t{}", result_to_string(&stream_message));
     println!("Check Grammar\t\t{}", result_to_string(&check_grammar));
     println!("Spell Check\t\t{}", result_to_string(&spell_check));
     println!("Summarize\t\t{}", result_to_string(&summarize));
@@ -48,9 +56,15 @@ async fn test_writing_assistant() -> Result<()> {
     println!("Rewrite\t\t\t{}", result_to_string(&rewrite));
     println!("Fact Check\t\t{}", result_to_string(&fact_check));
     println!("Apply Suggestion\t\t{}", result_to_string(&apply_suggestion));
+    println!("Apply Conflict\t\t{}", result_to_string(&apply_conflict));
+    println!("Credit Refund\t\t{}", result_to_string(&credit_refund));
     println!("Decide Proactive\t\t{}", result_to_string(&decide_proactive));
     println!("Sanitize Text\t\t{}", result_to_string(&sanitize_text));
     println!("Delete Session\t\t{}", result_to_string(&delete_session));
+    println!("Search Isolation\t\t{}", result_to_string(&search_isolation));
+    println!("Session Isolation\t\t{}", result_to_string(&session_isolation));
+    println!("Tokens Charged\t\t{}", result_to_string(&tokens_charged));
+    println!("Token Budget\t\t{}", result_to_string(&token_budget));
     println!("Reset Database\t\t{}", result_to_string(&reset_db));
     println!("==============================\n");
 
@@ -161,6 +175,35 @@ async fn test_send_writing_message_success(hc: &Client) -> Result<()> {
     Ok(())
 }
 
+async fn test_stream_writing_message_success(hc: &Client) -> Result<()> {
+    println!("TEST - Stream Writing Message");
+
+    let response = hc
+        .do_post(
+            "/api/writing-assistant/1/message/stream",
+            json!({
+                "content": "Can you suggest a title for my essay?"
+            }),
+        )
+        .await?;
+
+    if !response.status().is_success() {
+        return Err(anyhow::anyhow!(
+            "Stream writing message failed with status: {}",
+            response.status()
+        ));
+    }
+
+    // The stream should contain tokens followed by a final done event
+    let body = response.text_body()?;
+    println!("{}", body);
+    if !body.contains("event:token") || !body.contains("event:done") {
+        return Err(anyhow::anyhow!("Stream did not contain token and done events"));
+    }
+
+    Ok(())
+}
+
 async fn test_check_grammar_success(hc: &Client) -> Result<()> {
     println!("TEST - Check Grammar");
 
@@ -349,12 +392,15 @@ async fn test_fact_check_success(hc: &Client) -> Result<()> {
 async fn test_apply_suggestion_success(hc: &Client) -> Result<()> {
     println!("TEST - Apply Suggestion");
 
+    let session_id = create_linked_session(hc, 3).await?;
+
+    // "roject" is in both "Project" and "project" in document 3, but only once in documents 5 and 6
     let response = hc
         .do_post(
-            "/api/writing-assistant/1/apply-suggestion",
+            &format!("/api/writing-assistant/{}/apply-suggestion", session_id),
             json!({
-                "suggestion_content": "Fix the grammar in this document.",
-                "current_document_id": 1
+                "suggestion_content": "Replace \"roject\" with \"rogramme\" everywhere.",
+                "current_document_id": 3
             }),
         )
         .await?;
@@ -367,6 +413,138 @@ async fn test_apply_suggestion_success(hc: &Client) -> Result<()> {
         ));
     }
 
+    let body = response.json_body()?;
+    let changes = body["changes"].as_array().ok_or(anyhow!("Changes are not a list"))?;
+    let mut changed: Vec<i64> = changes.iter().filter_map(|change| change["document_id"].as_i64()).collect();
+    changed.sort();
+    if changed != [5, 6] {
+        return Err(anyhow!("Expected changes to documents 5 and 6, got {:?}", changed));
+    }
+
+    // Each change is stored as a suggestion, only the quoted text is replaced
+    for change in changes {
+        if !change["suggestion_id"].is_i64() || change["hunks"].as_array().is_none_or(Vec::is_empty) {
+            return Err(anyhow!("Change was not stored as a suggestion: {}", change));
+        }
+        let new_content = change["new_content"].as_str().unwrap_or_default();
+        if !new_content.contains("programme") || new_content.contains("project") {
+            return Err(anyhow!("Unexpected new content: {:?}", new_content));
+        }
+    }
+
+    // Document 3 is reported as rejected with the reason
+    let rejected = body["rejected"].as_array().ok_or(anyhow!("Rejected documents are not a list"))?;
+    if rejected.len() != 1
+        || rejected[0]["document_id"] != 3
+        || !rejected[0]["reason"].as_str().unwrap_or_default().contains("more than once")
+    {
+        return Err(anyhow!("Document 3 was not reported as rejected: {:?}", rejected));
+    }
+
+    Ok(())
+}
+
+async fn test_apply_suggestion_conflict(hc: &Client) -> Result<()> {
+    println!("TEST - Apply Suggestion Conflict");
+
+    // Overlapping matches are ambiguous too, "aa" occurs twice in "aaa"
+    let version = hc.do_get("/api/document/1").await?.json_body()?["version"]
+        .as_i64()
+        .ok_or(anyhow!("Document has no version"))?;
+    let updated = hc
+        .do_put(
+            "/api/document/1",
+            json!({
+                "name": "Test Document 1",
+                "content": "A baaad example.",
+                "updated_at": Utc::now().naive_utc(),
+                "version": version
+            }),
+        )
+        .await?;
+    if !updated.status().is_success() {
+        return Err(anyhow!("Update document failed with status: {}", updated.status()));
+    }
+
+    let session_id = create_linked_session(hc, 1).await?;
+    let response = hc
+        .do_post(
+            &format!("/api/writing-assistant/{}/apply-suggestion", session_id),
+            json!({
+                "suggestion_content": "Replace \"aa\" with \"a\".",
+                "current_document_id": 1
+            }),
+        )
+        .await?;
+    response.print().await?;
+
+    // No document could be changed
+    if response.status().as_u16() != 409 {
+        return Err(anyhow!("Expected 409 when every edit is rejected, got: {}", response.status()));
+    }
+
+    Ok(())
+}
+
+// Writing session on a document, apply suggestion works on that document's project
+async fn create_linked_session(hc: &Client, document_id: i32) -> Result<i64> {
+    let response = hc
+        .do_post(
+            "/api/writing-assistant",
+            json!({ "title": "Apply Suggestion Session", "document_id": document_id }),
+        )
+        .await?;
+    if !response.status().is_success() {
+        return Err(anyhow!("Create writing session failed with status: {}", response.status()));
+    }
+
+    response.json_body()?["id"].as_i64().ok_or(anyhow!("Session has no id"))
+}
+
+async fn current_ai_credits(hc: &Client) -> Result<i64> {
+    let response = hc.do_get("/api/users/current").await?;
+    response.json_body()?["ai_credits"].as_i64().ok_or(anyhow!("Missing ai_credits"))
+}
+
+async fn test_credits_refunded_on_failure(hc: &Client) -> Result<()> {
+    println!("TEST - Credits Refunded On Failure");
+
+    let before = current_ai_credits(hc).await?;
+
+    // The session does not exist, so the operation fails after the credit was reserved
+    let response = hc
+        .do_post(
+            "/api/writing-assistant/999999/apply-suggestion",
+            json!({
+                "suggestion_content": "Fix the grammar in this document.",
+                "current_document_id": 1
+            }),
+        )
+        .await?;
+    response.print().await?;
+
+    if response.status().is_success() {
+        return Err(anyhow!("Apply suggestion on a missing session should fail"));
+    }
+
+    let after_failure = current_ai_credits(hc).await?;
+    if after_failure != before {
+        return Err(anyhow!("Failed operation charged credits: {} -> {}", before, after_failure));
+    }
+
+    // A successful operation is still charged
+    let response = hc
+        .do_post("/api/writing-assistant/summarize", json!({ "content": "A short text to summarize." }))
+        .await?;
+    if !response.status().is_success() {
+        return Err(anyhow!("Summarize failed with status: {}", response.status()));
+    }
+
+    let after_success = current_ai_credits(hc).await?;
+    if after_success != before - 1 {
+        return Err(anyhow!("Expected {} credits after a charge, got {}", before - 1, after_success));
+    }
+
     Ok(())
 }
 
@@ -396,7 +574,8 @@ async fn test_decide_proactive_diff_success(hc: &Client) -> Result<()> {
         ));
     }
 
-    Ok(())
+    // Charged and counted like every other operation
+    expect_metered(hc, "proactive_diff").await
 }
 
 async fn test_sanitize_text_success(hc: &Client) -> Result<()> {
@@ -419,7 +598,8 @@ async fn test_sanitize_text_success(hc: &Client) -> Result<()> {
         ));
     }
 
-    Ok(())
+    // Charged and counted like every other operation
+    expect_metered(hc, "sanitize_text").await
 }
 
 async fn test_delete_writing_session_success(hc: &Client) -> Result<()> {
@@ -436,4 +616,253 @@ async fn test_delete_writing_session_success(hc: &Client) -> Result<()> {
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+
+// Helper to send a message in a fresh session and return the document ids used as context
+async fn send_message_and_get_sources(hc: &Client, content: &str) -> Result<Vec<i64>> {
+    let session = hc
+        .do_post(
+            "/api/writing-assistant",
+            json!({
+                "title": "Search Isolation Session",
+                "document_id": null
+            }),
+        )
+        .await?;
+
+    if !session.status().is_success() {
+        return Err(anyhow!("Create session failed with status: {}", session.status()));
+    }
+    let session_id = session.json_body()?["id"].as_i64().ok_or(anyhow!("Missing session id"))?;
+
+    let response = hc
+        .do_post(
+            &format!("/api/writing-assistant/{}/message", session_id),
+            json!({ "content": content }),
+        )
+        .await?;
+    response.print().await?;
+
+    if !response.status().is_success() {
+        return Err(anyhow!("Send message failed with status: {}", response.status()));
+    }
+
+    let sources = response.json_body()?["sources"]
+        .as_array()
+        .map(|list| list.iter().filter_map(|s| s["document_id"].as_i64()).collect())
+        .unwrap_or_default();
+
+    Ok(sources)
+}
+
+async fn test_semantic_search_user_isolation(hc: &Client) -> Result<()> {
+    println!("TEST - Semantic Search User Isolation");
+    let question = "What is the secret ingredient in grandma's zucchini bread?";
+
+    // Log in as user 2 and create a private document
+    let login = hc
+        .do_post(
+            "/api/users/login",
+            json!({
+                "email": "MarkoP@gmail.com",
+                "password": "MarkosPassword"
+            }),
+        )
+        .await?;
+    if !login.status().is_success() {
+        return Err(anyhow!("Login as user 2 failed with status: {}", login.status()));
+    }
+
+    let now = Utc::now().naive_utc();
+    let created = hc
+        .do_post(
+            "/api/document",
+            json!({
+                "name": "Private Recipe",
+                "content": null,
+                "created_at": now,
+                "updated_at": now
+            }),
+        )
+        .await?;
+    if !created.status().is_success() {
+        return Err(anyhow!("Create document failed with status: {}", created.status()));
+    }
+    let private_doc_id = created.json_body()?["id"].as_i64().ok_or(anyhow!("Missing document id"))?;
+
+    // Updating the content generates the embedding
+    let version = created.json_body()?["version"].as_i64().ok_or(anyhow!("Missing document version"))?;
+    let updated = hc
+        .do_put(
+            &format!("/api/document/{}", private_doc_id),
+            json!({
+                "name": "Private Recipe",
+                "content": "<p>The secret ingredient in grandma's zucchini bread is cardamom.</p>",
+                "updated_at": now,
+                "version": version
+            }),
+        )
+        .await?;
+    if !updated.status().is_success() {
+        return Err(anyhow!("Update document failed with status: {}", updated.status()));
+    }
+
+    // The owner should get their own document back as context
+    let owner_sources = send_message_and_get_sources(hc, question).await?;
+    if !owner_sources.contains(&private_doc_id) {
+        return Err(anyhow!("Owner's document was not retrieved as context"));
+    }
+
+    // User 1 asks the same question and must never see user 2's document
+    test_good_login(hc).await?;
+    let other_sources = send_message_and_get_sources(hc, question).await?;
+    if other_sources.contains(&private_doc_id) {
+        return Err(anyhow!("Another user's private document leaked into the context"));
+    }
+
+    Ok(())
+}
+
+async fn test_linked_session_user_isolation(hc: &Client) -> Result<()> {
+    println!("TEST - Linked Session User Isolation");
+
+    // User 2 has no access to user 1's document 1
+    let login = hc
+        .do_post(
+            "/api/users/login",
+            json!({
+                "email": "MarkoP@gmail.com",
+                "password": "MarkosPassword"
+            }),
+        )
+        .await?;
+    if !login.status().is_success() {
+        return Err(anyhow!("Login as user 2 failed with status: {}", login.status()));
+    }
+
+    // Linking a session to it would pull its content into the document and project context
+    let response = hc
+        .do_post(
+            "/api/writing-assistant",
+            json!({ "title": "Someone Else's Document", "document_id": 1 }),
+        )
+        .await?;
+    response.print().await?;
+
+    let status = response.status().as_u16();
+    test_good_login(hc).await?;
+    if status != 403 {
+        return Err(anyhow!("Expected 403 when linking a session to another user's document, got: {}", status));
+    }
+
+    Ok(())
+}
+
+// Latest entry of the current user's AI usage history
+async fn latest_ai_usage(hc: &Client) -> Result<serde_json::Value> {
+    let usage = hc.do_get("/api/users/ai-usage?limit=1").await?.json_body()?;
+    Ok(usage["history"][0].clone())
+}
+
+// The latest operation was charged for the tokens it used
+async fn expect_metered(hc: &Client, operation: &str) -> Result<()> {
+    let entry = latest_ai_usage(hc).await?;
+    if entry["operation"] != operation || entry["status"] != "committed" || entry["prompt_tokens"].as_i64().unwrap_or_default() == 0 {
+        return Err(anyhow!("{} was not recorded in the ledger: {}", operation, entry));
+    }
+
+    Ok(())
+}
+
+async fn summarize(hc: &Client, content: &str) -> Result<httpc_test::Response> {
+    Ok(hc.do_post("/api/writing-assistant/summarize", json!({ "content": content })).await?)
+}
+
+async fn test_tokens_charged(hc: &Client) -> Result<()> {
+    println!("TEST - Tokens Charged");
+
+    // Long enough to cost several credits, the mock summary repeats the text
+    let text = "The quick brown fox jumps over the lazy dog. ".repeat(250);
+    let before = current_ai_credits(hc).await?;
+
+    let response = summarize(hc, &text).await?;
+    if !response.status().is_success() {
+        return Err(anyhow!("Summarize failed with status: {}", response.status()));
+    }
+
+    // The tokens are recorded and the credits follow from them
+    let entry = latest_ai_usage(hc).await?;
+    let prompt_tokens = entry["prompt_tokens"].as_i64().unwrap_or_default();
+    let completion_tokens = entry["completion_tokens"].as_i64().unwrap_or_default();
+    let amount = entry["amount"].as_i64().unwrap_or_default();
+    if entry["operation"] != "summarize" || entry["status"] != "committed" || prompt_tokens < 2500 || completion_tokens == 0 {
+        return Err(anyhow!("Tokens were not recorded: {}", entry));
+    }
+    if amount <= 1 || amount != (prompt_tokens + completion_tokens + 999) / 1000 {
+        return Err(anyhow!("Expected one credit per 1000 tokens, got {}", entry));
+    }
+
+    let after = current_ai_credits(hc).await?;
+    if after != before - amount {
+        return Err(anyhow!("Balance went from {} to {} for a charge of {}", before, after, amount));
+    }
+
+    // With too few credits left the balance stops at zero and the ledger shows what was taken
+    let adjusted = hc
+        .do_post("/api/admin/users/1/credits", json!({ "delta": 2 - after, "reason": "Token charge test" }))
+        .await?;
+    if !adjusted.status().is_success() {
+        return Err(anyhow!("Adjust credits failed with status: {}", adjusted.status()));
+    }
+
+    let response = summarize(hc, &text).await?;
+    if !response.status().is_success() {
+        return Err(anyhow!("Summarize failed with status: {}", response.status()));
+    }
+
+    let entry = latest_ai_usage(hc).await?;
+    let balance = current_ai_credits(hc).await?;
+    if entry["amount"] != 2 || balance != 0 {
+        return Err(anyhow!("Expected 2 credits recorded and none left, got {} with {} left", entry, balance));
+    }
+
+    hc.do_post("/api/admin/users/1/credits", json!({ "delta": before, "reason": "Token charge test" })).await?;
+
+    Ok(())
+}
+
+async fn test_token_budget_exceeded(hc: &Client) -> Result<()> {
+    println!("TEST - Token Budget Exceeded");
+
+    // About 220k tokens, more than the free plan's 200k a month
+    let text = "a".repeat(440_000);
+    let response = summarize(hc, &text).await?;
+    if !response.status().is_success() {
+        return Err(anyhow!("Summarize failed with status: {}", response.status()));
+    }
+
+    // The budget is used up, nothing is reserved or charged
+    let before = current_ai_credits(hc).await?;
+    let response = summarize(hc, "A short text to summarize.").await?;
+    response.print().await?;
+    if response.status().as_u16() != 402 || response.json_body()?["error"]["type"] != "TOKEN_BUDGET_EXCEEDED" {
+        return Err(anyhow!("Expected 402 TOKEN_BUDGET_EXCEEDED, got: {}", response.status()));
+    }
+    if current_ai_credits(hc).await? != before {
+        return Err(anyhow!("An operation over the token budget was charged"));
+    }
+
+    // A plan with a larger budget, set by an admin, lets the user continue
+    let response = hc.do_put("/api/admin/users/1/limits", json!({ "plan": "team" })).await?;
+    response.print().await?;
+    if !response.status().is_success() || response.json_body()?["plan"] != "team" {
+        return Err(anyhow!("Changing the plan failed with status: {}", response.status()));
+    }
+
+    let response = summarize(hc, "A short text to summarize.").await?;
+    if !response.status().is_success() {
+        return Err(anyhow!("Summarize on the new plan failed with status: {}", response.status()));
+    }
+
+    Ok(())
+}
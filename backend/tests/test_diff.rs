@@ -0,0 +1,152 @@
+#![allow(unused)]
+
+use anyhow::{anyhow, Result};
+use backend::diff::{apply_hunks, diff_hunks, find_unique, DiffHunk};
+use backend::result_to_string;
+
+#[test]
+fn test_diff() -> Result<()> {
+    println!("\n===== RUNNING DIFF TESTS =====\n");
+
+    // Run all tests and collect results
+    let plain_result = test_round_trip_plain();
+    let utf8_result = test_round_trip_utf8();
+    let html_result = test_round_trip_html();
+    let edited_result = test_apply_to_edited_content();
+    let ambiguous_result = test_ambiguous_matches();
+
+    // Print summary
+    println!("\n==== TEST RESULTS ====");
+    println!("Round Trip Plain:\t{}", result_to_string(&plain_result));
+    println!("Round Trip UTF-8:\t{}", result_to_string(&utf8_result));
+    println!("Round Trip HTML:\t{}", result_to_string(&html_result));
+    println!("Apply To Edited:\t{}", result_to_string(&edited_result));
+    println!("Ambiguous Matches:\t{}", result_to_string(&ambiguous_result));
+    println!("======================\n");
+
+    Ok(())
+}
+
+// Applying the hunks of old -> new to old must give new, and each hunk must describe both texts
+fn check_round_trip(old: &str, new: &str) -> Result<()> {
+    let hunks = diff_hunks(old, new);
+    println!("{:?} -> {:?}: {:?}", old, new, hunks);
+
+    let slice = |text: &str, start: usize, end: usize| -> String { text.chars().skip(start).take(end - start).collect() };
+    for hunk in &hunks {
+        if slice(old, hunk.old_start, hunk.old_end) != hunk.old_text
+            || slice(new, hunk.new_start, hunk.new_end) != hunk.new_text
+        {
+            return Err(anyhow!("Hunk offsets do not match its text: {:?}", hunk));
+        }
+    }
+
+    let applied = apply_hunks(old, old, &hunks).ok_or(anyhow!("Hunks of {:?} could not be applied", old))?;
+    if applied != new {
+        return Err(anyhow!("Round trip of {:?} gave {:?} instead of {:?}", old, applied, new));
+    }
+
+    Ok(())
+}
+
+fn test_round_trip_plain() -> Result<()> {
+    println!("TEST - Round Trip Plain");
+
+    let pairs = [
+        ("", ""),
+        ("", "Everything is new"),
+        ("Everything goes", ""),
+        ("The quick brown fox", "The quick brown fox"),
+        ("The quick brown fox", "A quick brown fox"),
+        ("The quick brown fox", "The quick red fox jumps"),
+        ("The quick brown fox", "The brown fox"),
+        ("One two three four five", "One 2 three four 5"),
+        ("Ends without a stop", "Ends without a stop."),
+        ("Spaces  and\ttabs\n", "Spaces and tabs\n\n"),
+    ];
+    for (old, new) in pairs {
+        check_round_trip(old, new)?;
+    }
+
+    Ok(())
+}
+
+fn test_round_trip_utf8() -> Result<()> {
+    println!("TEST - Round Trip UTF-8");
+
+    let pairs = [
+        ("Crème brûlée für alle", "Crème brûlée für niemanden"),
+        ("日本語のテキスト", "日本語の新しいテキスト"),
+        ("Thumbs 👍🏽 up", "Thumbs 👎 down 🎉"),
+        ("naïve café", "naïve résumé café"),
+        ("Ω≈ç√∫", "Ω≈√∫µ"),
+    ];
+    for (old, new) in pairs {
+        check_round_trip(old, new)?;
+    }
+
+    Ok(())
+}
+
+fn test_round_trip_html() -> Result<()> {
+    println!("TEST - Round Trip HTML");
+
+    let pairs = [
+        ("<p>Hello <b>world</b></p>", "<p>Hello <i>world</i></p>"),
+        ("<p>Hello world</p>", "<p>Hello <b>bold</b> world</p><p>Second paragraph</p>"),
+        ("<h1>Title</h1><p>Body</p>", "<p>Body</p>"),
+        ("<p class=\"a\">Same text</p>", "<p class=\"b\">Same text</p>"),
+        ("<ul><li>One</li><li>Two</li></ul>", "<ul><li>One</li><li>Zwei</li><li>Drei</li></ul>"),
+        ("<p>Grüße &amp; <em>ciao</em></p>", "<p>Grüße, <em>ciao</em> 👋</p>"),
+    ];
+    for (old, new) in pairs {
+        check_round_trip(old, new)?;
+    }
+
+    // Tags are never split, a changed attribute replaces the whole tag
+    let hunks = diff_hunks("<p class=\"a\">Same text</p>", "<p class=\"b\">Same text</p>");
+    if hunks.len() != 1 || hunks[0].old_text != "<p class=\"a\">" {
+        return Err(anyhow!("Expected the whole tag to be replaced, got {:?}", hunks));
+    }
+
+    Ok(())
+}
+
+fn test_apply_to_edited_content() -> Result<()> {
+    println!("TEST - Apply To Edited Content");
+
+    // A suggestion made against base still applies after someone edited another part of the document
+    let base = "<p>First paragraph stays the same.</p><p>The second paragraph has a typo in it.</p>";
+    let suggested = "<p>First paragraph stays the same.</p><p>The second paragraph has no typo in it.</p>";
+    let current = "<p>First paragraph was édited by someone else.</p><p>The second paragraph has a typo in it.</p>";
+
+    let hunks = diff_hunks(base, suggested);
+    let applied = apply_hunks(base, current, &hunks).ok_or(anyhow!("Suggestion no longer applies"))?;
+    let expected = "<p>First paragraph was édited by someone else.</p><p>The second paragraph has no typo in it.</p>";
+    if applied != expected {
+        return Err(anyhow!("Expected {:?}, got {:?}", expected, applied));
+    }
+
+    // Once the changed text itself is gone the suggestion conflicts
+    let rewritten = "<p>First paragraph stays the same.</p><p>Rewritten completely.</p>";
+    if apply_hunks(base, rewritten, &hunks).is_some() {
+        return Err(anyhow!("Suggestion applied to text that no longer exists"));
+    }
+
+    Ok(())
+}
+
+fn test_ambiguous_matches() -> Result<()> {
+    println!("TEST - Ambiguous Matches");
+
+    if find_unique("abc", "b") != Some(1) || find_unique("añb", "b") != Some(3) {
+        return Err(anyhow!("A single match was not found"));
+    }
+
+    // Overlapping occurrences count, "aa" is in "aaa" twice
+    if find_unique("aaa", "aa").is_some() || find_unique("abab", "ab").is_some() || find_unique("abc", "x").is_some() {
+        return Err(anyhow!("A missing or repeated match was reported as unique"));
+    }
+
+    Ok(())
+}
@@ -5,8 +5,11 @@ use std::result;
 use anyhow::{anyhow, Result};
 use backend::result_to_string;
 use chrono::Utc;
+use futures_util::{SinkExt, StreamExt};
 use httpc_test::Client;
-use serde_json::json;
+use serde_json::{json, Value};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::Message;
 
 #[tokio::test]
 async fn test_documents() -> Result<()> {
@@ -21,9 +24,22 @@ async fn test_documents() -> Result<()> {
     let get_docs_res = test_get_all_doc(&hc).await;
     let proj_from_doc = test_get_project_from_document(&hc).await;
     let update_result = test_update_document(&hc).await;
+    let revisions_result = test_get_revisions(&hc).await;
+    let diff_result = test_diff_revisions(&hc).await;
+    let diff_doc_result = test_diff_document(&hc).await;
+    let restore_rev_result = test_restore_revision(&hc).await;
+    let conflict_result = test_update_document_conflict(&hc).await;
+    let collab_result = test_collaborative_editing(&hc).await;
+    let collab_revoked = test_collaborative_access_revoked(&hc).await;
     let add_permissions = test_add_permissions(&hc).await;
+    let unknown_role = test_add_permissions_unknown_role(&hc).await;
     let upd_perm = test_update_permissions(&hc).await;
     let get_perm = test_get_permissions(&hc).await;
+    let share_links = test_share_links(&hc).await;
+    let share_link_limits = test_share_link_limits(&hc).await;
+    let publish_result = test_publish_document(&hc).await;
+    let comments_result = test_comments(&hc).await;
+    let suggestions_result = test_suggestions(&hc).await;
     let delete_result = test_delete_document(&hc).await;
     let get_bad_result = test_get_document(&hc).await;
     let reset_db = backend::test_reset_db(&hc).await;
@@ -36,9 +52,22 @@ async fn test_documents() -> Result<()> {
     println!("Get All Documents\t{}", result_to_string(&get_docs_res));
     println!("Get Project From Doc\t{}", result_to_string(&proj_from_doc));
     println!("Update Document:\t{}", result_to_string(&update_result));
+    println!("Get Revisions:\t\t{}", result_to_string(&revisions_result));
+    println!("Diff Revisions:\t\t{}", result_to_string(&diff_result));
+    println!("Diff Document:\t\t{}", result_to_string(&diff_doc_result));
+    println!("Restore Revision:\t{}", result_to_string(&restore_rev_result));
+    println!("Stale Update Conflict:\t{}", result_to_string(&conflict_result));
+    println!("Collaborative Editing:\t{}", result_to_string(&collab_result));
+    println!("Live Access Revoked:\t{}", result_to_string(&collab_revoked));
     println!("Add Permissions:\t{}", result_to_string(&add_permissions));
+    println!("Reject Unknown Role:\t{}", result_to_string(&unknown_role));
     println!("Update Permissions:\t{}", result_to_string(&upd_perm));
     println!("Get Users Permissions:\t{}", result_to_string(&get_perm));
+    println!("Share Links:\t\t{}", result_to_string(&share_links));
+    println!("Share Link Limits:\t{}", result_to_string(&share_link_limits));
+    println!("Publish Document:\t{}", result_to_string(&publish_result));
+    println!("Comment Threads:\t{}", result_to_string(&comments_result));
+    println!("Suggestions:\t\t{}", result_to_string(&suggestions_result));
     println!("Delete Document\t\t{}", result_to_string(&delete_result));
     println!("Get Bad Document:\t{}", result_to_string(&get_result));
     println!("Reset Database:\t\t{}", result_to_string(&reset_db));
@@ -159,14 +188,35 @@ async fn test_update_document(hc: &Client) -> Result<()> {
     // generate new updated_at time
     let now = Utc::now().naive_utc();
 
+    // A save that does not say which version it is based on could overwrite someone else's edit
+    let unversioned_response = hc
+        .do_put(
+            "/api/document/2",
+            json!({
+                "name": "Updated Test Document",
+                "content": "This save does not carry a version",
+                "updated_at": now
+            }),
+        )
+        .await?;
+
+    if unversioned_response.status().as_u16() != 422 {
+        return Err(anyhow!(
+            "Expected 422 for a save without a version, got: {}",
+            unversioned_response.status()
+        ));
+    }
+
     // Now update the document we just created
+    let version = get_document_version(hc, 2).await?;
     let update_response = hc
         .do_put(
-            &format!("/api/document/2"),
+            "/api/document/2",
             json!({
                 "name": "Updated Test Document",
                 "content": "This document has been updated",
-                "updated_at": now
+                "updated_at": now,
+                "version": version
             }),
         )
         .await?;
@@ -184,6 +234,378 @@ async fn test_update_document(hc: &Client) -> Result<()> {
     Ok(())
 }
 
+// The version a save of the document has to be based on
+async fn get_document_version(hc: &Client, document_id: i32) -> Result<i64> {
+    hc.do_get(&format!("/api/document/{}", document_id))
+        .await?
+        .json_body()?["version"]
+        .as_i64()
+        .ok_or(anyhow!("Document has no version"))
+}
+
+async fn test_get_revisions(hc: &Client) -> Result<()> {
+    println!("TEST - Get Revisions");
+
+    // Save an applied AI suggestion so document 2 has a second revision
+    let now = Utc::now().naive_utc();
+    let version = get_document_version(hc, 2).await?;
+    let update_response = hc
+        .do_put(
+            "/api/document/2",
+            json!({
+                "name": "Updated Test Document",
+                "content": "This document has been rewritten by the assistant",
+                "updated_at": now,
+                "version": version,
+                "origin": "ai_apply"
+            }),
+        )
+        .await?;
+    update_response.print().await?;
+
+    if !update_response.status().is_success() {
+        return Err(anyhow!(
+            "Update Document failed with status: {}",
+            update_response.status()
+        ));
+    }
+
+    let response = hc.do_get("/api/document/2/revisions").await?;
+    response.print().await?;
+
+    if !response.status().is_success() {
+        return Err(anyhow!(
+            "Get Revisions failed with status: {}",
+            response.status()
+        ));
+    }
+
+    // Newest revision comes first
+    let body = response.json_body()?;
+    let revisions = body.as_array().ok_or(anyhow!("Expected an array of revisions"))?;
+    if revisions.len() < 2 || revisions[0]["origin"] != "ai_apply" {
+        return Err(anyhow!("Expected the AI apply to be the newest of at least two revisions"));
+    }
+
+    // The first revision holds the content from test_update_document
+    let response = hc.do_get("/api/document/2/revisions/1").await?;
+    response.print().await?;
+
+    if !response.status().is_success() {
+        return Err(anyhow!(
+            "Get Revision failed with status: {}",
+            response.status()
+        ));
+    }
+
+    if response.json_body()?["content"] != "This document has been updated" {
+        return Err(anyhow!("Revision 1 does not hold the first saved content"));
+    }
+
+    Ok(())
+}
+
+async fn test_diff_revisions(hc: &Client) -> Result<()> {
+    println!("TEST - Diff Revisions");
+
+    let response = hc.do_get("/api/document/2/revisions/diff?from=1&to=2").await?;
+    response.print().await?;
+
+    if !response.status().is_success() {
+        return Err(anyhow!(
+            "Diff Revisions failed with status: {}",
+            response.status()
+        ));
+    }
+
+    let body = response.json_body()?;
+    let changes = body["changes"].as_array().ok_or(anyhow!("Expected a list of changes"))?;
+    if !changes.iter().any(|change| change["type"] == "insert") {
+        return Err(anyhow!("Expected the diff to contain an insertion"));
+    }
+
+    Ok(())
+}
+
+async fn test_diff_document(hc: &Client) -> Result<()> {
+    println!("TEST - Diff Document");
+
+    let response = hc.do_get("/api/document/2/diff?from=1&to=2").await?;
+    response.print().await?;
+
+    if !response.status().is_success() {
+        return Err(anyhow!(
+            "Diff Document failed with status: {}",
+            response.status()
+        ));
+    }
+
+    // Every hunk points at the text it replaces
+    let body = response.json_body()?;
+    let hunks = body["hunks"].as_array().ok_or(anyhow!("Expected a list of hunks"))?;
+    if hunks.is_empty() || hunks.iter().any(|hunk| hunk["kind"].is_null() || hunk["old_start"].is_null()) {
+        return Err(anyhow!("Expected the diff to contain changed ranges"));
+    }
+
+    // Without `to` the revision is compared with the current content
+    let current_response = hc.do_get("/api/document/2/diff?from=1").await?;
+    if !current_response.status().is_success() || !current_response.json_body()?["to"].is_null() {
+        return Err(anyhow!(
+            "Diff against the current content failed with status: {}",
+            current_response.status()
+        ));
+    }
+
+    Ok(())
+}
+
+async fn test_restore_revision(hc: &Client) -> Result<()> {
+    println!("TEST - Restore Revision");
+
+    let response = hc.do_post("/api/document/2/revisions/1/restore", json!({})).await?;
+    response.print().await?;
+
+    if !response.status().is_success() {
+        return Err(anyhow!(
+            "Restore Revision failed with status: {}",
+            response.status()
+        ));
+    }
+
+    // The document content is back to revision 1
+    let get_response = hc.do_get("/api/document/2").await?;
+    if get_response.json_body()?["content"] != "This document has been updated" {
+        return Err(anyhow!("Document content was not restored"));
+    }
+
+    Ok(())
+}
+
+async fn test_update_document_conflict(hc: &Client) -> Result<()> {
+    println!("TEST - Update Document Conflict");
+
+    // Load the document to learn the version our edit is based on
+    let version = get_document_version(hc, 2).await?;
+
+    let save = |content: &'static str| {
+        hc.do_put(
+            "/api/document/2",
+            json!({
+                "name": "Updated Test Document",
+                "content": content,
+                "updated_at": Utc::now().naive_utc(),
+                "version": version
+            }),
+        )
+    };
+
+    // The first save based on this version goes through
+    let first_response = save("First editor's change").await?;
+    first_response.print().await?;
+
+    if !first_response.status().is_success() {
+        return Err(anyhow!(
+            "Update Document failed with status: {}",
+            first_response.status()
+        ));
+    }
+
+    // A second save based on the same version is stale and must not overwrite the first
+    let second_response = save("Second editor's change").await?;
+    second_response.print().await?;
+
+    if second_response.status().as_u16() != 409 {
+        return Err(anyhow!(
+            "Expected 409 Conflict for a stale save, got: {}",
+            second_response.status()
+        ));
+    }
+
+    let body = second_response.json_body()?;
+    if body["error"]["data"]["current_version"] != version + 1 {
+        return Err(anyhow!("Conflict response did not carry the current version"));
+    }
+
+    Ok(())
+}
+
+async fn test_collaborative_editing(hc: &Client) -> Result<()> {
+    println!("TEST - Collaborative Editing");
+
+    // The WebSocket is authenticated with the same cookie as the rest of the API
+    let token = hc
+        .cookie_value("auth-token")
+        .ok_or(anyhow!("Not logged in"))?;
+    let mut request = "ws://localhost:3001/api/document/2/ws".into_client_request()?;
+    request
+        .headers_mut()
+        .insert("Cookie", format!("auth-token={}", token).parse()?);
+
+    let (mut socket, _) = tokio_tungstenite::connect_async(request).await?;
+
+    // The server starts with a snapshot of the document
+    let init = next_json(&mut socket).await?;
+    println!("{}", init);
+    if init["type"] != "init" || init["read_only"] != false {
+        return Err(anyhow!("Expected an editable init snapshot"));
+    }
+    let version = init["version"].as_u64().ok_or(anyhow!("Snapshot has no version"))?;
+
+    // Send an edit and expect it to be broadcast back with the next version
+    socket
+        .send(Message::Text(
+            json!({
+                "type": "op",
+                "base_version": version,
+                "ops": [{ "type": "insert", "pos": 0, "text": "Live " }]
+            })
+            .to_string(),
+        ))
+        .await?;
+
+    let broadcast = next_json(&mut socket).await?;
+    println!("{}", broadcast);
+    if broadcast["type"] != "op"
+        || broadcast["version"] != version + 1
+        || broadcast["client_id"] != init["client_id"]
+    {
+        return Err(anyhow!("Edit was not broadcast back to the sender"));
+    }
+
+    // A save outside the room wins over edits the room has not saved yet.
+    // The room may save on its own between reading the version and saving, so allow a retry.
+    let mut saved_outside = false;
+    for _ in 0..3 {
+        let version = get_document_version(hc, 2).await?;
+        let response = hc
+            .do_put(
+                "/api/document/2",
+                json!({
+                    "name": "Updated Test Document",
+                    "content": "Saved outside the room",
+                    "updated_at": Utc::now().naive_utc(),
+                    "version": version
+                }),
+            )
+            .await?;
+        if response.status().is_success() {
+            saved_outside = true;
+            break;
+        }
+    }
+    if !saved_outside {
+        return Err(anyhow!("Could not save the document outside the room"));
+    }
+
+    socket
+        .send(Message::Text(
+            json!({
+                "type": "op",
+                "base_version": version + 1,
+                "ops": [{ "type": "insert", "pos": 0, "text": "Unsaved " }]
+            })
+            .to_string(),
+        ))
+        .await?;
+
+    // Once the room tries to save it finds the newer version and starts everyone over from it
+    let reload = tokio::time::timeout(std::time::Duration::from_secs(15), async {
+        loop {
+            let message = next_json(&mut socket).await?;
+            if message["type"] == "init" {
+                return Ok::<Value, anyhow::Error>(message);
+            }
+        }
+    })
+    .await
+    .map_err(|_| anyhow!("Room did not reload after the document was saved outside it"))??;
+    println!("{}", reload);
+
+    if reload["content"] != "Saved outside the room" {
+        return Err(anyhow!("Room kept its own text over the saved document"));
+    }
+
+    socket.close(None).await?;
+
+    Ok(())
+}
+
+async fn test_collaborative_access_revoked(hc: &Client) -> Result<()> {
+    println!("TEST - Collaborative Access Revoked");
+
+    // User 2 gets to edit document 2 and joins its live session
+    let granted = hc
+        .do_post("/api/document/2/permissions", json!({ "user_id": 2, "role": "editor" }))
+        .await?;
+    if !granted.status().is_success() {
+        return Err(anyhow!("Add permissions failed with status: {}", granted.status()));
+    }
+
+    let other = httpc_test::new_client("http://localhost:3001")?;
+    other
+        .do_post(
+            "/api/users/login",
+            json!({
+                "email": "MarkoP@gmail.com",
+                "password": "MarkosPassword"
+            }),
+        )
+        .await?;
+    let token = other
+        .cookie_value("auth-token")
+        .ok_or(anyhow!("User 2 is not logged in"))?;
+    let mut request = "ws://localhost:3001/api/document/2/ws".into_client_request()?;
+    request
+        .headers_mut()
+        .insert("Cookie", format!("auth-token={}", token).parse()?);
+
+    let (mut socket, _) = tokio_tungstenite::connect_async(request).await?;
+    let init = next_json(&mut socket).await?;
+    if init["type"] != "init" || init["read_only"] != false {
+        return Err(anyhow!("Expected an editable init snapshot for user 2"));
+    }
+
+    // Taking the permission away ends the session on the next access check
+    let removed = hc.do_delete("/api/document/2/permissions/2").await?;
+    if !removed.status().is_success() {
+        return Err(anyhow!("Remove permission failed with status: {}", removed.status()));
+    }
+
+    let error = tokio::time::timeout(std::time::Duration::from_secs(15), async {
+        loop {
+            let message = next_json(&mut socket).await?;
+            if message["type"] == "error" {
+                return Ok::<Value, anyhow::Error>(message);
+            }
+        }
+    })
+    .await
+    .map_err(|_| anyhow!("Socket stayed open after access was removed"))??;
+    println!("{}", error);
+
+    // Nothing follows but the close frame
+    if next_json(&mut socket).await.is_ok() {
+        return Err(anyhow!("Socket kept sending after access was removed"));
+    }
+
+    Ok(())
+}
+
+// Read the next JSON text frame from a WebSocket
+async fn next_json<S>(socket: &mut S) -> Result<Value>
+where
+    S: StreamExt<Item = std::result::Result<Message, tokio_tungstenite::tungstenite::Error>> + Unpin,
+{
+    loop {
+        match socket.next().await {
+            Some(Ok(Message::Text(text))) => return Ok(serde_json::from_str(&text)?),
+            Some(Ok(_)) => continue,
+            Some(Err(e)) => return Err(e.into()),
+            None => return Err(anyhow!("WebSocket closed")),
+        }
+    }
+}
+
 async fn test_get_project_from_document(hc: &Client) -> Result<()> {
     println!("TEST - Get Project From Document");
 
@@ -211,7 +633,7 @@ async fn test_delete_document(hc: &Client) -> Result<()> {
     println!("TEST - Delete Document");
 
     // Now delete the document we just created
-    let delete_response = hc.do_delete(&format!("/api/document/2")).await?;
+    let delete_response = hc.do_delete("/api/document/2").await?;
 
     delete_response.print().await?;
 
@@ -252,6 +674,32 @@ async fn test_add_permissions(hc: &Client) -> Result<()> {
     Ok(())
 }
 
+async fn test_add_permissions_unknown_role(hc: &Client) -> Result<()> {
+    println!("TEST - Add Document Permissions With Unknown Role");
+
+    // Roles are a fixed set, anything else must be rejected before it reaches the database
+    let add_perm_response = hc
+        .do_post(
+            "/api/document/1/permissions",
+            json!({
+                "user_id": 2,
+                "role": "admin"
+            }),
+        )
+        .await?;
+
+    add_perm_response.print().await?;
+
+    if !add_perm_response.status().is_client_error() {
+        return Err(anyhow::anyhow!(
+            "Expected a client error for an unknown role, got: {}",
+            add_perm_response.status()
+        ));
+    }
+
+    Ok(())
+}
+
 async fn test_get_permissions(hc: &Client) -> Result<()> {
     println!("TEST - Get Document Permissions");
 
@@ -293,6 +741,486 @@ async fn test_update_permissions(hc: &Client) -> Result<()> {
     Ok(())
 }
 
+async fn test_share_links(hc: &Client) -> Result<()> {
+    println!("TEST - Share Links");
+
+    // User 1 shares document 3 through a single use link
+    let create_response = hc
+        .do_post(
+            "/api/document/3/links",
+            json!({
+                "role": "commenter",
+                "max_uses": 1
+            }),
+        )
+        .await?;
+    create_response.print().await?;
+
+    if !create_response.status().is_success() {
+        return Err(anyhow!(
+            "Create share link failed with status: {}",
+            create_response.status()
+        ));
+    }
+
+    let link = create_response.json_body()?;
+    let token = link["token"].as_str().ok_or(anyhow!("Share link has no token"))?;
+    let link_id = link["id"].as_i64().ok_or(anyhow!("Share link has no id"))?;
+
+    // User 2 opens the link while logged in
+    let other = httpc_test::new_client("http://localhost:3001")?;
+    other
+        .do_post(
+            "/api/users/login",
+            json!({
+                "email": "MarkoP@gmail.com",
+                "password": "MarkosPassword"
+            }),
+        )
+        .await?;
+
+    let accept_response = other
+        .do_post(&format!("/api/share/{}/accept", token), json!({}))
+        .await?;
+    accept_response.print().await?;
+
+    if !accept_response.status().is_success() || accept_response.json_body()?["role"] != "commenter" {
+        return Err(anyhow!(
+            "Accept share link failed with status: {}",
+            accept_response.status()
+        ));
+    }
+
+    // The use is counted against the link
+    let list_response = hc.do_get("/api/document/3/links").await?;
+    let links = list_response.json_body()?;
+    let counted = links
+        .as_array()
+        .is_some_and(|links| links.iter().any(|l| l["id"] == link_id && l["use_count"] == 1));
+
+    if !counted {
+        return Err(anyhow!("Share link use was not counted"));
+    }
+
+    // A revoked link can no longer be opened
+    let revoke_response = hc.do_delete(&format!("/api/document/3/links/{}", link_id)).await?;
+    if !revoke_response.status().is_success() {
+        return Err(anyhow!(
+            "Revoke share link failed with status: {}",
+            revoke_response.status()
+        ));
+    }
+
+    let preview_response = other.do_get(&format!("/api/share/{}", token)).await?;
+    if preview_response.status().as_u16() != 404 {
+        return Err(anyhow!(
+            "Expected 404 for a revoked link, got: {}",
+            preview_response.status()
+        ));
+    }
+
+    Ok(())
+}
+
+async fn test_share_link_limits(hc: &Client) -> Result<()> {
+    println!("TEST - Share Link Limits");
+
+    let other = httpc_test::new_client("http://localhost:3001")?;
+    other
+        .do_post(
+            "/api/users/login",
+            json!({
+                "email": "MarkoP@gmail.com",
+                "password": "MarkosPassword"
+            }),
+        )
+        .await?;
+
+    // A link that has expired can no longer be opened
+    let expiring_response = hc
+        .do_post(
+            "/api/document/3/links",
+            json!({
+                "role": "editor",
+                "expires_at": Utc::now().naive_utc() + chrono::Duration::seconds(1)
+            }),
+        )
+        .await?;
+    if !expiring_response.status().is_success() {
+        return Err(anyhow!(
+            "Create share link failed with status: {}",
+            expiring_response.status()
+        ));
+    }
+    let expiring_token = expiring_response.json_body()?["token"]
+        .as_str()
+        .ok_or(anyhow!("Share link has no token"))?
+        .to_string();
+
+    tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+
+    let expired_response = other
+        .do_post(&format!("/api/share/{}/accept", expiring_token), json!({}))
+        .await?;
+    if expired_response.status().as_u16() != 410 {
+        return Err(anyhow!(
+            "Expected 410 for an expired link, got: {}",
+            expired_response.status()
+        ));
+    }
+
+    // A password protected link refuses a missing or wrong password
+    let protected_response = hc
+        .do_post(
+            "/api/document/3/links",
+            json!({
+                "role": "editor",
+                "password": "correct horse"
+            }),
+        )
+        .await?;
+    let token = protected_response.json_body()?["token"]
+        .as_str()
+        .ok_or(anyhow!("Share link has no token"))?
+        .to_string();
+
+    let preview = other.do_get(&format!("/api/share/{}", token)).await?.json_body()?;
+    if preview["requires_password"] != true {
+        return Err(anyhow!("Preview does not say the link needs a password"));
+    }
+
+    let accept_path = format!("/api/share/{}/accept", token);
+    let accept = |password: Option<&'static str>| other.do_post(&accept_path, json!({ "password": password }));
+
+    let missing_response = accept(None).await?;
+    if missing_response.status().as_u16() != 403 {
+        return Err(anyhow!(
+            "Expected 403 without a password, got: {}",
+            missing_response.status()
+        ));
+    }
+
+    // Four more wrong passwords lock the link, even the right one is refused until the lock runs out
+    for _ in 0..4 {
+        let wrong_response = accept(Some("wrong horse")).await?;
+        if wrong_response.status().as_u16() != 403 {
+            return Err(anyhow!(
+                "Expected 403 for a wrong password, got: {}",
+                wrong_response.status()
+            ));
+        }
+    }
+
+    let locked_response = accept(Some("correct horse")).await?;
+    locked_response.print().await?;
+    if locked_response.status().as_u16() != 429
+        || !locked_response.json_body()?["error"]["data"]["retry_after_seconds"].is_i64()
+    {
+        return Err(anyhow!(
+            "Expected 429 once too many wrong passwords were tried, got: {}",
+            locked_response.status()
+        ));
+    }
+
+    Ok(())
+}
+
+async fn test_publish_document(hc: &Client) -> Result<()> {
+    println!("TEST - Publish Document");
+
+    let publish_response = hc.do_post("/api/document/1/publish", json!({})).await?;
+    publish_response.print().await?;
+
+    if !publish_response.status().is_success() {
+        return Err(anyhow!(
+            "Publish document failed with status: {}",
+            publish_response.status()
+        ));
+    }
+
+    let slug = publish_response.json_body()?["slug"]
+        .as_str()
+        .ok_or(anyhow!("Publication has no slug"))?
+        .to_string();
+
+    // Readers do not need an account
+    let anonymous = httpc_test::new_client("http://localhost:3001")?;
+    let page_response = anonymous.do_get(&format!("/p/{}", slug)).await?;
+
+    if !page_response.status().is_success() {
+        return Err(anyhow!(
+            "Published page failed with status: {}",
+            page_response.status()
+        ));
+    }
+
+    let json_response = anonymous.do_get(&format!("/p/{}/json", slug)).await?;
+    json_response.print().await?;
+
+    if json_response.json_body()?["view_count"].as_i64().unwrap_or(0) < 2 {
+        return Err(anyhow!("Views of the published document were not counted"));
+    }
+
+    // Publishing again keeps the URL
+    let republish_response = hc.do_post("/api/document/1/publish", json!({})).await?;
+    if republish_response.json_body()?["slug"] != slug.as_str() {
+        return Err(anyhow!("Republishing changed the slug"));
+    }
+
+    // Once unpublished the page is gone
+    let unpublish_response = hc.do_delete("/api/document/1/publish").await?;
+    if !unpublish_response.status().is_success() {
+        return Err(anyhow!(
+            "Unpublish document failed with status: {}",
+            unpublish_response.status()
+        ));
+    }
+
+    let gone_response = anonymous.do_get(&format!("/p/{}/json", slug)).await?;
+    if gone_response.status().as_u16() != 404 {
+        return Err(anyhow!(
+            "Expected 404 for an unpublished document, got: {}",
+            gone_response.status()
+        ));
+    }
+
+    // Scripts, event handlers and javascript: links in a document never reach its readers
+    let version = get_document_version(hc, 3).await?;
+    let update_response = hc
+        .do_put(
+            "/api/document/3",
+            json!({
+                "name": "Unsafe Document",
+                "content": "<p>Safe text</p><script>alert('script')</script>\
+                            <img src=\"x\" onerror=\"alert('img')\">\
+                            <a href=\"javascript:alert('link')\">Click me</a>",
+                "updated_at": Utc::now().naive_utc(),
+                "version": version
+            }),
+        )
+        .await?;
+    if !update_response.status().is_success() {
+        return Err(anyhow!(
+            "Update Document failed with status: {}",
+            update_response.status()
+        ));
+    }
+
+    let unsafe_slug = hc.do_post("/api/document/3/publish", json!({})).await?.json_body()?["slug"]
+        .as_str()
+        .ok_or(anyhow!("Publication has no slug"))?
+        .to_string();
+
+    let page = anonymous.do_get(&format!("/p/{}", unsafe_slug)).await?.text_body()?;
+    let content = anonymous.do_get(&format!("/p/{}/json", unsafe_slug)).await?.json_body()?["content"]
+        .as_str()
+        .ok_or(anyhow!("Published JSON has no content"))?
+        .to_string();
+
+    for (served, body) in [("page", &page), ("JSON", &content)] {
+        let lower = body.to_lowercase();
+        if !lower.contains("safe text") {
+            return Err(anyhow!("Published {} lost the safe content", served));
+        }
+        if lower.contains("<script") || lower.contains("alert(") || lower.contains("onerror") || lower.contains("javascript:") {
+            return Err(anyhow!("Published {} still contains active content: {}", served, body));
+        }
+    }
+
+    hc.do_delete("/api/document/3/publish").await?;
+
+    Ok(())
+}
+
+async fn test_comments(hc: &Client) -> Result<()> {
+    println!("TEST - Comment Threads");
+
+    // User 1 starts a thread on document 1 and mentions user 2, who is an editor there
+    let create_response = hc
+        .do_post(
+            "/api/document/1/comments",
+            json!({
+                "content": "Can we back this claim up with a source?",
+                "anchor_start": 0,
+                "anchor_end": 12,
+                "quoted_text": "This documen",
+                "mentions": [2]
+            }),
+        )
+        .await?;
+    create_response.print().await?;
+
+    if !create_response.status().is_success() {
+        return Err(anyhow!(
+            "Create comment failed with status: {}",
+            create_response.status()
+        ));
+    }
+
+    let thread = create_response.json_body()?;
+    let thread_id = thread["id"].as_i64().ok_or(anyhow!("Comment has no id"))?;
+
+    if thread["mentions"] != json!([2]) {
+        return Err(anyhow!("Mention was not stored"));
+    }
+
+    // User 2 finds the thread through their mentions and replies
+    let other = httpc_test::new_client("http://localhost:3001")?;
+    other
+        .do_post(
+            "/api/users/login",
+            json!({
+                "email": "MarkoP@gmail.com",
+                "password": "MarkosPassword"
+            }),
+        )
+        .await?;
+
+    let mentioned_response = other.do_get("/api/document/1/comments?mentioned=true").await?;
+    let mentioned = mentioned_response.json_body()?;
+    if !mentioned.as_array().is_some_and(|threads| threads.iter().any(|t| t["id"] == thread_id)) {
+        return Err(anyhow!("Mentioned thread was not listed"));
+    }
+
+    let reply_response = other
+        .do_post(
+            &format!("/api/document/1/comments/{}/replies", thread_id),
+            json!({ "content": "Added one in the next paragraph." }),
+        )
+        .await?;
+    reply_response.print().await?;
+
+    if !reply_response.status().is_success() || reply_response.json_body()?["thread_id"] != thread_id {
+        return Err(anyhow!(
+            "Reply to comment failed with status: {}",
+            reply_response.status()
+        ));
+    }
+
+    // Only the author can edit a comment
+    let edit_response = other
+        .do_put(
+            &format!("/api/document/1/comments/{}", thread_id),
+            json!({ "content": "Not my comment" }),
+        )
+        .await?;
+    if edit_response.status().as_u16() != 403 {
+        return Err(anyhow!(
+            "Expected 403 when editing someone else's comment, got: {}",
+            edit_response.status()
+        ));
+    }
+
+    // Resolving hides the thread from the open ones
+    let resolve_response = hc
+        .do_post(&format!("/api/document/1/comments/{}/resolve", thread_id), json!({}))
+        .await?;
+    resolve_response.print().await?;
+
+    let resolved = resolve_response.json_body()?;
+    if resolved["resolved_at"].is_null() || resolved["replies"].as_array().map_or(0, |r| r.len()) != 1 {
+        return Err(anyhow!("Thread was not resolved"));
+    }
+
+    let open_response = hc.do_get("/api/document/1/comments?resolved=false").await?;
+    let open = open_response.json_body()?;
+    if open.as_array().is_none_or(|threads| threads.iter().any(|t| t["id"] == thread_id)) {
+        return Err(anyhow!("Resolved thread is still listed as open"));
+    }
+
+    Ok(())
+}
+
+async fn test_suggestions(hc: &Client) -> Result<()> {
+    println!("TEST - Suggestions");
+
+    let get_response = hc.do_get("/api/document/1").await?;
+    let document = get_response.json_body()?;
+    let version = document["version"].as_i64().ok_or(anyhow!("Document has no version"))?;
+    let content = document["content"].as_str().unwrap_or_default().to_string();
+    let suggested = format!("{} With a suggested ending.", content);
+
+    // Suggest a change instead of saving it
+    let create_response = hc
+        .do_post(
+            "/api/document/1/suggestions",
+            json!({
+                "base_version": version,
+                "new_content": suggested
+            }),
+        )
+        .await?;
+    create_response.print().await?;
+
+    if !create_response.status().is_success() {
+        return Err(anyhow!(
+            "Create suggestion failed with status: {}",
+            create_response.status()
+        ));
+    }
+
+    let suggestion = create_response.json_body()?;
+    let suggestion_id = suggestion["id"].as_i64().ok_or(anyhow!("Suggestion has no id"))?;
+
+    if suggestion["status"] != "pending" || suggestion["hunks"].as_array().map_or(0, |h| h.len()) != 1 {
+        return Err(anyhow!("Suggestion was not stored as a single pending change"));
+    }
+
+    let pending_response = hc.do_get("/api/document/1/suggestions?status=pending").await?;
+    let pending = pending_response.json_body()?;
+    if !pending.as_array().is_some_and(|s| s.iter().any(|s| s["id"] == suggestion_id)) {
+        return Err(anyhow!("Suggestion is not listed as pending"));
+    }
+
+    // Accepting saves the suggested content as a new revision
+    let accept_response = hc
+        .do_post(&format!("/api/document/1/suggestions/{}/accept", suggestion_id), json!({}))
+        .await?;
+    accept_response.print().await?;
+
+    if !accept_response.status().is_success() || accept_response.json_body()?["revision_number"].is_null() {
+        return Err(anyhow!(
+            "Accept suggestion failed with status: {}",
+            accept_response.status()
+        ));
+    }
+
+    let updated_response = hc.do_get("/api/document/1").await?;
+    if updated_response.json_body()?["content"] != suggested.as_str() {
+        return Err(anyhow!("Accepted suggestion was not applied to the document"));
+    }
+
+    // A suggestion is only applied once
+    let again_response = hc
+        .do_post(&format!("/api/document/1/suggestions/{}/accept", suggestion_id), json!({}))
+        .await?;
+    if again_response.status().as_u16() != 409 {
+        return Err(anyhow!(
+            "Expected 409 when accepting a suggestion twice, got: {}",
+            again_response.status()
+        ));
+    }
+
+    // Suggestions against an outdated version are refused
+    let stale_response = hc
+        .do_post(
+            "/api/document/1/suggestions",
+            json!({
+                "base_version": version,
+                "new_content": "Based on an old version"
+            }),
+        )
+        .await?;
+    if stale_response.status().as_u16() != 409 {
+        return Err(anyhow!(
+            "Expected 409 for a suggestion on a stale version, got: {}",
+            stale_response.status()
+        ));
+    }
+
+    Ok(())
+}
+
 async fn test_remove_permissions(hc: &Client) -> Result<()> {
     println!("TEST - Remove Document Permission");
 
@@ -15,12 +15,16 @@ async fn test_environment() -> Result<()> {
     // Run all tests and collect results
     let db_result = test_database(&hc).await;
     let fallback_result = trigger_fallback(&hc).await;
+    let requires_auth_result = test_requires_auth(&hc).await;
+    let reset_wrong_secret_result = test_reset_db_wrong_secret(&hc).await;
     let db_reset = backend::test_reset_db(&hc).await;
 
     // Print summary
     println!("\n==== TEST RESULTS ====");
     println!("Database Query:\t{}", result_to_string(&db_result));
     println!("Test Fallback:\t{}", result_to_string(&fallback_result));
+    println!("Requires Auth:\t{}", result_to_string(&requires_auth_result));
+    println!("Reset Bad Secret:\t{}", result_to_string(&reset_wrong_secret_result));
     println!("Reset Database:\t{}", result_to_string(&db_reset));
     println!("======================\n");
 
@@ -29,16 +33,48 @@ async fn test_environment() -> Result<()> {
 
 async fn trigger_fallback(hc: &Client) -> Result<()> {
     print!("TEST - Trigger Fallback Route");
-    let response = hc.do_get("/src/main.rs").await?;
+
+    // Backend sources and dotfiles must never be served
+    for path in ["/src/main.rs", "/.env", "/migrations/0001_initial_schema.sql"] {
+        let response = hc.do_get(path).await?;
+        response.print().await?;
+
+        if response.status().as_u16() != 404 {
+            return Err(anyhow::anyhow!(
+                "Expected 404 for {}, got: {}",
+                path,
+                response.status()
+            ));
+        }
+    }
+
+    // Unknown API paths answer with a JSON error instead of the frontend
+    let logged_in = httpc_test::new_client("http://localhost:3001")?;
+    logged_in
+        .do_post(
+            "/api/users/login",
+            json!({
+                "email": "CFdefence@gmail.com",
+                "password": "MyPassword"
+            }),
+        )
+        .await?;
+
+    let response = logged_in.do_get("/api/does-not-exist").await?;
     response.print().await?;
 
-    if !response.status().is_success() {
+    if response.status().as_u16() != 404 {
         return Err(anyhow::anyhow!(
-            "Fallback failed with status: {}",
+            "Expected 404 for an unknown API path, got: {}",
             response.status()
         ));
     }
 
+    let body = response.json_body()?;
+    if body["error"]["type"] != "RESOURCE_NOT_FOUND" {
+        return Err(anyhow::anyhow!("Expected a JSON error body, got: {}", body));
+    }
+
     Ok(())
 }
 
@@ -56,3 +92,40 @@ async fn test_database(hc: &Client) -> Result<()> {
 
     Ok(())
 }
+
+async fn test_requires_auth(hc: &Client) -> Result<()> {
+    print!("TEST - Unauthenticated API Request");
+    // This client never logged in, so protected routes must be rejected
+    let response = hc.do_get("/api/users/search?q=test").await?;
+    response.print().await?;
+
+    if response.status().as_u16() != 401 {
+        return Err(anyhow::anyhow!(
+            "Expected 401 for unauthenticated request, got: {}",
+            response.status()
+        ));
+    }
+
+    Ok(())
+}
+
+async fn test_reset_db_wrong_secret(hc: &Client) -> Result<()> {
+    print!("TEST - Reset Database With Wrong Secret");
+    let response = hc.do_post("/api/db/reset", json!({ "secret": "not_the_secret" })).await?;
+    response.print().await?;
+
+    if response.status().as_u16() != 403 {
+        return Err(anyhow::anyhow!(
+            "Expected 403 for a wrong reset secret, got: {}",
+            response.status()
+        ));
+    }
+
+    // The old GET endpoint must be gone
+    let response = hc.do_get("/api/db/reset?secret=secret_key").await?;
+    if response.status().is_success() {
+        return Err(anyhow::anyhow!("GET reset should no longer be accepted"));
+    }
+
+    Ok(())
+}
@@ -16,9 +16,11 @@ async fn test_projects() -> Result<()> {
     let get_p_result = test_get_project(&hc).await;
     let update_p_result = test_update_project(&hc).await;
     let add_perm_result = test_add_permissions(&hc).await;
+    let inherited_result = test_inherited_document_access(&hc).await;
     let get_perm_result = test_get_permissions(&hc).await;
     let upd_perm_result = test_update_permission(&hc).await;
     let rem_perm_result = test_remove_permissions(&hc).await;
+    let share_links_result = test_share_links(&hc).await;
     let add_doc_result = test_add_document_to_project(&hc).await;
     let get_docs_result = test_get_project_documents(&hc).await;
     let remove_doc_result = test_remove_document_from_project(&hc).await;
@@ -40,9 +42,11 @@ async fn test_projects() -> Result<()> {
     println!("Get Project:\t\t{}", result_to_string(&get_p_result));
     println!("Update Project:\t\t{}", result_to_string(&update_p_result));
     println!("Add Permissions:\t{}", result_to_string(&add_perm_result));
+    println!("Inherited Doc Access:\t{}", result_to_string(&inherited_result));
     println!("Get Permissions:\t{}", result_to_string(&get_perm_result));
     println!("Update Permission:\t{}", result_to_string(&upd_perm_result));
     println!("Remove Permission:\t{}", result_to_string(&rem_perm_result));
+    println!("Share Links:\t\t{}", result_to_string(&share_links_result));
     println!("Add Document:\t\t{}", result_to_string(&add_doc_result));
     println!("Get Documents:\t\t{}", result_to_string(&get_docs_result));
     println!("Remove Document:\t{}", result_to_string(&remove_doc_result));
@@ -128,7 +132,7 @@ async fn test_get_project(hc: &Client) -> Result<()> {
     println!("TEST - Get Project");
 
     // Now get the project we just made
-    let get_response = hc.do_get(&format!("/api/project/2")).await?;
+    let get_response = hc.do_get("/api/project/2").await?;
 
     get_response.print().await?;
 
@@ -148,7 +152,7 @@ async fn test_update_project(hc: &Client) -> Result<()> {
     // Update the created project
     let update_response = hc
         .do_put(
-            &format!("/api/project/2"),
+            "/api/project/2",
             json!({
                 "_name": "Updated Project Name"
             }),
@@ -171,7 +175,7 @@ async fn test_delete_project(hc: &Client) -> Result<()> {
     println!("TEST - Delete Project");
 
     // Now delete the created project
-    let delete_response = hc.do_delete(&format!("/api/project/2")).await?;
+    let delete_response = hc.do_delete("/api/project/2").await?;
 
     delete_response.print().await?;
 
@@ -211,6 +215,51 @@ async fn test_add_permissions(hc: &Client) -> Result<()> {
     Ok(())
 }
 
+async fn test_inherited_document_access(hc: &Client) -> Result<()> {
+    println!("TEST - Inherited Document Access");
+
+    // User 2 was just added to project 1 and has no permission on document 3 itself
+    let other = httpc_test::new_client("http://localhost:3001")?;
+    let login_response = other
+        .do_post(
+            "/api/users/login",
+            json!({
+                "email": "MarkoP@gmail.com",
+                "password": "MarkosPassword"
+            }),
+        )
+        .await?;
+
+    if !login_response.status().is_success() {
+        return Err(anyhow::anyhow!("Login as user 2 failed"));
+    }
+
+    let get_doc_response = other.do_get("/api/document/3").await?;
+    get_doc_response.print().await?;
+
+    if !get_doc_response.status().is_success() {
+        return Err(anyhow::anyhow!(
+            "Project member could not open a project document, status: {}",
+            get_doc_response.status()
+        ));
+    }
+
+    // The owner sees user 2 on the document as an inherited collaborator
+    let perm_response = hc.do_get("/api/document/3/permissions").await?;
+    let users = perm_response.json_body()?;
+    let inherited = users
+        .as_array()
+        .is_some_and(|users| {
+            users.iter().any(|u| u["user_id"] == 2 && u["inherited"] == true)
+        });
+
+    if !inherited {
+        return Err(anyhow::anyhow!("User 2 is not listed as inherited on document 3"));
+    }
+
+    Ok(())
+}
+
 async fn test_get_permissions(hc: &Client) -> Result<()> {
     println!("TEST - Get Project Permissions");
 
@@ -296,6 +345,107 @@ async fn test_force_delete_project(hc: &Client) -> Result<()> {
     Ok(())
 }
 
+async fn test_share_links(hc: &Client) -> Result<()> {
+    println!("TEST - Project Share Links");
+
+    // User 2 no longer has access to project 1, share it with them through a single use link
+    let create_response = hc
+        .do_post(
+            "/api/project/1/links",
+            json!({
+                "role": "viewer",
+                "password": "letmein",
+                "max_uses": 1
+            }),
+        )
+        .await?;
+    create_response.print().await?;
+
+    if !create_response.status().is_success() {
+        return Err(anyhow::anyhow!(
+            "Create share link failed with status: {}",
+            create_response.status()
+        ));
+    }
+
+    let link = create_response.json_body()?;
+    let token = link["token"].as_str().ok_or(anyhow::anyhow!("Share link has no token"))?.to_string();
+    let link_id = link["id"].as_i64().ok_or(anyhow::anyhow!("Share link has no id"))?;
+
+    let other = httpc_test::new_client("http://localhost:3001")?;
+    other
+        .do_post(
+            "/api/users/login",
+            json!({
+                "email": "MarkoP@gmail.com",
+                "password": "MarkosPassword"
+            }),
+        )
+        .await?;
+
+    // The link only works with its password
+    let wrong_response = other
+        .do_post(&format!("/api/share/{}/accept", token), json!({ "password": "guess" }))
+        .await?;
+    if wrong_response.status().as_u16() != 403 {
+        return Err(anyhow::anyhow!(
+            "Expected 403 for a wrong password, got: {}",
+            wrong_response.status()
+        ));
+    }
+
+    let accept_response = other
+        .do_post(&format!("/api/share/{}/accept", token), json!({ "password": "letmein" }))
+        .await?;
+    accept_response.print().await?;
+
+    let accepted = accept_response.json_body()?;
+    if !accept_response.status().is_success() || accepted["project_id"] != 1 || accepted["role"] != "viewer" {
+        return Err(anyhow::anyhow!(
+            "Accept share link failed with status: {}",
+            accept_response.status()
+        ));
+    }
+
+    // The wrong password did not count as a use, the right one did
+    let links = hc.do_get("/api/project/1/links").await?.json_body()?;
+    let counted = links
+        .as_array()
+        .is_some_and(|links| links.iter().any(|l| l["id"] == link_id && l["use_count"] == 1));
+    if !counted {
+        return Err(anyhow::anyhow!("Share link use was not counted"));
+    }
+
+    // Once used up the link grants nothing, even to someone who lost their access again
+    let remove_response = hc.do_delete("/api/project/1/permissions/2").await?;
+    if !remove_response.status().is_success() {
+        return Err(anyhow::anyhow!(
+            "Remove permission failed with status: {}",
+            remove_response.status()
+        ));
+    }
+
+    let used_up_response = other
+        .do_post(&format!("/api/share/{}/accept", token), json!({ "password": "letmein" }))
+        .await?;
+    if used_up_response.status().as_u16() != 410 {
+        return Err(anyhow::anyhow!(
+            "Expected 410 for a used up link, got: {}",
+            used_up_response.status()
+        ));
+    }
+
+    let revoke_response = hc.do_delete(&format!("/api/project/1/links/{}", link_id)).await?;
+    if !revoke_response.status().is_success() {
+        return Err(anyhow::anyhow!(
+            "Revoke share link failed with status: {}",
+            revoke_response.status()
+        ));
+    }
+
+    Ok(())
+}
+
 async fn test_add_document_to_project(hc: &Client) -> Result<()> {
     println!("TEST - Add Document to Project");
 
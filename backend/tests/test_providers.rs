@@ -0,0 +1,371 @@
+#![allow(unused)]
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use backend::rag::llm::QueryModel;
+use backend::rag::provider::mock::{MockLlmProvider, MOCK_RESPONSE};
+use backend::rag::provider::{
+    Completion, LlmProvider, ProviderConfig, ProviderKind, TokenUsage, CONTEXT_DECISION_FORMAT,
+    DOCUMENT_CHANGES_SCHEMA, PROACTIVE_DIFF_FORMAT,
+};
+use backend::rag::structured::OutputSchema;
+use backend::result_to_string;
+use backend::Error;
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+#[tokio::test]
+async fn test_providers() -> Result<()> {
+    println!("\n===== RUNNING PROVIDER SELECTION TESTS =====\n");
+
+    // Run all tests and collect results
+    let openai_result = test_select_openai();
+    let local_result = test_select_local();
+    let mock_result = test_select_mock();
+    let unknown_result = test_unknown_provider();
+    let embedding_fallback_result = test_embedding_fallback();
+    let mock_replies_result = test_mock_replies().await;
+    let wrapped_json_result = test_wrapped_json_reply().await;
+    let repair_result = test_invalid_reply_repaired().await;
+
+    // Print summary
+    println!("\n==== TEST RESULTS ====");
+    println!("Select OpenAI:\t\t{}", result_to_string(&openai_result));
+    println!("Select Local:\t\t{}", result_to_string(&local_result));
+    println!("Select Mock:\t\t{}", result_to_string(&mock_result));
+    println!("Unknown Provider:\t{}", result_to_string(&unknown_result));
+    println!("Embedding Fallback:\t{}", result_to_string(&embedding_fallback_result));
+    println!("Mock Replies:\t\t{}", result_to_string(&mock_replies_result));
+    println!("Wrapped JSON Reply:\t{}", result_to_string(&wrapped_json_result));
+    println!("Invalid Reply Repaired:\t{}", result_to_string(&repair_result));
+    println!("======================\n");
+
+    Ok(())
+}
+
+// Settings as they would be read from the environment
+fn vars(pairs: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+    let vars: HashMap<String, String> = pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+    move |name| vars.get(name).cloned()
+}
+
+fn test_select_openai() -> Result<()> {
+    println!("TEST - Select OpenAI");
+
+    // OpenAI is the default and needs its key
+    match ProviderConfig::llm_from_vars(&vars(&[])) {
+        Err(Error::APIKeyError) => {}
+        other => return Err(anyhow!("Expected a missing key error, got {:?}", other)),
+    }
+
+    let config = ProviderConfig::llm_from_vars(&vars(&[("OPENAI_API_KEY", "sk-test")]))?;
+    if config.kind != ProviderKind::OpenAi || config.api_key.as_deref() != Some("sk-test") || !config.json_schema {
+        return Err(anyhow!("Unexpected default config: {:?}", config));
+    }
+    if config.base_url.is_some() || config.model.is_some() {
+        return Err(anyhow!("OpenAI should use its default URL and model: {:?}", config));
+    }
+
+    let config = ProviderConfig::llm_from_vars(&vars(&[
+        ("LLM_PROVIDER", "OpenAI"),
+        ("LLM_MODEL", "gpt-4o"),
+        ("OPENAI_API_KEY", "sk-test"),
+    ]))?;
+    if config.kind != ProviderKind::OpenAi || config.model.as_deref() != Some("gpt-4o") {
+        return Err(anyhow!("LLM_PROVIDER=OpenAI was not selected: {:?}", config));
+    }
+
+    Ok(())
+}
+
+fn test_select_local() -> Result<()> {
+    println!("TEST - Select Local");
+
+    // Local servers need no OpenAI key and do not get structured outputs unless asked for
+    let config = ProviderConfig::llm_from_vars(&vars(&[
+        ("LLM_PROVIDER", "local"),
+        ("LLM_BASE_URL", "http://localhost:8080/v1"),
+        ("LLM_MODEL", "mistral"),
+    ]))?;
+    if config.kind != ProviderKind::Local
+        || config.base_url.as_deref() != Some("http://localhost:8080/v1")
+        || config.model.as_deref() != Some("mistral")
+        || config.api_key.is_some()
+        || config.json_schema
+    {
+        return Err(anyhow!("Unexpected local config: {:?}", config));
+    }
+
+    // ollama is another name for local, LLM_API_KEY and LLM_JSON_SCHEMA are honoured
+    let config = ProviderConfig::llm_from_vars(&vars(&[
+        ("LLM_PROVIDER", "ollama"),
+        ("LLM_API_KEY", "local-key"),
+        ("LLM_JSON_SCHEMA", "true"),
+    ]))?;
+    if config.kind != ProviderKind::Local || config.api_key.as_deref() != Some("local-key") || !config.json_schema {
+        return Err(anyhow!("Unexpected ollama config: {:?}", config));
+    }
+
+    Ok(())
+}
+
+fn test_select_mock() -> Result<()> {
+    println!("TEST - Select Mock");
+
+    let config = ProviderConfig::llm_from_vars(&vars(&[("LLM_PROVIDER", "mock")]))?;
+    if config.kind != ProviderKind::Mock {
+        return Err(anyhow!("LLM_PROVIDER=mock was not selected: {:?}", config));
+    }
+
+    // Embeddings follow the LLM provider unless set on their own
+    let config = ProviderConfig::embedding_from_vars(&vars(&[("LLM_PROVIDER", "mock")]))?;
+    if config.kind != ProviderKind::Mock {
+        return Err(anyhow!("Embeddings did not follow LLM_PROVIDER=mock: {:?}", config));
+    }
+
+    let config = ProviderConfig::embedding_from_vars(&vars(&[
+        ("LLM_PROVIDER", "local"),
+        ("EMBEDDING_PROVIDER", "mock"),
+    ]))?;
+    if config.kind != ProviderKind::Mock {
+        return Err(anyhow!("EMBEDDING_PROVIDER=mock was not selected: {:?}", config));
+    }
+
+    Ok(())
+}
+
+fn test_unknown_provider() -> Result<()> {
+    println!("TEST - Unknown Provider");
+
+    match ProviderConfig::llm_from_vars(&vars(&[("LLM_PROVIDER", "anthropic")])) {
+        Err(Error::UnknownLlmProviderError { provider }) if provider == "anthropic" => {}
+        other => return Err(anyhow!("Expected an unknown provider error, got {:?}", other)),
+    }
+
+    // A bad LLM_PROVIDER also breaks the embedding settings that fall back to it
+    match ProviderConfig::embedding_from_vars(&vars(&[("LLM_PROVIDER", "anthropic"), ("EMBEDDING_PROVIDER", "mock")])) {
+        Err(Error::UnknownLlmProviderError { .. }) => Ok(()),
+        other => Err(anyhow!("Expected an unknown provider error, got {:?}", other)),
+    }
+}
+
+fn test_embedding_fallback() -> Result<()> {
+    println!("TEST - Embedding Fallback");
+
+    // Same kind of provider, the embedding server is the LLM server
+    let config = ProviderConfig::embedding_from_vars(&vars(&[
+        ("LLM_PROVIDER", "local"),
+        ("LLM_BASE_URL", "http://localhost:11434/v1"),
+        ("LLM_MODEL", "llama3"),
+    ]))?;
+    if config.kind != ProviderKind::Local || config.base_url.as_deref() != Some("http://localhost:11434/v1") {
+        return Err(anyhow!("Embeddings did not fall back to the local LLM server: {:?}", config));
+    }
+    if config.model.is_some() {
+        return Err(anyhow!("The chat model must not be used for embeddings: {:?}", config));
+    }
+
+    // OpenAI embeddings with a local LLM must not be sent to the local server
+    let config = ProviderConfig::embedding_from_vars(&vars(&[
+        ("LLM_PROVIDER", "local"),
+        ("LLM_BASE_URL", "http://localhost:11434/v1"),
+        ("EMBEDDING_PROVIDER", "openai"),
+        ("OPENAI_API_KEY", "sk-test"),
+    ]))?;
+    if config.kind != ProviderKind::OpenAi || config.base_url.is_some() {
+        return Err(anyhow!("OpenAI embeddings fell back to the local server: {:?}", config));
+    }
+
+    // An explicit embedding URL always wins
+    let config = ProviderConfig::embedding_from_vars(&vars(&[
+        ("LLM_PROVIDER", "local"),
+        ("LLM_BASE_URL", "http://localhost:11434/v1"),
+        ("EMBEDDING_BASE_URL", "http://embeddings:8000/v1"),
+    ]))?;
+    if config.base_url.as_deref() != Some("http://embeddings:8000/v1") {
+        return Err(anyhow!("EMBEDDING_BASE_URL was ignored: {:?}", config));
+    }
+
+    Ok(())
+}
+
+async fn test_mock_replies() -> Result<()> {
+    println!("TEST - Mock Replies");
+
+    // Decision prompts are recognised by their answer format wherever it appears
+    let context = MockLlmProvider
+        .complete(&format!("Some new wording. {}\n\nUser Prompt:\nhello", CONTEXT_DECISION_FORMAT))
+        .await?;
+    let diff = MockLlmProvider
+        .complete(&format!("Decide about the diff view. {}", PROACTIVE_DIFF_FORMAT))
+        .await?;
+    if context.text != "none" || diff.text != "False" {
+        return Err(anyhow!("Unexpected decision replies: {:?} {:?}", context.text, diff.text));
+    }
+
+    // Mentioning JSON in a chat message does not turn the reply into JSON
+    let chat = MockLlmProvider.complete("Please return a JSON array of ideas").await?;
+    if chat.text != MOCK_RESPONSE {
+        return Err(anyhow!("Chat prompt got an unexpected reply: {:?}", chat.text));
+    }
+
+    // A replace instruction in the suggestion becomes an edit of each document quoting the text
+    let prompt = concat!(
+        "Apply the suggestion.\n\nProject Documents:\n```json\n",
+        r#"[{"id": 3, "name": "Overview", "content": "Our project goals"}, {"id": 4, "name": "Specs", "content": "Details"}]"#,
+        "\n```\n\n---\n\nSuggestion to Apply:\nReplace \"project\" with \"programme\".\n\n---\n\n"
+    );
+    let schema = OutputSchema::new(DOCUMENT_CHANGES_SCHEMA, json!({ "type": "array" }));
+    let changes: Vec<DocumentChange> = schema
+        .parse(&MockLlmProvider.complete_json(prompt, &schema).await?.text)
+        .map_err(|e| anyhow!(e))?;
+    if changes != expected_changes_to("project", "programme") {
+        return Err(anyhow!("Unexpected mock document changes: {:?}", changes));
+    }
+
+    // Any other suggestion changes nothing
+    let unchanged = MockLlmProvider.complete_json(&prompt.replace("Replace", "Improve"), &schema).await?;
+    if unchanged.text != "[]" {
+        return Err(anyhow!("Expected no document changes, got {:?}", unchanged.text));
+    }
+
+    Ok(())
+}
+
+// Answers with the given replies in order and keeps the prompts it was sent
+struct ScriptedLlmProvider {
+    replies: Mutex<VecDeque<String>>,
+    prompts: Arc<Mutex<Vec<String>>>,
+}
+
+impl ScriptedLlmProvider {
+    fn new(replies: &[&str]) -> Self {
+        Self {
+            replies: Mutex::new(replies.iter().map(|reply| reply.to_string()).collect()),
+            prompts: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for ScriptedLlmProvider {
+    async fn complete(&self, prompt: &str) -> backend::Result<Completion> {
+        self.prompts.lock().unwrap().push(prompt.to_string());
+        let text = self.replies.lock().unwrap().pop_front().ok_or(Error::LlmQueryError)?;
+        let usage = TokenUsage::estimate(prompt, &text);
+        Ok(Completion { text, usage })
+    }
+}
+
+// Same shape as the apply suggestion reply
+#[derive(Debug, Deserialize, PartialEq)]
+struct DocumentChange {
+    document_id: i32,
+    edits: Vec<Edit>,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Edit {
+    find: String,
+    replace: String,
+}
+
+fn document_changes_schema() -> OutputSchema {
+    OutputSchema::new(
+        DOCUMENT_CHANGES_SCHEMA,
+        json!({
+            "type": "array",
+            "items": {
+                "type": "object",
+                "properties": {
+                    "document_id": { "type": "integer" },
+                    "edits": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "find": { "type": "string" },
+                                "replace": { "type": "string" }
+                            },
+                            "required": ["find", "replace"],
+                            "additionalProperties": false
+                        }
+                    }
+                },
+                "required": ["document_id", "edits"],
+                "additionalProperties": false
+            }
+        }),
+    )
+}
+
+fn expected_changes() -> Vec<DocumentChange> {
+    expected_changes_to("teh", "the")
+}
+
+fn expected_changes_to(find: &str, replace: &str) -> Vec<DocumentChange> {
+    vec![DocumentChange {
+        document_id: 3,
+        edits: vec![Edit { find: find.to_string(), replace: replace.to_string() }],
+    }]
+}
+
+async fn test_wrapped_json_reply() -> Result<()> {
+    println!("TEST - Wrapped JSON Reply");
+
+    let change = r#"[{"document_id": 3, "edits": [{"find": "teh", "replace": "the"}]}]"#;
+    let replies = [
+        format!("```json\n{}\n```", change),
+        format!("Here are the changes you asked for:\n\n{}\n\nLet me know if you need anything else.", change),
+        format!("Sure! Below is the JSON.\n```\n{{\"items\": {}}}\n```\nDone.", change),
+    ];
+
+    // Each reply is used as is, without a second request
+    for reply in &replies {
+        let model = QueryModel::with_provider(Box::new(ScriptedLlmProvider::new(&[reply.as_str()])));
+        let changes: Vec<DocumentChange> = model.query_json("Apply the suggestion", &document_changes_schema()).await?;
+        if changes != expected_changes() {
+            return Err(anyhow!("Unexpected changes from {:?}: {:?}", reply, changes));
+        }
+    }
+
+    Ok(())
+}
+
+async fn test_invalid_reply_repaired() -> Result<()> {
+    println!("TEST - Invalid Reply Repaired");
+
+    // Valid JSON that does not match the schema, then a reply that does
+    let invalid = r#"[{"document_id": "three", "edits": []}]"#;
+    let valid = r#"[{"document_id": 3, "edits": [{"find": "teh", "replace": "the"}]}]"#;
+    let provider = ScriptedLlmProvider::new(&[invalid, valid]);
+    let sent_prompts = provider.prompts.clone();
+    let model = QueryModel::with_provider(Box::new(provider));
+
+    let changes: Vec<DocumentChange> = model.query_json("Apply the suggestion", &document_changes_schema()).await?;
+    if changes != expected_changes() {
+        return Err(anyhow!("Unexpected changes after the repair: {:?}", changes));
+    }
+
+    // The second prompt tells the model what was wrong with its first reply
+    let prompts = sent_prompts.lock().unwrap().clone();
+    if prompts.len() != 2 || !prompts[1].contains(invalid) || !prompts[1].contains("$[0].document_id should be integer") {
+        return Err(anyhow!("The repair prompt did not carry the reply and its problem: {:?}", prompts));
+    }
+
+    // Both requests are counted
+    let first = TokenUsage::estimate(&prompts[0], invalid);
+    let second = TokenUsage::estimate(&prompts[1], valid);
+    if model.usage().total() != first.total() + second.total() {
+        return Err(anyhow!("Usage of the repair was not counted: {:?}", model.usage()));
+    }
+
+    // A reply that is still unusable after the repair is a format error
+    let model = QueryModel::with_provider(Box::new(ScriptedLlmProvider::new(&[invalid, "No JSON, sorry"])));
+    match model.query_json::<Vec<DocumentChange>>("Apply the suggestion", &document_changes_schema()).await {
+        Err(Error::LlmResponseFormatError) => Ok(()),
+        other => Err(anyhow!("Expected a response format error, got {:?}", other)),
+    }
+}
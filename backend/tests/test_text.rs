@@ -0,0 +1,160 @@
+#![allow(unused)]
+
+use anyhow::{anyhow, Result};
+use backend::rag::chunk::{chunk_document, html_to_text, TextChunk};
+use backend::result_to_string;
+
+#[test]
+fn test_text() -> Result<()> {
+    println!("\n===== RUNNING TEXT PROCESSING TESTS =====\n");
+
+    // Run all tests and collect results
+    let paragraph_result = test_chunk_paragraphs();
+    let heading_result = test_chunk_headings();
+    let offsets_result = test_chunk_offsets();
+    let long_paragraph_result = test_chunk_long_paragraph();
+
+    // Print summary
+    println!("\n==== TEST RESULTS ====");
+    println!("Chunk Paragraphs:\t{}", result_to_string(&paragraph_result));
+    println!("Chunk Headings:\t\t{}", result_to_string(&heading_result));
+    println!("Chunk Offsets:\t\t{}", result_to_string(&offsets_result));
+    println!("Chunk Long Paragraph:\t{}", result_to_string(&long_paragraph_result));
+    println!("======================\n");
+
+    Ok(())
+}
+
+// Every chunk's offsets must point at the HTML it was made from
+fn check_offsets(html: &str, chunks: &[TextChunk]) -> Result<()> {
+    for chunk in chunks {
+        let source = html
+            .get(chunk.start_offset as usize..chunk.end_offset as usize)
+            .ok_or(anyhow!("Chunk {} offsets are not on character boundaries", chunk.chunk_index))?;
+
+        for line in chunk.content.lines() {
+            if !html_to_text(source).contains(line) {
+                return Err(anyhow!("Chunk {} text {:?} is not in its source {:?}", chunk.chunk_index, line, source));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn test_chunk_paragraphs() -> Result<()> {
+    println!("TEST - Chunk Paragraphs");
+
+    // Small paragraphs are packed into one chunk, one line each
+    let html = "<p>First paragraph.</p><p>Second &amp; <b>bold</b> paragraph.</p>";
+    let chunks = chunk_document(html);
+    println!("{:?}", chunks);
+
+    if chunks.len() != 1 || chunks[0].content != "First paragraph.\nSecond & bold paragraph." {
+        return Err(anyhow!("Expected one chunk with both paragraphs, got {:?}", chunks));
+    }
+
+    // Plain text paragraphs are split on blank lines
+    let chunks = chunk_document("One line\n\nAnother line");
+    if chunks.len() != 1 || chunks[0].content != "One line\nAnother line" {
+        return Err(anyhow!("Blank lines were not treated as paragraph breaks: {:?}", chunks));
+    }
+
+    // Empty paragraphs and bare tags produce no chunks
+    if !chunk_document("<p></p><p> &nbsp; </p>").is_empty() {
+        return Err(anyhow!("Empty paragraphs produced chunks"));
+    }
+
+    Ok(())
+}
+
+fn test_chunk_headings() -> Result<()> {
+    println!("TEST - Chunk Headings");
+
+    let html = "<p>Intro text.</p><h2>Methods</h2><p>We measured things.</p><h2>Results</h2><p>It worked.</p>";
+    let chunks = chunk_document(html);
+    println!("{:?}", chunks);
+
+    // Each heading starts a new chunk and is carried as the heading of the text below it
+    let expected = [
+        (None, "Intro text."),
+        (Some("Methods"), "Methods\nWe measured things."),
+        (Some("Results"), "Results\nIt worked."),
+    ];
+    let actual: Vec<(Option<&str>, &str)> = chunks
+        .iter()
+        .map(|chunk| (chunk.heading.as_deref(), chunk.content.as_str()))
+        .collect();
+
+    if actual != expected {
+        return Err(anyhow!("Unexpected chunks around headings: {:?}", actual));
+    }
+
+    if chunks.iter().enumerate().any(|(i, chunk)| chunk.chunk_index != i as i32) {
+        return Err(anyhow!("Chunk indexes are not in order"));
+    }
+
+    Ok(())
+}
+
+fn test_chunk_offsets() -> Result<()> {
+    println!("TEST - Chunk Offsets");
+
+    // Offsets are byte offsets, multi-byte characters must not break them
+    let html = "<h1>Café</h1><p>Ünïcödé text 🚀 here.</p><h2>Next</h2><p>More — text.</p>";
+    let chunks = chunk_document(html);
+    println!("{:?}", chunks);
+
+    if chunks.len() != 2 {
+        return Err(anyhow!("Expected two chunks, got {:?}", chunks));
+    }
+    check_offsets(html, &chunks)?;
+
+    // Chunks cover the document in order without overlapping
+    let first_end = chunks[0].end_offset;
+    if chunks[0].start_offset != 0 || chunks[1].start_offset < first_end || chunks[1].end_offset != html.len() as i32 {
+        return Err(anyhow!("Chunk offsets do not cover the document in order: {:?}", chunks));
+    }
+
+    if &html[chunks[1].start_offset as usize..] != "<h2>Next</h2><p>More — text.</p>" {
+        return Err(anyhow!("Second chunk does not start at its heading"));
+    }
+
+    Ok(())
+}
+
+fn test_chunk_long_paragraph() -> Result<()> {
+    println!("TEST - Chunk Long Paragraph");
+
+    // A paragraph longer than a chunk is split, each piece pointing at the whole paragraph
+    let sentence = "This sentence is repeated to make one very long paragraph. ";
+    let paragraph = sentence.repeat(60);
+    let html = format!("<h2>Long</h2><p>{}</p><p>Short closing paragraph.</p>", paragraph.trim_end());
+    let chunks = chunk_document(&html);
+    println!("{} chunks", chunks.len());
+
+    if chunks.len() < 3 {
+        return Err(anyhow!("Expected the long paragraph to be split, got {} chunks", chunks.len()));
+    }
+    check_offsets(&html, &chunks)?;
+
+    // The heading stays with the first piece instead of becoming a chunk of its own
+    if !chunks[0].content.starts_with("Long\nThis sentence") || chunks[0].start_offset != 0 {
+        return Err(anyhow!("Heading was not kept with the paragraph below it: {:?}", chunks[0]));
+    }
+
+    let pieces = &chunks[..chunks.len() - 1];
+    if pieces.iter().any(|chunk| chunk.content.len() > 1500 + "Long\n".len()) {
+        return Err(anyhow!("A piece of the long paragraph is larger than a chunk"));
+    }
+    if pieces.iter().any(|chunk| chunk.heading.as_deref() != Some("Long")) {
+        return Err(anyhow!("Pieces of the long paragraph lost their heading"));
+    }
+
+    let last = chunks.last().unwrap();
+    if last.content != "Short closing paragraph." {
+        return Err(anyhow!("Paragraph after the long one was merged into it: {:?}", last));
+    }
+
+    Ok(())
+}
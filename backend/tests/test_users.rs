@@ -24,8 +24,13 @@ async fn test_users() -> Result<()> {
     let get_user_result = test_get_user(&hc).await;
     let get_current_user_result = test_get_current_user(&hc).await;
     let check_auth_result = test_check_auth(&hc).await;
+    let ai_usage_result = test_get_ai_usage(&hc).await;
+    let select_plan_result = test_select_plan().await;
     let upload_image_result = test_upload_profile_image(&hc).await;
     let get_image_result = test_get_profile_image(&hc, 1).await; // Assuming user 1 exists
+    let get_sessions_result = test_get_sessions(&hc).await;
+    let revoke_session_result = test_revoke_session(&hc).await;
+    let revoke_all_sessions_result = test_revoke_all_sessions(&hc).await;
     let logout_result = test_logout(&hc).await;
     let reset_db_result = backend::test_reset_db(&hc).await;
 
@@ -38,8 +43,13 @@ async fn test_users() -> Result<()> {
     println!("Get User:\t{}", result_to_string(&get_user_result));
     println!("Get Current User:\t{}", result_to_string(&get_current_user_result));
     println!("Check Auth:\t\t{}", result_to_string(&check_auth_result));
+    println!("AI Usage:\t\t{}", result_to_string(&ai_usage_result));
+    println!("Select Plan:\t\t{}", result_to_string(&select_plan_result));
     println!("Upload Image:\t{}", result_to_string(&upload_image_result));
     println!("Get Image:\t\t{}", result_to_string(&get_image_result));
+    println!("Get Sessions:\t{}", result_to_string(&get_sessions_result));
+    println!("Revoke Session:\t{}", result_to_string(&revoke_session_result));
+    println!("Revoke All Sessions:\t{}", result_to_string(&revoke_all_sessions_result));
     println!("Logout:\t\t{}", result_to_string(&logout_result));
     println!("Reset Database:\t{}", result_to_string(&reset_db_result));
     println!("======================\n");
@@ -209,6 +219,77 @@ async fn test_get_current_user(hc: &Client) -> Result<()> {
     Ok(())
 }
 
+async fn test_get_ai_usage(hc: &Client) -> Result<()> {
+    println!("TEST - Get AI Usage");
+
+    let response = hc.do_get("/api/users/ai-usage?limit=10").await?;
+    response.print().await?;
+
+    if !response.status().is_success() {
+        return Err(anyhow::anyhow!("Get AI Usage failed with status: {}", response.status()));
+    }
+
+    let body = response.json_body()?;
+    if body["plan"]["name"].as_str().is_none() || !body["history"].is_array() || body["tokens_used"].as_i64().is_none() {
+        return Err(anyhow::anyhow!("AI usage is missing the plan, history or tokens used"));
+    }
+
+    // Plans can be listed without signing in
+    let anonymous = httpc_test::new_client("http://localhost:3001")?;
+    let response = anonymous.do_get("/api/users/plans").await?;
+    if !response.status().is_success() {
+        return Err(anyhow::anyhow!("Listing plans without signing in failed with status: {}", response.status()));
+    }
+    let plans = response.json_body()?;
+    let has_plan = plans
+        .as_array()
+        .is_some_and(|plans| plans.iter().any(|plan| plan["name"] == body["plan"]["name"]));
+    if !has_plan {
+        return Err(anyhow::anyhow!("The user's plan is not among the listed plans"));
+    }
+
+    Ok(())
+}
+
+async fn test_select_plan() -> Result<()> {
+    println!("TEST - Select Plan");
+
+    // User 2 is not an admin
+    let hc = httpc_test::new_client("http://localhost:3001")?;
+    let login_response = hc
+        .do_post("/api/users/login", json!({ "email": "MarkoP@gmail.com", "password": "MarkosPassword" }))
+        .await?;
+    if !login_response.status().is_success() {
+        return Err(anyhow::anyhow!("Could not login as user 2"));
+    }
+
+    // Paid plans cannot be picked without paying for them
+    let response = hc.do_put("/api/users/plan", json!({ "plan": "team" })).await?;
+    response.print().await?;
+    if response.status().as_u16() != 403 {
+        return Err(anyhow::anyhow!("Expected 403 for a paid plan, got: {}", response.status()));
+    }
+
+    // Plans that do not exist are refused
+    let response = hc.do_put("/api/users/plan", json!({ "plan": "no-such-plan" })).await?;
+    if response.status().as_u16() != 400 {
+        return Err(anyhow::anyhow!("Expected 400 for an unknown plan, got: {}", response.status()));
+    }
+
+    // Free plans can be picked
+    let response = hc.do_put("/api/users/plan", json!({ "plan": "free" })).await?;
+    if !response.status().is_success() || response.json_body()?["name"] != "free" {
+        return Err(anyhow::anyhow!("Select plan failed with status: {}", response.status()));
+    }
+
+    let usage = hc.do_get("/api/users/ai-usage?limit=1").await?.json_body()?;
+    if usage["plan"]["name"] != "free" {
+        return Err(anyhow::anyhow!("The paid plan was applied anyway: {}", usage["plan"]));
+    }
+
+    Ok(())
+}
+
 async fn test_check_auth(hc: &Client) -> Result<()> {
     println!("TEST - Check Auth");
     let res = hc.do_get("/api/users/check-auth").await?;
@@ -248,4 +329,95 @@ async fn test_get_profile_image(hc: &Client, user_id: i32) -> Result<()> {
         ));
     }
     Ok(())
-}
\ No newline at end of file
+}
+
+async fn test_get_sessions(hc: &Client) -> Result<()> {
+    println!("TEST - Get Sessions");
+    let res = hc.do_get("/api/users/sessions").await?;
+    res.print().await?;
+
+    if !res.status().is_success() {
+        return Err(anyhow::anyhow!(
+            "Get sessions failed with status: {}",
+            res.status()
+        ));
+    }
+
+    // The session we are using should be flagged as current
+    let sessions = res.json_body()?;
+    let has_current = sessions
+        .as_array()
+        .map(|list| list.iter().any(|s| s["is_current"] == true))
+        .unwrap_or(false);
+
+    if !has_current {
+        return Err(anyhow::anyhow!("Current session missing from session list"));
+    }
+
+    Ok(())
+}
+
+async fn test_revoke_session(hc: &Client) -> Result<()> {
+    println!("TEST - Revoke Session");
+
+    // Sign in a second time from another client with the updated credentials, then revoke that
+    // session from the first one
+    let other = httpc_test::new_client("http://localhost:3001")?;
+    let login = other
+        .do_post(
+            "/api/users/login",
+            json!({ "email": "updated_email", "password": "updated_password" }),
+        )
+        .await?;
+    if !login.status().is_success() {
+        return Err(anyhow::anyhow!("Second login failed with status: {}", login.status()));
+    }
+
+    let sessions = hc.do_get("/api/users/sessions").await?.json_body()?;
+    let other_id = sessions
+        .as_array()
+        .and_then(|list| list.iter().find(|s| s["is_current"] == false))
+        .and_then(|s| s["id"].as_str().map(str::to_string))
+        .ok_or(anyhow::anyhow!("Second session missing from session list"))?;
+
+    let res = hc.do_delete(&format!("/api/users/sessions/{}", other_id)).await?;
+    res.print().await?;
+
+    if !res.status().is_success() {
+        return Err(anyhow::anyhow!(
+            "Revoke session failed with status: {}",
+            res.status()
+        ));
+    }
+
+    // Only the revoked session is signed out
+    let other_check = other.do_get("/api/users/check-auth").await?.json_body()?;
+    let own_check = hc.do_get("/api/users/check-auth").await?.json_body()?;
+    if other_check["authenticated"] == true || own_check["authenticated"] != true {
+        return Err(anyhow::anyhow!("Revoking one session signed out the wrong session"));
+    }
+
+    Ok(())
+}
+
+async fn test_revoke_all_sessions(hc: &Client) -> Result<()> {
+    println!("TEST - Revoke All Sessions");
+    let res = hc.do_delete("/api/users/sessions").await?;
+    res.print().await?;
+
+    if !res.status().is_success() {
+        return Err(anyhow::anyhow!(
+            "Revoke all sessions failed with status: {}",
+            res.status()
+        ));
+    }
+
+    // We should no longer be authenticated
+    let check = hc.do_get("/api/users/check-auth").await?;
+    let body = check.json_body()?;
+    if body["authenticated"] == true {
+        return Err(anyhow::anyhow!("Still authenticated after revoking all sessions"));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,232 @@
+// frontend/src/lib/ts/admin.ts
+/*
+/ admin.ts
+/
+/ File containing functions required for the admin pages
+/ Will provide the communication with the backend and pass necessary information to API calls
+/ Every call requires the current user to be an admin, the backend answers 403 otherwise
+/
+/ Summary:
+/ Interface AdminUser: A user with their limits and what they are using of them
+/ Interface AdminAuditEntry: One change made by an admin
+/ list_users: Function to list or search users
+/ get_user: Function to get a single user
+/ adjust_credits: Function to add or remove AI credits
+/ update_limits: Function to change a user's plan, limits and storage quota
+/ set_account_disabled: Function to disable or enable an account
+/ get_storage: Function to get the storage overview
+/ get_audit_log: Function to get the changes made by admins
+/
+*/
+
+const API_BASE_URL = process.env.API_BASE_URL;
+
+export interface AdminUser {
+	id: number;
+	name: string;
+	email: string;
+	is_admin: boolean;
+	disabled_at: string | null;
+	plan: string;
+	ai_credits: number;
+	max_projects: number;
+	max_documents: number;
+	storage_quota_bytes: number | null; // null is the default quota
+	project_count: number;
+	document_count: number;
+	storage_bytes: number;
+}
+
+export interface AdminAuditEntry {
+	id: number;
+	admin_id: number | null;
+	admin_name: string | null;
+	action: 'adjust_credits' | 'update_limits' | 'disable_user' | 'enable_user';
+	target_user_id: number | null;
+	details: Record<string, unknown>;
+	created_at: string;
+}
+
+// Only the fields that are set are changed, a null storage_quota_bytes goes back to the default quota
+export interface UserLimitsUpdate {
+	plan?: string;
+	max_projects?: number;
+	max_documents?: number;
+	storage_quota_bytes?: number | null;
+	reason?: string;
+}
+
+/**
+ * Function to list users, optionally matching a name or email
+ * Calls: GET /api/admin/users?q=&disabled=&limit=&offset=
+ * Test: test_admin.rs/test_list_users()
+ */
+export async function list_users(
+	query = '',
+	disabled?: boolean,
+	limit = 50,
+	offset = 0
+): Promise<AdminUser[]> {
+	try {
+		const params = new URLSearchParams({ q: query, limit: String(limit), offset: String(offset) });
+		if (disabled !== undefined) {
+			params.set('disabled', String(disabled));
+		}
+
+		const response = await fetch(`${API_BASE_URL}/api/admin/users?${params}`, {
+			credentials: 'include'
+		});
+
+		if (!response.ok) {
+			console.error('Failed to list users:', response.status);
+			return [];
+		}
+
+		return await response.json();
+	} catch (error) {
+		console.error('Error listing users:', error);
+		return [];
+	}
+}
+
+/**
+ * Function to get a single user with their limits and usage
+ * Calls: GET /api/admin/users/:id
+ * Test: test_admin.rs/test_update_limits()
+ */
+export async function get_user(user_id: number): Promise<AdminUser | null> {
+	try {
+		const response = await fetch(`${API_BASE_URL}/api/admin/users/${user_id}`, {
+			credentials: 'include'
+		});
+
+		if (!response.ok) {
+			console.error('Failed to fetch user:', response.status);
+			return null;
+		}
+
+		return await response.json();
+	} catch (error) {
+		console.error('Error fetching user:', error);
+		return null;
+	}
+}
+
+/**
+ * Function to add AI credits to a user, or take them away with a negative delta
+ * Calls: POST /api/admin/users/:id/credits
+ * Test: test_admin.rs/test_adjust_credits()
+ */
+export async function adjust_credits(
+	user_id: number,
+	delta: number,
+	reason?: string
+): Promise<AdminUser | null> {
+	return await send_change('POST', `/api/admin/users/${user_id}/credits`, { delta, reason });
+}
+
+/**
+ * Function to change a user's plan, project and document limits and storage quota
+ * Calls: PUT /api/admin/users/:id/limits
+ * Test: test_admin.rs/test_update_limits()
+ */
+export async function update_limits(
+	user_id: number,
+	limits: UserLimitsUpdate
+): Promise<AdminUser | null> {
+	return await send_change('PUT', `/api/admin/users/${user_id}/limits`, limits);
+}
+
+/**
+ * Function to disable an account, signing the user out everywhere, or to enable it again
+ * Calls: POST /api/admin/users/:id/disable, POST /api/admin/users/:id/enable
+ * Test: test_admin.rs/test_disable_user()
+ */
+export async function set_account_disabled(
+	user_id: number,
+	disabled: boolean,
+	reason?: string
+): Promise<AdminUser | null> {
+	const action = disabled ? 'disable' : 'enable';
+	return await send_change('POST', `/api/admin/users/${user_id}/${action}`, { reason });
+}
+
+/**
+ * Function to get the database size, default quotas and the users using the most storage
+ * Calls: GET /api/admin/storage
+ * Test: test_admin.rs/test_get_storage()
+ */
+export async function get_storage(): Promise<any | null> {
+	try {
+		const response = await fetch(`${API_BASE_URL}/api/admin/storage`, {
+			credentials: 'include'
+		});
+
+		if (!response.ok) {
+			console.error('Failed to fetch storage overview:', response.status);
+			return null;
+		}
+
+		return await response.json();
+	} catch (error) {
+		console.error('Error fetching storage overview:', error);
+		return null;
+	}
+}
+
+/**
+ * Function to get the changes made by admins, newest first
+ * Calls: GET /api/admin/audit?target_user_id=&before=&limit=
+ * Test: test_admin.rs/test_get_audit_log()
+ */
+export async function get_audit_log(
+	target_user_id?: number,
+	before?: number,
+	limit = 50
+): Promise<AdminAuditEntry[]> {
+	try {
+		const params = new URLSearchParams({ limit: String(limit) });
+		if (target_user_id !== undefined) {
+			params.set('target_user_id', String(target_user_id));
+		}
+		if (before !== undefined) {
+			params.set('before', String(before));
+		}
+
+		const response = await fetch(`${API_BASE_URL}/api/admin/audit?${params}`, {
+			credentials: 'include'
+		});
+
+		if (!response.ok) {
+			console.error('Failed to fetch audit log:', response.status);
+			return [];
+		}
+
+		return await response.json();
+	} catch (error) {
+		console.error('Error fetching audit log:', error);
+		return [];
+	}
+}
+
+// Send a change to a user and return the user as it is afterwards
+async function send_change(method: string, path: string, body: object): Promise<AdminUser | null> {
+	try {
+		const response = await fetch(`${API_BASE_URL}${path}`, {
+			method,
+			headers: { 'Content-Type': 'application/json' },
+			credentials: 'include',
+			body: JSON.stringify(body)
+		});
+
+		if (!response.ok) {
+			console.error(`Failed to ${method} ${path}:`, response.status);
+			return null;
+		}
+
+		return await response.json();
+	} catch (error) {
+		console.error(`Error sending ${method} ${path}:`, error);
+		return null;
+	}
+}
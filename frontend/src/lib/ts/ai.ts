@@ -17,6 +17,8 @@
 / - AiRewritePayload: Payload for the rewrite command, including style.
 / - AiCommandResponse: Expected structure for responses from AI text commands.
 / - SuggestedDocumentChange: Represents the proposed changes for a single document.
+/ - RejectedDocumentChange: A document whose AI edits could not be placed, with the reason.
+/ - ApplySuggestionResult: The changes stored as suggestions and the rejected documents.
 / - SanitizeTextPayload: Payload for the new sanitize-text endpoint.
 / - SanitizeTextResponse: Response for the new sanitize-text endpoint.
 / 
@@ -118,11 +120,35 @@ interface AiRewritePayload {
 	style: string;
 }
 
+// A changed range, offsets count characters of the old and new content
+export interface DiffHunk {
+    kind: 'insert' | 'delete' | 'replace';
+    old_start: number;
+    old_end: number;
+    new_start: number;
+    new_end: number;
+    old_text: string;
+    new_text: string;
+}
+
 // Represents the suggested changes for a given document
 export interface SuggestedDocumentChange {
+    suggestion_id: number; // Pending suggestion stored on the server
     document_id: number;
     old_content: string;
     new_content: string;
+    hunks: DiffHunk[];
+}
+
+// A document the AI wanted to change but whose quoted text was missing or ambiguous
+export interface RejectedDocumentChange {
+    document_id: number;
+    reason: string;
+}
+
+export interface ApplySuggestionResult {
+    changes: SuggestedDocumentChange[];
+    rejected: RejectedDocumentChange[];
 }
 
 // Define expected Response structure
@@ -157,7 +183,7 @@ export async function get_all_writing_sessions(): Promise<WritingAssistantSessio
 /**
  * Creates a new writing assistant session.
  * Calls: POST /api/writing-assistant
- * Test: test_ai.rs/test_create_writing_session_success()
+ * Test: test_ai.rs/test_create_writing_session_success(), test_ai.rs/test_linked_session_user_isolation()
  */
 export async function create_writing_session(payload: CreateSessionPayload): Promise<WritingAssistantSession | null> {
     const apiUrl = `${API_BASE_URL}/api/writing-assistant`;
@@ -491,25 +517,25 @@ export async function check_spelling(content: string): Promise<AiCommandResponse
 /**
  * Function to generate an AI suggestion to the backend to determine necessary document changes.
  * Calls: POST /api/ai/writing-assistant/:sessionId/apply-suggestion
- * Test: TODO
+ * Test: test_ai.rs/test_apply_suggestion_success(), test_ai.rs/test_apply_suggestion_conflict()
  */
 export async function apply_ai_suggestion(
     sessionId: number, 
     suggestionContent: string,
     currentDocumentId: number | null
-): Promise<SuggestedDocumentChange[]> {
+): Promise<ApplySuggestionResult> {
     console.log(`[apply_ai_suggestion] Session ID: ${sessionId}, Current Doc ID: ${currentDocumentId}`);
     const payload = { 
         suggestion_content: suggestionContent,
         current_document_id: currentDocumentId 
     };
-    const result = await makeRequest<SuggestedDocumentChange[]>(
+    const result = await makeRequest<ApplySuggestionResult>(
         `${API_BASE_URL}/api/writing-assistant/${sessionId}/apply-suggestion`, 
         'POST', 
         payload
     );
-    // Ensure the result is always an array, even if the backend sends null/undefined by mistake
-    return result || []; 
+    // Ensure both lists are always arrays, even if the backend sends null/undefined by mistake
+    return { changes: result?.changes || [], rejected: result?.rejected || [] }; 
 }
 
 // Define interfaces for ProactiveDiffDecision - these were previously inline
@@ -39,6 +39,7 @@ export class Document {
 	is_starred: boolean;
 	is_trashed: boolean;
 	project_id?: number;
+	version: number; // Version the next save is based on, kept up to date by update_document
 
 	constructor(
 		new_id: number,
@@ -48,7 +49,8 @@ export class Document {
 		new_updated_at: string,
 		new_is_starred: boolean = false,
 		new_is_trashed: boolean = false,
-		new_project_id?: number
+		new_project_id?: number,
+		new_version: number = 1
 	) {
 		this.id = new_id;
 		this.name = new_name;
@@ -58,6 +60,7 @@ export class Document {
 		this.is_starred = new_is_starred;
 		this.is_trashed = new_is_trashed;
 		this.project_id = new_project_id;
+		this.version = new_version;
 	}
 }
 
@@ -106,8 +109,9 @@ export async function get_document(id: number): Promise<Document | null> {
 
 /**
  * Function to update a document
+ * Sends the version the edit is based on, a save based on a stale version is refused with 409 DOCUMENT_CONFLICT
  * Calls: PUT /api/document/:id
- * Test: test_documents.rs/test_update_document()
+ * Test: test_documents.rs/test_update_document(), test_documents.rs/test_update_document_conflict()
  */
 export async function update_document(document: Document): Promise<boolean> {
 	try {
@@ -116,7 +120,8 @@ export async function update_document(document: Document): Promise<boolean> {
 		const payload = {
 			name: document.name,
 			content: document.content,
-			updated_at: new Date().toISOString().replace('Z', '')
+			updated_at: new Date().toISOString().replace('Z', ''),
+			version: document.version
 		};
 
 		const response = await fetch(apiUrl, {
@@ -128,7 +133,26 @@ export async function update_document(document: Document): Promise<boolean> {
 			credentials: 'include'
 		});
 
-		return response.ok;
+		if (response.status === 409) {
+			// Someone else saved first, keep their changes instead of overwriting them
+			const body = await response.json();
+			if (body?.error?.type === 'DOCUMENT_CONFLICT') {
+				console.error(
+					`Document ${document.id} was changed elsewhere (now at version ${body.error.data?.current_version}), reload it before saving`
+				);
+			}
+			return false;
+		}
+
+		if (!response.ok) {
+			console.error('Failed to update document:', response.status);
+			return false;
+		}
+
+		// The next save is based on the version just written
+		const data = await response.json();
+		document.version = data.version;
+		return true;
 	} catch (error) {
 		console.error('Error updating document:', error);
 		return false;
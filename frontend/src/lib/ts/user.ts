@@ -13,6 +13,9 @@
 / get_current_user: Function to get the currently logged in user
 / update_user: Function to update user information
 / check_auth: Function to check if a user is authenticated
+/ get_ai_usage: Function to get the user's plan, credits and AI usage
+/ get_plans: Function to list the available plans
+/ select_plan: Function to move the user to another plan
 /
 */
 
@@ -388,3 +391,108 @@ export async function get_user_storage(): Promise<{
 		return null;
 	}
 }
+
+export interface Plan {
+	name: string;
+	description: string;
+	price_cents: number;
+	monthly_token_budget: number | null;
+	refill_credits: number;
+	refill_interval_days: number;
+}
+
+export interface AiUsageEntry {
+	id: number;
+	operation: string;
+	amount: number;
+	status: 'reserved' | 'committed' | 'released';
+	failure: string | null;
+	prompt_tokens: number;
+	completion_tokens: number;
+	tokens_estimated: boolean;
+	created_at: string;
+	settled_at: string | null;
+}
+
+/**
+ * Function to get the user's plan, AI credits, tokens used this month and AI operation history
+ * Calls: GET /api/users/ai-usage?limit=&before=
+ * Test: test_users.rs/test_get_ai_usage()
+ */
+export async function get_ai_usage(limit?: number, before?: number): Promise<{
+	plan: Plan;
+	ai_credits: number;
+	period_start: string;
+	tokens_used: number;
+	next_refill_at: string;
+	history: AiUsageEntry[];
+} | null> {
+	try {
+		const params = new URLSearchParams();
+		if (limit !== undefined) params.set('limit', String(limit));
+		if (before !== undefined) params.set('before', String(before));
+
+		const response = await fetch(`${API_BASE_URL}/api/users/ai-usage?${params}`, {
+			credentials: 'include'
+		});
+
+		if (!response.ok) {
+			console.error('Failed to fetch AI usage:', response.status);
+			return null;
+		}
+
+		return await response.json();
+	} catch (error) {
+		console.error('Error fetching AI usage:', error);
+		return null;
+	}
+}
+
+/**
+ * Function to get the available plans
+ * Calls: GET /api/users/plans
+ * Test: test_users.rs/test_get_ai_usage()
+ */
+export async function get_plans(): Promise<Plan[]> {
+	try {
+		const response = await fetch(`${API_BASE_URL}/api/users/plans`, {
+			credentials: 'include'
+		});
+
+		if (!response.ok) {
+			console.error('Failed to fetch plans:', response.status);
+			return [];
+		}
+
+		return await response.json();
+	} catch (error) {
+		console.error('Error fetching plans:', error);
+		return [];
+	}
+}
+
+/**
+ * Function to move the current user to another free plan, paid plans are refused with 403
+ * Calls: PUT /api/users/plan
+ * Test: test_users.rs/test_select_plan()
+ */
+export async function select_plan(plan: string): Promise<Plan | null> {
+	try {
+		const response = await fetch(`${API_BASE_URL}/api/users/plan`, {
+			method: 'PUT',
+			headers: { 'Content-Type': 'application/json' },
+			credentials: 'include',
+			body: JSON.stringify({ plan })
+		});
+
+		if (!response.ok) {
+			console.error('Failed to select plan:', response.status);
+			return null;
+		}
+
+		return await response.json();
+	} catch (error) {
+		console.error('Error selecting plan:', error);
+		return null;
+	}
+}